@@ -0,0 +1,140 @@
+// Response policy for detected events: log, notify, or actively block
+use colored::*;
+use std::collections::HashSet;
+use std::process::Command;
+
+use crate::kernel_monitor::{ProcessInfo, UsbDeviceInfo};
+use crate::notification::NotificationManager;
+use crate::scoring::{Severity, SeverityThresholds};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum ResponseAction {
+    // Print the event
+    Log,
+    // Print the event and send a desktop notification
+    Notify,
+    // Print, notify, and try to disable the device or stop the process
+    Block,
+}
+
+pub enum SecurityEvent<'a> {
+    UsbInserted(&'a UsbDeviceInfo),
+    SuspiciousProcess(&'a ProcessInfo),
+}
+
+impl SecurityEvent<'_> {
+    // Stable key so an event still reported on the next poll isn't acted on twice
+    fn key(&self) -> String {
+        match self {
+            SecurityEvent::UsbInserted(device) => format!("usb:{}", device.device_id),
+            SecurityEvent::SuspiciousProcess(process) => format!("process:{}:{}", process.pid, process.name),
+        }
+    }
+
+    fn score(&self) -> u8 {
+        match self {
+            SecurityEvent::UsbInserted(device) => device.class.risk_score(),
+            SecurityEvent::SuspiciousProcess(process) => process.suspicious_score,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            SecurityEvent::UsbInserted(device) => {
+                format!("New USB device: {} [{}] (ID: {})", device.description, device.class.name(), device.device_id)
+            },
+            SecurityEvent::SuspiciousProcess(process) => {
+                format!("Suspicious process: {} (PID: {}, Score: {})", process.name, process.pid, process.suspicious_score)
+            },
+        }
+    }
+}
+
+pub struct ActionDispatcher {
+    on_usb: ResponseAction,
+    on_process: ResponseAction,
+    thresholds: SeverityThresholds,
+    notification_manager: NotificationManager,
+    handled: HashSet<String>,
+}
+
+impl ActionDispatcher {
+    pub fn new(on_usb: ResponseAction, on_process: ResponseAction, thresholds: SeverityThresholds, notification_manager: NotificationManager) -> Self {
+        if on_usb == ResponseAction::Block || on_process == ResponseAction::Block {
+            say!("{}", "Active blocking is enabled: flagged devices/processes will be disabled or stopped.".bright_red());
+        }
+
+        ActionDispatcher {
+            on_usb,
+            on_process,
+            thresholds,
+            notification_manager,
+            handled: HashSet::new(),
+        }
+    }
+
+    // Apply the configured response to an event, once per event. Returns whether the event
+    // was new
+    pub fn dispatch(&mut self, event: &SecurityEvent) -> bool {
+        if !self.handled.insert(event.key()) {
+            return false;
+        }
+
+        let action = match event {
+            SecurityEvent::UsbInserted(_) => self.on_usb,
+            SecurityEvent::SuspiciousProcess(_) => self.on_process,
+        };
+
+        // Stopping a process is destructive, so only critical processes are blocked;
+        // anything lower is downgraded to a notification
+        let action = match event {
+            SecurityEvent::SuspiciousProcess(_)
+                if action == ResponseAction::Block && self.thresholds.classify(event.score()) != Severity::Critical => ResponseAction::Notify,
+            _ => action,
+        };
+
+        let description = event.describe();
+        say!("{} {}", "[EVENT]".bright_yellow(), description);
+
+        if action == ResponseAction::Notify || action == ResponseAction::Block {
+            let _ = self.notification_manager.send_notification("ThreatSentry Ultra", &description, event.score().max(50));
+        }
+
+        if action == ResponseAction::Block {
+            let result = match event {
+                SecurityEvent::UsbInserted(device) => Self::disable_usb_device(&device.device_id),
+                SecurityEvent::SuspiciousProcess(process) => Self::stop_process(process.pid),
+            };
+
+            match result {
+                Ok(_) => say!("{} {}", "[BLOCKED]".bright_red(), description),
+                Err(e) => say!("{} {}: {}", "[BLOCK FAILED]".bright_red(), description, e),
+            }
+        }
+
+        true
+    }
+
+    // Disabling a PnP device needs an elevated shell
+    fn disable_usb_device(device_id: &str) -> Result<(), String> {
+        let command = format!("Disable-PnpDevice -InstanceId '{}' -Confirm:$false", device_id.replace('\'', "''"));
+        Self::run_powershell(&command)
+    }
+
+    fn stop_process(pid: u32) -> Result<(), String> {
+        Self::run_powershell(&format!("Stop-Process -Id {} -Force", pid))
+    }
+
+    fn run_powershell(command: &str) -> Result<(), String> {
+        let output = Command::new("powershell")
+            .args(["-Command", command])
+            .output()
+            .map_err(|e| format!("Failed to execute PowerShell command: {}", e))?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    }
+}
@@ -0,0 +1,96 @@
+// Trusted domains whose URLs always score 0, e.g. internal portals with "login" in the path.
+// Entries are exact hosts ("portal.example.com") or wildcards ("*.corp.example.com"), which
+// match any subdomain but not the domain itself, so trust never spreads further than listed
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+pub struct Allowlist {
+    exact: HashSet<String>,
+    wildcards: Vec<String>, // Suffixes including the leading dot, e.g. ".corp.example.com"
+}
+
+impl Allowlist {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read allowlist {}: {}", path.display(), e))?;
+
+        let allowlist = Self::parse(&contents);
+        info!("Loaded {} allowlist entries from {}", allowlist.exact.len() + allowlist.wildcards.len(), path.display());
+        Ok(allowlist)
+    }
+
+    // One entry per line; "#" starts a comment
+    pub fn parse(contents: &str) -> Self {
+        let mut allowlist = Allowlist {
+            exact: HashSet::new(),
+            wildcards: Vec::new(),
+        };
+
+        for line in contents.lines() {
+            let entry = line.split('#').next().unwrap_or("").trim().to_lowercase();
+            if entry.is_empty() {
+                continue;
+            }
+
+            match entry.strip_prefix("*.") {
+                Some(suffix) if !suffix.is_empty() => allowlist.wildcards.push(format!(".{}", suffix.trim_end_matches('.'))),
+                Some(_) => continue,
+                None => {
+                    allowlist.exact.insert(entry.trim_end_matches('.').to_string());
+                },
+            }
+        }
+
+        allowlist
+    }
+
+    // Whether the URL's host is listed exactly or falls under a wildcard entry
+    pub fn contains_url(&self, url: &str) -> bool {
+        match Self::host(url) {
+            Some(host) => self.contains_host(&host),
+            None => false,
+        }
+    }
+
+    fn contains_host(&self, host: &str) -> bool {
+        self.exact.contains(host)
+            || self.wildcards.iter().any(|suffix| host.ends_with(suffix.as_str()) && host.len() > suffix.len())
+    }
+
+    // Lowercased host without scheme, credentials or port
+    pub fn host(url: &str) -> Option<String> {
+        let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let authority = without_scheme.split(['/', '?', '#']).next()?;
+        let host = authority.rsplit('@').next()?.split(':').next()?;
+        let host = host.trim_end_matches('.').to_lowercase();
+        (!host.is_empty()).then_some(host)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard_matches_subdomains_but_not_the_domain_itself() {
+        let allowlist = Allowlist::parse("*.corp.example.com # internal portals\n");
+
+        assert!(allowlist.contains_url("https://portal.corp.example.com/login"));
+        assert!(allowlist.contains_url("https://a.b.corp.example.com/"));
+        assert!(!allowlist.contains_url("https://corp.example.com/login"));
+        assert!(!allowlist.contains_url("https://evilcorp.example.com/login"));
+        assert!(!allowlist.contains_url("https://portal.corp.example.com.evil.net/login"));
+    }
+
+    #[test]
+    fn exact_entry_matches_only_that_host() {
+        let allowlist = Allowlist::parse("Portal.Example.com.\n\n# comment\n");
+
+        assert!(allowlist.contains_url("https://portal.example.com/login"));
+        assert!(allowlist.contains_url("https://user@PORTAL.example.com.:8443/login"));
+        assert!(!allowlist.contains_url("https://sso.portal.example.com/login"));
+        assert!(!allowlist.contains_url("https://example.com/login"));
+    }
+}
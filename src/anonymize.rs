@@ -0,0 +1,54 @@
+// Replaces personal identifiers in report output with stable pseudonyms, so findings
+// can be shared for support without exposing the account or machine they came from
+use regex::Regex;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use sysinfo::System;
+
+pub struct Anonymizer {
+    email_pattern: Regex,
+    // Configured identifiers (IMAP username, hostname) and the pseudonym each is replaced with
+    identifiers: Vec<(Regex, String)>,
+}
+
+impl Anonymizer {
+    pub fn new(username: Option<&str>) -> Self {
+        let mut identifiers = Vec::new();
+
+        if let Some(username) = username.filter(|u| !u.is_empty()) {
+            identifiers.push(Self::identifier("user", username));
+        }
+        if let Some(hostname) = System::host_name().filter(|h| !h.is_empty()) {
+            identifiers.push(Self::identifier("host", &hostname));
+        }
+
+        Anonymizer {
+            email_pattern: Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap(),
+            identifiers,
+        }
+    }
+
+    // Same input always maps to the same token, so repeated findings can still be correlated
+    fn pseudonym(kind: &str, value: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        value.to_lowercase().hash(&mut hasher);
+        format!("{}-{:08x}", kind, hasher.finish() as u32)
+    }
+
+    fn identifier(kind: &str, value: &str) -> (Regex, String) {
+        let pattern = Regex::new(&format!(r"(?i)\b{}\b", regex::escape(value))).unwrap();
+        (pattern, Self::pseudonym(kind, value))
+    }
+
+    pub fn scrub(&self, text: &str) -> String {
+        let mut scrubbed = self.email_pattern
+            .replace_all(text, |caps: &regex::Captures| Self::pseudonym("email", &caps[0]))
+            .into_owned();
+
+        for (pattern, pseudonym) in &self.identifiers {
+            scrubbed = pattern.replace_all(&scrubbed, pseudonym.as_str()).into_owned();
+        }
+
+        scrubbed
+    }
+}
@@ -0,0 +1,146 @@
+// Saving the audio around an ultrasonic detection as WAV clips for later analysis. The
+// recorder keeps the last few seconds of raw input in a ring buffer, so a clip covers the
+// lead-up to the detection as well as a few seconds after it
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tracing::{error, info};
+
+// Audio kept from before the detection
+const PRE_TRIGGER_SECS: u32 = 5;
+
+// Audio recorded after the detection before the clip is written
+const POST_TRIGGER_SECS: u32 = 3;
+
+// Clips written per run at most, so a beacon that keeps firing can't fill the disk
+pub const MAX_CLIPS_PER_RUN: usize = 10;
+
+pub struct ClipRecorder {
+    dir: PathBuf,
+    audio: Mutex<VecDeque<f32>>,
+    format: Mutex<(u32, u16)>, // Sample rate and channel count of the samples in audio
+    clips: Mutex<Vec<PathBuf>>,
+    triggered: Mutex<usize>,
+    recording: Mutex<bool>,
+    pending: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl ClipRecorder {
+    pub fn new(dir: &Path) -> Result<Self, String> {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create recording directory {}: {}", dir.display(), e))?;
+
+        Ok(ClipRecorder {
+            dir: dir.to_path_buf(),
+            audio: Mutex::new(VecDeque::new()),
+            format: Mutex::new((44100, 1)),
+            clips: Mutex::new(Vec::new()),
+            triggered: Mutex::new(0),
+            recording: Mutex::new(false),
+            pending: Mutex::new(Vec::new()),
+        })
+    }
+
+    // Called when a stream is (re)opened; samples in another format can't go in the same clip
+    pub fn set_format(&self, sample_rate: u32, channels: u16) {
+        *self.format.lock().unwrap() = (sample_rate, channels.max(1));
+        self.audio.lock().unwrap().clear();
+    }
+
+    // Append interleaved input samples, dropping the oldest beyond the ring buffer's length
+    pub fn push(&self, samples: impl Iterator<Item = f32>) {
+        let (sample_rate, channels) = *self.format.lock().unwrap();
+        let capacity = ((PRE_TRIGGER_SECS + POST_TRIGGER_SECS) * sample_rate) as usize * channels as usize;
+
+        let mut audio = self.audio.lock().unwrap();
+        audio.extend(samples);
+        if audio.len() > capacity {
+            // Drop whole frames so the channels stay interleaved in order
+            let channels = channels as usize;
+            let excess = audio.len() - capacity;
+            let excess = (excess + (channels - excess % channels) % channels).min(audio.len());
+            audio.drain(..excess);
+        }
+    }
+
+    // Start a clip for a detection that just began. The clip is written once the post-trigger
+    // audio has come in; detections while a clip is in progress are part of that clip
+    pub fn trigger(self: &Arc<Self>) {
+        let mut recording = self.recording.lock().unwrap();
+        if *recording {
+            return;
+        }
+
+        let mut triggered = self.triggered.lock().unwrap();
+        if *triggered >= MAX_CLIPS_PER_RUN {
+            return;
+        }
+        *triggered += 1;
+        *recording = true;
+
+        let recorder = self.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_secs(POST_TRIGGER_SECS as u64));
+
+            match recorder.write_clip() {
+                Ok(path) => {
+                    info!("Saved audio clip to {}", path.display());
+                    recorder.clips.lock().unwrap().push(path);
+                },
+                Err(e) => error!("Failed to save audio clip: {}", e),
+            }
+            *recorder.recording.lock().unwrap() = false;
+        });
+        self.pending.lock().unwrap().push(handle);
+    }
+
+    // Wait for clips that are still collecting post-trigger audio
+    pub fn finish(&self) {
+        let pending: Vec<JoinHandle<()>> = self.pending.lock().unwrap().drain(..).collect();
+        if !pending.is_empty() {
+            info!("Finishing {} audio clip(s)...", pending.len());
+        }
+        for handle in pending {
+            let _ = handle.join();
+        }
+    }
+
+    pub fn clips(&self) -> Vec<PathBuf> {
+        self.clips.lock().unwrap().clone()
+    }
+
+    fn write_clip(&self) -> Result<PathBuf, String> {
+        let (sample_rate, channels) = *self.format.lock().unwrap();
+        let samples: Vec<f32> = self.audio.lock().unwrap().iter().copied().collect();
+        if samples.is_empty() {
+            return Err("no audio was captured".to_string());
+        }
+
+        let index = self.clips.lock().unwrap().len() + 1;
+        let file_name = format!("ultrasonic-{}-{}.wav", chrono::Local::now().format("%Y%m%d-%H%M%S"), index);
+        let path = self.dir.join(file_name);
+
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        // Only write whole frames, in case a callback delivered a partial one
+        let whole_frames = samples.len() - samples.len() % channels as usize;
+        for &sample in &samples[samples.len() - whole_frames..] {
+            writer.write_sample(sample)
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        writer.finalize()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        Ok(path)
+    }
+}
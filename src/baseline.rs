@@ -0,0 +1,60 @@
+// What normal looks like on this machine, learned by `baseline`: the mean and standard deviation
+// of temperature, CPU load and microphone band power over a quiet period. With a baseline in the
+// config file, thermal scoring flags readings more than `sigma` standard deviations above the
+// learned mean instead of fixed cutoffs, so a hot gaming rig isn't always red and a cool server
+// still flags a miner. The microphone threshold becomes mean + sigma standard deviations
+
+// Standard deviations above the mean that count as anomalous
+pub const DEFAULT_SIGMA: f32 = 3.0;
+
+// Smallest spread a baseline records for each metric. A very steady machine would otherwise
+// learn a deviation so small that sensor noise alone flags it
+pub const MIN_TEMP_STD_DEV: f32 = 1.0; // °C
+pub const MIN_CPU_STD_DEV: f32 = 5.0; // Percentage points
+pub const MIN_MIC_STD_DEV: f32 = 0.005; // Normalized band power
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricBaseline {
+    pub mean: f32,
+    pub std_dev: f32,
+}
+
+impl MetricBaseline {
+    // Sample mean and standard deviation, with the deviation raised to at least min_std_dev.
+    // None with fewer than two samples
+    pub fn from_samples(samples: &[f32], min_std_dev: f32) -> Option<Self> {
+        if samples.len() < 2 {
+            return None;
+        }
+
+        let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+        let variance = samples.iter().map(|sample| (sample - mean).powi(2)).sum::<f32>() / (samples.len() - 1) as f32;
+        Some(MetricBaseline {
+            mean,
+            std_dev: variance.sqrt().max(min_std_dev),
+        })
+    }
+
+    // Highest value still considered normal
+    pub fn upper_bound(&self, sigma: f32) -> f32 {
+        self.mean + sigma * self.std_dev
+    }
+
+    // Score for a value above the upper bound: half of max just past it, plus a tenth of max
+    // for every further standard deviation. Anything at or below the bound scores 0
+    pub fn deviation_score(&self, value: f32, sigma: f32, max: f32) -> f32 {
+        let deviations = (value - self.mean) / self.std_dev;
+        if deviations <= sigma {
+            return 0.0;
+        }
+        (max / 2.0 + (deviations - sigma) * max / 10.0).min(max)
+    }
+}
+
+// Learned temperature and CPU load, which the thermal score is judged against
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThermalBaseline {
+    pub temperature: MetricBaseline,
+    pub cpu: MetricBaseline,
+    pub sigma: f32,
+}
@@ -0,0 +1,202 @@
+// Offline URL blocklist (URLhaus, hpHosts and similar text dumps), matched on hashes
+// so scanning never sends the URLs anywhere
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{error, info};
+
+// How often the file's modification time is checked for a reload
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+struct BlocklistState {
+    hashes: HashSet<u64>,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+pub struct Blocklist {
+    path: Option<PathBuf>, // None when parsed from a string, which is never reloaded
+    state: Mutex<BlocklistState>,
+}
+
+impl Blocklist {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let (contents, modified) = Self::read_file(path)?;
+        let mut blocklist = Self::parse(&contents);
+        let state = blocklist.state.get_mut().unwrap();
+        state.modified = modified;
+        info!("Loaded {} blocklist entries from {}", state.hashes.len(), path.display());
+
+        blocklist.path = Some(path.to_path_buf());
+        Ok(blocklist)
+    }
+
+    // One entry per line in any of the formats normalize_entry accepts
+    pub fn parse(contents: &str) -> Self {
+        Blocklist {
+            path: None,
+            state: Mutex::new(BlocklistState {
+                hashes: Self::hash_entries(contents),
+                modified: None,
+                last_check: Instant::now(),
+            }),
+        }
+    }
+
+    // Whether the URL's host (or a parent domain) or any host + path prefix is listed
+    pub fn contains_url(&self, url: &str) -> bool {
+        self.reload_if_changed();
+
+        let state = self.state.lock().unwrap();
+        Self::url_expressions(url)
+            .iter()
+            .any(|expression| state.hashes.contains(&Self::hash(expression)))
+    }
+
+    // Pick up a refreshed dump without restarting long-running scans
+    fn reload_if_changed(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let mut state = self.state.lock().unwrap();
+        if state.last_check.elapsed() < RELOAD_CHECK_INTERVAL {
+            return;
+        }
+        state.last_check = Instant::now();
+
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        if modified.is_some() && modified == state.modified {
+            return;
+        }
+
+        match Self::read_file(path) {
+            Ok((contents, modified)) => {
+                state.hashes = Self::hash_entries(&contents);
+                state.modified = modified;
+                info!("Reloaded {} blocklist entries from {}", state.hashes.len(), path.display());
+            },
+            Err(e) => error!("Error reloading blocklist: {}", e),
+        }
+    }
+
+    fn read_file(path: &Path) -> Result<(String, Option<SystemTime>), String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read blocklist {}: {}", path.display(), e))?;
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        Ok((contents, modified))
+    }
+
+    fn hash_entries(contents: &str) -> HashSet<u64> {
+        contents.lines()
+            .filter_map(Self::normalize_entry)
+            .map(|entry| Self::hash(&entry))
+            .collect()
+    }
+
+    // Accepts plain URLs, bare domains, and hosts-file lines ("0.0.0.0 evil.com");
+    // comments and blank lines are skipped
+    fn normalize_entry(line: &str) -> Option<String> {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            return None;
+        }
+
+        // "*.evil.com" means the same as "evil.com": a domain entry already covers its subdomains
+        let entry = line.split_whitespace().last()?;
+        let entry = entry.strip_prefix("*.").unwrap_or(entry);
+        let (host, path) = Self::split_url(entry);
+        if host.is_empty() {
+            return None;
+        }
+
+        let path = path.trim_end_matches('/');
+        Some(format!("{}{}", host, path))
+    }
+
+    // Lowercased host without scheme, credentials, port or "www.", and the path including any query
+    fn split_url(url: &str) -> (String, String) {
+        let url = url.trim();
+        let without_scheme = match url.find("://") {
+            Some(idx) => &url[idx + 3..],
+            None => url,
+        };
+        let without_fragment = without_scheme.split('#').next().unwrap_or("");
+
+        let host_end = without_fragment.find(['/', '?']).unwrap_or(without_fragment.len());
+        let (authority, path) = without_fragment.split_at(host_end);
+
+        let host = authority.rsplit('@').next().unwrap_or("");
+        let host = host.split(':').next().unwrap_or("").to_lowercase();
+        let host = host.trim_start_matches("www.").trim_end_matches('.').to_string();
+
+        (host, path.to_string())
+    }
+
+    // Host suffixes down to the registrable domain, each combined with every path prefix,
+    // so both "evil.com" and "evil.com/specific-path" entries match
+    fn url_expressions(url: &str) -> Vec<String> {
+        let (host, path) = Self::split_url(url);
+        if host.is_empty() {
+            return Vec::new();
+        }
+
+        let labels: Vec<&str> = host.split('.').collect();
+        let hosts: Vec<String> = (0..labels.len().saturating_sub(1).max(1))
+            .map(|start| labels[start..].join("."))
+            .collect();
+
+        let path_only = path.split('?').next().unwrap_or("").trim_end_matches('/');
+        let mut paths = vec![String::new()];
+        let mut prefix = String::new();
+        for segment in path_only.split('/').filter(|s| !s.is_empty()) {
+            prefix.push('/');
+            prefix.push_str(segment);
+            paths.push(prefix.clone());
+        }
+        let full_path = path.trim_end_matches('/');
+        if !paths.iter().any(|p| p == full_path) {
+            paths.push(full_path.to_string());
+        }
+
+        hosts.iter()
+            .flat_map(|host| paths.iter().map(move |path| format!("{}{}", host, path)))
+            .collect()
+    }
+
+    fn hash(expression: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        expression.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn domain_entries_cover_subdomains_only() {
+        let blocklist = Blocklist::parse("evil.com\n*.phish.net\n0.0.0.0 tracker.org # hosts file\n");
+
+        assert!(blocklist.contains_url("https://evil.com/login"));
+        assert!(blocklist.contains_url("https://login.evil.com/x"));
+        assert!(blocklist.contains_url("http://www.phish.net"));
+        assert!(blocklist.contains_url("https://a.b.phish.net/"));
+        assert!(blocklist.contains_url("https://tracker.org/pixel.gif"));
+        assert!(!blocklist.contains_url("https://notevil.com/login"));
+        assert!(!blocklist.contains_url("https://evil.com.example.org/login"));
+    }
+
+    #[test]
+    fn path_entries_match_that_path_only() {
+        let blocklist = Blocklist::parse("https://files.example.com/malware/\n");
+
+        assert!(blocklist.contains_url("https://files.example.com/malware/payload.exe"));
+        assert!(!blocklist.contains_url("https://files.example.com/docs/readme.txt"));
+    }
+}
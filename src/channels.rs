@@ -0,0 +1,351 @@
+// Notification destinations besides the desktop: team chat webhooks and syslog. Each channel
+// formats the alert for its service; webhooks are retried with backoff when the service is down
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use syslog::{Facility, Formatter5424, Logger, LoggerBackend};
+use sysinfo::System;
+use tracing::warn;
+
+use crate::scoring::Severity;
+
+// Wait before the first retry; each further retry waits twice as long
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+// Only alerts reach the channels: orange for high, red for critical. Teams takes the Slack
+// colors without the "#"
+const SLACK_COLORS: [&str; 2] = ["#E8912D", "#E01E5A"];
+const DISCORD_COLORS: [u32; 2] = [0xE67E22, 0xE74C3C];
+
+// Syslog app-name, and the structured-data ID for the monitor and score. IDs without an
+// IANA-registered name need an enterprise number; 32473 is the one reserved for examples
+const SYSLOG_APP_NAME: &str = "threatsentry";
+const SYSLOG_SD_ID: &str = "threatsentry@32473";
+const DEFAULT_SYSLOG_PORT: u16 = 514;
+
+// Channels are sent to from the notification delivery thread
+pub trait NotificationChannel: Send + Sync {
+    // Short name used when reporting a failed delivery
+    fn name(&self) -> &str;
+
+    // monitor is the monitor that raised the notification, when known
+    fn send(&self, title: &str, message: &str, monitor: Option<&str>, score: u8, severity: Severity) -> Result<(), String>;
+}
+
+// Payload shape for the --webhook-url endpoint: ThreatSentry's own JSON, or the message
+// format of a chat service so its incoming webhook accepts the alert as is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum WebhookFormat {
+    Generic,
+    Slack,
+    Discord,
+    Teams,
+}
+
+// Slack incoming webhook; the alert is a message attachment with a band-colored bar
+pub struct SlackWebhook {
+    url: String,
+    timeout: Duration,
+    retries: u32,
+}
+
+impl SlackWebhook {
+    pub fn new(url: &str, timeout: Duration, retries: u32) -> Self {
+        SlackWebhook {
+            url: url.to_string(),
+            timeout,
+            retries,
+        }
+    }
+}
+
+impl NotificationChannel for SlackWebhook {
+    fn name(&self) -> &str {
+        "Slack"
+    }
+
+    fn send(&self, title: &str, message: &str, monitor: Option<&str>, score: u8, severity: Severity) -> Result<(), String> {
+        post_json(&self.url, &slack_payload(title, message, monitor, score, severity), self.timeout, self.retries)
+    }
+}
+
+pub fn slack_payload(title: &str, message: &str, monitor: Option<&str>, score: u8, severity: Severity) -> Value {
+    json!({
+        "attachments": [{
+            "color": SLACK_COLORS[alert_index(severity)],
+            "title": title,
+            "text": message,
+            "fields": [
+                { "title": "Monitor", "value": monitor.unwrap_or("unknown"), "short": true },
+                { "title": "Score", "value": format!("{}/100", score), "short": true },
+                { "title": "Host", "value": hostname(), "short": true },
+            ],
+            "footer": "ThreatSentry Ultra",
+            "ts": chrono::Utc::now().timestamp(),
+        }],
+    })
+}
+
+// Discord webhook; the alert is an embed with a band-colored border
+pub struct DiscordWebhook {
+    url: String,
+    timeout: Duration,
+    retries: u32,
+}
+
+impl DiscordWebhook {
+    pub fn new(url: &str, timeout: Duration, retries: u32) -> Self {
+        DiscordWebhook {
+            url: url.to_string(),
+            timeout,
+            retries,
+        }
+    }
+}
+
+impl NotificationChannel for DiscordWebhook {
+    fn name(&self) -> &str {
+        "Discord"
+    }
+
+    fn send(&self, title: &str, message: &str, monitor: Option<&str>, score: u8, severity: Severity) -> Result<(), String> {
+        post_json(&self.url, &discord_payload(title, message, monitor, score, severity), self.timeout, self.retries)
+    }
+}
+
+pub fn discord_payload(title: &str, message: &str, monitor: Option<&str>, score: u8, severity: Severity) -> Value {
+    json!({
+        "embeds": [{
+            "title": title,
+            "description": message,
+            "color": DISCORD_COLORS[alert_index(severity)],
+            "fields": [
+                { "name": "Monitor", "value": monitor.unwrap_or("unknown"), "inline": true },
+                { "name": "Score", "value": format!("{}/100", score), "inline": true },
+                { "name": "Host", "value": hostname(), "inline": true },
+            ],
+            "footer": { "text": "ThreatSentry Ultra" },
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }],
+    })
+}
+
+// Microsoft Teams incoming webhook (connector MessageCard) with a band-colored accent
+pub fn teams_payload(title: &str, message: &str, monitor: Option<&str>, score: u8, severity: Severity) -> Value {
+    json!({
+        "@type": "MessageCard",
+        "@context": "https://schema.org/extensions",
+        "themeColor": SLACK_COLORS[alert_index(severity)].trim_start_matches('#'),
+        "summary": title,
+        "title": title,
+        "text": message,
+        "sections": [{
+            "facts": [
+                { "name": "Monitor", "value": monitor.unwrap_or("unknown") },
+                { "name": "Score", "value": format!("{}/100", score) },
+                { "name": "Host", "value": hostname() },
+            ],
+        }],
+    })
+}
+
+// Transport to the syslog server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SyslogProtocol {
+    Udp,
+    Tcp,
+}
+
+// Syslog facilities a monitoring tool would reasonably log under
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SyslogFacility {
+    User,
+    Daemon,
+    Auth,
+    Local0,
+    Local1,
+    Local2,
+    Local3,
+    Local4,
+    Local5,
+    Local6,
+    Local7,
+}
+
+impl SyslogFacility {
+    fn facility(&self) -> Facility {
+        match self {
+            SyslogFacility::User => Facility::LOG_USER,
+            SyslogFacility::Daemon => Facility::LOG_DAEMON,
+            SyslogFacility::Auth => Facility::LOG_AUTH,
+            SyslogFacility::Local0 => Facility::LOG_LOCAL0,
+            SyslogFacility::Local1 => Facility::LOG_LOCAL1,
+            SyslogFacility::Local2 => Facility::LOG_LOCAL2,
+            SyslogFacility::Local3 => Facility::LOG_LOCAL3,
+            SyslogFacility::Local4 => Facility::LOG_LOCAL4,
+            SyslogFacility::Local5 => Facility::LOG_LOCAL5,
+            SyslogFacility::Local6 => Facility::LOG_LOCAL6,
+            SyslogFacility::Local7 => Facility::LOG_LOCAL7,
+        }
+    }
+}
+
+// RFC 5424 syslog. Alerts are logged as error (high) or critical, and a
+// structured-data element carries the monitor and numeric score for SIEM filtering
+pub struct SyslogChannel {
+    server: String,
+    protocol: SyslogProtocol,
+    facility: SyslogFacility,
+    logger: Mutex<Option<Logger<LoggerBackend, Formatter5424>>>, // Connected on first use
+}
+
+impl SyslogChannel {
+    // server is "host", "host:port", an IP address, or "[ipv6]:port" (default port 514). A bare
+    // IPv6 address like fe80::1 is taken as an address, not as a host and port
+    pub fn new(server: &str, protocol: SyslogProtocol, facility: SyslogFacility) -> Self {
+        let server = if server.parse::<SocketAddr>().is_ok() {
+            server.to_string()
+        } else if let Ok(ip) = server.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+            SocketAddr::new(ip, DEFAULT_SYSLOG_PORT).to_string()
+        } else if server.contains(':') {
+            server.to_string()
+        } else {
+            format!("{}:{}", server, DEFAULT_SYSLOG_PORT)
+        };
+
+        SyslogChannel {
+            server,
+            protocol,
+            facility,
+            logger: Mutex::new(None),
+        }
+    }
+
+    fn connect(&self) -> Result<Logger<LoggerBackend, Formatter5424>, String> {
+        let formatter = Formatter5424 {
+            facility: self.facility.facility(),
+            hostname: Some(hostname()).filter(|host| !host.is_empty()),
+            process: SYSLOG_APP_NAME.to_string(),
+            pid: std::process::id(),
+        };
+
+        // UDP sends from an unbound local socket of the server's address family
+        let local = match self.server.to_socket_addrs().ok().and_then(|mut addrs| addrs.next()) {
+            Some(SocketAddr::V6(_)) => "[::]:0",
+            _ => "0.0.0.0:0",
+        };
+        match self.protocol {
+            SyslogProtocol::Udp => syslog::udp(formatter, local, self.server.as_str()),
+            SyslogProtocol::Tcp => syslog::tcp(formatter, self.server.as_str()),
+        }.map_err(|e| format!("Failed to connect to syslog server {}: {}", self.server, e))
+    }
+}
+
+impl NotificationChannel for SyslogChannel {
+    fn name(&self) -> &str {
+        "syslog"
+    }
+
+    fn send(&self, title: &str, message: &str, monitor: Option<&str>, score: u8, severity: Severity) -> Result<(), String> {
+        let mut logger = self.logger.lock().unwrap();
+        if logger.is_none() {
+            *logger = Some(self.connect()?);
+        }
+
+        let mut params = HashMap::new();
+        params.insert("monitor".to_string(), monitor.unwrap_or("unknown").to_string());
+        params.insert("score".to_string(), score.to_string());
+        let mut structured_data = HashMap::new();
+        structured_data.insert(SYSLOG_SD_ID.to_string(), params);
+
+        // Syslog messages are single lines
+        let text = format!("{}: {}", title, message.replace('\n', " | "));
+        let entry = (1, structured_data, text);
+
+        let result = match severity {
+            Severity::Critical => logger.as_mut().unwrap().crit(entry),
+            _ => logger.as_mut().unwrap().err(entry),
+        };
+
+        // Reconnect on the next notification, e.g. after the server restarted
+        result.map_err(|e| {
+            *logger = None;
+            format!("Failed to write to syslog server {}: {}", self.server, e)
+        })
+    }
+}
+
+// POST a JSON body, retrying failed requests (network errors and non-2xx responses)
+// with exponential backoff. Chat webhook URLs are secrets, so errors only name the host
+pub fn post_json(url: &str, payload: &Value, timeout: Duration, retries: u32) -> Result<(), String> {
+    let http = reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        let result = http.post(url)
+            .json(payload)
+            .send()
+            .map_err(|e| e.without_url().to_string())
+            .and_then(|response| match response.status() {
+                status if status.is_success() => Ok(()),
+                status => Err(format!("HTTP {}", status)),
+            });
+
+        match result {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < retries => {
+                warn!("POST to {} failed ({}); retrying in {}s", redact_url(url), e, backoff.as_secs());
+                thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            },
+            Err(e) => return Err(format!("POST to {} failed after {} attempt(s): {}", redact_url(url), attempt + 1, e)),
+        }
+    }
+}
+
+// scheme://host of a URL, for logs: the path of a Slack or Discord webhook is its token
+fn redact_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => format!("{}://{}", parsed.scheme(), parsed.host_str().unwrap_or("")),
+        Err(_) => "<invalid URL>".to_string(),
+    }
+}
+
+pub fn hostname() -> String {
+    System::host_name().unwrap_or_default()
+}
+
+// Index into the alert colors; lower bands never leave the machine
+fn alert_index(severity: Severity) -> usize {
+    match severity {
+        Severity::Critical => 1,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chat_payloads_carry_the_band_color() {
+        let slack = slack_payload("ThreatSentry Ultra", "Suspicious process", Some("kernel"), 90, Severity::Critical);
+        assert_eq!(slack["attachments"][0]["color"], "#E01E5A");
+
+        let discord = discord_payload("ThreatSentry Ultra", "Suspicious process", Some("kernel"), 90, Severity::Critical);
+        assert_eq!(discord["embeds"][0]["color"], 0xE74C3C);
+
+        let teams = teams_payload("ThreatSentry Ultra", "Suspicious process", Some("kernel"), 60, Severity::High);
+        assert_eq!(teams["@type"], "MessageCard");
+        assert_eq!(teams["themeColor"], "E8912D");
+        assert_eq!(teams["sections"][0]["facts"][1]["value"], "60/100");
+    }
+}
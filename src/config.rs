@@ -0,0 +1,428 @@
+// Settings file (TOML), so thresholds, bands and credentials don't have to be passed on every
+// command line, where passwords end up in shell history. Every key is optional; command-line
+// flags and environment variables override the file
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::baseline::{MetricBaseline, ThermalBaseline};
+use crate::scoring::MonitorWeights;
+use crate::{baseline, daemon, email_monitor, kernel_monitor, mic_monitor, notification, smtp_alert, thermal_monitor};
+
+// Written by `config init` when no path is given
+pub const DEFAULT_CONFIG_FILE: &str = "threatsentry.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub scoring: ScoringConfig,
+    pub email: EmailConfig,
+    pub mic: MicConfig,
+    pub thermal: ThermalConfig,
+    pub kernel: KernelConfig,
+    pub notifications: NotificationConfig,
+    pub daemon: DaemonConfig,
+    pub history: HistoryConfig,
+    pub geoip: GeoIpConfig,
+    pub baseline: BaselineConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoringConfig {
+    pub min_score: Option<u8>,
+    pub low_max: Option<u8>,
+    pub medium_max: Option<u8>,
+    pub high_max: Option<u8>,
+    pub weights: WeightsConfig,
+}
+
+// Relative weight of each monitor in the combined score
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeightsConfig {
+    pub mic: Option<f32>,
+    pub thermal: Option<f32>,
+    pub kernel: Option<f32>,
+    pub email: Option<f32>,
+}
+
+impl WeightsConfig {
+    // Monitors without a weight in the file keep the default of 1
+    pub fn weights(&self) -> MonitorWeights {
+        let defaults = MonitorWeights::default();
+        MonitorWeights {
+            mic: self.mic.unwrap_or(defaults.mic),
+            thermal: self.thermal.unwrap_or(defaults.thermal),
+            kernel: self.kernel.unwrap_or(defaults.kernel),
+            email: self.email.unwrap_or(defaults.email),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailConfig {
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub oauth_token: Option<String>,
+    pub imap_server: Option<String>,
+    pub imap_port: Option<u16>,
+    pub imap_security: Option<String>,
+    pub blocklist: Option<PathBuf>,
+    pub allowlist: Option<PathBuf>,
+    pub brand_domains: Option<Vec<String>>,
+    pub phishtank_key: Option<String>,
+    pub body_phrases: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MicConfig {
+    pub min_freq: Option<f32>,
+    pub max_freq: Option<f32>,
+    pub threshold: Option<f32>,
+    pub device: Option<String>,
+    pub window: Option<String>,
+    pub hop_size: Option<u64>,
+    pub detection_window: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThermalConfig {
+    pub spike_delta: Option<f32>,
+    pub spike_window: Option<u64>,
+    pub spike_cooldown: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KernelConfig {
+    pub signatures: Option<PathBuf>,
+    pub process_allowlist: Option<PathBuf>,
+    pub spawn_threshold: Option<f32>,
+    pub usb_alert_classes: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationConfig {
+    pub mute: Option<bool>,
+    pub cooldown: Option<u64>,
+    pub webhook_url: Option<String>,
+    pub webhook_format: Option<String>,
+    pub webhook_timeout: Option<u64>,
+    pub webhook_retries: Option<u32>,
+    pub slack_webhook: Option<String>,
+    pub discord_webhook: Option<String>,
+    pub syslog_server: Option<String>,
+    pub syslog_protocol: Option<String>,
+    pub syslog_facility: Option<String>,
+    pub alert_email: Option<String>,
+    pub smtp_server: Option<String>,
+    pub smtp_port: Option<u16>,
+    pub smtp_security: Option<String>,
+    pub smtp_user: Option<String>,
+    pub smtp_pass: Option<String>,
+    pub smtp_from: Option<String>,
+    pub alert_email_min_score: Option<u8>,
+    pub alert_email_window: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DaemonConfig {
+    pub window: Option<u64>,
+    pub percentile: Option<u8>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    pub db: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GeoIpConfig {
+    pub db: Option<PathBuf>,
+    pub resolve_hosts: Option<bool>,
+}
+
+// Learned by `baseline`; every value except sigma is written by it
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BaselineConfig {
+    pub sigma: Option<f32>,
+    pub temp_mean: Option<f32>,
+    pub temp_std: Option<f32>,
+    pub cpu_mean: Option<f32>,
+    pub cpu_std: Option<f32>,
+    pub mic_mean: Option<f32>,
+    pub mic_std: Option<f32>,
+}
+
+impl BaselineConfig {
+    pub fn sigma(&self) -> f32 {
+        self.sigma.unwrap_or(baseline::DEFAULT_SIGMA)
+    }
+
+    // Thermal scoring is only made relative once both temperature and CPU load were learned
+    pub fn thermal(&self) -> Option<ThermalBaseline> {
+        Some(ThermalBaseline {
+            temperature: Self::metric(self.temp_mean, self.temp_std)?,
+            cpu: Self::metric(self.cpu_mean, self.cpu_std)?,
+            sigma: self.sigma(),
+        })
+    }
+
+    // Microphone detection threshold from the learned band power, mean + sigma deviations
+    pub fn mic_threshold(&self) -> Option<f32> {
+        let mic = Self::metric(self.mic_mean, self.mic_std)?;
+        Some(mic.upper_bound(self.sigma()).clamp(mic_monitor::MIN_CALIBRATED_THRESHOLD, 1.0))
+    }
+
+    fn metric(mean: Option<f32>, std_dev: Option<f32>) -> Option<MetricBaseline> {
+        Some(MetricBaseline { mean: mean?, std_dev: std_dev? })
+    }
+}
+
+impl Config {
+    // Relative paths in the file are taken relative to the file, not the working directory
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut config: Config = toml::from_str(&text)
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        for file in [
+            &mut config.email.blocklist,
+            &mut config.email.allowlist,
+            &mut config.email.body_phrases,
+            &mut config.kernel.signatures,
+            &mut config.kernel.process_allowlist,
+            &mut config.history.db,
+            &mut config.geoip.db,
+        ].into_iter().flatten() {
+            if file.is_relative() {
+                *file = base.join(&*file);
+            }
+        }
+
+        config.validate().map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+        Ok(config)
+    }
+
+    // Write settings changed at runtime (the GUI's Settings panel) back into the file, keeping
+    // its comments and layout. Keys are dotted paths like "mic.threshold"; missing tables are
+    // added. Values are rounded to 3 decimals so f32 sliders don't write 0.20000000298
+    pub fn save_values(path: &Path, values: &[(&str, f64)]) -> Result<(), String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut document: toml_edit::DocumentMut = text.parse()
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+
+        for (key, value) in values {
+            let mut tables: Vec<&str> = key.split('.').collect();
+            let name = tables.pop().unwrap_or(key);
+            let mut table = document.as_table_mut();
+            for part in tables {
+                table = table.entry(part)
+                    .or_insert_with(toml_edit::table)
+                    .as_table_mut()
+                    .ok_or_else(|| format!("{} in {} is not a table", part, path.display()))?;
+            }
+            table[name] = toml_edit::value((value * 1000.0).round() / 1000.0);
+        }
+
+        fs::write(path, document.to_string())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    // The same range checks the command line applies to these values
+    fn validate(&self) -> Result<(), String> {
+        let weights = &self.scoring.weights;
+        for (name, weight) in [("mic", weights.mic), ("thermal", weights.thermal), ("kernel", weights.kernel), ("email", weights.email)] {
+            if let Some(weight) = weight {
+                if !weight.is_finite() || weight < 0.0 {
+                    return Err(format!("scoring.weights.{} {} must be 0 or more", name, weight));
+                }
+            }
+        }
+        if let Some(threshold) = self.mic.threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                return Err(format!("mic.threshold {} is outside 0.0-1.0", threshold));
+            }
+        }
+        if let Some(hop_size) = self.mic.hop_size {
+            if !(1..=mic_monitor::FFT_BUFFER_SIZE as u64).contains(&hop_size) {
+                return Err(format!("mic.hop_size {} is outside 1-{}", hop_size, mic_monitor::FFT_BUFFER_SIZE));
+            }
+        }
+        if self.mic.detection_window == Some(0) {
+            return Err("mic.detection_window must be at least 1".to_string());
+        }
+        if self.thermal.spike_window == Some(0) {
+            return Err("thermal.spike_window must be at least 1".to_string());
+        }
+        if self.thermal.spike_cooldown == Some(0) {
+            return Err("thermal.spike_cooldown must be at least 1".to_string());
+        }
+        if let Some(rate) = self.kernel.spawn_threshold {
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err(format!("kernel.spawn_threshold {} must be positive", rate));
+            }
+        }
+        if let Some(window) = self.daemon.window {
+            if !(1..=daemon::MAX_WINDOW_SECS).contains(&window) {
+                return Err(format!("daemon.window {} is outside 1-{}", window, daemon::MAX_WINDOW_SECS));
+            }
+        }
+        if let Some(percentile) = self.daemon.percentile {
+            if !(1..=100).contains(&percentile) {
+                return Err(format!("daemon.percentile {} is outside 1-100", percentile));
+            }
+        }
+        if self.notifications.webhook_timeout == Some(0) {
+            return Err("notifications.webhook_timeout must be at least 1".to_string());
+        }
+        let learned = &self.baseline;
+        for (name, value) in [("sigma", learned.sigma), ("temp_std", learned.temp_std), ("cpu_std", learned.cpu_std), ("mic_std", learned.mic_std)] {
+            if let Some(value) = value {
+                if !value.is_finite() || value <= 0.0 {
+                    return Err(format!("baseline.{} {} must be positive", name, value));
+                }
+            }
+        }
+        for (name, value) in [("temp_mean", learned.temp_mean), ("cpu_mean", learned.cpu_mean), ("mic_mean", learned.mic_mean)] {
+            if let Some(value) = value {
+                if !value.is_finite() {
+                    return Err(format!("baseline.{} {} must be a number", name, value));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Commented-out template listing every key with its default, written by `config init`
+pub fn default_config() -> String {
+    format!(r#"# ThreatSentry configuration. Every key is optional; uncomment the ones you need.
+# Command-line flags and environment variables override values set here.
+# Relative paths are relative to this file. Keep it private (chmod 600) if it holds passwords.
+
+[scoring]
+# Only print and notify findings scored at or above this value
+# min_score = 0
+# Highest score still considered low, medium and high severity; anything above is critical.
+# Scores above medium_max raise notifications
+# low_max = 30
+# medium_max = 50
+# high_max = 70
+
+# Relative weight of each monitor in the combined score (0 leaves a monitor out)
+[scoring.weights]
+# mic = 1.0
+# thermal = 1.0
+# kernel = 1.0
+# email = 1.0
+
+[email]
+# username = "you@gmail.com"
+# password = "app password"
+# oauth_token = "OAuth2 access token, used instead of the password"
+# imap_server = "imap.gmail.com"
+# imap_port = {imap_port}
+# imap_security = "implicit-tls"      # implicit-tls, start-tls or plaintext
+# blocklist = "blocklist.txt"
+# allowlist = "allowlist.txt"
+# brand_domains = ["example-bank.com"]
+# phishtank_key = "PhishTank application key"
+# body_phrases = "phrases.txt"
+
+[mic]
+# min_freq = {min_freq:.1}
+# max_freq = {max_freq:.1}
+# threshold = {threshold}
+# device = "default"
+# window = "hann"                     # hann, hamming, blackman, flat-top or rectangular
+# hop_size = {hop_size}
+# detection_window = {detection_window}
+
+[thermal]
+# spike_delta = {spike_delta:.1}
+# spike_window = {spike_window}
+# spike_cooldown = {spike_cooldown}
+
+[kernel]
+# signatures = "signatures.txt"
+# process_allowlist = "process-allowlist.txt"
+# spawn_threshold = {spawn_threshold:.1}
+# usb_alert_classes = ["mass-storage", "hid"]   # hid, mass-storage, network, audio, imaging, phone, printer, other
+
+[notifications]
+# mute = false
+# cooldown = {cooldown}
+# webhook_url = "https://soc.example.com/intake"
+# webhook_format = "generic"          # generic, slack, discord or teams
+# webhook_timeout = {webhook_timeout}
+# webhook_retries = {webhook_retries}
+# slack_webhook = "https://hooks.slack.com/services/..."
+# discord_webhook = "https://discord.com/api/webhooks/..."
+# syslog_server = "siem.example.com:514"
+# syslog_protocol = "udp"             # udp or tcp
+# syslog_facility = "user"            # user, daemon, auth or local0-local7
+# alert_email = "soc@example.com"
+# smtp_server = "smtp.example.com"
+# smtp_port = 465
+# smtp_security = "implicit-tls"      # implicit-tls, start-tls or plaintext
+# smtp_user = "alerts@example.com"
+# smtp_pass = "password"
+# smtp_from = "alerts@example.com"
+# alert_email_min_score = {alert_email_min_score}
+# alert_email_window = {alert_email_window}
+
+[daemon]
+# Alerts and the exit code follow this percentile of the combined scores of the last window seconds
+# window = {daemon_window}
+# percentile = {daemon_percentile}
+
+[history]
+# Record every run's scores and findings in this SQLite database, for `history`
+# db = "history.db"
+
+[geoip]
+# MaxMind-format city database (GeoLite2-City or DB-IP City Lite) for the GUI's threat map
+# db = "GeoLite2-City.mmdb"
+# Resolve phishing URL host names through DNS to place them on the map. Each lookup of an
+# attacker's domain is sent from this machine, so it's off and only IP-literal URLs are placed
+# resolve_hosts = false
+
+[baseline]
+# This machine's normal, written by `baseline`. Thermal readings more than sigma standard
+# deviations above the learned mean are flagged, and the microphone threshold becomes
+# mic_mean + sigma * mic_std unless [mic] sets threshold
+# sigma = {sigma:.1}
+"#,
+        imap_port = email_monitor::DEFAULT_IMAP_PORT,
+        min_freq = mic_monitor::DEFAULT_MIN_FREQ_HZ,
+        max_freq = mic_monitor::DEFAULT_MAX_FREQ_HZ,
+        threshold = mic_monitor::DEFAULT_THRESHOLD,
+        hop_size = mic_monitor::DEFAULT_HOP_SIZE,
+        detection_window = mic_monitor::DEFAULT_DETECTION_WINDOW.as_secs(),
+        spike_delta = thermal_monitor::DEFAULT_SPIKE_DELTA_C,
+        spike_window = thermal_monitor::DEFAULT_SPIKE_WINDOW.as_secs(),
+        daemon_window = daemon::DEFAULT_WINDOW_SECS,
+        daemon_percentile = daemon::DEFAULT_PERCENTILE,
+        spike_cooldown = thermal_monitor::DEFAULT_SPIKE_COOLDOWN.as_secs(),
+        spawn_threshold = kernel_monitor::DEFAULT_SPAWN_RATE_THRESHOLD,
+        cooldown = notification::DEFAULT_COOLDOWN_SECS,
+        webhook_timeout = notification::DEFAULT_WEBHOOK_TIMEOUT_SECS,
+        webhook_retries = notification::DEFAULT_WEBHOOK_RETRIES,
+        alert_email_min_score = smtp_alert::DEFAULT_MIN_SCORE,
+        alert_email_window = smtp_alert::DEFAULT_COALESCE_WINDOW_SECS,
+        sigma = baseline::DEFAULT_SIGMA,
+    )
+}
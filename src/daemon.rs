@@ -0,0 +1,72 @@
+// Stop and reload requests for the daemon. SIGINT and SIGTERM ask it to stop (a second one exits
+// at once), SIGHUP to reload the config file. The handlers only set flags, which the monitoring
+// loop polls between samples so every monitor is stopped cleanly
+use signal_hook::consts::{SIGINT, SIGTERM};
+#[cfg(unix)]
+use signal_hook::consts::SIGHUP;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often a wait checks whether it should end early
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Default time between checks for new mail
+pub const DEFAULT_EMAIL_INTERVAL_SECS: u64 = 300;
+
+// How often the daemon logs its scores and records them in the scan history
+pub const STATUS_INTERVAL: Duration = Duration::from_secs(60);
+
+// Default observation window: alerts and the exit code follow the 90th percentile of the
+// combined score over the last 60 seconds, so one noisy sample doesn't decide on its own
+pub const DEFAULT_WINDOW_SECS: u64 = 60;
+pub const DEFAULT_PERCENTILE: u8 = 90;
+
+// Longest window: one combined score is recorded a second and the score history keeps an hour
+pub const MAX_WINDOW_SECS: u64 = 3600;
+
+#[derive(Clone, Default)]
+pub struct ShutdownSignal {
+    stop: Arc<AtomicBool>,
+    reload: Arc<AtomicBool>,
+}
+
+impl ShutdownSignal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Install the signal handlers. SIGHUP only exists on Unix; elsewhere config changes need a restart
+    pub fn register(&self) -> Result<(), String> {
+        for signal in [SIGINT, SIGTERM] {
+            // Registered first, so it only fires when the stop flag is already set
+            signal_hook::flag::register_conditional_shutdown(signal, 1, self.stop.clone())
+                .and_then(|_| signal_hook::flag::register(signal, self.stop.clone()))
+                .map_err(|e| format!("Failed to install handler for signal {}: {}", signal, e))?;
+        }
+
+        #[cfg(unix)]
+        signal_hook::flag::register(SIGHUP, self.reload.clone())
+            .map_err(|e| format!("Failed to install handler for SIGHUP: {}", e))?;
+
+        Ok(())
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+
+    // Whether a reload was requested since the last call
+    pub fn take_reload(&self) -> bool {
+        self.reload.swap(false, Ordering::SeqCst)
+    }
+
+    // Sleep for up to duration, returning early once a stop or reload is requested
+    pub fn wait(&self, duration: Duration) {
+        let start = Instant::now();
+        while start.elapsed() < duration && !self.is_stopping() && !self.reload.load(Ordering::SeqCst) {
+            thread::sleep(POLL_INTERVAL.min(duration.saturating_sub(start.elapsed())));
+        }
+    }
+}
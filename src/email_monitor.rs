@@ -1,30 +1,330 @@
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use imap::Session;
+use mailparse::{MailHeaderMap, ParsedMail};
+use tracing::{debug, error, info, warn};
+
+use crate::allowlist::Allowlist;
+use crate::blocklist::Blocklist;
+use crate::homograph::{self, HomographFinding};
+use crate::monitor::{Finding, FindingKind, Monitor, MonitorSample};
+use crate::phishtank::PhishTankClient;
+
+// When polled as a Monitor, mail is checked at most this often unless set otherwise
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+const POLL_FETCH_LIMIT: usize = 5;
+
+// Characters of surrounding email text kept on each side of a URL
+const SNIPPET_CONTEXT_CHARS: usize = 60;
+
+// Minimum score for a link whose visible text names a different domain than its target
+const LINK_MISMATCH_SCORE: u8 = 90;
+
+// Added to the score of a URL on a punycode domain imitating a brand or mixing scripts
+const HOMOGRAPH_SCORE_BOOST: u8 = 50;
+
+// Scores for sender headers that don't add up
+const BRAND_DISPLAY_NAME_SCORE: u8 = 80;
+const REPLY_TO_MISMATCH_SCORE: u8 = 60;
+
+// Urgency and credential-harvesting phrases typical of text-only scams, with their weight
+const DEFAULT_BODY_PHRASES: [(&str, u8); 14] = [
+    ("verify your account", 40),
+    ("confirm your password", 45),
+    ("login credentials", 35),
+    ("update your payment", 35),
+    ("wire transfer", 40),
+    ("gift card", 40),
+    ("click here immediately", 35),
+    ("account is suspended", 40),
+    ("suspended", 25),
+    ("within 24 hours", 25),
+    ("unusual activity", 25),
+    ("reset your password", 20),
+    ("act now", 15),
+    ("urgent", 15),
+];
+
+// Weight for a phrase listed in a phrase file without one
+const DEFAULT_PHRASE_WEIGHT: u8 = 30;
+
+// Extra points for each matched phrase beyond the heaviest one
+const PHRASE_HIT_BONUS: u8 = 10;
+
+// Brand domains protected against typosquatting unless more are configured
+const DEFAULT_BRAND_DOMAINS: [&str; 5] = ["paypal.com", "microsoft.com", "apple.com", "amazon.com", "google.com"];
+
+// Scores for a domain one or two edits away from a protected brand
+const TYPOSQUAT_DISTANCE_1_SCORE: u8 = 85;
+const TYPOSQUAT_DISTANCE_2_SCORE: u8 = 65;
+
+// Brand names shorter than this only match at distance 1, since two edits turn
+// short names into unrelated words ("apple" -> "ample", "maple")
+const TYPOSQUAT_DISTANCE_2_MIN_LEN: usize = 6;
+
+// IMAPS (implicit TLS) port used unless another one is configured
+pub const DEFAULT_IMAP_PORT: u16 = 993;
+
+// A scored URL along with the text around it, to judge whether it sat in a
+// legitimate footer or a fake "verify your account" pitch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanResult {
+    pub url: String,
+    pub score: u8,
+    pub snippet: String,
+    // Raw PhishTank database entry for the URL, when it had one
+    pub phishtank_match: Option<String>,
+}
+
+// Transport security of the IMAP connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ConnectionSecurity {
+    // TLS from the first byte, usually on port 993
+    #[default]
+    ImplicitTls,
+    // Plain connection upgraded with STARTTLS, usually on port 143
+    StartTls,
+    // No encryption at all; credentials and mail cross the network in the clear
+    Plaintext,
+}
+
+// A fetched message: its UID, the headers shown in a mail client, and the decoded text
+#[derive(Debug, Clone, Default)]
+pub struct EmailMessage {
+    pub uid: u32,
+    pub from: String,
+    pub subject: String,
+    pub date: String,
+    pub body: String,
+}
+
+// Sender headers of a fetched message, kept for spoofing checks
+#[derive(Debug, Clone, Default)]
+struct SenderHeaders {
+    from: Option<String>,
+    reply_to: Option<String>,
+    return_path: Option<String>,
+}
+
+// A message whose sender headers look spoofed
+#[derive(Debug, Clone)]
+pub struct HeaderFinding {
+    pub from: String,
+    pub return_path: Option<String>,
+    pub reason: String,
+    pub score: u8,
+}
+
+// How the monitor authenticates to the IMAP server
+enum ImapCredential {
+    Password(String),
+    OAuth2AccessToken(String),
+}
+
+// SASL XOAUTH2 initial response; the imap crate base64-encodes it on the wire
+struct XOAuth2 {
+    user: String,
+    access_token: String,
+}
+
+impl imap::Authenticator for XOAuth2 {
+    type Response = String;
+
+    fn process(&self, challenge: &[u8]) -> Self::Response {
+        // A non-empty challenge carries the server's error details; an empty reply
+        // lets it finish the exchange with a NO instead of waiting for more input
+        if !challenge.is_empty() {
+            return String::new();
+        }
+        format!("user={}\x01auth=Bearer {}\x01\x01", self.user, self.access_token)
+    }
+}
 
 pub struct EmailMonitor {
     username: String,
-    password: String,
+    credential: ImapCredential,
     imap_server: String,
+    imap_port: u16,
+    security: ConnectionSecurity,
+    cursor_path: PathBuf,
+    blocklist: Option<Blocklist>,
+    allowlist: Option<Allowlist>,
+    brand_domains: Vec<String>, // Protected against typosquatting
+    body_phrases: Vec<(String, u8)>, // Lowercased phrase and weight for score_body_text
+    phishtank: Option<PhishTankClient>,
+    last_headers: Mutex<Vec<SenderHeaders>>, // Sender headers of the most recent fetch
+    poll_interval: Duration,
+    incremental_polls: bool, // Whether poll only fetches mail that arrived since the previous check
+    last_poll: Option<Instant>,
+    poll_score: Option<u8>, // Highest score of the mail fetched by the latest check
+    reported: HashSet<(FindingKind, String)>, // Findings poll already returned
 }
 
 impl EmailMonitor {
     pub fn new(username: String, password: String, imap_server: String) -> Self {
+        Self::with_credential(username, ImapCredential::Password(password), imap_server)
+    }
+
+    // Authenticate with an OAuth2 bearer token (AUTHENTICATE XOAUTH2) instead of a password,
+    // for accounts where the provider no longer accepts app passwords
+    pub fn with_oauth2(username: String, access_token: String, imap_server: String) -> Self {
+        Self::with_credential(username, ImapCredential::OAuth2AccessToken(access_token), imap_server)
+    }
+
+    fn with_credential(username: String, credential: ImapCredential, imap_server: String) -> Self {
+        let cursor_path = Self::default_cursor_path(&username, &imap_server);
         EmailMonitor {
             username,
-            password,
+            credential,
             imap_server,
+            imap_port: DEFAULT_IMAP_PORT,
+            security: ConnectionSecurity::ImplicitTls,
+            cursor_path,
+            blocklist: None,
+            allowlist: None,
+            brand_domains: DEFAULT_BRAND_DOMAINS.iter().map(|domain| domain.to_string()).collect(),
+            body_phrases: DEFAULT_BODY_PHRASES.iter()
+                .map(|(phrase, weight)| (phrase.to_string(), *weight))
+                .collect(),
+            phishtank: None,
+            last_headers: Mutex::new(Vec::new()),
+            poll_interval: POLL_INTERVAL,
+            incremental_polls: false,
+            last_poll: None,
+            poll_score: None,
+            reported: HashSet::new(),
+        }
+    }
+
+    // Use STARTTLS or no encryption instead of implicit TLS
+    pub fn with_security(mut self, security: ConnectionSecurity) -> Self {
+        self.security = security;
+        self
+    }
+
+    // Trusted domains score 0 unless the blocklist also lists them
+    pub fn with_allowlist(mut self, allowlist: Allowlist) -> Self {
+        self.allowlist = Some(allowlist);
+        self
+    }
+
+    // Protect more brand domains against typosquatting, on top of the defaults
+    pub fn with_brand_domains(mut self, domains: Vec<String>) -> Self {
+        for domain in domains {
+            let domain = domain.trim().to_lowercase();
+            if !domain.is_empty() && !self.brand_domains.contains(&domain) {
+                self.brand_domains.push(domain);
+            }
+        }
+        self
+    }
+
+    // Keep the incremental-scan cursor in this file instead of the per-account default
+    pub fn with_state_file(mut self, path: PathBuf) -> Self {
+        self.cursor_path = path;
+        self
+    }
+
+    // When polled as a Monitor, check every interval for mail that arrived since the previous
+    // check, moving the cursor, so each check is scored on its own new mail. Meant for monitors
+    // that run indefinitely; by default each poll re-reads the newest messages instead
+    pub fn with_incremental_polls(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self.incremental_polls = true;
+        self
+    }
+
+    // Connect on a port other than the standard IMAPS one, e.g. for a self-hosted server
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.imap_port = port;
+        self
+    }
+
+    // Check scanned URLs against a local blocklist before the heuristics
+    pub fn with_blocklist(mut self, blocklist: Blocklist) -> Self {
+        self.blocklist = Some(blocklist);
+        self
+    }
+
+    // Score URLs by PhishTank verdict instead of the keyword heuristic; the heuristic is
+    // still used for any URL whose lookup fails
+    pub fn with_phishtank(mut self, api_key: String) -> Self {
+        match PhishTankClient::new(api_key) {
+            Ok(client) => self.phishtank = Some(client),
+            Err(e) => warn!("PhishTank lookups disabled: {}", e),
+        }
+        self
+    }
+
+    // Per-account file holding the highest UID already scanned
+    fn default_cursor_path(username: &str, imap_server: &str) -> PathBuf {
+        let base = std::env::var_os("APPDATA")
+            .or_else(|| std::env::var_os("HOME"))
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        let account: String = format!("{}@{}", username, imap_server)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '@' { c } else { '_' })
+            .collect();
+
+        base.join(".threatsentry").join("cursors").join(format!("{}.uid", account))
+    }
+
+    fn load_cursor(&self) -> Option<u32> {
+        fs::read_to_string(&self.cursor_path)
+            .ok()
+            .and_then(|contents| contents.trim().parse().ok())
+    }
+
+    fn save_cursor(&self, uid: u32) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.cursor_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.cursor_path, uid.to_string())?;
+        Ok(())
+    }
+
+    // Forget the last-seen UID so the next incremental fetch starts from scratch
+    pub fn reset_cursor(&self) {
+        if fs::remove_file(&self.cursor_path).is_ok() {
+            info!("Reset email cursor for {}", self.username);
         }
     }
 
     fn connect_to_imap(&self) -> Result<Session<imap::Connection>, Box<dyn Error>> {
-        println!("Connecting to IMAP server: {}", self.imap_server);
+        info!("Connecting to IMAP server: {}:{}", self.imap_server, self.imap_port);
 
         // Connect to the server
-        let client = imap::ClientBuilder::new(&self.imap_server, 993).connect()?;
+        let mode = match self.security {
+            ConnectionSecurity::ImplicitTls => imap::ConnectionMode::Tls,
+            ConnectionSecurity::StartTls => imap::ConnectionMode::StartTls,
+            ConnectionSecurity::Plaintext => {
+                warn!("Connecting to {} WITHOUT encryption. Your password and every scanned email \
+                    are sent in the clear and can be read by anyone on the network path.", self.imap_server);
+                imap::ConnectionMode::Plaintext
+            },
+        };
+        let client = imap::ClientBuilder::new(&self.imap_server, self.imap_port).mode(mode).connect()?;
 
         // Login to the server
-        let session = match client.login(&self.username, &self.password) {
+        let result = match &self.credential {
+            ImapCredential::Password(password) => client.login(&self.username, password),
+            ImapCredential::OAuth2AccessToken(access_token) => {
+                let authenticator = XOAuth2 {
+                    user: self.username.clone(),
+                    access_token: access_token.clone(),
+                };
+                client.authenticate("XOAUTH2", &authenticator)
+            }
+        };
+        let session = match result {
             Ok(session) => session,
             Err((err, _client)) => return Err(Box::new(err)),
         };
@@ -32,92 +332,762 @@ impl EmailMonitor {
         Ok(session)
     }
 
-    pub fn fetch_emails(&self, limit: usize) -> Result<Vec<String>, Box<dyn Error>> {
-        println!("Connecting to IMAP server: {}", self.imap_server);
-        println!("Fetching {} most recent emails", limit);
+    // Check that the IMAP server is reachable and the credentials are accepted
+    pub fn probe_connection(&self) -> Result<String, String> {
+        let mut session = self.connect_to_imap().map_err(|e| e.to_string())?;
+        let _ = session.logout();
+        Ok(format!("Logged in to {}:{}", self.imap_server, self.imap_port))
+    }
+
+    pub fn fetch_emails(&self, limit: usize) -> Result<Vec<EmailMessage>, Box<dyn Error>> {
+        self.last_headers.lock().unwrap().clear();
+        debug!("Connecting to IMAP server: {}", self.imap_server);
+        info!("Fetching {} most recent emails", limit);
 
-        // Try to connect to the IMAP server
-        match self.connect_to_imap() {
-            Ok(mut session) => {
-                // Select the INBOX mailbox
-                session.select("INBOX")?;
+        let mut session = self.connect_to_imap()?;
 
-                // Get the total number of messages
-                let mailbox_data = session.examine("INBOX")?;
-                let total_messages = mailbox_data.exists;
+        // Select the INBOX mailbox
+        session.select("INBOX")?;
 
-                // Calculate the range of messages to fetch (most recent ones)
-                let start = if total_messages > limit as u32 {
-                    total_messages - limit as u32 + 1
-                } else {
-                    1
+        // Get the total number of messages
+        let mailbox_data = session.examine("INBOX")?;
+        let total_messages = mailbox_data.exists;
+
+        let sequence = match Self::recent_sequence(total_messages, limit) {
+            Some(sequence) => sequence,
+            None => {
+                if total_messages == 0 {
+                    info!("Mailbox is empty; nothing to scan");
+                }
+                session.logout()?;
+                return Ok(Vec::new());
+            }
+        };
+
+        // Fetch the messages
+        let messages = session.fetch(sequence, "(UID RFC822)")?;
+
+        let mut emails = Vec::new();
+        let mut headers = Vec::new();
+
+        for message in messages.iter() {
+            // Extract the decoded message and the sender headers
+            if let Some(raw) = message.body() {
+                let (email, sender) = Self::parse_message(message.uid.unwrap_or(0), raw);
+                emails.push(email);
+                headers.push(sender);
+            }
+        }
+        *self.last_headers.lock().unwrap() = headers;
+
+        // Logout
+        session.logout()?;
+
+        // The mailbox has messages, so a fetch without bodies means the server didn't answer
+        // the request as expected; an empty result would read as a clean mailbox
+        if emails.is_empty() {
+            return Err(format!("Fetched {} message(s) from {} but none had a body", messages.len(), self.imap_server).into());
+        }
+
+        Ok(emails)
+    }
+
+    // Sequence set covering the `limit` most recent of `total_messages` messages. None when there
+    // is nothing to fetch, since an empty range like "1:0" is invalid
+    fn recent_sequence(total_messages: u32, limit: usize) -> Option<String> {
+        let count = total_messages.min(u32::try_from(limit).unwrap_or(u32::MAX));
+        if count == 0 {
+            return None;
+        }
+
+        let start = total_messages - count + 1;
+        Some(format!("{}:{}", start, total_messages))
+    }
+
+    // Fetch only messages that arrived since the last call, tracked by a persisted UID cursor.
+    // On the first run (no cursor yet) the most recent `first_run_limit` messages are scanned
+    pub fn fetch_new_since_last(&self, first_run_limit: usize) -> Result<Vec<EmailMessage>, Box<dyn Error>> {
+        self.last_headers.lock().unwrap().clear();
+        let mut session = self.connect_to_imap()?;
+        session.select("INBOX")?;
+
+        let last_uid = self.load_cursor();
+        let mut uids: Vec<u32> = match last_uid {
+            Some(last_uid) => {
+                // "n:*" always matches the newest message, so drop anything already seen
+                session.uid_search(format!("UID {}:*", last_uid + 1))?
+                    .into_iter()
+                    .filter(|uid| *uid > last_uid)
+                    .collect()
+            },
+            None => session.uid_search("ALL")?.into_iter().collect(),
+        };
+        uids.sort_unstable();
+
+        if last_uid.is_none() && uids.len() > first_run_limit {
+            uids.drain(..uids.len() - first_run_limit);
+        }
+
+        if uids.is_empty() {
+            info!("No new emails since last scan");
+            session.logout()?;
+            return Ok(Vec::new());
+        }
+
+        info!("Fetching {} new email(s)", uids.len());
+
+        let uid_set = uids.iter().map(|uid| uid.to_string()).collect::<Vec<_>>().join(",");
+        let messages = session.uid_fetch(uid_set, "RFC822")?;
+
+        let mut emails = Vec::new();
+        let mut headers = Vec::new();
+        for message in messages.iter() {
+            if let Some(raw) = message.body() {
+                let (email, sender) = Self::parse_message(message.uid.unwrap_or(0), raw);
+                emails.push(email);
+                headers.push(sender);
+            }
+        }
+        *self.last_headers.lock().unwrap() = headers;
+
+        session.logout()?;
+
+        // Advance the cursor past everything we just scanned
+        if let Some(&highest) = uids.last() {
+            self.save_cursor(highest)?;
+        }
+
+        Ok(emails)
+    }
+
+    // Text of a full RFC 822 message: every text/plain and text/html part, with base64 and
+    // quoted-printable transfer encodings and the charset decoded, joined together, plus its
+    // sender headers. Falls back to the raw bytes if the message can't be parsed as MIME
+    fn parse_message(uid: u32, raw: &[u8]) -> (EmailMessage, SenderHeaders) {
+        match mailparse::parse_mail(raw) {
+            Ok(mail) => {
+                let mut parts = Vec::new();
+                Self::collect_text_parts(&mail, &mut parts);
+
+                let sender = SenderHeaders {
+                    from: mail.headers.get_first_value("From"),
+                    reply_to: mail.headers.get_first_value("Reply-To"),
+                    return_path: mail.headers.get_first_value("Return-Path"),
                 };
-                let end = total_messages;
+                let email = EmailMessage {
+                    uid,
+                    from: sender.from.clone().unwrap_or_default(),
+                    subject: mail.headers.get_first_value("Subject").unwrap_or_default(),
+                    date: mail.headers.get_first_value("Date").unwrap_or_default(),
+                    body: parts.join("\n"),
+                };
+                (email, sender)
+            },
+            Err(_) => {
+                let email = EmailMessage {
+                    uid,
+                    body: String::from_utf8_lossy(raw).to_string(),
+                    ..Default::default()
+                };
+                (email, SenderHeaders::default())
+            },
+        }
+    }
 
-                // Fetch the messages
-                let sequence = format!("{}:{}", start, end);
-                let messages = session.fetch(sequence, "BODY[TEXT]")?;
+    // Spoofing signals in the sender headers of the most recent fetch: a display name
+    // claiming a brand the address doesn't belong to, or replies routed to another domain
+    pub fn analyze_headers(&self) -> Vec<HeaderFinding> {
+        let headers = self.last_headers.lock().unwrap();
+        let mut findings = Vec::new();
 
-                let mut email_bodies = Vec::new();
+        for sender in headers.iter() {
+            let (display_name, from_address) = match sender.from.as_deref().and_then(Self::parse_address) {
+                Some(from) => from,
+                None => continue,
+            };
+            let from_domain = match Self::address_domain(&from_address) {
+                Some(domain) => domain,
+                None => continue,
+            };
+            let return_path = sender.return_path.as_deref()
+                .and_then(Self::parse_address)
+                .map(|(_, address)| address);
+            let finding = |reason: String, score: u8| HeaderFinding {
+                from: sender.from.clone().unwrap_or_default(),
+                return_path: return_path.clone(),
+                reason,
+                score,
+            };
 
-                for message in messages.iter() {
-                    // Extract the body text
-                    if let Some(body) = message.body() {
-                        let body_str = String::from_utf8_lossy(body);
-                        email_bodies.push(body_str.to_string());
-                    }
+            if let Some(display_name) = display_name {
+                // Whole words only, so "Purchase Dept" doesn't claim to be Chase; multi-word
+                // brands like "Bank of America" match on the words run together
+                let words: Vec<String> = display_name.to_lowercase()
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|word| !word.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                let sending: String = from_domain.chars().filter(|c| c.is_alphanumeric()).collect();
+                if let Some(brand) = homograph::KNOWN_BRANDS.iter().find(|brand| Self::names_brand(&words, brand) && !sending.contains(*brand)) {
+                    findings.push(finding(
+                        format!("display name \"{}\" claims {} but the address is on {}", display_name, brand, from_domain),
+                        BRAND_DISPLAY_NAME_SCORE,
+                    ));
                 }
+            }
 
-                // Logout
-                session.logout()?;
+            let reply_domain = sender.reply_to.as_deref()
+                .and_then(Self::parse_address)
+                .and_then(|(_, address)| Self::address_domain(&address));
+            if let Some(reply_domain) = reply_domain.filter(|domain| !Self::hosts_related(domain, &from_domain)) {
+                findings.push(finding(
+                    format!("Reply-To goes to {} instead of {}", reply_domain, from_domain),
+                    REPLY_TO_MISMATCH_SCORE,
+                ));
+            }
+        }
 
-                if email_bodies.is_empty() {
-                    println!("No emails found. Using sample data for testing.");
-                    // Return sample data if no emails were found
-                    return Ok(vec![
-                        "Check out this link: https://example.com/login".to_string(),
-                        "Important security update: https://secure-site.com/update".to_string(),
-                    ]);
-                }
+        findings
+    }
 
-                Ok(email_bodies)
-            },
-            Err(e) => {
-                println!("Failed to connect to IMAP server: {}. Using sample data for testing.", e);
-                // Return sample data if connection failed
-                Ok(vec![
-                    "Check out this link: https://example.com/login".to_string(),
-                    "Important security update: https://secure-site.com/update".to_string(),
-                ])
+    // Whether some run of consecutive words spells the brand
+    fn names_brand(words: &[String], brand: &str) -> bool {
+        (0..words.len()).any(|start| {
+            let mut run = String::new();
+            words[start..].iter().any(|word| {
+                run.push_str(word);
+                run == brand
+            })
+        })
+    }
+
+    // (display name, address) of the first mailbox in an address header
+    fn parse_address(header: &str) -> Option<(Option<String>, String)> {
+        let info = mailparse::addrparse(header).ok()?.extract_single_info()?;
+        Some((info.display_name.filter(|name| !name.trim().is_empty()), info.addr))
+    }
+
+    fn address_domain(address: &str) -> Option<String> {
+        address.rsplit_once('@')
+            .map(|(_, domain)| domain.trim_end_matches('>').to_lowercase())
+            .filter(|domain| !domain.is_empty())
+    }
+
+    // Same host, or one is a subdomain of the other
+    fn hosts_related(a: &str, b: &str) -> bool {
+        a == b || a.ends_with(&format!(".{}", b)) || b.ends_with(&format!(".{}", a))
+    }
+
+    fn collect_text_parts(part: &ParsedMail, texts: &mut Vec<String>) {
+        if part.subparts.is_empty() {
+            // Attachments are skipped even when they are text
+            let is_attachment = matches!(
+                part.headers.get_first_value("Content-Disposition"),
+                Some(disposition) if disposition.to_lowercase().starts_with("attachment")
+            );
+            let mimetype = part.ctype.mimetype.to_lowercase();
+
+            if !is_attachment && (mimetype == "text/plain" || mimetype == "text/html") {
+                if let Ok(body) = part.get_body() {
+                    texts.push(body);
+                }
+            }
+        } else {
+            for subpart in &part.subparts {
+                Self::collect_text_parts(subpart, texts);
             }
         }
     }
 
-    pub fn extract_urls(&self, emails: Vec<String>) -> Vec<String> {
-        let url_regex = Regex::new(r"https?://[^\s/$.?#].[^\s]*").unwrap();
+    // Extract URLs together with a snippet of the text around each one
+    pub fn extract_urls_with_context(&self, emails: &[EmailMessage]) -> Vec<(String, String)> {
+        // Quotes and angle brackets end a URL so links inside HTML markup come out clean
+        let url_regex = Regex::new(r#"https?://[^\s/$.?#"'<>][^\s"'<>]+"#).unwrap();
         let mut urls = Vec::new();
 
         for email in emails {
-            for capture in url_regex.captures_iter(&email) {
-                urls.push(capture[0].to_string());
+            for url_match in url_regex.find_iter(&email.body) {
+                let snippet = Self::snippet_around(&email.body, url_match.start(), url_match.end());
+                urls.push((url_match.as_str().to_string(), snippet));
             }
         }
 
         urls
     }
 
+    // Extract, score and attach context to every URL in the given emails
+    pub fn scan_emails(&self, emails: &[EmailMessage]) -> Vec<ScanResult> {
+        let mismatched_targets: Vec<String> = self.extract_link_mismatches(emails)
+            .into_iter()
+            .map(|(href, _)| href)
+            .collect();
+
+        let (urls, snippets): (Vec<String>, Vec<String>) = self.extract_urls_with_context(emails)
+            .into_iter()
+            .unzip();
+
+        self.scan_urls(urls)
+            .into_iter()
+            .zip(snippets)
+            .map(|((url, mut score), snippet)| {
+                if mismatched_targets.contains(&url) && !self.is_allowlisted(&url) {
+                    score = score.max(LINK_MISMATCH_SCORE);
+                }
+                let phishtank_match = self.phishtank.as_ref().and_then(|client| client.raw_match(&url));
+                ScanResult { url, score, snippet, phishtank_match }
+            })
+            .collect()
+    }
+
+    // Anchors in HTML bodies whose visible text shows a different domain than the link target,
+    // e.g. <a href="http://evil.com">https://paypal.com</a>. Returns (href, display text) pairs;
+    // plain-text emails have no anchors and yield nothing
+    pub fn extract_link_mismatches(&self, emails: &[EmailMessage]) -> Vec<(String, String)> {
+        let anchor_regex = Regex::new(r#"(?is)<a\s[^>]*?href\s*=\s*["']([^"']+)["'][^>]*>(.*?)</a\s*>"#).unwrap();
+        let tag_regex = Regex::new(r"<[^>]*>").unwrap();
+        let mut mismatches = Vec::new();
+
+        for email in emails {
+            for capture in anchor_regex.captures_iter(&email.body) {
+                let href = capture[1].trim().to_string();
+                let text = tag_regex.replace_all(&capture[2], "").trim().to_string();
+
+                // Only text that itself looks like a URL or domain makes a claim about the target
+                let (href_host, text_host) = match (Self::link_host(&href), Self::link_host(&text)) {
+                    (Some(href_host), Some(text_host)) => (href_host, text_host),
+                    _ => continue,
+                };
+
+                if !Self::hosts_related(&href_host, &text_host) {
+                    mismatches.push((href, text));
+                }
+            }
+        }
+
+        mismatches
+    }
+
+    // Lowercased host of a URL or bare domain ("www." dropped), if the text looks like one
+    fn link_host(text: &str) -> Option<String> {
+        let text = text.trim().to_lowercase();
+        let without_scheme = text.split_once("://").map_or(text.as_str(), |(_, rest)| rest);
+        let host = without_scheme
+            .split(['/', '?', '#', ':'])
+            .next()?
+            .rsplit('@')
+            .next()?;
+        let host = host.strip_prefix("www.").unwrap_or(host);
+
+        let looks_like_domain = host.contains('.')
+            && !host.starts_with('.')
+            && !host.ends_with('.')
+            && host.chars().all(|c| c.is_alphanumeric() || c == '.' || c == '-');
+        looks_like_domain.then(|| host.to_string())
+    }
+
+    // Text around a match, on character boundaries, with whitespace collapsed
+    fn snippet_around(text: &str, start: usize, end: usize) -> String {
+        let before: Vec<char> = text[..start].chars().rev().take(SNIPPET_CONTEXT_CHARS).collect();
+        let before: String = before.into_iter().rev().collect();
+        let after: String = text[end..].chars().take(SNIPPET_CONTEXT_CHARS).collect();
+
+        format!("{}{}{}", before, &text[start..end], after)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
     pub fn scan_urls(&self, urls: Vec<String>) -> Vec<(String, u8)> {
-        // In a real implementation, we would check URLs against PhishTank
-        // For now, just assign random scores
         urls.into_iter()
             .map(|url| {
-                let score = if url.contains("login") {
-                    70
+                // The blocklist wins over the allowlist, which wins over everything else
+                let blocklisted = matches!(&self.blocklist, Some(blocklist) if blocklist.contains_url(&url));
+                if blocklisted {
+                    return (url, 100);
+                }
+                if self.is_allowlisted(&url) {
+                    return (url, 0);
+                }
+
+                let score = match self.phishtank.as_ref().map(|client| client.check_url(&url)) {
+                    Some(Ok(verdict)) => verdict.score,
+                    Some(Err(e)) => {
+                        warn!("{}; using heuristic score for {}", e, url);
+                        Self::heuristic_score(&url)
+                    },
+                    None => Self::heuristic_score(&url),
+                };
+                // Near-misses of a protected brand domain score high on their own
+                let score = match Self::detect_typosquat(&url, &self.brand_domains) {
+                    Some((_, typo_score)) => score.max(typo_score),
+                    None => score,
+                };
+
+                // Lookalike IDN domains get a boost on top of whatever scored them
+                let score = if self.homograph_finding(&url).is_some() {
+                    score.saturating_add(HOMOGRAPH_SCORE_BOOST).min(100)
                 } else {
-                    30
+                    score
                 };
                 (url, score)
             })
             .collect()
     }
+
+    // The brand domain a URL imitates, with a score, when its registrable domain (or one
+    // hyphen-separated word of it, as in "micros0ft-support.com") is 1-2 edits from a brand.
+    // The brand's own domain and its subdomains are never flagged
+    pub fn detect_typosquat(url: &str, brands: &[String]) -> Option<(String, u8)> {
+        let host = Self::link_host(url)?;
+        let labels: Vec<&str> = host.split('.').collect();
+        if labels.len() < 2 {
+            return None;
+        }
+        let registrable = labels[labels.len() - 2..].join(".");
+        let name = labels[labels.len() - 2];
+
+        let mut best: Option<(String, usize)> = None;
+        for brand in brands {
+            let brand = brand.to_lowercase();
+            if registrable == brand {
+                return None;
+            }
+            let brand_name = brand.split('.').next().unwrap_or(&brand);
+            let max_distance = if brand_name.chars().count() >= TYPOSQUAT_DISTANCE_2_MIN_LEN { 2 } else { 1 };
+
+            let distance = std::iter::once(Self::edit_distance(&registrable, &brand))
+                .chain(name.split('-').map(|word| Self::edit_distance(word, brand_name)))
+                .min()
+                .unwrap_or(usize::MAX);
+
+            let closer = match &best {
+                Some((_, best_distance)) => distance < *best_distance,
+                None => true,
+            };
+            if (1..=max_distance).contains(&distance) && closer {
+                best = Some((brand, distance));
+            }
+        }
+
+        best.map(|(brand, distance)| {
+            let score = if distance == 1 { TYPOSQUAT_DISTANCE_1_SCORE } else { TYPOSQUAT_DISTANCE_2_SCORE };
+            (brand, score)
+        })
+    }
+
+    // Levenshtein distance over characters
+    fn edit_distance(a: &str, b: &str) -> usize {
+        let b: Vec<char> = b.chars().collect();
+        let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+        for (i, a_char) in a.chars().enumerate() {
+            let mut current = vec![i + 1; b.len() + 1];
+            for (j, b_char) in b.iter().enumerate() {
+                let substitution = previous[j] + if a_char == *b_char { 0 } else { 1 };
+                current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            }
+            previous = current;
+        }
+
+        previous[b.len()]
+    }
+
+    fn is_allowlisted(&self, url: &str) -> bool {
+        matches!(&self.allowlist, Some(allowlist) if allowlist.contains_url(url))
+    }
+
+    // URLs on punycode domains that render as a known brand or mix lookalike scripts
+    pub fn detect_homographs(&self, urls: &[String]) -> Vec<(String, HomographFinding)> {
+        urls.iter()
+            .filter_map(|url| self.homograph_finding(url).map(|finding| (url.clone(), finding)))
+            .collect()
+    }
+
+    fn homograph_finding(&self, url: &str) -> Option<HomographFinding> {
+        Self::link_host(url).and_then(|host| homograph::analyze_domain(&host))
+    }
+
+    // Add phrases to the body-text list, replacing the weight of any phrase already on it,
+    // e.g. for scams written in other languages
+    pub fn with_body_phrases(mut self, phrases: Vec<(String, u8)>) -> Self {
+        for (phrase, weight) in phrases {
+            let phrase = phrase.to_lowercase();
+            match self.body_phrases.iter_mut().find(|(existing, _)| *existing == phrase) {
+                Some(entry) => entry.1 = weight,
+                None => self.body_phrases.push((phrase, weight)),
+            }
+        }
+        self
+    }
+
+    // Read a phrase file: one phrase per line, optionally followed by "= weight" (0-100).
+    // Blank lines and lines starting with '#' are ignored
+    pub fn load_body_phrases(path: &Path) -> Result<Vec<(String, u8)>, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+        let mut phrases = Vec::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (phrase, weight) = match line.rsplit_once('=') {
+                Some((phrase, weight)) => {
+                    let weight = weight.trim().parse::<u8>()
+                        .ok()
+                        .filter(|weight| *weight <= 100)
+                        .ok_or_else(|| format!("{}:{}: weight must be 0-100", path.display(), number + 1))?;
+                    (phrase.trim(), weight)
+                },
+                None => (line, DEFAULT_PHRASE_WEIGHT),
+            };
+            if !phrase.is_empty() {
+                phrases.push((phrase.to_string(), weight));
+            }
+        }
+
+        Ok(phrases)
+    }
+
+    // 0-100 score for social-engineering wording: the heaviest matched phrase, plus a
+    // bonus for every further phrase, so several weak signals together still add up
+    pub fn score_body_text(&self, body: &str) -> u8 {
+        let body = body.to_lowercase();
+        let mut weights: Vec<u8> = self.body_phrases.iter()
+            .filter(|(phrase, _)| body.contains(phrase.as_str()))
+            .map(|(_, weight)| *weight)
+            .collect();
+        weights.sort_unstable_by(|a, b| b.cmp(a));
+
+        match weights.split_first() {
+            Some((heaviest, rest)) => {
+                let bonus = (rest.len() as u32 * PHRASE_HIT_BONUS as u32).min(100) as u8;
+                heaviest.saturating_add(bonus).min(100)
+            },
+            None => 0,
+        }
+    }
+
+    // Keyword-based score used when no PhishTank verdict is available
+    fn heuristic_score(url: &str) -> u8 {
+        if url.contains("login") {
+            70
+        } else {
+            30
+        }
+    }
+}
+
+impl Monitor for EmailMonitor {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    // Each check opens its own IMAP session
+    fn start(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    // The score is that of the latest check, so it falls again once suspicious mail is no
+    // longer among what was fetched. By default the newest messages are re-read, so the cursor
+    // the GUI and incremental scans rely on isn't moved, and findings already returned by an
+    // earlier poll are left out
+    fn poll(&mut self) -> MonitorSample {
+        if let Some(at) = self.last_poll {
+            if at.elapsed() < self.poll_interval {
+                return MonitorSample { score: self.poll_score, findings: Vec::new() };
+            }
+        }
+        self.last_poll = Some(Instant::now());
+
+        let fetched = if self.incremental_polls {
+            self.fetch_new_since_last(POLL_FETCH_LIMIT)
+        } else {
+            self.fetch_emails(POLL_FETCH_LIMIT)
+        };
+        let emails = match fetched {
+            Ok(emails) => emails,
+            Err(e) => {
+                error!("Error fetching emails: {}", e);
+                return MonitorSample { score: self.poll_score, findings: Vec::new() };
+            },
+        };
+
+        // Scam wording and spoofed senders count toward the score even without links
+        let mut findings: Vec<Finding> = emails.iter()
+            .map(|email| Finding::new(FindingKind::Wording, self.score_body_text(&email.body), email.subject.clone()))
+            .filter(|finding| finding.score > 0)
+            .collect();
+        findings.extend(self.scan_emails(&emails)
+            .into_iter()
+            .map(|result| Finding::new(FindingKind::Url, result.score, result.url)));
+        findings.extend(self.analyze_headers()
+            .into_iter()
+            .map(|finding| Finding::new(FindingKind::Sender, finding.score, format!("{} ({})", finding.from, finding.reason))));
+
+        let score = findings.iter().map(|finding| finding.score).max().unwrap_or(0);
+        self.poll_score = Some(score);
+
+        // Incremental checks never fetch a message twice
+        if !self.incremental_polls {
+            findings.retain(|finding| self.reported.insert((finding.kind, finding.detail.clone())));
+        }
+        MonitorSample {
+            score: self.poll_score,
+            findings,
+        }
+    }
+
+    fn stop(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn monitor() -> EmailMonitor {
+        EmailMonitor::new("user@example.com".to_string(), "password".to_string(), "imap.example.com".to_string())
+    }
+
+    fn canned_message(uid: u32, subject: &str, body: &str) -> (u32, String) {
+        (uid, format!("From: Security Team <alerts@example.com>\r\nTo: user@example.com\r\n\
+            Subject: {}\r\nDate: Mon, 5 Oct 2026 09:00:00 +0000\r\n\r\n{}\r\n", subject, body))
+    }
+
+    // Plaintext IMAP server on a local port answering just the commands the monitor sends, from
+    // a mailbox the test can add messages to between fetches
+    fn fake_imap_server(mailbox: Arc<Mutex<Vec<(u32, String)>>>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                stream.write_all(b"* OK [CAPABILITY IMAP4rev1] fake server ready\r\n").unwrap();
+
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 0 {
+                    let (tag, command) = line.trim_end().split_once(' ').unwrap();
+                    let messages = mailbox.lock().unwrap().clone();
+                    let fetched = |selected: Vec<(usize, &(u32, String))>| {
+                        selected.into_iter()
+                            .map(|(index, (uid, raw))| format!("* {} FETCH (UID {} RFC822 {{{}}}\r\n{})\r\n", index + 1, uid, raw.len(), raw))
+                            .collect::<String>()
+                    };
+
+                    let response = match command.split(' ').collect::<Vec<_>>().as_slice() {
+                        ["LOGIN", ..] => String::new(),
+                        ["SELECT" | "EXAMINE", "\"INBOX\""] => format!("* {} EXISTS\r\n* OK [UIDVALIDITY 1] UIDs valid\r\n", messages.len()),
+                        ["FETCH", sequence, "(UID", "RFC822)"] => {
+                            let (start, end) = sequence.split_once(':').unwrap();
+                            let (start, end): (usize, usize) = (start.parse().unwrap(), end.parse().unwrap());
+                            fetched(messages.iter().enumerate().filter(|(index, _)| (start..=end).contains(&(index + 1))).collect())
+                        },
+                        ["UID", "SEARCH", "ALL"] => format!("* SEARCH {}\r\n", messages.iter().map(|(uid, _)| uid.to_string()).collect::<Vec<_>>().join(" ")),
+                        ["UID", "SEARCH", "UID", range] => {
+                            // Like a real server, "n:*" matches the newest message even when it's older than n
+                            let first: u32 = range.trim_end_matches(":*").parse().unwrap();
+                            let newest = messages.iter().map(|(uid, _)| *uid).max().unwrap_or(0);
+                            let uids: Vec<String> = messages.iter().map(|(uid, _)| *uid)
+                                .filter(|uid| *uid >= first || *uid == newest)
+                                .map(|uid| uid.to_string())
+                                .collect();
+                            format!("* SEARCH {}\r\n", uids.join(" "))
+                        },
+                        ["UID", "FETCH", uids, "RFC822"] => {
+                            let uids: Vec<u32> = uids.split(',').map(|uid| uid.parse().unwrap()).collect();
+                            fetched(messages.iter().enumerate().filter(|(_, (uid, _))| uids.contains(uid)).collect())
+                        },
+                        ["LOGOUT"] => "* BYE logging out\r\n".to_string(),
+                        _ => panic!("Unexpected IMAP command: {}", command),
+                    };
+                    stream.write_all(format!("{}{} OK done\r\n", response, tag).as_bytes()).unwrap();
+                    line.clear();
+                }
+            }
+        });
+
+        port
+    }
+
+    #[test]
+    fn fetches_from_an_imap_server_and_scores_the_links() {
+        let mailbox = Arc::new(Mutex::new(vec![
+            canned_message(11, "Account suspended", "Verify now at http://secure-login.example.net/verify or lose access."),
+            canned_message(12, "Lunch", "Menu is at http://cafe.example.org/menu today."),
+        ]));
+        let port = fake_imap_server(mailbox.clone());
+        let cursor = std::env::temp_dir().join(format!("threatsentry-cursor-{}", std::process::id()));
+        let _ = fs::remove_file(&cursor);
+        let monitor = EmailMonitor::new("user@example.com".to_string(), "password".to_string(), "127.0.0.1".to_string())
+            .with_port(port)
+            .with_security(ConnectionSecurity::Plaintext)
+            .with_state_file(cursor.clone());
+
+        let emails = monitor.fetch_emails(10).unwrap();
+        assert_eq!(emails.iter().map(|email| email.uid).collect::<Vec<_>>(), vec![11, 12]);
+        assert_eq!(emails[0].subject, "Account suspended");
+
+        let results = monitor.scan_emails(&emails);
+        let scores: Vec<(&str, u8)> = results.iter().map(|result| (result.url.as_str(), result.score)).collect();
+        assert_eq!(scores, vec![("http://secure-login.example.net/verify", 70), ("http://cafe.example.org/menu", 30)]);
+        assert!(results[0].snippet.contains("Verify now"));
+
+        // The first incremental fetch reads everything and leaves the cursor on the newest UID
+        assert_eq!(monitor.fetch_new_since_last(10).unwrap().len(), 2);
+        assert_eq!(fs::read_to_string(&cursor).unwrap(), "12");
+
+        mailbox.lock().unwrap().push(canned_message(13, "Invoice", "Pay at http://billing-login.example.com/pay"));
+        let emails = monitor.fetch_new_since_last(10).unwrap();
+        assert_eq!(emails.iter().map(|email| email.uid).collect::<Vec<_>>(), vec![13]);
+        assert_eq!(monitor.scan_emails(&emails)[0].score, 70);
+        assert_eq!(fs::read_to_string(&cursor).unwrap(), "13");
+
+        // Nothing new: the server still answers "14:*" with UID 13, which is already scanned
+        assert!(monitor.fetch_new_since_last(10).unwrap().is_empty());
+        let _ = fs::remove_file(&cursor);
+    }
+
+    #[test]
+    fn recent_sequence_handles_empty_single_and_full_mailboxes() {
+        assert_eq!(EmailMonitor::recent_sequence(0, 10), None);
+        assert_eq!(EmailMonitor::recent_sequence(1, 10), Some("1:1".to_string()));
+        assert_eq!(EmailMonitor::recent_sequence(10, 10), Some("1:10".to_string()));
+        assert_eq!(EmailMonitor::recent_sequence(25, 10), Some("16:25".to_string()));
+        assert_eq!(EmailMonitor::recent_sequence(5, 0), None);
+    }
+
+    #[test]
+    fn cyrillic_apple_lookalike_is_flagged_and_boosted() {
+        let monitor = monitor();
+        let lookalike = "https://xn--80ak6aa92e.com/signin".to_string();
+
+        let findings = monitor.detect_homographs(std::slice::from_ref(&lookalike));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].1.rendered, "аррӏе.com");
+        assert!(findings[0].1.reason.contains("apple"));
+
+        let score = monitor.scan_urls(vec![lookalike.clone()])[0].1;
+        assert!(score >= EmailMonitor::heuristic_score(&lookalike).saturating_add(HOMOGRAPH_SCORE_BOOST).min(100));
+        assert!(monitor.detect_homographs(&["https://apple.com/signin".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn paypa1_is_a_typosquat_of_paypal() {
+        let brands: Vec<String> = DEFAULT_BRAND_DOMAINS.iter().map(|domain| domain.to_string()).collect();
+
+        assert_eq!(
+            EmailMonitor::detect_typosquat("https://paypa1.com/verify", &brands),
+            Some(("paypal.com".to_string(), TYPOSQUAT_DISTANCE_1_SCORE)));
+        assert!(EmailMonitor::detect_typosquat("https://micros0ft-support.com/", &brands).is_some());
+        assert_eq!(EmailMonitor::detect_typosquat("https://www.paypal.com/signin", &brands), None);
+        assert_eq!(EmailMonitor::detect_typosquat("https://example.com/", &brands), None);
+    }
 }
@@ -0,0 +1,108 @@
+// Locates remote indicators (phishing URL hosts, outbound connection addresses) with a
+// MaxMind-format city database such as GeoLite2-City or DB-IP City Lite, given with --geoip-db.
+// GeoLite2 needs a MaxMind account and can't be redistributed. DB-IP City Lite is CC BY 4.0 and
+// could ship with attribution, but it's a large monthly-updated file, so neither is bundled
+use maxminddb::{geoip2, Reader};
+use std::collections::HashMap;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::path::Path;
+
+use crate::allowlist::Allowlist;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoLocation {
+    pub place: String, // "City, Country", or just the country when the city isn't known
+    pub latitude: f32,
+    pub longitude: f32,
+}
+
+pub struct GeoIp {
+    reader: Reader<Vec<u8>>,
+    hosts: HashMap<String, Option<IpAddr>>, // Resolved URL hosts, so each is only looked up in DNS once
+    resolve_hosts: bool, // Off unless asked for: every lookup of an attacker's domain leaves this machine
+}
+
+impl GeoIp {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let reader = Reader::open_readfile(path)
+            .map_err(|e| format!("Failed to open GeoIP database {}: {}", path.display(), e))?;
+
+        Ok(GeoIp {
+            reader,
+            hosts: HashMap::new(),
+            resolve_hosts: false,
+        })
+    }
+
+    // Let locate_url resolve host names through DNS, not just place IP literals
+    pub fn with_host_resolution(mut self, resolve_hosts: bool) -> Self {
+        self.resolve_hosts = resolve_hosts;
+        self
+    }
+
+    // None for private, loopback and other non-routable addresses, which have no real origin,
+    // and for addresses the database has no coordinates for
+    pub fn locate(&self, ip: IpAddr) -> Option<GeoLocation> {
+        if !Self::is_public(ip) {
+            return None;
+        }
+
+        let record: geoip2::City = self.reader.lookup(ip).ok()?;
+        let location = record.location?;
+        let (latitude, longitude) = (location.latitude?, location.longitude?);
+
+        let city = record.city.and_then(|city| city.names).and_then(|names| names.get("en").copied());
+        let country = record.country.and_then(|country| country.names).and_then(|names| names.get("en").copied());
+        let place = match (city, country) {
+            (Some(city), Some(country)) => format!("{}, {}", city, country),
+            (Some(name), None) | (None, Some(name)) => name.to_string(),
+            (None, None) => format!("{:.1}, {:.1}", latitude, longitude),
+        };
+
+        Some(GeoLocation {
+            place,
+            latitude: latitude as f32,
+            longitude: longitude as f32,
+        })
+    }
+
+    // Location of the server a URL points at. Only IP-literal hosts are placed unless host resolution
+    // is on, in which case names go to the system resolver; failed lookups are cached too, so an
+    // unresolvable host doesn't stall every scan
+    pub fn locate_url(&mut self, url: &str) -> Option<GeoLocation> {
+        let host = Allowlist::host(url)?;
+        let resolve_hosts = self.resolve_hosts;
+        let ip = *self.hosts.entry(host).or_insert_with_key(|host| Self::resolve(host, resolve_hosts));
+        self.locate(ip?)
+    }
+
+    fn resolve(host: &str, resolve_hosts: bool) -> Option<IpAddr> {
+        let literal = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(ip) = literal.parse() {
+            return Some(ip);
+        }
+        if !resolve_hosts {
+            return None;
+        }
+        (host, 0).to_socket_addrs().ok()?.next().map(|address| address.ip())
+    }
+
+    fn is_public(ip: IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(ip) => !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || (ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64)), // Carrier-grade NAT
+            IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+                Some(ip) => Self::is_public(IpAddr::V4(ip)),
+                None => !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.segments()[0] & 0xfe00 == 0xfc00 // Unique local
+                    || ip.segments()[0] & 0xffc0 == 0xfe80), // Link-local
+            },
+        }
+    }
+}
@@ -1,15 +1,29 @@
 use eframe::{egui, App};
 use egui_plot::{Line, Plot, PlotPoints, Legend, Corner};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::thread;
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
 use egui::Color32;
-
-use crate::email_monitor::EmailMonitor;
-use crate::mic_monitor::MicMonitor;
-use crate::thermal_monitor::ThermalMonitor;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::anonymize::Anonymizer;
+use crate::allowlist::Allowlist;
+use crate::blocklist::Blocklist;
+use crate::email_monitor::{ConnectionSecurity, EmailMonitor, ScanResult};
+use crate::baseline::ThermalBaseline;
+use crate::geoip::{GeoIp, GeoLocation};
+use crate::report::{self, ChartSeries, SessionReport};
+use crate::mic_monitor::{self, MicHealth, MicMonitor, Spectrum};
+use crate::thermal_monitor::{self, ThermalMonitor};
+use crate::config::Config;
 use crate::kernel_monitor::KernelMonitor;
+use crate::monitor::Monitor;
+use crate::scoring::{self, MonitorWeights, ScoreHistory, Severity, SeverityClassifier, SeverityThresholds};
+use crate::world_map;
 
 // 3D point structure for visualization
 #[derive(Clone)]
@@ -21,10 +35,10 @@ struct Point3D {
     size: f32,
 }
 
-// Threat origin data
+// Where a group of remote indicators of one type was located, for the threat map
 #[derive(Clone)]
 struct ThreatOrigin {
-    country: String,
+    place: String,
     latitude: f32,
     longitude: f32,
     threat_count: i32,
@@ -36,16 +50,26 @@ pub struct MonitoringData {
     pub mic_score: Arc<Mutex<u8>>,
     pub thermal_score: Arc<Mutex<u8>>,
     pub kernel_score: Arc<Mutex<u8>>,
+    pub surface_score: Arc<Mutex<u8>>, // Posture change since the kernel baseline
     pub email_score: Arc<Mutex<u8>>,
     pub combined_score: Arc<Mutex<u8>>,
-    pub temperature_history: Arc<Mutex<Vec<f32>>>,
-    pub mic_power_history: Arc<Mutex<Vec<f32>>>,
-    pub time_history: Arc<Mutex<Vec<f64>>>,
-    pub urls: Arc<Mutex<Vec<(String, u8)>>>,
-    pub suspicious_processes: Arc<Mutex<Vec<String>>>,
+    pub score_history: Arc<Mutex<ScoreHistory>>, // Recent combined scores
+    pub monitor_score_history: Arc<Mutex<HashMap<String, ScoreHistory>>>, // Recent scores of each monitor, by name
+    pub temperature_history: Arc<Mutex<Vec<(f64, f32)>>>, // (Unix time, °C)
+    pub hottest_component: Arc<Mutex<Option<(String, f32)>>>,
+    pub mic_power_history: Arc<Mutex<Vec<(f64, f32)>>>, // (Unix time, band power)
+    pub urls: Arc<Mutex<Vec<ScanResult>>>,
+    pub suspicious_processes: Arc<Mutex<Vec<(String, bool)>>>, // (summary, memory-resident)
+    pub all_processes: Arc<Mutex<Vec<String>>>,
+    pub top_talkers: Arc<Mutex<Option<Vec<String>>>>, // None when per-process network counters are unavailable
+    pub suspicious_connections: Arc<Mutex<Vec<String>>>,
     pub new_usb_devices: Arc<Mutex<Vec<String>>>,
     pub is_monitoring: Arc<Mutex<bool>>,
-    pub fft_data: Arc<Mutex<Vec<f32>>>,  // Added for FFT visualization
+    pub sample_interval_ms: Arc<Mutex<u64>>, // Monitoring loop interval, adjustable while running
+    pub weights: Arc<Mutex<MonitorWeights>>, // Combined-score weights, adjustable while running
+    pub tuning: Arc<Mutex<Tuning>>, // Detector sensitivity, adjustable while running
+    pub fft_data: Arc<Mutex<Spectrum>>,  // Added for FFT visualization
+    pub mic_health: Arc<Mutex<MicHealth>>,
     pub system_activity_3d: Arc<Mutex<Vec<Point3D>>>, // 3D system activity
     pub threat_origins: Arc<Mutex<Vec<ThreatOrigin>>>, // Threat origins for map
     pub selected_threat: Arc<Mutex<Option<String>>>, // For drill-down
@@ -58,16 +82,26 @@ impl MonitoringData {
             mic_score: Arc::new(Mutex::new(0)),
             thermal_score: Arc::new(Mutex::new(0)),
             kernel_score: Arc::new(Mutex::new(0)),
+            surface_score: Arc::new(Mutex::new(0)),
             email_score: Arc::new(Mutex::new(0)),
             combined_score: Arc::new(Mutex::new(0)),
+            score_history: Arc::new(Mutex::new(ScoreHistory::new())),
+            monitor_score_history: Arc::new(Mutex::new(HashMap::new())),
             temperature_history: Arc::new(Mutex::new(Vec::new())),
+            hottest_component: Arc::new(Mutex::new(None)),
             mic_power_history: Arc::new(Mutex::new(Vec::new())),
-            time_history: Arc::new(Mutex::new(Vec::new())),
             urls: Arc::new(Mutex::new(Vec::new())),
             suspicious_processes: Arc::new(Mutex::new(Vec::new())),
+            all_processes: Arc::new(Mutex::new(Vec::new())),
+            top_talkers: Arc::new(Mutex::new(Some(Vec::new()))),
+            suspicious_connections: Arc::new(Mutex::new(Vec::new())),
             new_usb_devices: Arc::new(Mutex::new(Vec::new())),
             is_monitoring: Arc::new(Mutex::new(false)),
-            fft_data: Arc::new(Mutex::new(Vec::new())),
+            sample_interval_ms: Arc::new(Mutex::new(100)),
+            weights: Arc::new(Mutex::new(MonitorWeights::default())),
+            tuning: Arc::new(Mutex::new(Tuning::default())),
+            fft_data: Arc::new(Mutex::new(Spectrum::default())),
+            mic_health: Arc::new(Mutex::new(MicHealth::Simulated)),
             system_activity_3d: Arc::new(Mutex::new(Vec::new())),
             threat_origins: Arc::new(Mutex::new(Vec::new())),
             selected_threat: Arc::new(Mutex::new(None)),
@@ -76,28 +110,132 @@ impl MonitoringData {
     }
 }
 
+// Bounds for the monitoring sample interval and the repaint rate
+pub const MIN_SAMPLE_MS: u64 = 50;
+pub const MAX_SAMPLE_MS: u64 = 5000;
+pub const MIN_REPAINT_FPS: u64 = 1;
+pub const MAX_REPAINT_FPS: u64 = 60;
+
+// Highest weight the settings sliders offer for a monitor
+const MAX_WEIGHT: f32 = 5.0;
+
+// Threat types plotted on the map
+const PHISHING: &str = "Phishing";
+const SUSPICIOUS_CONNECTION: &str = "Suspicious Connection";
+
+// Samples kept in the temperature and microphone charts
+const HISTORY_SAMPLES: usize = 100;
+
+// Scanned URLs kept in the list and saved between sessions; the oldest go first
+const HISTORY_URLS: usize = 500;
+
+// Samples further apart than this belong to different sessions; the charts don't join them
+const SESSION_GAP_SECS: f64 = 30.0;
+
+// Key of the saved histories in eframe's app storage
+const HISTORY_STORAGE_KEY: &str = "session_history";
+
+// Chart and URL history saved on exit and restored on the next start, so the charts show
+// earlier sessions too
+#[derive(Default, Serialize, Deserialize)]
+struct SavedHistory {
+    temperature: Vec<(f64, f32)>,
+    mic_power: Vec<(f64, f32)>,
+    urls: Vec<ScanResult>,
+}
+
+// Lowest microphone band edge and narrowest band the Settings panel offers, in Hz
+const MIN_BAND_HZ: f32 = 1000.0;
+const MIN_BAND_WIDTH_HZ: f32 = 100.0;
+
+// Detector settings the Settings panel can change while monitoring; the monitoring thread
+// applies them to the running monitors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tuning {
+    pub mic_threshold: f32,
+    pub min_freq: f32,
+    pub max_freq: f32,
+    pub spike_delta: f32,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning {
+            mic_threshold: mic_monitor::DEFAULT_THRESHOLD,
+            min_freq: mic_monitor::DEFAULT_MIN_FREQ_HZ,
+            max_freq: mic_monitor::DEFAULT_MAX_FREQ_HZ,
+            spike_delta: thermal_monitor::DEFAULT_SPIKE_DELTA_C,
+        }
+    }
+}
+
+// Launch-time settings passed in from the command line
+#[derive(Clone, Default)]
+pub struct GuiOptions {
+    pub score_cap: Option<u8>,
+    pub reset_cursor: bool,
+    pub thresholds: SeverityThresholds,
+    pub blocklist: Option<PathBuf>,
+    pub allowlist: Option<PathBuf>,
+    pub brand_domains: Vec<String>,
+    pub phishtank_key: Option<String>,
+    pub imap_server: String,
+    pub imap_port: u16,
+    pub imap_security: ConnectionSecurity,
+    pub run_id: String,
+    pub hysteresis: u8,
+    pub anonymize: bool,
+    pub sample_ms: u64,
+    pub repaint_fps: u64,
+    pub signatures: Option<PathBuf>,
+    pub process_allowlist: Option<PathBuf>,
+    pub weights: MonitorWeights,
+    pub geoip_db: Option<PathBuf>, // Locates threat origins for the map; without it the map stays empty
+    pub geoip_resolve_hosts: bool, // Look up URL host names in DNS; otherwise only IP-literal URLs are placed
+    pub tuning: Tuning,
+    pub config_path: Option<PathBuf>, // Settings panel changes are saved here when set
+    pub thermal_baseline: Option<ThermalBaseline>, // Learned by `baseline`
+}
+
 pub struct ThreatSentryApp {
     monitoring_data: MonitoringData,
+    options: GuiOptions,
     start_time: Instant,
-    username: String,
-    password: String,
+    credentials: Option<(String, String)>, // None when no email account is configured
     visualization_tab: usize, // 0 = Classic, 1 = 3D, 2 = Map
     show_fft: bool,
     show_drill_down: bool,
+    show_all_processes: bool,
+    show_settings: bool,
     rotation_angle: f32,
+    gauge_classifiers: Mutex<HashMap<&'static str, SeverityClassifier>>, // Per-gauge hysteresis state
+    report_status: Mutex<Option<String>>, // Where the last report was saved, or why it failed
+    settings_dirty: bool, // Settings changed since they were last saved to the config file
+    settings_status: Option<String>, // Why saving the settings failed
 }
 
 impl ThreatSentryApp {
-    pub fn new(username: String, password: String) -> Self {
+    pub fn new(credentials: Option<(String, String)>, options: GuiOptions) -> Self {
+        let monitoring_data = MonitoringData::new();
+        *monitoring_data.sample_interval_ms.lock().unwrap() = options.sample_ms;
+        *monitoring_data.weights.lock().unwrap() = options.weights;
+        *monitoring_data.tuning.lock().unwrap() = options.tuning;
+
         ThreatSentryApp {
-            monitoring_data: MonitoringData::new(),
+            monitoring_data,
+            options,
             start_time: Instant::now(),
-            username,
-            password,
+            credentials,
             visualization_tab: 0,
             show_fft: false,
             show_drill_down: false,
+            show_all_processes: false,
+            show_settings: false,
             rotation_angle: 0.0,
+            gauge_classifiers: Mutex::new(HashMap::new()),
+            report_status: Mutex::new(None),
+            settings_dirty: false,
+            settings_status: None,
         }
     }
 
@@ -113,95 +251,274 @@ impl ThreatSentryApp {
         let mic_score = self.monitoring_data.mic_score.clone();
         let thermal_score = self.monitoring_data.thermal_score.clone();
         let kernel_score = self.monitoring_data.kernel_score.clone();
+        let surface_score = self.monitoring_data.surface_score.clone();
         let email_score = self.monitoring_data.email_score.clone();
         let combined_score = self.monitoring_data.combined_score.clone();
+        let score_history = self.monitoring_data.score_history.clone();
+        let monitor_score_history = self.monitoring_data.monitor_score_history.clone();
         let temperature_history = self.monitoring_data.temperature_history.clone();
+        let hottest_component = self.monitoring_data.hottest_component.clone();
         let mic_power_history = self.monitoring_data.mic_power_history.clone();
-        let time_history = self.monitoring_data.time_history.clone();
         let urls = self.monitoring_data.urls.clone();
         let suspicious_processes = self.monitoring_data.suspicious_processes.clone();
+        let all_processes = self.monitoring_data.all_processes.clone();
+        let top_talkers = self.monitoring_data.top_talkers.clone();
+        let suspicious_connections = self.monitoring_data.suspicious_connections.clone();
         let new_usb_devices = self.monitoring_data.new_usb_devices.clone();
         let is_monitoring_clone = self.monitoring_data.is_monitoring.clone();
-        let username = self.username.clone();
-        let password = self.password.clone();
+        let sample_interval_ms = self.monitoring_data.sample_interval_ms.clone();
+        let weights = self.monitoring_data.weights.clone();
+        let tuning = self.monitoring_data.tuning.clone();
+        let credentials = self.credentials.clone();
         let fft_data = self.monitoring_data.fft_data.clone(); // Added for FFT data
+        let mic_health = self.monitoring_data.mic_health.clone();
+        let threat_origins = self.monitoring_data.threat_origins.clone();
+        let threat_details = self.monitoring_data.threat_details.clone();
+        let thresholds = self.options.thresholds;
+        let score_cap = self.options.score_cap;
+        let reset_cursor = self.options.reset_cursor;
+        let blocklist_path = self.options.blocklist.clone();
+        let allowlist_path = self.options.allowlist.clone();
+        let brand_domains = self.options.brand_domains.clone();
+        let phishtank_key = self.options.phishtank_key.clone();
+        let imap_server = self.options.imap_server.clone();
+        let imap_port = self.options.imap_port;
+        let imap_security = self.options.imap_security;
+        let anonymize = self.options.anonymize;
+        let signatures_path = self.options.signatures.clone();
+        let process_allowlist_path = self.options.process_allowlist.clone();
+        let geoip_path = self.options.geoip_db.clone();
+        let geoip_resolve_hosts = self.options.geoip_resolve_hosts;
+        let thermal_baseline = self.options.thermal_baseline;
 
         // Start the monitoring thread
         thread::spawn(move || {
             // Initialize monitors
-            let mic_monitor = MicMonitor::new();
+            let mut applied = *tuning.lock().unwrap();
+            let mut mic_monitor = MicMonitor::with_band(applied.min_freq, applied.max_freq);
+            mic_monitor.set_threshold(applied.mic_threshold);
             let mut thermal_monitor = ThermalMonitor::new();
-            let kernel_monitor = KernelMonitor::new();
-            let email_monitor = EmailMonitor::new(
-                username,
-                password,
-                "imap.gmail.com".to_string(),
-            );
+            if let Err(e) = thermal_monitor.set_spike_delta(applied.spike_delta) {
+                error!("Error setting thermal spike delta: {}", e);
+            }
+            if let Some(baseline) = thermal_baseline {
+                thermal_monitor.set_baseline(baseline);
+            }
+            // Extra process signatures on top of the built-in list
+            let kernel_monitor = match signatures_path.as_deref().map(KernelMonitor::load_signatures) {
+                Some(Ok(signatures)) => KernelMonitor::new().with_signatures(signatures),
+                Some(Err(e)) => {
+                    error!("Error loading signatures: {}", e);
+                    KernelMonitor::new()
+                },
+                None => KernelMonitor::new(),
+            };
+
+            // Trusted processes are never flagged
+            let mut kernel_monitor = match process_allowlist_path.as_deref().map(KernelMonitor::load_process_allowlist) {
+                Some(Ok(entries)) => kernel_monitor.with_process_allowlist(entries),
+                Some(Err(e)) => {
+                    error!("Error loading process allowlist: {}", e);
+                    kernel_monitor
+                },
+                None => kernel_monitor,
+            };
+            let anonymizer = anonymize.then(|| Anonymizer::new(credentials.as_ref().map(|(username, _)| username.as_str())));
+            let email_monitor = credentials.map(|(username, password)| {
+                let email_monitor = EmailMonitor::new(
+                    username,
+                    password,
+                    imap_server.clone(),
+                ).with_port(imap_port).with_security(imap_security);
+
+                // Score by PhishTank verdict when a key was given
+                let email_monitor = match phishtank_key.clone() {
+                    Some(api_key) => email_monitor.with_phishtank(api_key),
+                    None => email_monitor,
+                };
 
-            // Start microphone monitoring
-            match mic_monitor.start_monitoring() {
-                Ok(_) => println!("Microphone monitoring started"),
-                Err(e) => println!("Error starting microphone monitoring: {}", e),
+                let email_monitor = email_monitor.with_brand_domains(brand_domains.clone());
+
+                // Trusted domains score 0
+                let email_monitor = match allowlist_path.as_deref().map(Allowlist::load) {
+                    Some(Ok(allowlist)) => email_monitor.with_allowlist(allowlist),
+                    Some(Err(e)) => {
+                        error!("Error loading allowlist: {}", e);
+                        email_monitor
+                    },
+                    None => email_monitor,
+                };
+
+                // Listed URLs score 100; the list is re-read when the file changes
+                match blocklist_path.as_deref().map(Blocklist::load) {
+                    Some(Ok(blocklist)) => email_monitor.with_blocklist(blocklist),
+                    Some(Err(e)) => {
+                        error!("Error loading blocklist: {}", e);
+                        email_monitor
+                    },
+                    None => email_monitor,
+                }
+            });
+
+            if let Some(email_monitor) = email_monitor.as_ref().filter(|_| reset_cursor) {
+                email_monitor.reset_cursor();
             }
 
-            // Start kernel monitoring
-            match kernel_monitor.start_monitoring() {
-                Ok(_) => println!("Kernel monitoring started"),
-                Err(e) => println!("Error starting kernel monitoring: {}", e),
+            let mut geoip = match geoip_path.as_deref().map(GeoIp::open) {
+                Some(Ok(geoip)) => Some(geoip.with_host_resolution(geoip_resolve_hosts)),
+                Some(Err(e)) => {
+                    error!("Error loading GeoIP database: {}", e);
+                    None
+                },
+                None => None,
+            };
+
+            // Start the background capture of each monitor
+            for monitor in [&mic_monitor as &dyn Monitor, &thermal_monitor, &kernel_monitor] {
+                match monitor.start() {
+                    Ok(_) => info!("Started {} monitoring", monitor.name()),
+                    Err(e) => error!("Error starting {} monitoring: {}", monitor.name(), e),
+                }
             }
 
             // Monitoring loop
-            let start_time = Instant::now();
             let mut last_email_check = Instant::now() - Duration::from_secs(60); // Check emails immediately
+            let mut message_score = 0; // Highest spoofed-sender or scam-wording score seen so far
+            let mut url_score = 0; // Highest URL score this session; URLs restored from earlier sessions don't count
+            let mut email_scanned = false; // Email only counts toward the combined score once it has run
+            let mut phishing_origins: Vec<(GeoLocation, String)> = Vec::new(); // Located alerting URLs, with the URL
 
             while *is_monitoring_clone.lock().unwrap() {
-                // Check temperature
-                if let Ok(temp) = thermal_monitor.check_temperature() {
+                // Apply Settings panel changes to the running monitors
+                let mut requested = *tuning.lock().unwrap();
+                if requested != applied {
+                    mic_monitor.set_threshold(requested.mic_threshold);
+                    if let Err(e) = thermal_monitor.set_spike_delta(requested.spike_delta) {
+                        error!("Error setting thermal spike delta: {}", e);
+                    }
+                    if (requested.min_freq, requested.max_freq) != mic_monitor.get_band() {
+                        if let Err(e) = mic_monitor.set_band(requested.min_freq, requested.max_freq) {
+                            // Show the band that is actually monitored again
+                            error!("Error setting microphone band: {}", e);
+                            (requested.min_freq, requested.max_freq) = mic_monitor.get_band();
+                            let mut tuning = tuning.lock().unwrap();
+                            (tuning.min_freq, tuning.max_freq) = (requested.min_freq, requested.max_freq);
+                        }
+                    }
+                    applied = requested;
+                }
+
+                // Sample each monitor, publish its score to its gauge and weigh it for the
+                // combined score. A monitor without a reading (a simulated mic) is left out
+                let current_weights = *weights.lock().unwrap();
+                let mut scores = Vec::new();
+                let polled: [(&mut dyn Monitor, &Arc<Mutex<u8>>); 3] = [
+                    (&mut thermal_monitor, &thermal_score),
+                    (&mut mic_monitor, &mic_score),
+                    (&mut kernel_monitor, &kernel_score),
+                ];
+                for (monitor, gauge) in polled {
+                    let score = monitor.poll().score;
+                    *gauge.lock().unwrap() = score.unwrap_or(0);
+                    if let Some(score) = score {
+                        monitor_score_history.lock().unwrap().entry(monitor.name().to_string()).or_insert_with(ScoreHistory::new).record(score);
+                        scores.push((scoring::cap_contribution(score, score_cap), current_weights.for_monitor(monitor.name())));
+                    }
+                }
+
+                let now = unix_time();
+                if let Some(temp) = thermal_monitor.last_temperature() {
                     let mut temp_history = temperature_history.lock().unwrap();
-                    temp_history.push(temp);
-                    if temp_history.len() > 100 {
+                    temp_history.push((now, temp));
+                    if temp_history.len() > HISTORY_SAMPLES {
                         temp_history.remove(0);
                     }
                 }
+                *hottest_component.lock().unwrap() = thermal_monitor.get_hottest_component();
 
-                // Get thermal score
-                let thermal_score_val = thermal_monitor.get_threat_score();
-                *thermal_score.lock().unwrap() = thermal_score_val;
+                *mic_health.lock().unwrap() = mic_monitor.get_health();
+                let mic_score_val = *mic_score.lock().unwrap();
 
-                // Get microphone score and FFT data
-                let mic_score_val = mic_monitor.get_threat_score();
-                *mic_score.lock().unwrap() = mic_score_val;
-                
                 // Get FFT data for visualization
-                let fft_results = mic_monitor.get_fft_results();
-                if !fft_results.is_empty() {
-                    *fft_data.lock().unwrap() = fft_results;
+                let spectrum = mic_monitor.get_fft_results();
+                if !spectrum.magnitudes.is_empty() {
+                    *fft_data.lock().unwrap() = spectrum;
                 }
 
-                // Get kernel score and update suspicious processes and USB devices
-                let kernel_score_val = kernel_monitor.get_threat_score();
-                *kernel_score.lock().unwrap() = kernel_score_val;
+                // Update the attack surface score, suspicious processes and USB devices
+                *surface_score.lock().unwrap() = kernel_monitor.get_surface_change_score();
 
-                // Update suspicious processes
-                let suspicious = kernel_monitor.get_suspicious_processes();
-                if !suspicious.is_empty() {
+                // Update suspicious processes, followed by suspicious parent -> child chains
+                let mut suspicious = kernel_monitor.get_suspicious_processes();
+                let chains = kernel_monitor.suspicious_chains();
+                if !suspicious.is_empty() || !chains.is_empty() {
+                    // Memory-resident processes are the most serious findings, so list them first
+                    suspicious.sort_by_key(|process| !process.memory_resident);
                     let mut processes = suspicious_processes.lock().unwrap();
                     processes.clear();
                     for process in suspicious {
+                        processes.push((format!("{} (PID: {}, CPU: {:.1}%, Score: {})",
+                            process.name, process.pid, process.cpu_usage, process.suspicious_score),
+                            process.memory_resident));
+                    }
+                    for chain in chains {
+                        processes.push((format!("Suspicious chain: {}", chain.join(" → ")), false));
+                    }
+                }
+
+                // Update the full process list, most suspicious first
+                let processes_snapshot = kernel_monitor.get_all_processes();
+                if !processes_snapshot.is_empty() {
+                    let mut processes = all_processes.lock().unwrap();
+                    processes.clear();
+                    for process in processes_snapshot {
                         processes.push(format!("{} (PID: {}, CPU: {:.1}%, Score: {})",
                             process.name, process.pid, process.cpu_usage, process.suspicious_score));
                     }
                 }
 
-                // Update USB devices
-                let usb_devices = kernel_monitor.get_new_usb_devices();
-                if !usb_devices.is_empty() {
-                    let mut devices = new_usb_devices.lock().unwrap();
-                    devices.clear();
-                    for device in usb_devices {
-                        devices.push(format!("{} (ID: {})", device.description, device.device_id));
-                    }
+                // Update top talkers by outbound throughput
+                *top_talkers.lock().unwrap() = kernel_monitor.get_top_talkers(5).map(|talkers| {
+                    talkers.iter()
+                        .map(|process| format!("{} (PID: {}, Out: {:.1} KB/s, In: {:.1} KB/s){}",
+                            process.name,
+                            process.pid,
+                            process.bytes_sent.unwrap_or(0.0) / 1024.0,
+                            process.bytes_recv.unwrap_or(0.0) / 1024.0,
+                            if process.high_outbound { " [sustained high outbound]" } else { "" }))
+                        .collect()
+                });
+
+                // Update outbound connections to non-standard ports
+                let connections = kernel_monitor.get_suspicious_connections();
+                let connection_summaries: Vec<String> = connections.iter()
+                    .map(|connection| format!("{} (PID: {}) :{} → {}",
+                        connection.process_name,
+                        connection.pid.map_or("?".to_string(), |pid| pid.to_string()),
+                        connection.local_port,
+                        connection.remote))
+                    .collect();
+
+                // Only remote indicators have an origin: the servers phishing URLs point at and
+                // the addresses of suspicious connections. Local monitors are never plotted
+                if let Some(geoip) = geoip.as_ref() {
+                    let located: Vec<(GeoLocation, &str, &str)> = phishing_origins.iter()
+                        .map(|(location, url)| (location.clone(), PHISHING, url.as_str()))
+                        .chain(connections.iter().zip(&connection_summaries).filter_map(|(connection, summary)| {
+                            geoip.locate(connection.remote.ip()).map(|location| (location, SUSPICIOUS_CONNECTION, summary.as_str()))
+                        }))
+                        .collect();
+                    let (origins, details) = summarize_origins(&located);
+                    *threat_origins.lock().unwrap() = origins;
+                    *threat_details.lock().unwrap() = details;
                 }
+                *suspicious_connections.lock().unwrap() = connection_summaries;
+
+                // Update USB devices inserted since startup; unplugged ones drop off the list
+                *new_usb_devices.lock().unwrap() = kernel_monitor.get_inserted_usb_devices()
+                    .iter()
+                    .map(|device| format!("{} [{}] (ID: {})", device.description, device.class.name(), device.device_id))
+                    .collect();
 
                 // Add microphone power 
                 let power = if mic_score_val > 0 {
@@ -217,77 +534,101 @@ impl ThreatSentryApp {
                 };
 
                 let mut mic_history = mic_power_history.lock().unwrap();
-                mic_history.push(power);
-                if mic_history.len() > 100 {
+                mic_history.push((now, power));
+                if mic_history.len() > HISTORY_SAMPLES {
                     mic_history.remove(0);
                 }
+                drop(mic_history);
 
-                // Add time point
-                let elapsed = start_time.elapsed().as_secs_f64();
-                let mut time_points = time_history.lock().unwrap();
-                time_points.push(elapsed);
-                if time_points.len() > 100 {
-                    time_points.remove(0);
-                }
-
-                // Calculate combined threat score
-                let mut scores = Vec::new();
-                scores.push(mic_score_val);
-                scores.push(thermal_score_val);
-                scores.push(kernel_score_val);
-                
-                let combined = if !scores.is_empty() {
-                    let sum: u32 = scores.iter().map(|&s| s as u32).sum();
-                    (sum / scores.len() as u32) as u8
-                } else {
-                    0
-                };
-                
-                *combined_score.lock().unwrap() = combined;
-
-                // Check emails every 60 seconds
-                if last_email_check.elapsed() > Duration::from_secs(60) {
+                // Check emails every 60 seconds, when an account is configured
+                if let Some(email_monitor) = email_monitor.as_ref().filter(|_| last_email_check.elapsed() > Duration::from_secs(60)) {
                     last_email_check = Instant::now();
 
-                    match email_monitor.fetch_emails(5) {
+                    // Only new mail is fetched each cycle, so the same message isn't re-scanned and re-alerted
+                    match email_monitor.fetch_new_since_last(5) {
                         Ok(emails) => {
-                            let extracted_urls = email_monitor.extract_urls(emails);
-                            let scored_urls = email_monitor.scan_urls(extracted_urls);
-
-                            // Update URLs
-                            *urls.lock().unwrap() = scored_urls.clone();
-
-                            // Update email score
-                            let max_score = scored_urls.iter()
-                                .map(|(_, score)| *score)
-                                .max()
-                                .unwrap_or(0);
-
-                            *email_score.lock().unwrap() = max_score;
-                            
-                            // Recalculate combined score with email
-                            scores.push(max_score);
-                            let combined = if !scores.is_empty() {
-                                let sum: u32 = scores.iter().map(|&s| s as u32).sum();
-                                (sum / scores.len() as u32) as u8
-                            } else {
-                                0
-                            };
-                            *combined_score.lock().unwrap() = combined;
+                            let body_score = emails.iter().map(|email| email_monitor.score_body_text(&email.body)).max().unwrap_or(0);
+                            let mut scan_results = email_monitor.scan_emails(&emails);
+                            if let Some(anonymizer) = anonymizer.as_ref() {
+                                for result in scan_results.iter_mut() {
+                                    result.url = anonymizer.scrub(&result.url);
+                                    result.snippet = anonymizer.scrub(&result.snippet);
+                                    result.phishtank_match = result.phishtank_match.as_deref().map(|raw| anonymizer.scrub(raw));
+                                }
+                            }
+
+                            // Locate the servers behind URLs scored high enough to alert on
+                            if let Some(geoip) = geoip.as_mut() {
+                                for result in scan_results.iter().filter(|result| thresholds.is_alert(result.score)) {
+                                    if let Some(location) = geoip.locate_url(&result.url) {
+                                        phishing_origins.push((location, result.url.clone()));
+                                    }
+                                }
+                            }
+
+                            // Update email score from the URLs and any spoofed sender headers
+                            url_score = scan_results.iter().map(|result| result.score).fold(url_score, u8::max);
+                            let header_score = email_monitor.analyze_headers().iter().map(|finding| finding.score).max().unwrap_or(0);
+                            message_score = message_score.max(header_score).max(body_score);
+
+                            // Append newly found URLs
+                            let mut urls = urls.lock().unwrap();
+                            urls.extend(scan_results);
+                            let excess = urls.len().saturating_sub(HISTORY_URLS);
+                            urls.drain(..excess);
+                            drop(urls);
+
+                            *email_score.lock().unwrap() = url_score.max(message_score);
+                            email_scanned = true;
                         },
-                        Err(e) => println!("Error fetching emails: {}", e),
+                        Err(e) => error!("Error fetching emails: {}", e),
                     }
                 }
 
-                thread::sleep(Duration::from_millis(100));
+                // Calculate the weighted combined threat score over the monitors that ran,
+                // clamping each monitor's contribution first
+                if email_scanned {
+                    let score = *email_score.lock().unwrap();
+                    scores.push((scoring::cap_contribution(score, score_cap), current_weights.email));
+                    monitor_score_history.lock().unwrap().entry("email".to_string()).or_insert_with(ScoreHistory::new).record(score);
+                }
+                *combined_score.lock().unwrap() = scoring::combined_score(&scores);
+
+                score_history.lock().unwrap().record(*combined_score.lock().unwrap());
+
+                let interval = *sample_interval_ms.lock().unwrap();
+                thread::sleep(Duration::from_millis(interval));
             }
 
             // Stop monitoring
-            mic_monitor.stop_monitoring();
-            kernel_monitor.stop_monitoring();
+            for monitor in [&mic_monitor as &dyn Monitor, &thermal_monitor, &kernel_monitor] {
+                monitor.stop();
+            }
         });
     }
 
+    // Restore the charts and URL list saved by the previous session
+    fn load_history(&self, storage: &dyn eframe::Storage) {
+        if let Some(saved) = eframe::get_value::<SavedHistory>(storage, HISTORY_STORAGE_KEY) {
+            *self.monitoring_data.temperature_history.lock().unwrap() = saved.temperature;
+            *self.monitoring_data.mic_power_history.lock().unwrap() = saved.mic_power;
+            let mut urls = saved.urls;
+            let excess = urls.len().saturating_sub(HISTORY_URLS);
+            urls.drain(..excess);
+            *self.monitoring_data.urls.lock().unwrap() = urls;
+        }
+    }
+
+    // Forget the chart, score and URL history, including what was saved from earlier sessions
+    fn clear_history(&self) {
+        let data = &self.monitoring_data;
+        data.temperature_history.lock().unwrap().clear();
+        data.mic_power_history.lock().unwrap().clear();
+        data.urls.lock().unwrap().clear();
+        *data.score_history.lock().unwrap() = ScoreHistory::new();
+        data.monitor_score_history.lock().unwrap().clear();
+    }
+
     pub fn stop_monitoring(&self) {
         let mut is_monitoring = self.monitoring_data.is_monitoring.lock().unwrap();
         *is_monitoring = false;
@@ -385,117 +726,26 @@ impl ThreatSentryApp {
             Self::rotate_point(point, 0.01, 0.02);
         }
     }
-    
-    fn generate_threat_map_data(&mut self) {
-        let mut threat_origins = self.monitoring_data.threat_origins.lock().unwrap();
-        
-        // Only regenerate occasionally
-        if !threat_origins.is_empty() && rand::random::<f32>() < 0.95 {
-            return;
-        }
-        
-        // Clear existing data
-        threat_origins.clear();
-        
-        // Get current threat scores
-        let mic_score = *self.monitoring_data.mic_score.lock().unwrap();
-        let thermal_score = *self.monitoring_data.thermal_score.lock().unwrap();
-        let kernel_score = *self.monitoring_data.kernel_score.lock().unwrap();
-        let email_score = *self.monitoring_data.email_score.lock().unwrap();
-        
-        // Add some example threat origins based on current scores
-        if email_score > 30 {
-            threat_origins.push(ThreatOrigin {
-                country: "Russia".to_string(),
-                latitude: 55.751244,
-                longitude: 37.618423,
-                threat_count: (email_score as i32 / 10).max(1),
-                threat_type: "Phishing".to_string(),
-            });
-            
-            threat_origins.push(ThreatOrigin {
-                country: "Nigeria".to_string(),
-                latitude: 9.0820,
-                longitude: 8.6753,
-                threat_count: (email_score as i32 / 15).max(1),
-                threat_type: "Phishing".to_string(),
-            });
-        }
-        
-        if mic_score > 50 {
-            threat_origins.push(ThreatOrigin {
-                country: "Local Network".to_string(),
-                latitude: 40.7128,
-                longitude: -74.0060,
-                threat_count: (mic_score as i32 / 20).max(1),
-                threat_type: "Ultrasonic Beacon".to_string(),
-            });
-        }
-        
-        if thermal_score > 40 {
-            threat_origins.push(ThreatOrigin {
-                country: "China".to_string(),
-                latitude: 39.9042,
-                longitude: 116.4074,
-                threat_count: (thermal_score as i32 / 10).max(1),
-                threat_type: "Cryptominer".to_string(),
-            });
-        }
-        
-        if kernel_score > 45 {
-            threat_origins.push(ThreatOrigin {
-                country: "Iran".to_string(), 
-                latitude: 35.6892,
-                longitude: 51.3890,
-                threat_count: (kernel_score as i32 / 15).max(1),
-                threat_type: "System Exploit".to_string(),
-            });
-        }
-        
-        // Add threat details for drill-down
-        let mut threat_details = self.monitoring_data.threat_details.lock().unwrap();
-        threat_details.clear();
-        
-        for origin in threat_origins.iter() {
-            let detail_key = format!("{}: {}", origin.country, origin.threat_type);
-            let detail_value = match origin.threat_type.as_str() {
-                "Phishing" => format!(
-                    "Origin: {}\nType: Phishing Campaign\nCount: {} attempts\nTarget: Credentials\nSeverity: {}/10\nMitigation: Email filtering, 2FA", 
-                    origin.country, 
-                    origin.threat_count,
-                    (email_score as f32 / 10.0).round()
-                ),
-                "Ultrasonic Beacon" => format!(
-                    "Origin: Local Network\nType: Ultrasonic Data Exfiltration\nFrequency: 18-19 kHz\nPower: High\nSeverity: {}/10\nMitigation: Isolate network, disable microphone",
-                    (mic_score as f32 / 10.0).round()
-                ),
-                "Cryptominer" => format!(
-                    "Origin: {}\nType: Cryptocurrency Mining Malware\nCPU Usage: {}%\nTarget Coin: Monero\nSeverity: {}/10\nMitigation: Process isolation, update AV",
-                    origin.country,
-                    thermal_score + 30,
-                    (thermal_score as f32 / 10.0).round()
-                ),
-                "System Exploit" => format!(
-                    "Origin: {}\nType: Kernel-level Exploit\nTarget: Memory Access\nElevation: Root/System\nSeverity: {}/10\nMitigation: Patch system, isolate affected processes",
-                    origin.country,
-                    (kernel_score as f32 / 10.0).round()
-                ),
-                _ => "No details available".to_string()
-            };
-            
-            threat_details.insert(detail_key, detail_value);
-        }
-    }
 }
 
 impl App for ThreatSentryApp {
+    // Called on exit and periodically while running
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        let data = &self.monitoring_data;
+        let saved = SavedHistory {
+            temperature: data.temperature_history.lock().unwrap().clone(),
+            mic_power: data.mic_power_history.lock().unwrap().clone(),
+            urls: data.urls.lock().unwrap().clone(),
+        };
+        eframe::set_value(storage, HISTORY_STORAGE_KEY, &saved);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Request repaint regularly for animation
-        ctx.request_repaint_after(Duration::from_millis(33)); // ~30 fps
+        // Request repaint regularly for animation, independent of the sampling rate
+        ctx.request_repaint_after(Duration::from_millis(1000 / self.options.repaint_fps.max(1)));
         
-        // Update 3D visualization and threat map data
+        // Update 3D visualization data
         self.update_3d_system_activity();
-        self.generate_threat_map_data();
         self.rotation_angle += 0.01;
 
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
@@ -515,7 +765,11 @@ impl App for ThreatSentryApp {
                             self.start_monitoring();
                         }
                     }
+                    if ui.button("Clear History").on_hover_text("Forget the charts and URLs, including those kept from earlier sessions").clicked() {
+                        self.clear_history();
+                    }
                     ui.label(format!("Monitoring: {:.1}s", self.start_time.elapsed().as_secs_f64()));
+                    ui.small(format!("Run ID: {}", self.options.run_id));
                 });
             });
             
@@ -527,10 +781,20 @@ impl App for ThreatSentryApp {
                 ui.selectable_value(&mut self.visualization_tab, 2, "Threat Map");
                 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
+                    ui.checkbox(&mut self.show_settings, "Settings");
                     ui.checkbox(&mut self.show_drill_down, "Threat Analysis");
+                    ui.checkbox(&mut self.show_all_processes, "All Processes");
                     ui.checkbox(&mut self.show_fft, "FFT Visualization");
                 });
             });
+
+            if self.show_settings {
+                ui.separator();
+                self.render_settings(ui);
+            }
+
+            ui.separator();
+            self.render_severity_legend(ui);
         });
 
         // Make the central panel scrollable
@@ -567,12 +831,174 @@ impl App for ThreatSentryApp {
 
 // Add these supporting methods
 impl ThreatSentryApp {
+    // Color for a one-off score (list entries), using the configured severity bands
+    fn severity_color(&self, score: u8) -> Color32 {
+        Self::band_color(self.options.thresholds.classify(score))
+    }
+
+    // Color for a live gauge; each gauge keeps its own hysteresis state so it doesn't
+    // flicker when the score hovers around a band boundary
+    fn gauge_color(&self, gauge: &'static str, score: u8) -> Color32 {
+        let mut classifiers = self.gauge_classifiers.lock().unwrap();
+        let classifier = classifiers.entry(gauge).or_insert_with(|| {
+            SeverityClassifier::new(self.options.thresholds, self.options.hysteresis)
+        });
+        Self::band_color(classifier.classify(score))
+    }
+
+    fn band_color(severity: Severity) -> Color32 {
+        match severity {
+            Severity::Low => Color32::GREEN,
+            Severity::Medium => Color32::YELLOW,
+            Severity::High => Color32::from_rgb(255, 140, 0),
+            Severity::Critical => Color32::RED,
+        }
+    }
+
+    // Sampling and repaint rates; lower both to save battery, raise them while investigating
+    fn render_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let mut sample_ms = *self.monitoring_data.sample_interval_ms.lock().unwrap();
+            let response = ui.add(
+                egui::Slider::new(&mut sample_ms, MIN_SAMPLE_MS..=MAX_SAMPLE_MS)
+                    .logarithmic(true)
+                    .suffix(" ms")
+                    .text("Sample interval"),
+            );
+            if response.changed() {
+                *self.monitoring_data.sample_interval_ms.lock().unwrap() = sample_ms;
+            }
+
+            ui.add_space(20.0);
+            ui.add(
+                egui::Slider::new(&mut self.options.repaint_fps, MIN_REPAINT_FPS..=MAX_REPAINT_FPS)
+                    .suffix(" fps")
+                    .text("Repaint rate"),
+            );
+        });
+
+        // Relative weight of each monitor in the combined score; 0 leaves a monitor out
+        ui.horizontal(|ui| {
+            ui.label("Weights:");
+            let mut weights = *self.monitoring_data.weights.lock().unwrap();
+            let mut changed = false;
+            for (name, weight) in [
+                ("Mic", &mut weights.mic),
+                ("Thermal", &mut weights.thermal),
+                ("Kernel", &mut weights.kernel),
+                ("Email", &mut weights.email),
+            ] {
+                changed |= ui.add(egui::Slider::new(weight, 0.0..=MAX_WEIGHT).step_by(0.1).text(name)).changed();
+                ui.add_space(10.0);
+            }
+            if changed {
+                *self.monitoring_data.weights.lock().unwrap() = weights;
+                self.settings_dirty = true;
+            }
+        });
+
+        // Detector sensitivity, applied to the running monitors on the next sample
+        let mut tuning = *self.monitoring_data.tuning.lock().unwrap();
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Microphone:");
+            changed |= ui.add(egui::Slider::new(&mut tuning.mic_threshold, 0.0..=1.0).step_by(0.01).text("Threshold"))
+                .on_hover_text("Band power that counts as a detection; raise it in a noisy room")
+                .changed();
+            ui.add_space(10.0);
+
+            // The band has to stay below the Nyquist frequency of the stream in use
+            let nyquist = match self.monitoring_data.fft_data.lock().unwrap().sample_rate {
+                0 => 22050.0,
+                rate => rate as f32 / 2.0,
+            };
+            let max_band = nyquist - 1.0;
+            changed |= ui.add(egui::Slider::new(&mut tuning.min_freq, MIN_BAND_HZ..=max_band - MIN_BAND_WIDTH_HZ).step_by(100.0).suffix(" Hz").text("Band from"))
+                .changed();
+            tuning.max_freq = tuning.max_freq.clamp(tuning.min_freq + MIN_BAND_WIDTH_HZ, max_band);
+            changed |= ui.add(egui::Slider::new(&mut tuning.max_freq, tuning.min_freq + MIN_BAND_WIDTH_HZ..=max_band).step_by(100.0).suffix(" Hz").text("to"))
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Thermal:");
+            changed |= ui.add(egui::Slider::new(&mut tuning.spike_delta, 1.0..=40.0).step_by(0.5).suffix(" °C").text("Spike delta"))
+                .on_hover_text("Temperature rise that counts as a spike")
+                .changed();
+        });
+        if changed {
+            *self.monitoring_data.tuning.lock().unwrap() = tuning;
+            self.settings_dirty = true;
+        }
+
+        // Save once a slider is let go rather than on every step of a drag
+        if self.settings_dirty && !ui.input(|input| input.pointer.any_down()) {
+            self.settings_dirty = false;
+            self.settings_status = self.save_settings().err();
+        }
+        match (&self.options.config_path, &self.settings_status) {
+            (None, _) => {
+                ui.label("Changes last until the GUI closes; start it with --config to keep them");
+            },
+            (Some(_), Some(e)) => {
+                ui.colored_label(Color32::RED, format!("Error saving settings: {}", e));
+            },
+            (Some(path), None) => {
+                ui.label(format!("Changes are saved to {}", path.display()));
+            },
+        }
+    }
+
+    // Write the tuning and weights to the config file the GUI was started with, if any
+    fn save_settings(&self) -> Result<(), String> {
+        let path = match &self.options.config_path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let tuning = *self.monitoring_data.tuning.lock().unwrap();
+        let weights = *self.monitoring_data.weights.lock().unwrap();
+        Config::save_values(path, &[
+            ("mic.threshold", tuning.mic_threshold as f64),
+            ("mic.min_freq", tuning.min_freq as f64),
+            ("mic.max_freq", tuning.max_freq as f64),
+            ("thermal.spike_delta", tuning.spike_delta as f64),
+            ("scoring.weights.mic", weights.mic as f64),
+            ("scoring.weights.thermal", weights.thermal as f64),
+            ("scoring.weights.kernel", weights.kernel as f64),
+            ("scoring.weights.email", weights.email as f64),
+        ])?;
+        info!("Settings saved to {}", path.display());
+        Ok(())
+    }
+
+    // Legend explaining the color bands with their numeric ranges
+    fn render_severity_legend(&self, ui: &mut egui::Ui) {
+        let thresholds = self.options.thresholds;
+        let bands = [
+            (Severity::Low, format!("0-{}", thresholds.low_max)),
+            (Severity::Medium, format!("{}-{}", thresholds.low_max + 1, thresholds.medium_max)),
+            (Severity::High, format!("{}-{}", thresholds.medium_max + 1, thresholds.high_max)),
+            (Severity::Critical, format!("{}-100", thresholds.high_max + 1)),
+        ];
+
+        ui.horizontal(|ui| {
+            ui.label("Severity:");
+            for (severity, range) in bands {
+                let (rect, _) = ui.allocate_exact_size(egui::Vec2::new(12.0, 12.0), egui::Sense::hover());
+                ui.painter().rect_filled(rect, 2.0, Self::band_color(severity));
+                ui.label(format!("{} ({})", severity.name(), range));
+                ui.add_space(8.0);
+            }
+        });
+    }
+
     fn render_threat_scores(&self, ui: &mut egui::Ui) {
         ui.heading("Threat Scores");
 
         let mic_score = *self.monitoring_data.mic_score.lock().unwrap();
         let thermal_score = *self.monitoring_data.thermal_score.lock().unwrap();
         let kernel_score = *self.monitoring_data.kernel_score.lock().unwrap();
+        let surface_score = *self.monitoring_data.surface_score.lock().unwrap();
         let email_score = *self.monitoring_data.email_score.lock().unwrap();
         let combined_score = *self.monitoring_data.combined_score.lock().unwrap();
 
@@ -581,14 +1007,16 @@ impl ThreatSentryApp {
                 ui.label("Microphone:");
                 ui.label(format!("{}", mic_score));
 
+                // A lost device would otherwise just read as a quiet room
+                let mic_health = self.monitoring_data.mic_health.lock().unwrap().clone();
+                match mic_health {
+                    MicHealth::Live => {},
+                    MicHealth::Simulated => { ui.colored_label(Color32::YELLOW, "simulated"); },
+                    _ => { ui.colored_label(Color32::RED, mic_health.to_string()); },
+                }
+
                 // Color indicator
-                let color = if mic_score < 30 {
-                    egui::Color32::GREEN
-                } else if mic_score < 70 {
-                    egui::Color32::YELLOW
-                } else {
-                    egui::Color32::RED
-                };
+                let color = self.gauge_color("mic", mic_score);
 
                 ui.painter().rect_filled(
                     egui::Rect::from_min_size(
@@ -606,13 +1034,7 @@ impl ThreatSentryApp {
                 ui.label(format!("{}", thermal_score));
 
                 // Color indicator
-                let color = if thermal_score < 30 {
-                    egui::Color32::GREEN
-                } else if thermal_score < 70 {
-                    egui::Color32::YELLOW
-                } else {
-                    egui::Color32::RED
-                };
+                let color = self.gauge_color("thermal", thermal_score);
 
                 ui.painter().rect_filled(
                     egui::Rect::from_min_size(
@@ -630,13 +1052,25 @@ impl ThreatSentryApp {
                 ui.label(format!("{}", kernel_score));
 
                 // Color indicator
-                let color = if kernel_score < 30 {
-                    egui::Color32::GREEN
-                } else if kernel_score < 70 {
-                    egui::Color32::YELLOW
-                } else {
-                    egui::Color32::RED
-                };
+                let color = self.gauge_color("kernel", kernel_score);
+
+                ui.painter().rect_filled(
+                    egui::Rect::from_min_size(
+                        ui.cursor().min,
+                        egui::Vec2::new(50.0, 20.0),
+                    ),
+                    0.0,
+                    color,
+                );
+                ui.add_space(25.0);
+            });
+
+            ui.vertical(|ui| {
+                ui.label("Surface Change:");
+                ui.label(format!("{}", surface_score));
+
+                // Color indicator
+                let color = self.gauge_color("surface", surface_score);
 
                 ui.painter().rect_filled(
                     egui::Rect::from_min_size(
@@ -654,13 +1088,7 @@ impl ThreatSentryApp {
                 ui.label(format!("{}", email_score));
 
                 // Color indicator
-                let color = if email_score < 30 {
-                    egui::Color32::GREEN
-                } else if email_score < 70 {
-                    egui::Color32::YELLOW
-                } else {
-                    egui::Color32::RED
-                };
+                let color = self.gauge_color("email", email_score);
 
                 ui.painter().rect_filled(
                     egui::Rect::from_min_size(
@@ -677,14 +1105,16 @@ impl ThreatSentryApp {
                 ui.label("Combined:");
                 ui.label(format!("{}", combined_score));
 
+                let peak = self.monitoring_data.score_history.lock().unwrap()
+                    .recent_scores(Duration::from_secs(60))
+                    .iter()
+                    .map(|&(_, score)| score)
+                    .max()
+                    .unwrap_or(combined_score);
+                ui.small(format!("peak (60s): {}", peak));
+
                 // Color indicator
-                let color = if combined_score < 30 {
-                    egui::Color32::GREEN
-                } else if combined_score < 70 {
-                    egui::Color32::YELLOW
-                } else {
-                    egui::Color32::RED
-                };
+                let color = self.gauge_color("combined", combined_score);
 
                 ui.painter().rect_filled(
                     egui::Rect::from_min_size(
@@ -702,18 +1132,25 @@ impl ThreatSentryApp {
     fn render_fft_visualization(&self, ui: &mut egui::Ui) {
         ui.heading("Frequency Spectrum Analysis");
                 
-        let fft_data = self.monitoring_data.fft_data.lock().unwrap().clone();
-        if !fft_data.is_empty() {
-            let points: PlotPoints = (0..fft_data.len())
-                .map(|i| {
-                    let freq = i as f64 * 22050.0 / fft_data.len() as f64; // Assuming 44.1kHz sample rate
-                    [freq, fft_data[i] as f64]
-                })
+        let spectrum = self.monitoring_data.fft_data.lock().unwrap().clone();
+        if !spectrum.magnitudes.is_empty() {
+            let points: PlotPoints = spectrum.magnitudes.iter()
+                .enumerate()
+                .map(|(i, magnitude)| [spectrum.bin_frequency(i) as f64, *magnitude as f64])
                 .collect();
             
-            // Highlight ultrasonic range
-            let ultrasonic_start = 15000.0;
-            let ultrasonic_end = 20000.0;
+            // Highlight the monitored band
+            let (ultrasonic_start, ultrasonic_end) = spectrum.band;
+
+            // The strongest frequency in the band tells a narrow carrier from broadband noise
+            match spectrum.peak {
+                Some((frequency, magnitude)) => {
+                    ui.label(format!("Peak: {:.1} kHz (magnitude {:.2})", frequency / 1000.0, magnitude));
+                },
+                None => {
+                    ui.label("Peak: none in band");
+                },
+            }
             
             Plot::new("fft_plot")
                 .height(120.0)
@@ -726,8 +1163,12 @@ impl ThreatSentryApp {
                     plot_ui.line(Line::new(points).name("Frequency Spectrum").color(Color32::LIGHT_BLUE));
                     
                     // Highlight ultrasonic range
-                    plot_ui.vline(egui_plot::VLine::new(ultrasonic_start).color(Color32::RED).width(1.0));
-                    plot_ui.vline(egui_plot::VLine::new(ultrasonic_end).color(Color32::RED).width(1.0));
+                    plot_ui.vline(egui_plot::VLine::new(ultrasonic_start as f64).color(Color32::RED).width(1.0));
+                    plot_ui.vline(egui_plot::VLine::new(ultrasonic_end as f64).color(Color32::RED).width(1.0));
+
+                    if let Some((frequency, _)) = spectrum.peak {
+                        plot_ui.vline(egui_plot::VLine::new(frequency as f64).color(Color32::YELLOW).width(1.0).name("Peak"));
+                    }
                 });
         } else {
             ui.label("No frequency data available. Start monitoring to collect data.");
@@ -740,9 +1181,16 @@ impl ThreatSentryApp {
         let threat_origins = self.monitoring_data.threat_origins.lock().unwrap().clone();
         let mut selected_threat = self.monitoring_data.selected_threat.lock().unwrap();
         let threat_details = self.monitoring_data.threat_details.lock().unwrap().clone();
+
+        // The save dialog blocks until it's closed, so the report is generated once the
+        // selection lock is released
+        let mut report_request = None;
         
         if threat_origins.is_empty() {
             ui.label("No active threats detected for analysis.");
+            if ui.button("Generate Report").clicked() {
+                report_request = Some(None);
+            }
         } else {
             // Show the list of threats
             egui::Grid::new("threats_grid").num_columns(4).striped(true).show(ui, |ui| {
@@ -753,11 +1201,11 @@ impl ThreatSentryApp {
                 ui.end_row();
                 
                 for origin in &threat_origins {
-                    ui.label(&origin.country);
+                    ui.label(&origin.place);
                     ui.label(&origin.threat_type);
                     ui.label(format!("{}", origin.threat_count));
                     
-                    let detail_key = format!("{}: {}", origin.country, origin.threat_type);
+                    let detail_key = format!("{}: {}", origin.place, origin.threat_type);
                     if ui.button("Analyze").clicked() {
                         *selected_threat = Some(detail_key.clone());
                     }
@@ -766,6 +1214,7 @@ impl ThreatSentryApp {
             });
             
             // Show details for selected threat
+            let mut close_analysis = false;
             if let Some(ref key) = *selected_threat {
                 if let Some(details) = threat_details.get(key) {
                     ui.separator();
@@ -794,35 +1243,125 @@ impl ThreatSentryApp {
                             // This would actually perform isolation in a real implementation
                         }
                         if ui.button("Generate Report").clicked() {
-                            // This would generate a report in a real implementation
+                            report_request = Some(Some((key.clone(), details.clone())));
                         }
                         if ui.button("Close Analysis").clicked() {
-                            *selected_threat = None;
+                            close_analysis = true;
                         }
                     });
                 }
             }
+            if close_analysis {
+                *selected_threat = None;
+            }
         }
+        drop(selected_threat);
+
+        if let Some(selected) = report_request {
+            self.generate_report(selected);
+        }
+
+        if let Some(status) = self.report_status.lock().unwrap().as_ref() {
+            ui.label(status);
+        }
+    }
+
+    // Asks where to save, then writes the session report there. Cancelling the dialog writes nothing
+    fn generate_report(&self, selected_threat: Option<(String, String)>) {
+        let path = match rfd::FileDialog::new()
+            .set_file_name(report::DEFAULT_REPORT_FILE)
+            .add_filter("HTML report", &["html", "htm"])
+            .save_file()
+        {
+            Some(path) => path,
+            None => return,
+        };
+
+        let data = &self.monitoring_data;
+        let mut scores = vec![
+            ("Microphone".to_string(), *data.mic_score.lock().unwrap()),
+            ("Thermal".to_string(), *data.thermal_score.lock().unwrap()),
+            ("Kernel".to_string(), *data.kernel_score.lock().unwrap()),
+            ("Attack Surface".to_string(), *data.surface_score.lock().unwrap()),
+        ];
+        if self.credentials.is_some() {
+            scores.push(("Email".to_string(), *data.email_score.lock().unwrap()));
+        }
+        scores.push(("Combined".to_string(), *data.combined_score.lock().unwrap()));
+
+        // Scores over the whole kept history, as seconds before now
+        let as_points = |history: &ScoreHistory| -> Vec<(f64, f64)> {
+            history.recent_scores(Duration::MAX).into_iter()
+                .map(|(age, score)| (-age, score as f64))
+                .collect()
+        };
+        let histories = data.monitor_score_history.lock().unwrap();
+        let mut score_history: Vec<ChartSeries> = [("mic", "Microphone"), ("thermal", "Thermal"), ("kernel", "Kernel"), ("email", "Email")]
+            .iter()
+            .filter_map(|(monitor, label)| histories.get(*monitor).map(|history| ChartSeries {
+                name: label.to_string(),
+                points: as_points(history),
+            }))
+            .collect();
+        drop(histories);
+        score_history.push(ChartSeries {
+            name: "Combined".to_string(),
+            points: as_points(&data.score_history.lock().unwrap()),
+        });
+
+        // Temperature and microphone samples as seconds before now, like the scores
+        let now = unix_time();
+        let before_now = |history: &[(f64, f32)]| -> Vec<(f64, f64)> {
+            history.iter().map(|(time, value)| (time - now, *value as f64)).collect()
+        };
+
+        let session = SessionReport {
+            run_id: self.options.run_id.clone(),
+            duration: self.start_time.elapsed(),
+            thresholds: self.options.thresholds,
+            scores,
+            score_history,
+            temperature: before_now(&data.temperature_history.lock().unwrap()),
+            mic_power: before_now(&data.mic_power_history.lock().unwrap()),
+            urls: data.urls.lock().unwrap().clone(),
+            processes: data.suspicious_processes.lock().unwrap().clone(),
+            connections: data.suspicious_connections.lock().unwrap().clone(),
+            usb_devices: data.new_usb_devices.lock().unwrap().clone(),
+            selected_threat,
+        };
+
+        let status = match session.write(&path) {
+            Ok(_) => {
+                info!("Report saved to {}", path.display());
+                format!("Report saved to {}", path.display())
+            },
+            Err(e) => {
+                error!("Error writing report: {}", e);
+                format!("Error writing report: {}", e)
+            },
+        };
+        *self.report_status.lock().unwrap() = Some(status);
     }
     
     fn render_classic_view(&self, ui: &mut egui::Ui) {
         // Temperature graph
         ui.heading("Temperature History");
 
-        let temp_history = self.monitoring_data.temperature_history.lock().unwrap().clone();
-        let time_history = self.monitoring_data.time_history.lock().unwrap().clone();
-
-        if !temp_history.is_empty() && temp_history.len() == time_history.len() {
-            let points: PlotPoints = (0..temp_history.len())
-                .map(|i| [time_history[i], temp_history[i] as f64])
-                .collect();
+        if let Some((label, temp)) = self.monitoring_data.hottest_component.lock().unwrap().clone() {
+            ui.label(format!("Hottest component: {} at {:.1}°C", label, temp));
+        }
 
-            let line = Line::new(points).name("Temperature (°C)");
+        let temp_history = self.monitoring_data.temperature_history.lock().unwrap().clone();
 
+        if !temp_history.is_empty() {
             Plot::new("temperature_plot")
                 .view_aspect(3.0)
+                .x_axis_formatter(clock_label)
+                .label_formatter(|name, value| format!("{}\n{}: {:.1}", clock_time(value.x, "%Y-%m-%d %H:%M:%S"), name, value.y))
                 .show(ui, |plot_ui| {
-                    plot_ui.line(line);
+                    for segment in session_segments(&temp_history) {
+                        plot_ui.line(Line::new(segment).name("Temperature (°C)").color(Color32::from_rgb(255, 165, 0)));
+                    }
                 });
         } else {
             ui.label("No temperature data yet");
@@ -835,17 +1374,15 @@ impl ThreatSentryApp {
 
         let mic_history = self.monitoring_data.mic_power_history.lock().unwrap().clone();
 
-        if !mic_history.is_empty() && mic_history.len() == time_history.len() {
-            let points: PlotPoints = (0..mic_history.len())
-                .map(|i| [time_history[i], mic_history[i] as f64])
-                .collect();
-
-            let line = Line::new(points).name("Microphone Power");
-
+        if !mic_history.is_empty() {
             Plot::new("microphone_plot")
                 .view_aspect(3.0)
+                .x_axis_formatter(clock_label)
+                .label_formatter(|name, value| format!("{}\n{}: {:.3}", clock_time(value.x, "%Y-%m-%d %H:%M:%S"), name, value.y))
                 .show(ui, |plot_ui| {
-                    plot_ui.line(line);
+                    for segment in session_segments(&mic_history) {
+                        plot_ui.line(Line::new(segment).name("Microphone Power").color(Color32::LIGHT_BLUE));
+                    }
                 });
         } else {
             ui.label("No microphone data yet");
@@ -858,19 +1395,19 @@ impl ThreatSentryApp {
 
         let urls = self.monitoring_data.urls.lock().unwrap().clone();
 
-        if !urls.is_empty() {
-            for (url, score) in urls {
-                let color = if score < 30 {
-                    egui::Color32::GREEN
-                } else if score < 70 {
-                    egui::Color32::YELLOW
-                } else {
-                    egui::Color32::RED
-                };
+        if self.credentials.is_none() {
+            ui.label("No email account configured");
+        } else if !urls.is_empty() {
+            for result in urls {
+                let color = self.severity_color(result.score);
 
                 ui.horizontal(|ui| {
-                    ui.colored_label(color, format!("[{}]", score));
-                    ui.label(url);
+                    ui.colored_label(color, format!("[{}]", result.score));
+                    let hover = match &result.phishtank_match {
+                        Some(raw) => format!("{}\n\nPhishTank: {}", result.snippet, raw),
+                        None => result.snippet.clone(),
+                    };
+                    ui.label(&result.url).on_hover_text(hover);
                 });
             }
         } else {
@@ -885,9 +1422,15 @@ impl ThreatSentryApp {
         let processes = self.monitoring_data.suspicious_processes.lock().unwrap().clone();
 
         if !processes.is_empty() {
-            for process in processes {
+            for (process, memory_resident) in processes {
                 ui.horizontal(|ui| {
-                    ui.colored_label(egui::Color32::YELLOW, "⚠");
+                    if memory_resident {
+                        ui.colored_label(egui::Color32::RED, "⚠");
+                        ui.label(egui::RichText::new(" MEMORY-RESIDENT ").strong().color(Color32::WHITE).background_color(Color32::DARK_RED))
+                            .on_hover_text("The executable image is no longer on disk; the process runs from memory only");
+                    } else {
+                        ui.colored_label(egui::Color32::YELLOW, "⚠");
+                    }
                     ui.label(process);
                 });
             }
@@ -897,6 +1440,68 @@ impl ThreatSentryApp {
 
         ui.separator();
 
+        // Top talkers by outbound throughput
+        ui.heading("Top Talkers");
+
+        match self.monitoring_data.top_talkers.lock().unwrap().clone() {
+            Some(talkers) if !talkers.is_empty() => {
+                for talker in talkers {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(egui::Color32::LIGHT_BLUE, "⇅");
+                        ui.label(talker);
+                    });
+                }
+            },
+            Some(_) => {
+                ui.label("No throughput data yet");
+            },
+            None => {
+                ui.label("Per-process network throughput unavailable");
+            },
+        }
+
+        ui.separator();
+
+        // Outbound connections to non-standard ports
+        ui.heading("Suspicious Connections");
+
+        let connections = self.monitoring_data.suspicious_connections.lock().unwrap().clone();
+
+        if !connections.is_empty() {
+            for connection in connections {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::YELLOW, "⚠");
+                    ui.label(connection);
+                });
+            }
+        } else {
+            ui.label("No suspicious connections detected");
+        }
+
+        ui.separator();
+
+        // Full process list for manual triage
+        if self.show_all_processes {
+            ui.heading("All Processes");
+
+            let all_processes = self.monitoring_data.all_processes.lock().unwrap().clone();
+
+            if !all_processes.is_empty() {
+                egui::ScrollArea::vertical()
+                    .id_source("all_processes_scroll")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for process in all_processes {
+                            ui.label(process);
+                        }
+                    });
+            } else {
+                ui.label("No process data yet");
+            }
+
+            ui.separator();
+        }
+
         // USB Devices
         ui.heading("USB Devices");
 
@@ -989,24 +1594,17 @@ impl ThreatSentryApp {
             ui.add_space(300.0); // Push below the visualization
             
             egui::Grid::new("activity_grid").show(ui, |ui| {
-                let text_color = |score: u8| -> Color32 {
-                    match score {
-                        0..=30 => Color32::GREEN,
-                        31..=70 => Color32::YELLOW,
-                        _ => Color32::RED,
-                    }
-                };
                 
                 ui.strong("Microphone Activity:");
-                ui.colored_label(text_color(mic_score), format!("{}/100", mic_score));
+                ui.colored_label(self.gauge_color("mic", mic_score), format!("{}/100", mic_score));
                 ui.end_row();
                 
                 ui.strong("Thermal Activity:");
-                ui.colored_label(text_color(thermal_score), format!("{}/100", thermal_score));
+                ui.colored_label(self.gauge_color("thermal", thermal_score), format!("{}/100", thermal_score));
                 ui.end_row();
                 
                 ui.strong("Kernel Activity:");
-                ui.colored_label(text_color(kernel_score), format!("{}/100", kernel_score));
+                ui.colored_label(self.gauge_color("kernel", kernel_score), format!("{}/100", kernel_score));
                 ui.end_row();
             });
         });
@@ -1018,53 +1616,35 @@ impl ThreatSentryApp {
         // Get threat origins data
         let threat_origins = self.monitoring_data.threat_origins.lock().unwrap().clone();
         
-        // Draw a simplified world map
+        // Equirectangular world map, as large as fits the panel at 2:1 so it isn't distorted
         let (response, painter) = ui.allocate_painter(
             egui::vec2(ui.available_width(), 350.0),
             egui::Sense::click_and_drag(),
         );
         
         let rect = response.rect;
-        
-        // Draw a basic world map outline (very simplified)
         painter.rect_filled(rect, 0.0, Color32::from_rgb(10, 20, 40)); // Dark blue background
-        
-        // Draw continent outlines (very simplified)
-        let continents = [
-            // North America
-            vec![
-                [0.1, 0.2], [0.2, 0.2], [0.3, 0.3], [0.25, 0.4], [0.2, 0.45], [0.1, 0.3]
-            ],
-            // South America
-            vec![
-                [0.25, 0.5], [0.3, 0.5], [0.35, 0.7], [0.25, 0.8], [0.2, 0.6]
-            ],
-            // Europe
-            vec![
-                [0.45, 0.2], [0.55, 0.2], [0.55, 0.35], [0.45, 0.35]
-            ],
-            // Africa
-            vec![
-                [0.45, 0.4], [0.55, 0.4], [0.55, 0.7], [0.45, 0.7]
-            ],
-            // Asia
-            vec![
-                [0.55, 0.2], [0.8, 0.2], [0.8, 0.5], [0.6, 0.5], [0.55, 0.4]
-            ],
-            // Australia
-            vec![
-                [0.8, 0.6], [0.9, 0.6], [0.9, 0.7], [0.8, 0.7]
-            ],
-        ];
-        
-        for continent in continents.iter() {
-            let points: Vec<egui::Pos2> = continent.iter()
-                .map(|[x, y]| {
-                    egui::pos2(
-                        rect.left() + x * rect.width(),
-                        rect.top() + y * rect.height()
-                    )
-                })
+
+        let map_width = rect.width().min(rect.height() * 2.0);
+        let map = egui::Rect::from_center_size(rect.center(), egui::vec2(map_width, map_width / 2.0));
+        let to_screen = |longitude: f32, latitude: f32| {
+            let (x, y) = world_map::project(longitude, latitude);
+            egui::pos2(map.left() + x * map.width(), map.top() + y * map.height())
+        };
+
+        // Graticule every 30 degrees
+        let grid_stroke = egui::Stroke::new(0.5, Color32::from_rgb(25, 45, 70));
+        for longitude in (-180..=180).step_by(30) {
+            painter.line_segment([to_screen(longitude as f32, 90.0), to_screen(longitude as f32, -90.0)], grid_stroke);
+        }
+        for latitude in (-90..=90).step_by(30) {
+            painter.line_segment([to_screen(-180.0, latitude as f32), to_screen(180.0, latitude as f32)], grid_stroke);
+        }
+
+        // Coastlines
+        for outline in world_map::LAND.iter().chain(world_map::INLAND_SEAS) {
+            let points: Vec<egui::Pos2> = outline.iter()
+                .map(|[longitude, latitude]| to_screen(*longitude, *latitude))
                 .collect();
             
             painter.add(egui::Shape::Path(egui::epaint::PathShape::closed_line(
@@ -1075,29 +1655,24 @@ impl ThreatSentryApp {
         
         // Draw threat points
         for origin in threat_origins.iter() {
-            // Convert lat/long to x/y coordinates (simple mapping)
-            // Note: real implementation would use proper map projection
-            let x = rect.left() + ((origin.longitude + 180.0) / 360.0) * rect.width();
-            let y = rect.top() + ((origin.latitude + 90.0) / 180.0) * rect.height();
+            let position = to_screen(origin.longitude, origin.latitude);
             
             // Determine color based on threat type
             let color = match origin.threat_type.as_str() {
-                "Phishing" => Color32::from_rgb(255, 100, 100),
-                "Ultrasonic Beacon" => Color32::from_rgb(255, 255, 100),
-                "Cryptominer" => Color32::from_rgb(255, 165, 0),
-                "System Exploit" => Color32::from_rgb(255, 50, 255),
+                PHISHING => Color32::from_rgb(255, 100, 100),
+                SUSPICIOUS_CONNECTION => Color32::from_rgb(255, 165, 0),
                 _ => Color32::WHITE,
             };
             
             // Draw threat point
             let size = 5.0 + (origin.threat_count as f32).min(10.0);
-            painter.circle_filled(egui::pos2(x, y), size, color);
+            painter.circle_filled(position, size, color);
             
             // Draw threat label
             painter.text(
-                egui::pos2(x + size + 5.0, y), 
+                position + egui::vec2(size + 5.0, 0.0), 
                 egui::Align2::LEFT_CENTER, 
-                &origin.country, 
+                &origin.place, 
                 egui::FontId::default(), 
                 Color32::WHITE,
             );
@@ -1108,10 +1683,8 @@ impl ThreatSentryApp {
         let legend_y = rect.top() + 20.0;
         
         let threat_types = [
-            ("Phishing", Color32::from_rgb(255, 100, 100)),
-            ("Ultrasonic", Color32::from_rgb(255, 255, 100)),
-            ("Cryptominer", Color32::from_rgb(255, 165, 0)),
-            ("System Exploit", Color32::from_rgb(255, 50, 255)),
+            (PHISHING, Color32::from_rgb(255, 100, 100)),
+            (SUSPICIOUS_CONNECTION, Color32::from_rgb(255, 165, 0)),
         ];
         
         for (i, (threat_type, color)) in threat_types.iter().enumerate() {
@@ -1143,18 +1716,98 @@ impl ThreatSentryApp {
                     let most_active = threat_origins.iter()
                         .max_by_key(|o| o.threat_count)
                         .unwrap();
-                    ui.strong(format!("Most Active: {} ({})", most_active.country, most_active.threat_count));
+                    ui.strong(format!("Most Active: {} ({})", most_active.place, most_active.threat_count));
                 }
             });
             
-            if !threat_origins.is_empty() {
+            if self.options.geoip_db.is_none() {
+                ui.label("No GeoIP database given (--geoip-db), so phishing URLs and suspicious connections can't be located.");
+            } else if !threat_origins.is_empty() {
                 ui.label("Click 'Threat Analysis' for detailed examination of each threat vector.");
             }
         });
     }
 }
 
-pub fn run_gui(username: String, password: String) -> Result<(), eframe::Error> {
+fn unix_time() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |elapsed| elapsed.as_secs_f64())
+}
+
+// Local wall-clock time of a Unix timestamp
+fn clock_time(unix_seconds: f64, format: &str) -> String {
+    chrono::DateTime::from_timestamp(unix_seconds as i64, 0)
+        .map(|time| time.with_timezone(&chrono::Local).format(format).to_string())
+        .unwrap_or_default()
+}
+
+// X-axis labels of the history charts: the time of day, with the date once the chart spans
+// more than a day
+fn clock_label(unix_seconds: f64, _max_chars: usize, range: &RangeInclusive<f64>) -> String {
+    let format = if range.end() - range.start() > 86400.0 { "%m-%d %H:%M" } else { "%H:%M:%S" };
+    clock_time(unix_seconds, format)
+}
+
+// Chart points split wherever samples are more than SESSION_GAP_SECS apart, so sessions
+// restored from earlier runs aren't joined by a line across the time the GUI was closed
+fn session_segments(history: &[(f64, f32)]) -> Vec<Vec<[f64; 2]>> {
+    let mut segments: Vec<Vec<[f64; 2]>> = Vec::new();
+    let mut previous: Option<f64> = None;
+    for (time, value) in history {
+        match previous {
+            Some(previous) if time - previous <= SESSION_GAP_SECS => {}
+            _ => segments.push(Vec::new()),
+        }
+        if let Some(segment) = segments.last_mut() {
+            segment.push([*time, *value as f64]);
+        }
+        previous = Some(*time);
+    }
+    segments
+}
+
+// Groups located indicators into one map marker per place and threat type, with the drill-down
+// text for each listing the indicators behind it
+fn summarize_origins(located: &[(GeoLocation, &str, &str)]) -> (Vec<ThreatOrigin>, HashMap<String, String>) {
+    let mut origins: Vec<ThreatOrigin> = Vec::new();
+    let mut indicators: HashMap<String, Vec<&str>> = HashMap::new();
+
+    for (location, threat_type, indicator) in located {
+        match origins.iter_mut().find(|origin| origin.place == location.place && origin.threat_type == *threat_type) {
+            Some(origin) => origin.threat_count += 1,
+            None => origins.push(ThreatOrigin {
+                place: location.place.clone(),
+                latitude: location.latitude,
+                longitude: location.longitude,
+                threat_count: 1,
+                threat_type: threat_type.to_string(),
+            }),
+        }
+        indicators.entry(format!("{}: {}", location.place, threat_type)).or_default().push(*indicator);
+    }
+
+    let details = origins.iter()
+        .map(|origin| {
+            let key = format!("{}: {}", origin.place, origin.threat_type);
+            let (description, mitigation) = match origin.threat_type.as_str() {
+                PHISHING => ("Phishing URLs hosted here", "Block the domains (--blocklist) and warn the recipients"),
+                _ => ("Outbound connections to non-standard ports", "Check the owning processes; block the addresses at the firewall if they aren't expected"),
+            };
+            let value = format!(
+                "Origin: {}\nType: {}\nCount: {}\nIndicators:\n  {}\nMitigation: {}",
+                origin.place,
+                description,
+                origin.threat_count,
+                indicators[&key].join("\n  "),
+                mitigation,
+            );
+            (key, value)
+        })
+        .collect();
+
+    (origins, details)
+}
+
+pub fn run_gui(credentials: Option<(String, String)>, gui_options: GuiOptions) -> Result<(), eframe::Error> {
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1000.0, 700.0])
@@ -1167,6 +1820,12 @@ pub fn run_gui(username: String, password: String) -> Result<(), eframe::Error>
     eframe::run_native(
         "ThreatSentry Ultra",
         options,
-        Box::new(|_cc| Box::new(ThreatSentryApp::new(username, password)))
+        Box::new(|cc| {
+            let app = ThreatSentryApp::new(credentials, gui_options);
+            if let Some(storage) = cc.storage {
+                app.load_history(storage);
+            }
+            Box::new(app)
+        })
     )
 }
@@ -0,0 +1,207 @@
+// Scan history in SQLite, to answer questions like "how many high thermal scores last week?".
+// Every monitor run records its score, and discrete events (thermal spikes, new USB devices,
+// phishing URLs) are recorded as they're found. Nothing is stored unless a database path is
+// given, and builds without the `history` feature don't include SQLite at all
+#[cfg(feature = "history")]
+use rusqlite::{params, Connection};
+#[cfg(feature = "history")]
+use std::fs;
+use std::path::Path;
+#[cfg(feature = "history")]
+use std::sync::Mutex;
+
+// Default period `history` summarizes
+pub const DEFAULT_SINCE: &str = "7d";
+
+#[cfg(feature = "history")]
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS events (
+        id INTEGER PRIMARY KEY,
+        timestamp INTEGER NOT NULL,
+        run_id TEXT NOT NULL,
+        monitor TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        score INTEGER NOT NULL,
+        detail TEXT NOT NULL DEFAULT ''
+    );
+    CREATE INDEX IF NOT EXISTS events_timestamp ON events (timestamp);
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    // Score of a monitor at the end of a run
+    Score,
+    ThermalSpike,
+    UsbInserted,
+    PhishingUrl,
+}
+
+impl EventKind {
+    #[cfg_attr(not(feature = "history"), allow(dead_code))]
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::Score => "score",
+            EventKind::ThermalSpike => "thermal_spike",
+            EventKind::UsbInserted => "usb_inserted",
+            EventKind::PhishingUrl => "phishing_url",
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "history"), allow(dead_code))]
+pub struct HistoryEvent {
+    pub monitor: String,
+    pub kind: EventKind,
+    pub score: u8,
+    pub detail: String, // What was found: the URL, the device, the peak temperature
+}
+
+impl HistoryEvent {
+    pub fn new(monitor: &str, kind: EventKind, score: u8, detail: &str) -> Self {
+        HistoryEvent {
+            monitor: monitor.to_string(),
+            kind,
+            score,
+            detail: detail.to_string(),
+        }
+    }
+
+    // Score sample of a monitor run
+    pub fn score(monitor: &str, score: u8) -> Self {
+        Self::new(monitor, EventKind::Score, score, "")
+    }
+}
+
+// Score samples of one monitor over the summarized period
+pub struct MonitorSummary {
+    pub monitor: String,
+    pub samples: i64,
+    pub average: f64,
+    pub max: u8,
+    pub high: i64, // Samples in the high severity band
+}
+
+// Discrete events of one kind from one monitor over the summarized period
+pub struct EventCount {
+    pub monitor: String,
+    pub kind: String,
+    pub count: i64,
+}
+
+pub struct HistoryStore {
+    #[cfg(feature = "history")]
+    connection: Mutex<Connection>,
+    #[cfg(feature = "history")]
+    run_id: String,
+}
+
+impl HistoryStore {
+    // Open the database, creating it (and its directory) on first use
+    #[cfg(feature = "history")]
+    pub fn open(path: &Path, run_id: &str) -> Result<Self, String> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+
+        let connection = Connection::open(path)
+            .map_err(|e| format!("Failed to open history database {}: {}", path.display(), e))?;
+        connection.execute_batch(SCHEMA)
+            .map_err(|e| format!("Failed to set up history database {}: {}", path.display(), e))?;
+
+        Ok(HistoryStore {
+            connection: Mutex::new(connection),
+            run_id: run_id.to_string(),
+        })
+    }
+
+    #[cfg(not(feature = "history"))]
+    pub fn open(_path: &Path, _run_id: &str) -> Result<Self, String> {
+        Err("this build has no scan history support (rebuild with the `history` feature)".to_string())
+    }
+
+    #[cfg(feature = "history")]
+    pub fn record(&self, event: &HistoryEvent) -> Result<(), String> {
+        self.connection.lock().unwrap()
+            .execute(
+                "INSERT INTO events (timestamp, run_id, monitor, kind, score, detail) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![chrono::Utc::now().timestamp(), self.run_id, event.monitor, event.kind.name(), event.score, event.detail],
+            )
+            .map(|_| ())
+            .map_err(|e| format!("Failed to record history: {}", e))
+    }
+
+    #[cfg(not(feature = "history"))]
+    pub fn record(&self, _event: &HistoryEvent) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Per-monitor score statistics and event counts since a Unix timestamp; samples above
+    // high_above count as high severity
+    #[cfg(feature = "history")]
+    pub fn summarize(&self, since: i64, high_above: u8) -> Result<(Vec<MonitorSummary>, Vec<EventCount>), String> {
+        let connection = self.connection.lock().unwrap();
+        let query_error = |e: rusqlite::Error| format!("Failed to query history: {}", e);
+
+        let mut statement = connection.prepare(
+            "SELECT monitor, COUNT(*), AVG(score), MAX(score), SUM(score > ?2) FROM events
+             WHERE kind = 'score' AND timestamp >= ?1 GROUP BY monitor ORDER BY monitor",
+        ).map_err(query_error)?;
+        let monitors = statement.query_map(params![since, high_above], |row| {
+            Ok(MonitorSummary {
+                monitor: row.get(0)?,
+                samples: row.get(1)?,
+                average: row.get(2)?,
+                max: row.get(3)?,
+                high: row.get(4)?,
+            })
+        }).map_err(query_error)?.collect::<Result<Vec<_>, _>>().map_err(query_error)?;
+
+        let mut statement = connection.prepare(
+            "SELECT monitor, kind, COUNT(*) FROM events
+             WHERE kind != 'score' AND timestamp >= ?1 GROUP BY monitor, kind ORDER BY monitor, kind",
+        ).map_err(query_error)?;
+        let events = statement.query_map(params![since], |row| {
+            Ok(EventCount {
+                monitor: row.get(0)?,
+                kind: row.get(1)?,
+                count: row.get(2)?,
+            })
+        }).map_err(query_error)?.collect::<Result<Vec<_>, _>>().map_err(query_error)?;
+
+        Ok((monitors, events))
+    }
+
+    #[cfg(not(feature = "history"))]
+    pub fn summarize(&self, _since: i64, _high_above: u8) -> Result<(Vec<MonitorSummary>, Vec<EventCount>), String> {
+        Ok((Vec::new(), Vec::new()))
+    }
+}
+
+// Start of a `--since` period as a Unix timestamp: a relative age ("30m", "12h", "7d"),
+// a local date ("2024-05-01") or an RFC 3339 time
+pub fn parse_since(value: &str) -> Result<i64, String> {
+    let now = chrono::Utc::now().timestamp();
+    let unit_secs = match value.chars().last() {
+        Some('m') => Some(60),
+        Some('h') => Some(3600),
+        Some('d') => Some(86400),
+        Some('w') => Some(7 * 86400),
+        _ => None,
+    };
+    if let Some(unit_secs) = unit_secs {
+        if let Ok(count) = value[..value.len() - 1].parse::<i64>() {
+            return Ok(now - count * unit_secs);
+        }
+    }
+
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return date.and_hms_opt(0, 0, 0)
+            .and_then(|midnight| midnight.and_local_timezone(chrono::Local).earliest())
+            .map(|midnight| midnight.timestamp())
+            .ok_or_else(|| format!("'{}' has no local midnight", value));
+    }
+
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|time| time.timestamp())
+        .map_err(|_| format!("'{}' is not an age like 7d, a date like 2024-05-01, or an RFC 3339 time", value))
+}
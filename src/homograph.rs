@@ -0,0 +1,101 @@
+// Detection of IDN homograph domains: punycode (xn--) labels that render as, or mix scripts
+// to imitate, a well-known brand, e.g. xn--80ak6aa92e.com showing as "аррӏе.com"
+
+// Brands commonly imitated in phishing domains, compared against each label's skeleton
+pub const KNOWN_BRANDS: [&str; 20] = [
+    "apple", "paypal", "google", "microsoft", "amazon", "facebook", "netflix", "instagram",
+    "whatsapp", "linkedin", "outlook", "office", "icloud", "dropbox", "adobe", "yahoo",
+    "chase", "wellsfargo", "bankofamerica", "coinbase",
+];
+
+// Non-Latin characters that render (nearly) identically to a Latin letter
+const CONFUSABLES: [(char, char); 32] = [
+    // Cyrillic
+    ('а', 'a'), ('е', 'e'), ('о', 'o'), ('р', 'p'), ('с', 'c'), ('у', 'y'), ('х', 'x'),
+    ('і', 'i'), ('ј', 'j'), ('ѕ', 's'), ('ԁ', 'd'), ('һ', 'h'), ('ӏ', 'l'), ('ԛ', 'q'),
+    ('ԝ', 'w'), ('к', 'k'), ('в', 'b'), ('н', 'h'), ('т', 't'), ('м', 'm'),
+    // Greek
+    ('α', 'a'), ('ο', 'o'), ('ρ', 'p'), ('ν', 'v'), ('κ', 'k'), ('ι', 'i'), ('τ', 't'),
+    ('υ', 'u'), ('χ', 'x'), ('ε', 'e'),
+    // Latin lookalikes outside ASCII
+    ('ɡ', 'g'), ('ı', 'i'),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Other,
+}
+
+// Why a domain was flagged, with the Unicode form a mail client would show
+#[derive(Debug, Clone)]
+pub struct HomographFinding {
+    pub rendered: String,
+    pub reason: String,
+}
+
+// Check a host name; only domains with punycode labels can be homographs
+pub fn analyze_domain(host: &str) -> Option<HomographFinding> {
+    let host = host.to_lowercase();
+    if !host.split('.').any(|label| label.starts_with("xn--")) {
+        return None;
+    }
+
+    let (rendered, result) = idna::domain_to_unicode(&host);
+    if result.is_err() {
+        return None;
+    }
+
+    for label in rendered.split('.') {
+        if label.is_ascii() {
+            continue;
+        }
+
+        let skeleton = skeleton(label);
+        if let Some(brand) = KNOWN_BRANDS.iter().find(|brand| skeleton.contains(*brand)) {
+            return Some(HomographFinding {
+                reason: format!("\"{}\" imitates {}", label, brand),
+                rendered,
+            });
+        }
+
+        if is_mixed_script(label) {
+            return Some(HomographFinding {
+                reason: format!("\"{}\" mixes Latin with lookalike scripts", label),
+                rendered,
+            });
+        }
+    }
+
+    None
+}
+
+// Label with every confusable character replaced by the Latin letter it imitates
+fn skeleton(label: &str) -> String {
+    label.chars()
+        .map(|c| CONFUSABLES.iter().find(|(confusable, _)| *confusable == c).map_or(c, |(_, latin)| *latin))
+        .collect()
+}
+
+fn script_of(c: char) -> Script {
+    match c {
+        'a'..='z' | 'A'..='Z' => Script::Latin,
+        '\u{0400}'..='\u{052F}' => Script::Cyrillic,
+        '\u{0370}'..='\u{03FF}' => Script::Greek,
+        _ if c.is_alphabetic() && (c as u32) < 0x0250 => Script::Latin,
+        _ => Script::Other,
+    }
+}
+
+// Latin letters alongside Cyrillic or Greek ones in the same label
+fn is_mixed_script(label: &str) -> bool {
+    let scripts: Vec<Script> = label.chars()
+        .filter(|c| c.is_alphabetic())
+        .map(script_of)
+        .collect();
+
+    scripts.contains(&Script::Latin)
+        && (scripts.contains(&Script::Cyrillic) || scripts.contains(&Script::Greek))
+}
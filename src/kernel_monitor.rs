@@ -1,32 +1,300 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
 use std::process::Command;
 use std::time::{Duration, Instant};
 use std::thread;
 use std::sync::{Arc, Mutex};
+use sysinfo::{Pid, System};
+use netstat2::{get_sockets_info, AddressFamilyFlags, ProtocolFlags, ProtocolSocketInfo, TcpState};
+use tracing::{debug, info};
+
+use crate::monitor::{Finding, FindingKind, Monitor, MonitorSample};
+
+// Memory sizes are always handled in bytes; this converts them for display and scoring
+const BYTES_PER_MB: f32 = 1024.0 * 1024.0;
+
+// Resident memory above which a process counts as suspicious on its own
+const SUSPICIOUS_MEMORY_MB: f32 = 500.0;
+
+// Outbound TCP rate (bytes/sec) that counts as high, how many consecutive process checks it has
+// to last before the process is flagged, and the score that adds
+const HIGH_OUTBOUND_BYTES_PER_SEC: f32 = 1024.0 * 1024.0;
+const SUSTAINED_OUTBOUND_SAMPLES: u32 = 3;
+const HIGH_OUTBOUND_SCORE_BUMP: u8 = 30;
+
+// Score added to a process whose image is no longer on disk (deleted after launch, or hollowed).
+// Legitimate software almost never does this, so it is enough to reach the high band alone
+const MEMORY_RESIDENT_SCORE_BUMP: u8 = 70;
+
+// Score added to an unsigned executable running from a temp or downloads directory, where
+// droppers stage their payloads and installed software almost never lives
+const UNSIGNED_STAGED_SCORE_BUMP: u8 = 60;
+
+// Score added to a process started by a parent that has no business starting it (a shell
+// launched by a document viewer or a web server), the usual living-off-the-land pattern
+const SUSPICIOUS_SPAWN_SCORE_BUMP: u8 = 60;
+
+// Apps that open untrusted documents or serve untrusted requests
+const DOCUMENT_APPS: [&str; 10] = [
+    "winword", "excel", "powerpnt", "outlook", "msaccess",
+    "mspub", "onenote", "visio", "acrord32", "acrobat",
+];
+const SERVER_PROCESSES: [&str; 7] = ["w3wp", "httpd", "apache2", "nginx", "php-fpm", "tomcat", "sqlservr"];
+
+// Shells, script hosts and system binaries abused to run payloads
+const SHELLS_AND_LOLBINS: [&str; 16] = [
+    "cmd", "powershell", "pwsh", "wscript", "cscript", "mshta", "rundll32", "regsvr32",
+    "certutil", "bitsadmin", "msiexec", "sh", "bash", "dash", "zsh", "nc",
+];
+
+// Parent -> child combinations that are flagged
+const SUSPICIOUS_SPAWNS: [(&[&str], &[&str]); 2] = [
+    (&DOCUMENT_APPS, &SHELLS_AND_LOLBINS),
+    (&SERVER_PROCESSES, &SHELLS_AND_LOLBINS),
+];
+
+// Ancestors followed at most when reconstructing a process chain
+const MAX_CHAIN_DEPTH: usize = 16;
+
+// Path fragments (lowercased, either separator) of directories where payloads get staged
+const STAGING_DIRS: [&str; 7] = [
+    "\\temp\\", "\\tmp\\", "\\downloads\\",
+    "/tmp/", "/var/tmp/", "/dev/shm/", "/downloads/",
+];
+
+// Process name fragments that identify known-bad software, with the score a match sets
+const DEFAULT_SIGNATURES: [(&str, u8); 16] = [
+    ("miner", 50), ("xmrig", 70), ("cryptonight", 60), ("monero", 50),
+    ("ethminer", 60), ("cgminer", 60), ("bfgminer", 60), ("nicehash", 50),
+    ("backdoor", 80), ("trojan", 90), ("keylogger", 90), ("spyware", 80),
+    ("malware", 90), ("virus", 90), ("rootkit", 90), ("exploit", 70),
+];
+
+// Score for a signature file entry without "= score"
+const DEFAULT_SIGNATURE_SCORE: u8 = 60;
+
+// OS and antivirus processes that routinely use a lot of CPU or memory (scanners, indexers,
+// update installers) and are trusted by name unless an allowlist file says otherwise
+const DEFAULT_TRUSTED_PROCESSES: [&str; 16] = [
+    "msmpeng", "mssense", "nissrv", "mpdefendercoreservice",
+    "searchindexer", "tiworker", "trustedinstaller", "compattelrunner",
+    "memory compression", "system", "dwm", "mrt",
+    "tracker-miner-fs", "tracker-miner-fs-3", "baloo_file", "mds_stores",
+];
+
+// Applications expected to talk to arbitrary hosts and ports and to move a lot of data
+const KNOWN_NETWORK_APPS: [&str; 16] = [
+    "chrome", "firefox", "msedge", "brave", "opera", "iexplore",
+    "teams", "slack", "zoom", "discord", "skype", "outlook",
+    "onedrive", "dropbox", "steam", "spotify",
+];
+
+// Remote ports an outbound connection is expected to use: web, mail, DNS, SSH and FTP
+const STANDARD_PORTS: [u16; 16] = [
+    20, 21, 22, 25, 53, 80, 110, 143,
+    443, 465, 587, 853, 993, 995, 8080, 8443,
+];
+
+// Insertion/removal events kept for reporting at most; the oldest are dropped if nobody collects them
+const MAX_PENDING_USB_EVENTS: usize = 100;
+
+// New processes per second above which a scan counts as a spawn burst (dropper or fork bomb)
+pub const DEFAULT_SPAWN_RATE_THRESHOLD: f32 = 5.0;
+
+// Score a spawn burst adds, and how long after the burst it keeps counting
+const SPAWN_BURST_SCORE: u8 = 60;
+const SPAWN_BURST_SCORE_WINDOW: Duration = Duration::from_secs(60);
+
+// Spawn bursts kept for reporting
+const MAX_SPAWN_BURSTS: usize = 50;
+
+// Base score for one suspicious connection, and what each further one adds
+const CONNECTION_BASE_SCORE: u8 = 40;
+const CONNECTION_EXTRA_SCORE: u8 = 10;
 
 #[derive(Debug, Clone)]
 pub struct ProcessInfo {
     pub name: String,
     pub pid: u32,
+    pub parent_pid: u32, // 0 when there is no parent
+    pub parent_name: String, // Empty when the parent has exited or can't be read
+    // Share of the whole machine's CPU time since the previous scan, 0-100
     pub cpu_usage: f32,
+    // Resident memory in bytes
     pub memory_usage: f32,
     pub suspicious_score: u8,
+    // Bytes/sec sent and received over TCP since the previous check; None where per-process
+    // network counters aren't available (or, on Linux, the process belongs to another user)
+    pub bytes_sent: Option<f32>,
+    pub bytes_recv: Option<f32>,
+    // Sustained high outbound throughput from a process that isn't a known network app
+    pub high_outbound: bool,
+    // Executable image path; None when it can't be resolved (e.g. protected system processes)
+    pub exe_path: Option<String>,
+    // Image path resolved but the file is gone from disk: running from memory only
+    pub memory_resident: bool,
+    // On the process allowlist: never flagged and always scores 0
+    pub trusted: bool,
+    // Image in a temp or downloads directory without a valid signature (or package owner)
+    pub unsigned_staged: bool,
+    // Started by a parent that shouldn't start it, e.g. a shell launched by an office app
+    pub suspicious_spawn: bool,
+}
+
+// Code-signing status of a process image. On Linux "signed" means the file is owned by an
+// installed package, and the publisher is that package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+    Signed { publisher: String },
+    Unsigned,
+    Unknown,
+}
+
+impl SignatureStatus {
+    pub fn describe(&self) -> String {
+        match self {
+            SignatureStatus::Signed { publisher } => format!("signed by {}", publisher),
+            SignatureStatus::Unsigned => "unsigned".to_string(),
+            SignatureStatus::Unknown => "unknown".to_string(),
+        }
+    }
+}
+
+// Allowlist entry: a process name, optionally pinned to the full path of its image
+#[derive(Debug, Clone)]
+struct TrustedProcess {
+    name: String,
+    path: Option<String>,
+}
+
+// An established outbound TCP connection to a non-standard port from a process that isn't a
+// known network app
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    pub pid: Option<u32>, // None when the owning process can't be resolved (e.g. another user's)
+    pub process_name: String,
+    pub local_port: u16,
+    pub remote: SocketAddr,
+}
+
+// A scan in which new processes appeared faster than the spawn-rate threshold. The parent is
+// the process that started the most of them, when it can be resolved
+#[derive(Debug, Clone)]
+pub struct SpawnBurst {
+    pub parent_name: String,
+    pub parent_pid: Option<u32>,
+    pub spawned: usize,
+    pub rate: f32, // New processes per second
+    pub detected: Instant,
+}
+
+// Cumulative TCP bytes (sent, received) per connection, keyed by owner PID and local and remote address
+type SocketCounters = HashMap<(u32, SocketAddr, SocketAddr), (u64, u64)>;
+
+// One reading of the per-connection counters. visible_pids lists the processes whose connections
+// could all be attributed (None: every process); the others' throughput is unknown, not zero
+struct NetworkCounters {
+    sockets: SocketCounters,
+    visible_pids: Option<HashSet<u32>>,
+}
+
+// How the machine's posture has shifted since the first scan established a baseline
+#[derive(Debug, Clone, Default)]
+pub struct SurfaceChanges {
+    pub new_usb_devices: usize,
+    pub new_processes: Vec<String>,
+    pub new_outbound: Vec<String>, // Processes that started sustained outbound traffic
+    pub new_connections: Vec<SocketAddr>, // Suspicious remote endpoints not connected to at the baseline
+}
+
+// Kind of USB device, derived from its Plug and Play class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum UsbClass {
+    Hid,
+    MassStorage,
+    Network,
+    Audio,
+    Imaging,
+    Phone,
+    Printer,
+    Other,
+}
+
+impl UsbClass {
+    fn from_pnp(class: &str, instance_id: &str) -> Self {
+        if instance_id.to_uppercase().starts_with("USBSTOR") {
+            return UsbClass::MassStorage;
+        }
+
+        match class.to_lowercase().as_str() {
+            "hidclass" | "keyboard" | "mouse" => UsbClass::Hid,
+            "diskdrive" | "volume" | "cdrom" | "usbstor" => UsbClass::MassStorage,
+            "net" => UsbClass::Network,
+            "media" | "audioendpoint" => UsbClass::Audio,
+            "image" | "camera" => UsbClass::Imaging,
+            "wpd" | "androidusbdeviceclass" | "apple mobile device usb driver" => UsbClass::Phone,
+            "printer" => UsbClass::Printer,
+            _ => UsbClass::Other,
+        }
+    }
+
+    // Base score for a newly inserted device of this class: input devices and storage
+    // are the BadUSB and exfiltration vectors, a phone plugged in to charge rarely is
+    pub fn risk_score(&self) -> u8 {
+        match self {
+            UsbClass::Hid => 50,
+            UsbClass::MassStorage => 45,
+            UsbClass::Network => 40,
+            UsbClass::Audio | UsbClass::Imaging => 25,
+            UsbClass::Printer | UsbClass::Other => 20,
+            UsbClass::Phone => 10,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            UsbClass::Hid => "HID",
+            UsbClass::MassStorage => "mass storage",
+            UsbClass::Network => "network",
+            UsbClass::Audio => "audio",
+            UsbClass::Imaging => "imaging",
+            UsbClass::Phone => "phone",
+            UsbClass::Printer => "printer",
+            UsbClass::Other => "other",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct UsbDeviceInfo {
     pub device_id: String,
     pub description: String,
+    pub class: UsbClass,
     #[allow(dead_code)]
     pub insertion_time: Instant,
 }
 
 pub struct KernelMonitor {
     processes: Arc<Mutex<HashMap<u32, ProcessInfo>>>,
-    usb_devices: Arc<Mutex<Vec<UsbDeviceInfo>>>,
+    usb_devices: Arc<Mutex<Vec<UsbDeviceInfo>>>, // Attached at the latest scan
     is_monitoring: Arc<Mutex<bool>>,
     suspicious_processes: Arc<Mutex<Vec<ProcessInfo>>>,
-    new_usb_devices: Arc<Mutex<Vec<UsbDeviceInfo>>>,
+    new_usb_devices: Arc<Mutex<Vec<UsbDeviceInfo>>>, // Insertions not yet reported
+    removed_usb_devices: Arc<Mutex<Vec<UsbDeviceInfo>>>, // Removals not yet reported
+    inserted_usb_devices: Arc<Mutex<Vec<UsbDeviceInfo>>>, // Inserted after the baseline and still attached
+    network_counters_available: Arc<Mutex<bool>>,
+    surface_changes: Arc<Mutex<SurfaceChanges>>,
+    suspicious_connections: Arc<Mutex<Vec<ConnectionInfo>>>,
+    reported_connections: HashSet<SocketAddr>, // Remote addresses already returned as findings by poll
+    usb_alert_classes: Option<Vec<UsbClass>>, // None alerts on every class
+    signatures: HashMap<String, u8>, // Lowercased name fragment and the score a match sets
+    trusted_processes: Vec<TrustedProcess>,
+    signature_cache: Arc<Mutex<HashMap<String, SignatureStatus>>>, // By image path; checks are slow
+    spawn_rate: Arc<Mutex<f32>>, // New processes per second in the latest scan
+    spawn_rate_threshold: f32,
+    spawn_bursts: Arc<Mutex<Vec<SpawnBurst>>>,
 }
 
 impl KernelMonitor {
@@ -37,11 +305,116 @@ impl KernelMonitor {
             is_monitoring: Arc::new(Mutex::new(false)),
             suspicious_processes: Arc::new(Mutex::new(Vec::new())),
             new_usb_devices: Arc::new(Mutex::new(Vec::new())),
+            removed_usb_devices: Arc::new(Mutex::new(Vec::new())),
+            inserted_usb_devices: Arc::new(Mutex::new(Vec::new())),
+            network_counters_available: Arc::new(Mutex::new(false)),
+            surface_changes: Arc::new(Mutex::new(SurfaceChanges::default())),
+            suspicious_connections: Arc::new(Mutex::new(Vec::new())),
+            reported_connections: HashSet::new(),
+            usb_alert_classes: None,
+            signatures: DEFAULT_SIGNATURES.iter()
+                .map(|(name, score)| (name.to_string(), *score))
+                .collect(),
+            trusted_processes: DEFAULT_TRUSTED_PROCESSES.iter()
+                .map(|name| TrustedProcess { name: name.to_string(), path: None })
+                .collect(),
+            signature_cache: Arc::new(Mutex::new(HashMap::new())),
+            spawn_rate: Arc::new(Mutex::new(0.0)),
+            spawn_rate_threshold: DEFAULT_SPAWN_RATE_THRESHOLD,
+            spawn_bursts: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
+    // Only newly inserted devices of these classes raise the score and alerts; empty means all
+    pub fn set_usb_alert_classes(&mut self, classes: Vec<UsbClass>) {
+        self.usb_alert_classes = if classes.is_empty() { None } else { Some(classes) };
+    }
+
+    // Add process name signatures; a name already on the built-in list takes the new score,
+    // and a score of 0 disables it
+    pub fn with_signatures(mut self, signatures: HashMap<String, u8>) -> Self {
+        for (name, score) in signatures {
+            self.signatures.insert(name.to_lowercase(), score);
+        }
+        self
+    }
+
+    // Read a signature file: one process name fragment per line, optionally followed by
+    // "= score" (0-100). Blank lines and lines starting with '#' are ignored
+    pub fn load_signatures(path: &Path) -> Result<HashMap<String, u8>, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+        let mut signatures = HashMap::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, score) = match line.rsplit_once('=') {
+                Some((name, score)) => {
+                    let score = score.trim().parse::<u8>()
+                        .ok()
+                        .filter(|score| *score <= 100)
+                        .ok_or_else(|| format!("{}:{}: score must be 0-100", path.display(), number + 1))?;
+                    (name.trim(), score)
+                },
+                None => (line, DEFAULT_SIGNATURE_SCORE),
+            };
+            if !name.is_empty() {
+                signatures.insert(name.to_lowercase(), score);
+            }
+        }
+
+        Ok(signatures)
+    }
+
+    // New processes per second above which a scan is recorded as a spawn burst
+    pub fn set_spawn_rate_threshold(&mut self, rate: f32) {
+        self.spawn_rate_threshold = rate;
+    }
+
+    // Add trusted processes as (name, optional full image path). A name listed with a path is
+    // only trusted when running from that path, so the path entry replaces any name-only one
+    pub fn with_process_allowlist(mut self, entries: Vec<(String, Option<String>)>) -> Self {
+        for (name, path) in entries {
+            let name = Self::normalize_process_name(&name);
+            if path.is_some() {
+                self.trusted_processes.retain(|entry| entry.name != name || entry.path.is_some());
+            }
+            self.trusted_processes.push(TrustedProcess { name, path });
+        }
+        self
+    }
+
+    // Read a process allowlist: one process name per line, optionally followed by "= full path"
+    // to its image. Blank lines and lines starting with '#' are ignored
+    pub fn load_process_allowlist(path: &Path) -> Result<Vec<(String, Option<String>)>, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+        let mut entries = Vec::new();
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, image_path) = match line.split_once('=') {
+                Some((name, image_path)) => (name.trim(), Some(image_path.trim().to_string()).filter(|p| !p.is_empty())),
+                None => (line, None),
+            };
+            if !name.is_empty() {
+                entries.push((name.to_string(), image_path));
+            }
+        }
+
+        Ok(entries)
+    }
+
     pub fn start_monitoring(&self) -> Result<(), String> {
-        println!("Starting kernel telemetry monitoring...");
+        debug!("Starting kernel telemetry monitoring...");
 
         // Set monitoring flag
         let mut is_monitoring = self.is_monitoring.lock().unwrap();
@@ -54,24 +427,93 @@ impl KernelMonitor {
         let is_monitoring_clone = self.is_monitoring.clone();
         let suspicious_processes = self.suspicious_processes.clone();
         let new_usb_devices = self.new_usb_devices.clone();
+        let removed_usb_devices = self.removed_usb_devices.clone();
+        let inserted_usb_devices = self.inserted_usb_devices.clone();
+        let network_counters_available = self.network_counters_available.clone();
+        let surface_changes = self.surface_changes.clone();
+        let suspicious_connections = self.suspicious_connections.clone();
+        let signatures = self.signatures.clone();
+        let trusted_processes = self.trusted_processes.clone();
+        let signature_cache = self.signature_cache.clone();
+        let spawn_rate = self.spawn_rate.clone();
+        let spawn_rate_threshold = self.spawn_rate_threshold;
+        let spawn_bursts = self.spawn_bursts.clone();
 
         // Start the monitoring thread
         thread::spawn(move || {
             let mut last_process_check = Instant::now();
             let mut last_usb_check = Instant::now();
-            let mut known_usb_ids = Vec::new();
+            let mut socket_previous: Option<(SocketCounters, Instant)> = None;
+            let mut outbound_streaks: HashMap<u32, u32> = HashMap::new();
+            let mut baseline_processes: Option<HashSet<String>> = None;
+            let mut baseline_connections: Option<HashSet<SocketAddr>> = None;
+            let mut usb_baseline_taken = false;
+            let mut known_pids: Option<HashSet<u32>> = None;
+
+            // CPU usage is measured between refreshes, so the same System is kept across scans
+            let mut system = System::new();
 
             while *is_monitoring_clone.lock().unwrap() {
                 // Check processes every 2 seconds
                 if last_process_check.elapsed() >= Duration::from_secs(2) {
-                    if let Ok(current_processes) = Self::get_running_processes() {
-                        // Update processes map
+                    let scan_interval = last_process_check.elapsed();
+                    if let Ok(mut current_processes) = Self::get_running_processes(&mut system, &signatures, &trusted_processes, &signature_cache) {
+                        // Count PIDs that weren't there at the previous scan. Processes that start and
+                        // exit between two scans are never seen, so this is a lower bound on the real rate
+                        let current_pids: HashSet<u32> = current_processes.iter().map(|p| p.pid).collect();
+                        if let Some(previous_pids) = &known_pids {
+                            let new_pids: Vec<u32> = current_pids.difference(previous_pids).copied().collect();
+                            let rate = new_pids.len() as f32 / scan_interval.as_secs_f32().max(0.001);
+                            *spawn_rate.lock().unwrap() = rate;
+
+                            if rate > spawn_rate_threshold {
+                                let mut bursts = spawn_bursts.lock().unwrap();
+                                bursts.push(Self::describe_spawn_burst(&system, &new_pids, rate));
+                                let excess = bursts.len().saturating_sub(MAX_SPAWN_BURSTS);
+                                bursts.drain(..excess);
+                            }
+                        }
+                        known_pids = Some(current_pids);
+
+                        // Attach throughput where the platform exposes per-connection byte counters
+                        match Self::get_network_counters() {
+                            Ok(counters) => {
+                                *network_counters_available.lock().unwrap() = true;
+                                Self::apply_throughput(&mut current_processes, counters, &mut socket_previous, &mut outbound_streaks);
+                            },
+                            Err(e) => {
+                                debug!("Per-process network counters unavailable: {}", e);
+                                *network_counters_available.lock().unwrap() = false;
+                            }
+                        }
+
+                        // The first scan is the baseline; anything after that is a surface change
+                        match &baseline_processes {
+                            None => {
+                                baseline_processes = Some(current_processes.iter().map(|p| p.name.to_lowercase()).collect());
+                            },
+                            Some(baseline) => {
+                                let mut changes = surface_changes.lock().unwrap();
+                                for process in &current_processes {
+                                    let name = process.name.to_lowercase();
+                                    if !baseline.contains(&name) && !changes.new_processes.contains(&name) {
+                                        changes.new_processes.push(name.clone());
+                                    }
+                                    if process.high_outbound && !changes.new_outbound.contains(&name) {
+                                        changes.new_outbound.push(name);
+                                    }
+                                }
+                            }
+                        }
+
+                        // Replace the processes map so exited processes drop out
                         let mut processes_map = processes.lock().unwrap();
+                        processes_map.clear();
                         let mut suspicious = Vec::new();
 
                         for process in current_processes {
                             // Check if process is suspicious
-                            if Self::is_process_suspicious(&process) {
+                            if Self::is_process_suspicious(&process, &signatures) {
                                 suspicious.push(process.clone());
                             }
                             processes_map.insert(process.pid, process);
@@ -82,6 +524,24 @@ impl KernelMonitor {
                             let mut suspicious_list = suspicious_processes.lock().unwrap();
                             *suspicious_list = suspicious;
                         }
+
+                        // Check connections against the fresh process map so owners resolve to names
+                        if let Ok(connections) = Self::get_suspicious_tcp_connections(&processes_map) {
+                            match &baseline_connections {
+                                None => {
+                                    baseline_connections = Some(connections.iter().map(|c| c.remote).collect());
+                                },
+                                Some(baseline) => {
+                                    let mut changes = surface_changes.lock().unwrap();
+                                    for connection in &connections {
+                                        if !baseline.contains(&connection.remote) && !changes.new_connections.contains(&connection.remote) {
+                                            changes.new_connections.push(connection.remote);
+                                        }
+                                    }
+                                }
+                            }
+                            *suspicious_connections.lock().unwrap() = connections;
+                        }
                     }
                     last_process_check = Instant::now();
                 }
@@ -89,26 +549,27 @@ impl KernelMonitor {
                 // Check USB devices every 5 seconds
                 if last_usb_check.elapsed() >= Duration::from_secs(5) {
                     if let Ok(current_devices) = Self::get_usb_devices() {
-                        // Check for new devices
-                        let mut new_devices = Vec::new();
-                        for device in &current_devices {
-                            if !known_usb_ids.contains(&device.device_id) {
-                                known_usb_ids.push(device.device_id.clone());
-                                new_devices.push(device.clone());
-                            }
+                        // The devices present at the first scan are the baseline, not insertions,
+                        // so the built-in keyboard doesn't count as a new HID device
+                        let (mut new_devices, removed_devices) = Self::diff_usb_devices(&usb_devices.lock().unwrap(), &current_devices);
+                        if !usb_baseline_taken {
+                            new_devices.clear();
                         }
+                        usb_baseline_taken = true;
+
+                        surface_changes.lock().unwrap().new_usb_devices += new_devices.len();
 
                         // Update USB devices list
-                        let mut usb_list = usb_devices.lock().unwrap();
-                        *usb_list = current_devices;
-
-                        // Update new USB devices
-                        if !new_devices.is_empty() {
-                            let mut new_list = new_usb_devices.lock().unwrap();
-                            for device in new_devices {
-                                new_list.push(device);
-                            }
-                        }
+                        *usb_devices.lock().unwrap() = current_devices;
+
+                        // Inserted devices stop counting towards the score once they're unplugged
+                        let mut inserted = inserted_usb_devices.lock().unwrap();
+                        inserted.retain(|device| !removed_devices.iter().any(|removed| removed.device_id == device.device_id));
+                        inserted.extend(new_devices.iter().cloned());
+                        drop(inserted);
+
+                        Self::queue_usb_events(&new_usb_devices, new_devices);
+                        Self::queue_usb_events(&removed_usb_devices, removed_devices);
                     }
                     last_usb_check = Instant::now();
                 }
@@ -117,7 +578,7 @@ impl KernelMonitor {
             }
         });
 
-        println!("Kernel telemetry monitoring started successfully");
+        info!("Kernel telemetry monitoring started successfully");
         Ok(())
     }
 
@@ -130,13 +591,125 @@ impl KernelMonitor {
         self.suspicious_processes.lock().unwrap().clone()
     }
 
+    // Every process seen in the latest scan, most suspicious first
+    pub fn get_all_processes(&self) -> Vec<ProcessInfo> {
+        let mut processes: Vec<ProcessInfo> = self.processes.lock().unwrap().values().cloned().collect();
+        processes.sort_by(|a, b| b.suspicious_score.cmp(&a.suspicious_score).then_with(|| a.name.cmp(&b.name)));
+        processes
+    }
+
+    // Processes with the highest outbound TCP throughput, or None when per-process network
+    // counters are unavailable
+    pub fn get_top_talkers(&self, limit: usize) -> Option<Vec<ProcessInfo>> {
+        if !*self.network_counters_available.lock().unwrap() {
+            return None;
+        }
+
+        let mut talkers: Vec<ProcessInfo> = self.processes.lock().unwrap()
+            .values()
+            .filter(|p| p.bytes_sent.unwrap_or(0.0) > 0.0)
+            .cloned()
+            .collect();
+        talkers.sort_by(|a, b| {
+            b.bytes_sent.unwrap_or(0.0)
+                .partial_cmp(&a.bytes_sent.unwrap_or(0.0))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        talkers.truncate(limit);
+        Some(talkers)
+    }
+
+    // Code-signing status of a running process's image, or Unknown if the process or its
+    // path can't be found. Results are cached per path
+    pub fn verify_signature(&self, pid: u32) -> SignatureStatus {
+        let exe_path = self.processes.lock().unwrap()
+            .get(&pid)
+            .and_then(|process| process.exe_path.clone());
+
+        match exe_path {
+            Some(path) => Self::cached_signature(&path, &self.signature_cache),
+            None => SignatureStatus::Unknown,
+        }
+    }
+
+    // Ancestry of every process started by a suspicious parent, root first, e.g.
+    // ["explorer", "winword", "powershell"]. Ancestors that have exited end the chain
+    pub fn suspicious_chains(&self) -> Vec<Vec<String>> {
+        let processes = self.processes.lock().unwrap();
+
+        let mut chains = Vec::new();
+        for process in processes.values().filter(|process| process.suspicious_spawn && !process.trusted) {
+            let mut chain = vec![process.name.clone()];
+            let mut visited = HashSet::from([process.pid]);
+            let mut parent_pid = process.parent_pid;
+
+            // PIDs get reused, so stop at a cycle as well as at a missing parent
+            while chain.len() < MAX_CHAIN_DEPTH && visited.insert(parent_pid) {
+                match processes.get(&parent_pid) {
+                    Some(parent) => {
+                        chain.push(parent.name.clone());
+                        parent_pid = parent.parent_pid;
+                    },
+                    None => break,
+                }
+            }
+
+            chain.reverse();
+            chains.push(chain);
+        }
+
+        chains
+    }
+
+    // New processes per second seen in the latest scan
+    pub fn get_spawn_rate(&self) -> f32 {
+        *self.spawn_rate.lock().unwrap()
+    }
+
+    // Recorded spawn bursts, oldest first
+    pub fn get_spawn_bursts(&self) -> Vec<SpawnBurst> {
+        self.spawn_bursts.lock().unwrap().clone()
+    }
+
+    // Outbound connections flagged in the latest scan
+    pub fn get_suspicious_connections(&self) -> Vec<ConnectionInfo> {
+        self.suspicious_connections.lock().unwrap().clone()
+    }
+
+    // Devices inserted since the previous call; each insertion is reported once
     pub fn get_new_usb_devices(&self) -> Vec<UsbDeviceInfo> {
-        self.new_usb_devices.lock().unwrap().clone()
+        self.new_usb_devices.lock().unwrap().drain(..).collect()
+    }
+
+    // Devices removed since the previous call; each removal is reported once
+    pub fn get_removed_usb_devices(&self) -> Vec<UsbDeviceInfo> {
+        self.removed_usb_devices.lock().unwrap().drain(..).collect()
+    }
+
+    // Devices inserted after monitoring started that are still attached
+    pub fn get_inserted_usb_devices(&self) -> Vec<UsbDeviceInfo> {
+        self.inserted_usb_devices.lock().unwrap().clone()
+    }
+
+    // Inserted, still attached devices whose class is selected for alerts
+    pub fn get_alerting_usb_devices(&self) -> Vec<UsbDeviceInfo> {
+        self.inserted_usb_devices.lock().unwrap()
+            .iter()
+            .filter(|device| self.alerts_on(device.class))
+            .cloned()
+            .collect()
+    }
+
+    fn alerts_on(&self, class: UsbClass) -> bool {
+        match &self.usb_alert_classes {
+            Some(classes) => classes.contains(&class),
+            None => true,
+        }
     }
 
     pub fn get_threat_score(&self) -> u8 {
         let suspicious_processes = self.suspicious_processes.lock().unwrap();
-        let new_usb_devices = self.new_usb_devices.lock().unwrap();
+        let new_usb_devices = self.get_alerting_usb_devices();
 
         // Calculate threat score based on suspicious processes and new USB devices
         let process_score = if suspicious_processes.is_empty() {
@@ -156,59 +729,549 @@ impl KernelMonitor {
         let usb_score = if new_usb_devices.is_empty() {
             0
         } else {
-            // Each new USB device adds to the score
-            let base_score = 30; // Base score for any USB insertion
+            // The riskiest device class sets the base, and each further device adds to it
+            let base_score = new_usb_devices.iter()
+                .map(|device| device.class.risk_score())
+                .max()
+                .unwrap_or(0);
             let count_factor = (new_usb_devices.len() as f32).min(3.0) / 3.0;
-            (base_score as f32 * (1.0 + count_factor)) as u8
+            (base_score as f32 * (1.0 + count_factor)).min(100.0) as u8
+        };
+
+        // Outbound connections to odd ports contribute to the score
+        let connection_score = Self::connection_score(self.suspicious_connections.lock().unwrap().len());
+
+        // A recent spawn burst contributes to the score
+        let spawn_score = match self.spawn_bursts.lock().unwrap().last() {
+            Some(burst) if burst.detected.elapsed() < SPAWN_BURST_SCORE_WINDOW => SPAWN_BURST_SCORE,
+            _ => 0,
         };
 
         // Combine scores, capping at 100
-        let combined = process_score.max(usb_score);
+        let combined = process_score.max(usb_score).max(connection_score).max(spawn_score);
         combined.min(100)
     }
 
-    // Helper function to get running processes
-    fn get_running_processes() -> Result<Vec<ProcessInfo>, String> {
-        let output = Command::new("powershell")
-            .args(&["-Command", "Get-Process | Select-Object Name, Id, CPU, WorkingSet | ConvertTo-Csv -NoTypeInformation"])
-            .output()
-            .map_err(|e| format!("Failed to execute PowerShell command: {}", e))?;
+    pub fn get_surface_changes(&self) -> SurfaceChanges {
+        self.surface_changes.lock().unwrap().clone()
+    }
 
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        let lines: Vec<&str> = output_str.lines().collect();
+    // Single 0-100 answer to "did something meaningful change on this machine?",
+    // combining new USB devices, new outbound talkers and connections, and newly observed processes
+    pub fn get_surface_change_score(&self) -> u8 {
+        let changes = self.surface_changes.lock().unwrap();
+
+        let usb_score = (changes.new_usb_devices as u32 * 25).min(50);
+        let outbound_score = (changes.new_outbound.len() as u32 * 20).min(40);
+        let connection_score = (changes.new_connections.len() as u32 * 20).min(40);
+        let process_score = (changes.new_processes.len() as u32 * 5).min(30);
+
+        (usb_score + outbound_score + connection_score + process_score).min(100) as u8
+    }
+
+    // Check that process enumeration works on this machine
+    pub fn probe_process_enumeration() -> Result<String, String> {
+        let processes = Self::get_running_processes(&mut System::new(), &HashMap::new(), &[], &Mutex::new(HashMap::new()))?;
+        if processes.is_empty() {
+            return Err("Process query returned no processes".to_string());
+        }
+        Ok(format!("{} processes enumerated", processes.len()))
+    }
+
+    // Check that USB device enumeration works on this machine
+    pub fn probe_usb_enumeration() -> Result<String, String> {
+        let devices = Self::get_usb_devices()?;
+        Ok(format!("{} USB devices enumerated", devices.len()))
+    }
+
+    // Helper function to get running processes. CPU usage is relative to the previous refresh
+    // of the same System, so it reads 0 on the first call
+    fn get_running_processes(
+        system: &mut System,
+        signatures: &HashMap<String, u8>,
+        trusted_processes: &[TrustedProcess],
+        signature_cache: &Mutex<HashMap<String, SignatureStatus>>,
+    ) -> Result<Vec<ProcessInfo>, String> {
+        system.refresh_processes();
+
+        // sysinfo reports CPU per core (a process pinning 4 cores reads 400%), so divide by
+        // the core count to get a share of the whole machine
+        let cores = thread::available_parallelism().map(|n| n.get()).unwrap_or(1) as f32;
 
         let mut processes = Vec::new();
+        for (pid, process) in system.processes() {
+            let name = Self::strip_exe_extension(process.name()).to_string();
 
-        // Skip header line
-        for line in lines.iter().skip(1) {
-            let parts: Vec<&str> = line.split(',').collect();
-            if parts.len() >= 4 {
-                // Remove quotes from CSV format
-                let name = parts[0].trim_matches('"').to_string();
-                let pid = parts[1].trim_matches('"').parse::<u32>().unwrap_or(0);
-                let cpu = parts[2].trim_matches('"').parse::<f32>().unwrap_or(0.0);
-                let memory = parts[3].trim_matches('"').parse::<f32>().unwrap_or(0.0);
-
-                // Calculate suspicious score
-                let suspicious_score = Self::calculate_process_score(&name, cpu, memory);
-
-                processes.push(ProcessInfo {
-                    name,
-                    pid,
-                    cpu_usage: cpu,
-                    memory_usage: memory,
-                    suspicious_score,
-                });
+            let parent_pid = process.parent().map_or(0, |parent| parent.as_u32());
+            let parent_name = process.parent()
+                .and_then(|parent| system.process(parent))
+                .map(|parent| Self::strip_exe_extension(parent.name()).to_string())
+                .unwrap_or_default();
+            let suspicious_spawn = Self::is_suspicious_spawn(&parent_name, &name);
+
+            let cpu = (process.cpu_usage() / cores).min(100.0);
+            let memory = process.memory() as f32;
+
+            let exe_path = process.exe()
+                .map(|path| path.to_string_lossy().to_string())
+                .filter(|path| !path.is_empty());
+
+            let memory_resident = Self::is_memory_resident(pid.as_u32(), exe_path.as_deref());
+
+            // Signature checks are slow, so only images in staging directories are checked here
+            let unsigned_staged = !memory_resident && match exe_path.as_deref() {
+                Some(path) => Self::is_staging_path(path) && Self::cached_signature(path, signature_cache) == SignatureStatus::Unsigned,
+                None => false,
+            };
+
+            // Calculate suspicious score
+            let mut suspicious_score = Self::calculate_process_score(&name, cpu, memory, signatures, unsigned_staged);
+            if memory_resident {
+                suspicious_score = suspicious_score.saturating_add(MEMORY_RESIDENT_SCORE_BUMP).min(100);
+            }
+            if suspicious_spawn {
+                suspicious_score = suspicious_score.saturating_add(SUSPICIOUS_SPAWN_SCORE_BUMP).min(100);
             }
+
+            let trusted = Self::is_trusted(&name, exe_path.as_deref(), trusted_processes);
+            if trusted {
+                suspicious_score = 0;
+            }
+
+            processes.push(ProcessInfo {
+                name,
+                pid: pid.as_u32(),
+                parent_pid,
+                parent_name,
+                cpu_usage: cpu,
+                memory_usage: memory,
+                suspicious_score,
+                bytes_sent: None,
+                bytes_recv: None,
+                high_outbound: false,
+                exe_path,
+                memory_resident,
+                trusted,
+                unsigned_staged,
+                suspicious_spawn,
+            });
+        }
+
+        if processes.is_empty() {
+            return Err("No processes could be enumerated".to_string());
         }
 
         Ok(processes)
     }
 
+    // Helper function to get cumulative TCP byte counters (sent, received) per connection and owner.
+    // /proc/net/tcp lists connections without byte counts, so ss reads them from the kernel's
+    // tcp_info over sock_diag; its socket inodes are matched to the "socket:[inode]" links under
+    // /proc/<pid>/fd. Without root only this user's processes can be read
+    #[cfg(target_os = "linux")]
+    fn get_network_counters() -> Result<NetworkCounters, String> {
+        let output = Command::new("ss")
+            .args(["-tinHe", "state", "established"])
+            .output()
+            .map_err(|e| format!("Failed to execute ss: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("ss failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+
+        let (owners, visible_pids) = Self::socket_owners();
+        let mut sockets = HashMap::new();
+
+        // Each connection is a line with its addresses and inode, followed by an indented line
+        // of tcp_info fields; a counter that is still zero is left out
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        let mut connection: Option<(u32, SocketAddr, SocketAddr)> = None;
+        for line in output_str.lines() {
+            if !line.starts_with(char::is_whitespace) {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let mut addresses = fields.iter().filter_map(|field| field.parse::<SocketAddr>().ok());
+                let owner = fields.iter()
+                    .find_map(|field| field.strip_prefix("ino:"))
+                    .and_then(|inode| inode.parse::<u64>().ok())
+                    .and_then(|inode| owners.get(&inode).copied());
+                connection = match (owner, addresses.next(), addresses.next()) {
+                    (Some(pid), Some(local), Some(remote)) => Some((pid, local, remote)),
+                    _ => None,
+                };
+                continue;
+            }
+
+            if let Some(key) = connection.take() {
+                let counter = |name: &str| line.split_whitespace()
+                    .find_map(|field| field.strip_prefix(name))
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .unwrap_or(0);
+                sockets.insert(key, (counter("bytes_acked:"), counter("bytes_received:")));
+            }
+        }
+
+        Ok(NetworkCounters { sockets, visible_pids: Some(visible_pids) })
+    }
+
+    // Helper function to map socket inodes to the PID holding them, along with every PID whose
+    // descriptors could be read
+    #[cfg(target_os = "linux")]
+    fn socket_owners() -> (HashMap<u64, u32>, HashSet<u32>) {
+        let mut owners = HashMap::new();
+        let mut visible_pids = HashSet::new();
+
+        let entries = match fs::read_dir("/proc") {
+            Ok(entries) => entries,
+            Err(_) => return (owners, visible_pids),
+        };
+        for entry in entries.flatten() {
+            let pid = match entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+            let descriptors = match fs::read_dir(entry.path().join("fd")) {
+                Ok(descriptors) => descriptors,
+                Err(_) => continue,
+            };
+            visible_pids.insert(pid);
+
+            for descriptor in descriptors.flatten() {
+                let inode = fs::read_link(descriptor.path()).ok()
+                    .and_then(|target| target.to_str()
+                        .and_then(|target| target.strip_prefix("socket:[")?.strip_suffix(']')?.parse::<u64>().ok()));
+                if let Some(inode) = inode {
+                    owners.insert(inode, pid);
+                }
+            }
+        }
+
+        (owners, visible_pids)
+    }
+
+    // Helper function to get cumulative TCP byte counters (sent, received) per connection and owner
+    // from the extended TCP statistics. Collection has to be switched on per connection, which
+    // needs an elevated process, so bytes moved before a connection is first seen aren't counted.
+    // Only IPv4 connections are covered
+    #[cfg(windows)]
+    fn get_network_counters() -> Result<NetworkCounters, String> {
+        use windows::Win32::Foundation::{BOOLEAN, FALSE};
+        use windows::Win32::NetworkManagement::IpHelper::{
+            GetExtendedTcpTable, GetPerTcpConnectionEStats, SetPerTcpConnectionEStats, TcpConnectionEstatsData,
+            MIB_TCPROW_LH, MIB_TCPROW_LH_0, MIB_TCPTABLE_OWNER_PID, MIB_TCP_STATE_ESTAB,
+            TCP_ESTATS_DATA_ROD_v0, TCP_ESTATS_DATA_RW_v0, TCP_TABLE_OWNER_PID_CONNECTIONS,
+        };
+        const AF_INET: u32 = 2;
+
+        // The table is read into u32s so the rows are suitably aligned
+        let mut size = 0u32;
+        unsafe { GetExtendedTcpTable(None, &mut size, FALSE, AF_INET, TCP_TABLE_OWNER_PID_CONNECTIONS, 0) };
+        let mut buffer = vec![0u32; size as usize / 4 + 1];
+        let result = unsafe {
+            GetExtendedTcpTable(Some(buffer.as_mut_ptr().cast()), &mut size, FALSE, AF_INET, TCP_TABLE_OWNER_PID_CONNECTIONS, 0)
+        };
+        if result != 0 {
+            return Err(format!("GetExtendedTcpTable failed with error {}", result));
+        }
+        let rows = unsafe {
+            let table = &*(buffer.as_ptr() as *const MIB_TCPTABLE_OWNER_PID);
+            std::slice::from_raw_parts(table.table.as_ptr(), table.dwNumEntries as usize)
+        };
+
+        let address = |addr: u32, port: u32| SocketAddr::from((std::net::Ipv4Addr::from(u32::from_be(addr)), u16::from_be(port as u16)));
+        let enable = TCP_ESTATS_DATA_RW_v0 { EnableCollection: BOOLEAN(1) };
+        let mut sockets = HashMap::new();
+        let mut established = 0;
+        for row in rows.iter().filter(|row| row.dwState == MIB_TCP_STATE_ESTAB.0 as u32) {
+            established += 1;
+            let tcp_row = MIB_TCPROW_LH {
+                Anonymous: MIB_TCPROW_LH_0 { dwState: row.dwState },
+                dwLocalAddr: row.dwLocalAddr,
+                dwLocalPort: row.dwLocalPort,
+                dwRemoteAddr: row.dwRemoteAddr,
+                dwRemotePort: row.dwRemotePort,
+            };
+
+            // Enabling collection on a connection that already has it is harmless
+            let mut data = TCP_ESTATS_DATA_ROD_v0::default();
+            let counted = unsafe {
+                let enable = std::slice::from_raw_parts(&enable as *const _ as *const u8, std::mem::size_of_val(&enable));
+                let data = std::slice::from_raw_parts_mut(&mut data as *mut _ as *mut u8, std::mem::size_of::<TCP_ESTATS_DATA_ROD_v0>());
+                SetPerTcpConnectionEStats(&tcp_row, TcpConnectionEstatsData, enable, 0, 0) == 0
+                    && GetPerTcpConnectionEStats(&tcp_row, TcpConnectionEstatsData, None, 0, None, 0, Some(data), 0) == 0
+            };
+            if counted {
+                let key = (row.dwOwningPid, address(row.dwLocalAddr, row.dwLocalPort), address(row.dwRemoteAddr, row.dwRemotePort));
+                sockets.insert(key, (data.DataBytesOut, data.DataBytesIn));
+            }
+        }
+
+        if established > 0 && sockets.is_empty() {
+            return Err("TCP connection statistics need an elevated process".to_string());
+        }
+
+        Ok(NetworkCounters { sockets, visible_pids: None })
+    }
+
+    #[cfg(not(any(target_os = "linux", windows)))]
+    fn get_network_counters() -> Result<NetworkCounters, String> {
+        Err("Per-process network counters aren't supported on this platform".to_string())
+    }
+
+    // Helper function to turn cumulative per-connection counters into per-process rates and flag
+    // sustained outbound traffic. A connection missing from the previous check was opened since,
+    // so all of its bytes are new; the first check only records the counters
+    fn apply_throughput(
+        processes: &mut [ProcessInfo],
+        counters: NetworkCounters,
+        previous: &mut Option<(SocketCounters, Instant)>,
+        outbound_streaks: &mut HashMap<u32, u32>,
+    ) {
+        let now = Instant::now();
+
+        if let Some((previous_sockets, previous_time)) = previous.as_ref() {
+            let elapsed = now.duration_since(*previous_time).as_secs_f32().max(0.001);
+            let mut moved: HashMap<u32, (u64, u64)> = HashMap::new();
+            for (key, &(sent, received)) in &counters.sockets {
+                let (previous_sent, previous_received) = previous_sockets.get(key).copied().unwrap_or((0, 0));
+                let totals = moved.entry(key.0).or_insert((0, 0));
+                totals.0 += sent.saturating_sub(previous_sent);
+                totals.1 += received.saturating_sub(previous_received);
+            }
+
+            for process in processes.iter_mut() {
+                if matches!(&counters.visible_pids, Some(visible) if !visible.contains(&process.pid)) {
+                    continue;
+                }
+
+                let (sent, received) = moved.get(&process.pid).copied().unwrap_or((0, 0));
+                let sent_rate = sent as f32 / elapsed;
+                process.bytes_sent = Some(sent_rate);
+                process.bytes_recv = Some(received as f32 / elapsed);
+
+                let name = process.name.to_lowercase();
+                let known_app = KNOWN_NETWORK_APPS.iter().any(|app| name.contains(app));
+                let streak = outbound_streaks.entry(process.pid).or_insert(0);
+                if sent_rate > HIGH_OUTBOUND_BYTES_PER_SEC && !known_app {
+                    *streak += 1;
+                } else {
+                    *streak = 0;
+                }
+
+                if *streak >= SUSTAINED_OUTBOUND_SAMPLES && !process.trusted {
+                    process.high_outbound = true;
+                    process.suspicious_score = process.suspicious_score.saturating_add(HIGH_OUTBOUND_SCORE_BUMP).min(100);
+                }
+            }
+
+            // Forget processes that have exited
+            outbound_streaks.retain(|pid, _| processes.iter().any(|process| process.pid == *pid));
+        }
+
+        *previous = Some((counters.sockets, now));
+    }
+
+    // Helper function to find established outbound TCP connections to non-standard ports from
+    // processes that aren't known network apps. Connections to a port this machine listens on
+    // are inbound (the remote port is the client's ephemeral one), so they are skipped
+    fn get_suspicious_tcp_connections(processes: &HashMap<u32, ProcessInfo>) -> Result<Vec<ConnectionInfo>, String> {
+        let sockets = get_sockets_info(AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6, ProtocolFlags::TCP)
+            .map_err(|e| format!("Failed to enumerate TCP connections: {}", e))?;
+
+        let listening_ports: HashSet<u16> = sockets.iter()
+            .filter_map(|socket| match &socket.protocol_socket_info {
+                ProtocolSocketInfo::Tcp(tcp) if tcp.state == TcpState::Listen => Some(tcp.local_port),
+                _ => None,
+            })
+            .collect();
+
+        let mut connections = Vec::new();
+        for socket in &sockets {
+            let tcp = match &socket.protocol_socket_info {
+                ProtocolSocketInfo::Tcp(tcp) => tcp,
+                _ => continue,
+            };
+
+            if tcp.state != TcpState::Established
+                || tcp.remote_addr.is_loopback()
+                || tcp.remote_addr.is_unspecified()
+                || STANDARD_PORTS.contains(&tcp.remote_port)
+                || listening_ports.contains(&tcp.local_port) {
+                continue;
+            }
+
+            let pid = socket.associated_pids.first().copied();
+            let process_name = pid
+                .and_then(|pid| processes.get(&pid))
+                .map(|process| process.name.clone())
+                .unwrap_or_else(|| "unknown".to_string());
+
+            let name = process_name.to_lowercase();
+            if KNOWN_NETWORK_APPS.iter().any(|app| name.contains(app)) {
+                continue;
+            }
+
+            connections.push(ConnectionInfo {
+                pid,
+                process_name,
+                local_port: tcp.local_port,
+                remote: SocketAddr::new(tcp.remote_addr, tcp.remote_port),
+            });
+        }
+
+        Ok(connections)
+    }
+
+    // Whether the process's image was deleted or replaced after launch. On Linux the kernel marks
+    // the /proc/<pid>/exe link of such a process; the path itself can't be checked on the host,
+    // since processes in containers, snaps and flatpaks have images in other mount namespaces.
+    // Elsewhere a resolved path that no longer exists counts. An unresolvable path says nothing
+    // (access denied looks the same)
+    fn is_memory_resident(pid: u32, exe_path: Option<&str>) -> bool {
+        if cfg!(target_os = "linux") {
+            match fs::read_link(format!("/proc/{}/exe", pid)) {
+                Ok(target) => target.to_string_lossy().ends_with(" (deleted)"),
+                Err(_) => false,
+            }
+        } else {
+            matches!(exe_path, Some(path) if !Path::new(path).exists())
+        }
+    }
+
+    // Score of this many suspicious connections: the first sets the base, each further one adds
+    fn connection_score(count: usize) -> u8 {
+        if count == 0 {
+            return 0;
+        }
+        let extra = (count - 1).min(3) as u8;
+        CONNECTION_BASE_SCORE + CONNECTION_EXTRA_SCORE * extra
+    }
+
+    // Helper function to record a spawn burst, attributing it to the parent of most new processes
+    fn describe_spawn_burst(system: &System, new_pids: &[u32], rate: f32) -> SpawnBurst {
+        let mut children_per_parent: HashMap<u32, usize> = HashMap::new();
+        for pid in new_pids {
+            if let Some(parent) = system.process(Pid::from_u32(*pid)).and_then(|process| process.parent()) {
+                *children_per_parent.entry(parent.as_u32()).or_insert(0) += 1;
+            }
+        }
+
+        let parent_pid = children_per_parent.into_iter()
+            .max_by_key(|(_, children)| *children)
+            .map(|(pid, _)| pid);
+        let parent_name = parent_pid
+            .and_then(|pid| system.process(Pid::from_u32(pid)))
+            .map(|process| Self::strip_exe_extension(process.name()).to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        SpawnBurst {
+            parent_name,
+            parent_pid,
+            spawned: new_pids.len(),
+            rate,
+            detected: Instant::now(),
+        }
+    }
+
+    // Helper function to look up a signature check, running it on a cache miss
+    fn cached_signature(path: &str, cache: &Mutex<HashMap<String, SignatureStatus>>) -> SignatureStatus {
+        if let Some(status) = cache.lock().unwrap().get(path) {
+            return status.clone();
+        }
+
+        let status = Self::check_signature(path);
+        cache.lock().unwrap().insert(path.to_string(), status.clone());
+        status
+    }
+
+    // Helper function to check an image's signature: Authenticode on Windows, package
+    // ownership (dpkg, then rpm) on Linux. Other platforms report Unknown
+    fn check_signature(path: &str) -> SignatureStatus {
+        if cfg!(windows) {
+            let script = format!(
+                "$s = Get-AuthenticodeSignature -LiteralPath '{}'; $s.Status.ToString() + '|' + $s.SignerCertificate.Subject",
+                path.replace('\'', "''"));
+            let output = match Command::new("powershell").args(["-Command", &script]).output() {
+                Ok(output) => output,
+                Err(_) => return SignatureStatus::Unknown,
+            };
+
+            let output_str = String::from_utf8_lossy(&output.stdout);
+            let (status, subject) = output_str.trim().split_once('|').unwrap_or((output_str.trim(), ""));
+            match status {
+                "Valid" => {
+                    // Subject is "CN=Publisher, O=..., C=..."; the common name is the publisher
+                    let publisher = subject.split(", ")
+                        .find_map(|part| part.strip_prefix("CN="))
+                        .unwrap_or(subject)
+                        .trim_matches('"')
+                        .to_string();
+                    SignatureStatus::Signed { publisher }
+                },
+                "NotSigned" | "HashMismatch" | "NotTrusted" => SignatureStatus::Unsigned,
+                _ => SignatureStatus::Unknown,
+            }
+        } else if cfg!(target_os = "linux") {
+            let mut any_package_manager = false;
+            for (tool, flag) in [("dpkg", "-S"), ("rpm", "-qf")] {
+                let output = match Command::new(tool).args([flag, path]).output() {
+                    Ok(output) => output,
+                    Err(_) => continue, // Not installed
+                };
+                any_package_manager = true;
+
+                if output.status.success() {
+                    // dpkg prints "package: /path", rpm prints the package name
+                    let output_str = String::from_utf8_lossy(&output.stdout);
+                    let publisher = output_str.lines().next().unwrap_or("")
+                        .split(':').next().unwrap_or("")
+                        .trim()
+                        .to_string();
+                    return SignatureStatus::Signed { publisher: format!("{} package {}", tool, publisher) };
+                }
+            }
+
+            if any_package_manager { SignatureStatus::Unsigned } else { SignatureStatus::Unknown }
+        } else {
+            SignatureStatus::Unknown
+        }
+    }
+
+    // Helper function to check whether an image sits in a temp or downloads directory
+    fn is_staging_path(path: &str) -> bool {
+        let path = path.to_lowercase();
+        let temp_dir = std::env::temp_dir().to_string_lossy().to_lowercase();
+        (!temp_dir.is_empty() && path.starts_with(&temp_dir))
+            || STAGING_DIRS.iter().any(|dir| path.contains(dir))
+    }
+
+    // Devices inserted and removed between two scans. Only the previous scan is compared, so
+    // a device plugged back in is new again
+    fn diff_usb_devices(previous: &[UsbDeviceInfo], current: &[UsbDeviceInfo]) -> (Vec<UsbDeviceInfo>, Vec<UsbDeviceInfo>) {
+        let previous_ids: HashSet<&str> = previous.iter().map(|device| device.device_id.as_str()).collect();
+        let current_ids: HashSet<&str> = current.iter().map(|device| device.device_id.as_str()).collect();
+
+        let inserted = current.iter()
+            .filter(|device| !previous_ids.contains(device.device_id.as_str()))
+            .cloned()
+            .collect();
+        let removed = previous.iter()
+            .filter(|device| !current_ids.contains(device.device_id.as_str()))
+            .cloned()
+            .collect();
+        (inserted, removed)
+    }
+
+    // Helper function to queue USB events until they're collected
+    fn queue_usb_events(queue: &Mutex<Vec<UsbDeviceInfo>>, events: Vec<UsbDeviceInfo>) {
+        let mut queue = queue.lock().unwrap();
+        queue.extend(events);
+        let excess = queue.len().saturating_sub(MAX_PENDING_USB_EVENTS);
+        queue.drain(..excess);
+    }
+
     // Helper function to get USB devices
     fn get_usb_devices() -> Result<Vec<UsbDeviceInfo>, String> {
         let output = Command::new("powershell")
-            .args(&["-Command", "Get-PnpDevice -Class USB | Select-Object InstanceId, FriendlyName | ConvertTo-Csv -NoTypeInformation"])
+            .args(["-Command", "Get-PnpDevice -PresentOnly | Where-Object { $_.InstanceId -match '^(USB|USBSTOR|HID)\\\\' } | Select-Object InstanceId, Class, FriendlyName | ConvertTo-Csv -NoTypeInformation"])
             .output()
             .map_err(|e| format!("Failed to execute PowerShell command: {}", e))?;
 
@@ -220,14 +1283,16 @@ impl KernelMonitor {
         // Skip header line
         for line in lines.iter().skip(1) {
             let parts: Vec<&str> = line.split(',').collect();
-            if parts.len() >= 2 {
-                // Remove quotes from CSV format
+            if parts.len() >= 3 {
+                // Remove quotes from CSV format; the friendly name is last since it may contain commas
                 let device_id = parts[0].trim_matches('"').to_string();
-                let description = parts[1].trim_matches('"').to_string();
+                let class = UsbClass::from_pnp(parts[1].trim_matches('"'), &device_id);
+                let description = parts[2..].join(",").trim_matches('"').to_string();
 
                 devices.push(UsbDeviceInfo {
                     device_id,
                     description,
+                    class,
                     insertion_time: Instant::now(),
                 });
             }
@@ -237,36 +1302,93 @@ impl KernelMonitor {
     }
 
     // Helper function to check if a process is suspicious
-    fn is_process_suspicious(process: &ProcessInfo) -> bool {
+    fn is_process_suspicious(process: &ProcessInfo, signatures: &HashMap<String, u8>) -> bool {
+        // Allowlisted processes are never flagged
+        if process.trusted {
+            return false;
+        }
+
         // Check for high CPU usage
         if process.cpu_usage > 70.0 {
             return true;
         }
 
+        // Check for an image that is no longer backed by a file on disk
+        if process.memory_resident {
+            return true;
+        }
+
+        // Check for a process started by a parent that shouldn't start it
+        if process.suspicious_spawn {
+            return true;
+        }
+
+        // Check for an unsigned image staged in a temp or downloads directory
+        if process.unsigned_staged {
+            return true;
+        }
+
+        // Check for sustained high outbound throughput
+        if process.high_outbound {
+            return true;
+        }
+
         // Check for high memory usage (> 500MB)
-        if process.memory_usage > 500_000_000.0 {
+        if process.memory_usage / BYTES_PER_MB > SUSPICIOUS_MEMORY_MB {
             return true;
         }
 
         // Check for suspicious process names
-        let suspicious_names = [
-            "miner", "xmrig", "cryptonight", "monero",
-            "ethminer", "cgminer", "bfgminer", "nicehash",
-            "backdoor", "trojan", "keylogger", "spyware",
-            "malware", "virus", "rootkit", "exploit",
-        ];
+        Self::signature_score(&process.name, signatures).is_some()
+    }
 
-        for name in suspicious_names.iter() {
-            if process.name.to_lowercase().contains(name) {
-                return true;
+    // Helper function to check a parent -> child pair against the suspicious combinations
+    fn is_suspicious_spawn(parent_name: &str, child_name: &str) -> bool {
+        let parent = parent_name.to_lowercase();
+        let child = child_name.to_lowercase();
+        SUSPICIOUS_SPAWNS.iter().any(|(parents, children)| {
+            parents.contains(&parent.as_str()) && children.contains(&child.as_str())
+        })
+    }
+
+    // Helper function to check a process against the allowlist. Names match exactly, not as
+    // fragments, so trust never spreads to similarly named processes
+    fn is_trusted(name: &str, exe_path: Option<&str>, trusted_processes: &[TrustedProcess]) -> bool {
+        let name = Self::normalize_process_name(name);
+        trusted_processes.iter().any(|entry| {
+            entry.name == name && match (&entry.path, exe_path) {
+                (None, _) => true,
+                (Some(expected), Some(actual)) if cfg!(windows) => expected.eq_ignore_ascii_case(actual),
+                (Some(expected), Some(actual)) => expected == actual,
+                (Some(_), None) => false,
             }
+        })
+    }
+
+    // Windows image names carry the extension; drop it so names match across platforms
+    fn strip_exe_extension(name: &str) -> &str {
+        let stem_len = name.len().saturating_sub(4);
+        match name.get(stem_len..) {
+            Some(extension) if extension.eq_ignore_ascii_case(".exe") => &name[..stem_len],
+            _ => name,
         }
+    }
+
+    fn normalize_process_name(name: &str) -> String {
+        Self::strip_exe_extension(name.trim()).to_lowercase()
+    }
 
-        false
+    // Helper function to get the highest score among the signatures a process name matches
+    fn signature_score(name: &str, signatures: &HashMap<String, u8>) -> Option<u8> {
+        let name = name.to_lowercase();
+        signatures.iter()
+            .filter(|(signature, score)| **score > 0 && name.contains(signature.as_str()))
+            .map(|(_, score)| *score)
+            .max()
     }
 
     // Helper function to calculate process suspicious score
-    fn calculate_process_score(name: &str, cpu: f32, memory: f32) -> u8 {
+    fn calculate_process_score(name: &str, cpu: f32, memory: f32, signatures: &HashMap<String, u8>, unsigned_staged: bool) -> u8 {
         let mut score = 0;
 
         // CPU usage contributes to score
@@ -279,30 +1401,190 @@ impl KernelMonitor {
         }
 
         // Memory usage contributes to score (in MB)
-        let memory_mb = memory / 1_000_000.0;
+        let memory_mb = memory / BYTES_PER_MB;
         if memory_mb > 1000.0 {
             score += 30;
-        } else if memory_mb > 500.0 {
+        } else if memory_mb > SUSPICIOUS_MEMORY_MB {
             score += 20;
         } else if memory_mb > 200.0 {
             score += 10;
         }
 
         // Check for suspicious process names
-        let suspicious_names = [
-            ("miner", 50), ("xmrig", 70), ("cryptonight", 60), ("monero", 50),
-            ("ethminer", 60), ("cgminer", 60), ("bfgminer", 60), ("nicehash", 50),
-            ("backdoor", 80), ("trojan", 90), ("keylogger", 90), ("spyware", 80),
-            ("malware", 90), ("virus", 90), ("rootkit", 90), ("exploit", 70),
-        ];
+        if let Some(name_score) = Self::signature_score(name, signatures) {
+            score = score.max(name_score);
+        }
 
-        for (suspicious_name, name_score) in suspicious_names.iter() {
-            if name.to_lowercase().contains(suspicious_name) {
-                score = score.max(*name_score);
-            }
+        // Unsigned executables running from temp or downloads directories
+        if unsigned_staged {
+            score += UNSIGNED_STAGED_SCORE_BUMP;
         }
 
         // Cap at 100
         score.min(100)
     }
 }
+
+impl Monitor for KernelMonitor {
+    fn name(&self) -> &str {
+        "kernel"
+    }
+
+    fn start(&self) -> Result<(), String> {
+        self.start_monitoring()
+    }
+
+    fn poll(&mut self) -> MonitorSample {
+        let mut findings: Vec<Finding> = self.get_new_usb_devices()
+            .into_iter()
+            .map(|device| Finding::new(
+                FindingKind::UsbDevice,
+                device.class.risk_score(),
+                format!("{} [{}] (ID: {})", device.description, device.class.name(), device.device_id)))
+            .collect();
+
+        // Each remote address is reported once, scored like the connections it was found with.
+        // The detail starts with the address so it can be exported as an indicator
+        let connections = self.get_suspicious_connections();
+        let connection_score = Self::connection_score(connections.len());
+        for connection in connections {
+            if self.reported_connections.insert(connection.remote) {
+                findings.push(Finding::new(
+                    FindingKind::Connection,
+                    connection_score,
+                    format!("{} ({})", connection.remote, connection.process_name)));
+            }
+        }
+
+        MonitorSample {
+            score: Some(self.get_threat_score()),
+            findings,
+        }
+    }
+
+    fn stop(&self) {
+        self.stop_monitoring();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(name: &str, memory_usage: f32) -> ProcessInfo {
+        ProcessInfo {
+            name: name.to_string(),
+            pid: 4242,
+            parent_pid: 0,
+            parent_name: String::new(),
+            cpu_usage: 0.0,
+            memory_usage,
+            suspicious_score: 0,
+            bytes_sent: None,
+            bytes_recv: None,
+            high_outbound: false,
+            exe_path: None,
+            memory_resident: false,
+            trusted: false,
+            unsigned_staged: false,
+            suspicious_spawn: false,
+        }
+    }
+
+    fn usb_device(device_id: &str) -> UsbDeviceInfo {
+        UsbDeviceInfo {
+            device_id: device_id.to_string(),
+            description: "USB Mass Storage Device".to_string(),
+            class: UsbClass::MassStorage,
+            insertion_time: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn reconnecting_a_device_reports_it_as_new_again() {
+        let keyboard = usb_device("HID\\VID_046D&PID_C31C\\1");
+        let stick = usb_device("USBSTOR\\DISK&VEN_SANDISK\\2");
+        let scans = [
+            vec![keyboard.clone(), stick.clone()],
+            vec![keyboard.clone()],
+            vec![keyboard.clone(), stick.clone()],
+        ];
+
+        let new_queue = Mutex::new(Vec::new());
+        let removed_queue = Mutex::new(Vec::new());
+        let mut previous = vec![keyboard.clone()];
+        let (mut new_events, mut removed_events) = (Vec::new(), Vec::new());
+        for current in scans {
+            let (inserted, removed) = KernelMonitor::diff_usb_devices(&previous, &current);
+            KernelMonitor::queue_usb_events(&new_queue, inserted);
+            KernelMonitor::queue_usb_events(&removed_queue, removed);
+            previous = current;
+
+            // Each event is consumed once, as get_new_usb_devices and get_removed_usb_devices do
+            new_events.extend(new_queue.lock().unwrap().drain(..));
+            removed_events.extend(removed_queue.lock().unwrap().drain(..));
+        }
+
+        let ids = |events: &[UsbDeviceInfo]| events.iter().map(|device| device.device_id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&new_events), vec![stick.device_id.clone(), stick.device_id.clone()]);
+        assert_eq!(ids(&removed_events), vec![stick.device_id.clone()]);
+    }
+
+    #[test]
+    fn large_working_set_is_flagged_by_check_and_score() {
+        let memory = 600.0 * BYTES_PER_MB;
+        let signatures = HashMap::new();
+
+        assert!(KernelMonitor::is_process_suspicious(&process("worker", memory), &signatures));
+        assert!(KernelMonitor::calculate_process_score("worker", 0.0, memory, &signatures, false) > 0);
+    }
+
+    #[test]
+    fn sustained_outbound_flags_only_unknown_network_apps() {
+        let mut processes = vec![process("exfil", 0.0), ProcessInfo { pid: 4243, ..process("firefox", 0.0) }];
+        let local: SocketAddr = "10.0.0.2:50000".parse().unwrap();
+        let remote: SocketAddr = "203.0.113.9:443".parse().unwrap();
+        let mut previous = None;
+        let mut streaks = HashMap::new();
+
+        // Each check is a second after the last and both processes sent another 4 MB
+        for check in 0..=SUSTAINED_OUTBOUND_SAMPLES as u64 {
+            let sent = check * 4 * 1024 * 1024;
+            let sockets = HashMap::from([((4242, local, remote), (sent, 1024)), ((4243, local, remote), (sent, 1024))]);
+            if let Some((_, at)) = previous.as_mut() {
+                *at -= Duration::from_secs(1);
+            }
+            KernelMonitor::apply_throughput(&mut processes, NetworkCounters { sockets, visible_pids: None }, &mut previous, &mut streaks);
+        }
+
+        assert!(processes[0].bytes_sent.unwrap() > HIGH_OUTBOUND_BYTES_PER_SEC);
+        assert_eq!(processes[0].bytes_recv, Some(0.0));
+        assert!(processes[0].high_outbound);
+        assert_eq!(processes[0].suspicious_score, HIGH_OUTBOUND_SCORE_BUMP);
+        assert!(!processes[1].high_outbound);
+
+        // A process whose connections can't be seen has no rate rather than a rate of zero
+        let mut unseen = vec![process("other-user", 0.0)];
+        let counters = || NetworkCounters { sockets: HashMap::new(), visible_pids: Some(HashSet::new()) };
+        let mut previous = None;
+        KernelMonitor::apply_throughput(&mut unseen, counters(), &mut previous, &mut streaks);
+        KernelMonitor::apply_throughput(&mut unseen, counters(), &mut previous, &mut streaks);
+        assert_eq!(unseen[0].bytes_sent, None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn only_a_deleted_image_is_memory_resident() {
+        assert!(!KernelMonitor::is_memory_resident(std::process::id(), None));
+
+        let image = std::env::temp_dir().join(format!("threatsentry-deleted-{}", std::process::id()));
+        fs::copy("/bin/sleep", &image).unwrap();
+        let mut child = Command::new(&image).arg("5").spawn().unwrap();
+        fs::remove_file(&image).unwrap();
+
+        let resident = KernelMonitor::is_memory_resident(child.id(), Some(&image.to_string_lossy()));
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(resident);
+    }
+}
@@ -0,0 +1,53 @@
+// Diagnostic logging with tracing: what the monitors are doing (connecting, falling back to
+// simulated data, retries, errors), kept apart from the command results printed with say!.
+// Goes to stderr, or to --log-file, at the level picked with -v/-q
+use std::fmt;
+use std::io::{self, Write};
+use tracing::Level;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::FormatTime;
+
+use crate::output;
+
+// Level for the number of -v and -q flags given; info when neither is
+pub fn level(verbose: u8, quiet: u8) -> Level {
+    match verbose as i16 - quiet as i16 {
+        i16::MIN..=-2 => Level::ERROR,
+        -1 => Level::WARN,
+        0 => Level::INFO,
+        1 => Level::DEBUG,
+        _ => Level::TRACE,
+    }
+}
+
+// Install the global subscriber; call after the log file, if any, is open
+pub fn init(level: Level, ansi: bool) {
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_target(false)
+        .with_timer(LocalTime)
+        .with_ansi(ansi && !output::has_log_file())
+        .with_writer(|| LogWriter)
+        .init();
+}
+
+// Local time in the same format as the daemon's other log lines
+struct LocalTime;
+
+impl FormatTime for LocalTime {
+    fn format_time(&self, w: &mut Writer<'_>) -> fmt::Result {
+        write!(w, "{}", chrono::Local::now().format(output::TIMESTAMP_FORMAT))
+    }
+}
+
+struct LogWriter;
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        output::write_log(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
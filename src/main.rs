@@ -1,23 +1,198 @@
+// Human-readable output. Under --format json it goes to stderr, leaving stdout to the JSON result,
+// and with a log file (`daemon --log-file`) it goes there instead
+macro_rules! say {
+    ($($arg:tt)*) => {
+        $crate::output::say(format_args!($($arg)*))
+    };
+}
+
+mod output;
 mod email_monitor;
 mod mic_monitor;
+mod audio_clip;
+mod spectrogram;
 mod thermal_monitor;
 mod notification;
+mod channels;
+mod smtp_alert;
 mod gui;
 mod kernel_monitor;
-
-use clap::{Parser, Subcommand};
+mod scoring;
+mod stix;
+mod anonymize;
+mod blocklist;
+mod allowlist;
+mod phishtank;
+mod homograph;
+mod actions;
+mod config;
+mod history;
+mod daemon;
+mod logging;
+mod monitor;
+mod world_map;
+mod geoip;
+mod report;
+mod metrics;
+mod baseline;
+
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use colored::*;
-use email_monitor::EmailMonitor;
-use mic_monitor::MicMonitor;
+use email_monitor::{ConnectionSecurity, EmailMessage, EmailMonitor, ScanResult};
+use mic_monitor::{MicHealth, MicMonitor, WindowFunction};
 use thermal_monitor::ThermalMonitor;
-use kernel_monitor::KernelMonitor;
-use notification::NotificationManager;
-use std::{thread, time::Duration};
+use kernel_monitor::{KernelMonitor, UsbClass};
+use notification::{DeliveryQueue, NotificationHistory, NotificationManager};
+use channels::{DiscordWebhook, SlackWebhook, SyslogChannel, SyslogFacility, SyslogProtocol, WebhookFormat};
+use smtp_alert::SmtpAlerter;
+use anonymize::Anonymizer;
+use blocklist::Blocklist;
+use allowlist::Allowlist;
+use actions::{ActionDispatcher, ResponseAction, SecurityEvent};
+use scoring::{MonitorWeights, ScoreHistory, SeverityThresholds, ERROR_EXIT_CODE};
+use config::Config;
+use history::{EventKind, HistoryEvent, HistoryStore};
+use daemon::ShutdownSignal;
+use metrics::{Detection, Metrics};
+use baseline::{MetricBaseline, ThermalBaseline};
+use monitor::{poll_until_stopped, Finding, FindingKind, Monitor, MonitorSample, PollResults};
+use output::OutputFormat;
+use serde_json::json;
+use std::{collections::HashMap, io::IsTerminal, net::{IpAddr, SocketAddr}, path::{Path, PathBuf}, process::ExitCode, sync::{atomic::{AtomicBool, Ordering}, Arc, Barrier, Mutex}, thread, time::{Duration, Instant}};
 use indicatif::{ProgressBar, ProgressStyle};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    /// Read settings from this TOML file (see `config init`); flags and environment variables override it
+    #[arg(long, global = true, env = "THREATSENTRY_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Output format; json prints one result object per command to stdout and the text to stderr
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// More diagnostic logging: -v adds debug messages, -vv trace
+    #[arg(short, long, global = true, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+
+    /// Less diagnostic logging: -q only shows warnings and errors, -qq only errors
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    quiet: u8,
+
+    /// Write diagnostic logging to this file instead of stderr; the daemon writes all its output there
+    #[arg(long, global = true, env = "THREATSENTRY_LOG_FILE")]
+    log_file: Option<PathBuf>,
+
+    /// Disable colored output (also honors NO_COLOR and non-terminal stdout)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Only print and notify findings scored at or above this value (combined scores still use everything)
+    #[arg(long, global = true, default_value_t = 0)]
+    min_score: u8,
+
+    /// Highest score still considered low severity (colors and notification urgency)
+    #[arg(long, global = true, default_value_t = 30)]
+    low_max: u8,
+
+    /// Highest score still considered medium severity; anything above is high and raises notifications
+    #[arg(long, global = true, default_value_t = 50)]
+    medium_max: u8,
+
+    /// Highest score still considered high severity; anything above is critical
+    #[arg(long, global = true, default_value_t = 70)]
+    high_max: u8,
+
+    /// Replace email addresses, the IMAP username and the hostname in reported findings with stable pseudonyms
+    #[arg(long, global = true)]
+    anonymize: bool,
+
+    /// Seconds during which identical notifications are suppressed and counted; repeats are then sent once as "(×N)" (0 sends every one)
+    #[arg(long, global = true, default_value_t = notification::DEFAULT_COOLDOWN_SECS)]
+    notify_cooldown: u64,
+
+    /// Don't play a sound or ring the terminal bell on critical notifications
+    #[arg(long, global = true)]
+    mute: bool,
+
+    /// Also POST high-severity notifications as JSON to this URL
+    #[arg(long, global = true, env = "THREATSENTRY_WEBHOOK_URL")]
+    webhook_url: Option<String>,
+
+    /// Payload shape for --webhook-url: generic JSON, or a Slack, Discord or Teams message
+    #[arg(long, global = true, value_enum, default_value_t = WebhookFormat::Generic)]
+    webhook_format: WebhookFormat,
+
+    /// Seconds one webhook request may take
+    #[arg(long, global = true, default_value_t = notification::DEFAULT_WEBHOOK_TIMEOUT_SECS, value_parser = clap::value_parser!(u64).range(1..))]
+    webhook_timeout: u64,
+
+    /// Times a failed webhook request is retried, waiting 1s, 2s, 4s, ... in between
+    #[arg(long, global = true, default_value_t = notification::DEFAULT_WEBHOOK_RETRIES)]
+    webhook_retries: u32,
+
+    /// Also send every notification to this Slack incoming-webhook URL
+    #[arg(long, global = true, env = "THREATSENTRY_SLACK_WEBHOOK", hide_env_values = true)]
+    slack_webhook: Option<String>,
+
+    /// Also send every notification to this Discord webhook URL
+    #[arg(long, global = true, env = "THREATSENTRY_DISCORD_WEBHOOK", hide_env_values = true)]
+    discord_webhook: Option<String>,
+
+    /// Also send every notification to this syslog server as RFC 5424 ("host" or "host:port", default port 514)
+    #[arg(long, global = true)]
+    syslog_server: Option<String>,
+
+    /// Syslog transport
+    #[arg(long, global = true, value_enum, default_value_t = SyslogProtocol::Udp)]
+    syslog_protocol: SyslogProtocol,
+
+    /// Syslog facility
+    #[arg(long, global = true, value_enum, default_value_t = SyslogFacility::User)]
+    syslog_facility: SyslogFacility,
+
+    /// Email alerts scored above --alert-email-min-score to this address (needs --smtp-server)
+    #[arg(long, global = true)]
+    alert_email: Option<String>,
+
+    /// SMTP server hostname for alert emails
+    #[arg(long, global = true)]
+    smtp_server: Option<String>,
+
+    /// SMTP server port
+    #[arg(long, global = true, default_value_t = 465)]
+    smtp_port: u16,
+
+    /// SMTP transport security (plaintext sends the password unencrypted)
+    #[arg(long, global = true, value_enum, default_value_t = ConnectionSecurity::ImplicitTls)]
+    smtp_security: ConnectionSecurity,
+
+    /// SMTP username
+    #[arg(long, global = true, env = "THREATSENTRY_SMTP_USER")]
+    smtp_user: Option<String>,
+
+    /// SMTP password
+    #[arg(long, global = true, env = "THREATSENTRY_SMTP_PASS", hide_env_values = true)]
+    smtp_pass: Option<String>,
+
+    /// Sender address for alert emails (default: the SMTP username, else the alert address)
+    #[arg(long, global = true)]
+    smtp_from: Option<String>,
+
+    /// Only alerts scored above this are emailed
+    #[arg(long, global = true, default_value_t = smtp_alert::DEFAULT_MIN_SCORE)]
+    alert_email_min_score: u8,
+
+    /// Alerts within this many seconds of the last email are sent together in the next one
+    #[arg(long, global = true, default_value_t = smtp_alert::DEFAULT_COALESCE_WINDOW_SECS)]
+    alert_email_window: u64,
+
+    /// Record every run's scores and findings (thermal spikes, new USB devices, phishing URLs) in this SQLite database, for `history`
+    #[arg(long, global = true, env = "THREATSENTRY_HISTORY_DB")]
+    history_db: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -25,493 +200,2656 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Monitor emails for phishing attempts
-    Email {
-        /// Gmail username
-        #[arg(short, long)]
-        username: String,
-
-        /// Gmail password or app password
-        #[arg(short, long)]
-        password: String,
-
-        /// Number of recent emails to check
-        #[arg(short, long, default_value_t = 5)]
-        limit: usize,
-    },
+    Email(EmailCommand),
 
     /// Monitor microphone for high-frequency signals
-    Mic {
-        /// Duration to monitor in seconds
-        #[arg(short, long, default_value_t = 10)]
-        duration: u64,
-    },
+    Mic(MicCommand),
 
     /// Monitor system temperature for anomalies
     Thermal {
         /// Duration to monitor in seconds
         #[arg(short, long, default_value_t = 30)]
         duration: u64,
+
+        /// Temperature rise in °C within the spike window that counts as a spike
+        #[arg(long, default_value_t = thermal_monitor::DEFAULT_SPIKE_DELTA_C)]
+        spike_delta: f32,
+
+        /// Append every reading (timestamp, temperature, CPU load, score) to this CSV file
+        #[arg(long)]
+        thermal_log: Option<PathBuf>,
+
+        /// Size in MB at which the thermal log is rotated to <PATH>.1
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        thermal_log_max_mb: u64,
+
+        /// Seconds back the rise is measured from, against the coolest reading in that time
+        #[arg(long, default_value_t = thermal_monitor::DEFAULT_SPIKE_WINDOW.as_secs(), value_parser = clap::value_parser!(u64).range(1..))]
+        spike_window: u64,
+
+        /// Seconds a spike keeps the score elevated before it falls back to the history-based score
+        #[arg(long, default_value_t = thermal_monitor::DEFAULT_SPIKE_COOLDOWN.as_secs(), value_parser = clap::value_parser!(u64).range(1..))]
+        spike_cooldown: u64,
     },
 
     /// Monitor system processes and USB devices
-    Kernel {
-        /// Duration to monitor in seconds
-        #[arg(short, long, default_value_t = 60)]
-        duration: u64,
-    },
+    Kernel(KernelCommand),
 
     /// Run all monitoring systems
-    Full {
-        /// Gmail username
-        #[arg(short, long)]
-        username: Option<String>,
+    Full(FullCommand),
 
-        /// Gmail password or app password
-        #[arg(short, long)]
-        password: Option<String>,
+    /// Run all monitors until stopped with SIGINT/SIGTERM; SIGHUP reloads the config file
+    Daemon(DaemonCommand),
 
-        /// Duration to monitor in seconds
-        #[arg(short, long, default_value_t = 60)]
-        duration: u64,
+    /// Learn this machine's normal temperature, CPU load and microphone noise and store it in the --config file
+    Baseline(BaselineCommand),
+
+    /// Check which monitoring capabilities actually work on this machine
+    Doctor {
+        #[command(flatten)]
+        imap: ImapArgs,
     },
 
     /// Launch the graphical user interface
-    Gui {
-        /// Gmail username
-        #[arg(short, long)]
-        username: String,
-
-        /// Gmail password or app password
-        #[arg(short, long)]
-        password: String,
-    },
-}
+    Gui(GuiCommand),
 
-fn main() {
-    print_banner();
+    /// Summarize the scan history recorded with --history-db
+    History {
+        /// Start of the period: an age like 12h or 7d, a date like 2024-05-01, or an RFC 3339 time
+        #[arg(long, default_value = history::DEFAULT_SINCE, value_parser = history::parse_since)]
+        since: i64,
+    },
 
-    let cli = Cli::parse();
+    /// Manage the configuration file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
 
-    match &cli.command {
-        Some(Commands::Email { username, password, limit }) => {
-            run_email_monitor(username, password, *limit);
-        },
-        Some(Commands::Mic { duration }) => {
-            run_mic_monitor(*duration);
-        },
-        Some(Commands::Thermal { duration }) => {
-            run_thermal_monitor(*duration);
-        },
-        Some(Commands::Kernel { duration }) => {
-            run_kernel_monitor(*duration);
-        },
-        Some(Commands::Full { username, password, duration }) => {
-            run_full_scan(username, password, *duration);
-        },
-        Some(Commands::Gui { username, password }) => {
-            run_gui(username, password);
-        },
-        None => {
-            println!("{}", "No command specified. Use --help for usage information.".yellow());
-        }
-    }
+    /// Print version information
+    Version {
+        /// Include build details, compiled-in backends and device availability (safe to paste into an issue)
+        #[arg(long)]
+        full: bool,
+    },
 }
 
-fn print_banner() {
-    println!("{}", r"
- _____ _                    _   _____            _              _   _ _ _
-|_   _| |                  | | /  ___|          | |            | | | | | |
-  | | | |__  _ __ ___  __ _| |_\ `--.  ___ _ __ | |_ _ __ _   _| | | | | |_ _ __ __ _
-  | | | '_ \| '__/ _ \/ _` | __|`--. \/ _ \ '_ \| __| '__| | | | | | | | __| '__/ _` |
-  | | | | | | | |  __/ (_| | |_/\__/ /  __/ | | | |_| |  | |_| | |_| | | |_| | | (_| |
-  \_/ |_| |_|_|  \___|\__,_|\__\____/ \___|_| |_|\__|_|   \__, |\___/|_|\__|_|  \__,_|
-                                                            __/ |
-                                                           |___/
-    ".bright_cyan());
-    println!("{}", "Hardware-Powered Cyber Threat Intelligence".bright_green());
-    println!("{}", "---------------------------------------------".bright_blue());
-}
+// IMAP account settings shared by the commands that read mail
+#[derive(Args)]
+struct ImapArgs {
+    /// Gmail username; `email` requires one, the other commands skip email scanning without it
+    #[arg(short, long, env = "THREATSENTRY_IMAP_USER")]
+    username: Option<String>,
 
-fn run_email_monitor(username: &str, password: &str, limit: usize) {
-    println!("{}", "\n[EMAIL MONITOR]".bright_blue());
-    println!("Scanning {} recent emails for threats...", limit);
+    /// Gmail password or app password
+    #[arg(short, long, env = "THREATSENTRY_IMAP_PASS", hide_env_values = true)]
+    password: Option<String>,
 
-    let email_monitor = EmailMonitor::new(
-        username.to_string(),
-        password.to_string(),
-        "imap.gmail.com".to_string(),
-    );
+    /// IMAP server hostname
+    #[arg(long, default_value = "imap.gmail.com")]
+    imap_server: String,
 
-    // Fetch emails
-    let emails = match email_monitor.fetch_emails(limit) {
-        Ok(emails) => emails,
-        Err(e) => {
-            println!("{} {}", "Error fetching emails:".bright_red(), e);
-            return;
-        }
-    };
+    /// IMAP server port
+    #[arg(long, default_value_t = email_monitor::DEFAULT_IMAP_PORT)]
+    imap_port: u16,
 
-    // Extract and scan URLs
-    let urls = email_monitor.extract_urls(emails);
-    let scored_urls = email_monitor.scan_urls(urls);
+    /// IMAP transport security (plaintext sends the password unencrypted)
+    #[arg(long, value_enum, default_value_t = ConnectionSecurity::ImplicitTls)]
+    imap_security: ConnectionSecurity,
+}
 
-    // Display results
-    println!("\nResults:");
-    for (url, score) in scored_urls {
-        let score_color = match score {
-            0..=30 => score.to_string().green(),
-            31..=70 => score.to_string().yellow(),
-            _ => score.to_string().red(),
-        };
+// URL scoring lists shared by the commands that scan mail
+#[derive(Args)]
+struct EmailFilterArgs {
+    /// Local URL/domain blocklist (URLhaus, hpHosts or plain list); listed URLs score 100
+    #[arg(long)]
+    blocklist: Option<PathBuf>,
 
-        println!("URL: {} | Threat Score: {}", url, score_color);
+    /// Trusted domains, one per line (exact host or *.wildcard); their URLs score 0
+    #[arg(long)]
+    allowlist: Option<PathBuf>,
 
-        // Send notification for high-risk URLs
-        if score > 50 {
-            let notification_manager = NotificationManager::new();
-            let _ = notification_manager.send_notification(
-                "ThreatSentry Ultra",
-                &format!("Suspicious URL detected: {}", url),
-                score,
-            );
-        }
-    }
+    /// Comma-separated extra brand domains to flag typosquats of (paypal.com, microsoft.com, apple.com, amazon.com and google.com are always included)
+    #[arg(long, value_delimiter = ',')]
+    brand_domain: Vec<String>,
+
+    /// PhishTank application key; URLs are scored by PhishTank verdict instead of keywords
+    #[arg(long, env = "THREATSENTRY_PHISHTANK_KEY", hide_env_values = true)]
+    phishtank_key: Option<String>,
 }
 
-fn run_mic_monitor(duration: u64) {
-    println!("{}", "\n[MICROPHONE MONITOR]".bright_blue());
-    println!("Monitoring microphone for high-frequency signals for {} seconds...", duration);
+// Process and USB settings shared by the commands that watch the kernel
+#[derive(Args)]
+struct KernelArgs {
+    /// Comma-separated USB device classes that raise alerts (default: all)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    usb_alert_class: Vec<UsbClass>,
 
-    let mic_monitor = MicMonitor::new();
+    /// Extra suspicious process names, one per line as "name = score" (score 0 disables a built-in name)
+    #[arg(long)]
+    signatures: Option<PathBuf>,
 
-    // Start monitoring
-    match mic_monitor.start_monitoring() {
-        Ok(_) => {
-            // Show progress bar
-            let pb = ProgressBar::new(duration);
-            pb.set_style(ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} seconds")
-                .unwrap()
-                .progress_chars("#>-"));
+    /// Trusted processes that are never flagged, one name per line, optionally "name = full image path"
+    #[arg(long)]
+    process_allowlist: Option<PathBuf>,
 
-            for _ in 0..duration {
-                thread::sleep(Duration::from_secs(1));
-                pb.inc(1);
-            }
+    /// New processes per second above which a scan counts as a spawn burst
+    #[arg(long, default_value_t = kernel_monitor::DEFAULT_SPAWN_RATE_THRESHOLD, value_parser = parse_spawn_rate)]
+    spawn_threshold: f32,
+}
 
-            pb.finish_with_message("Monitoring complete");
+// Monitor selection and settings shared by the full scan and the daemon
+#[derive(Args)]
+struct ScanArgs {
+    #[command(flatten)]
+    imap: ImapArgs,
 
-            // Stop monitoring and get results
-            mic_monitor.stop_monitoring();
-            let score = mic_monitor.get_threat_score();
+    /// Maximum amount any single monitor can contribute to the combined score (0-100)
+    #[arg(long)]
+    score_cap: Option<u8>,
 
-            // Display results
-            let score_color = match score {
-                0..=30 => score.to_string().green(),
-                31..=70 => score.to_string().yellow(),
-                _ => score.to_string().red(),
-            };
+    /// Comma-separated list of monitors to run (mic,thermal,kernel,email)
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [MonitorKind::Mic, MonitorKind::Thermal, MonitorKind::Kernel, MonitorKind::Email])]
+    monitors: Vec<MonitorKind>,
 
-            println!("\nResults:");
-            println!("Mic Threat Score: {}", score_color);
+    #[command(flatten)]
+    filters: EmailFilterArgs,
 
-            // Send notification for high scores
-            if score > 50 {
-                let notification_manager = NotificationManager::new();
-                let _ = notification_manager.send_notification(
-                    "ThreatSentry Ultra",
-                    "High-frequency audio signal detected!",
-                    score,
-                );
-            }
-        },
-        Err(e) => {
-            println!("{} {}", "Error starting microphone monitoring:".bright_red(), e);
-        }
-    }
+    /// Extra scam phrases to score email text with, one per line as "phrase = weight"
+    #[arg(long)]
+    body_phrases: Option<PathBuf>,
+
+    /// Microphone band power that counts as a detection, from 0.0 to 1.0 (see `mic --threshold`)
+    #[arg(long, default_value_t = mic_monitor::DEFAULT_THRESHOLD, value_parser = parse_threshold)]
+    threshold: f32,
+
+    #[command(flatten)]
+    kernel: KernelArgs,
 }
 
-fn run_thermal_monitor(duration: u64) {
-    println!("{}", "\n[THERMAL MONITOR]".bright_blue());
-    println!("Monitoring system temperature for {} seconds...", duration);
+#[derive(Args)]
+struct EmailCommand {
+    #[command(flatten)]
+    imap: ImapArgs,
 
-    let mut thermal_monitor = ThermalMonitor::new();
+    /// OAuth2 access token, used instead of the password (IMAP AUTHENTICATE XOAUTH2)
+    #[arg(long, env = "THREATSENTRY_IMAP_OAUTH_TOKEN", hide_env_values = true)]
+    oauth_token: Option<String>,
 
-    // Show progress bar
-    let pb = ProgressBar::new(duration);
-    pb.set_style(ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} seconds")
-        .unwrap()
-        .progress_chars("#>-"));
+    /// Number of recent emails to check
+    #[arg(short, long, default_value_t = 5)]
+    limit: usize,
 
-    for _ in 0..duration {
-        match thermal_monitor.check_temperature() {
-            Ok(temp) => {
-                pb.set_message(format!("Current temperature: {:.1}°C", temp));
-            },
-            Err(e) => {
-                println!("{} {}", "Error checking temperature:".bright_red(), e);
-            }
-        }
+    /// Only scan mail that arrived since the last incremental scan of this account
+    #[arg(long)]
+    incremental: bool,
 
-        thread::sleep(Duration::from_secs(1));
-        pb.inc(1);
-    }
+    /// Forget the last-seen message so the next incremental scan starts from scratch
+    #[arg(long)]
+    reset_cursor: bool,
 
-    pb.finish_with_message("Monitoring complete");
+    /// File holding the last scanned message UID (default: per-account file under ~/.threatsentry/cursors)
+    #[arg(long)]
+    state_file: Option<PathBuf>,
 
-    // Get results
-    let score = thermal_monitor.get_threat_score();
+    /// Also write flagged URLs to this file as a STIX 2.1 bundle
+    #[arg(long)]
+    stix: Option<PathBuf>,
 
-    // Display results
-    let score_color = match score {
-        0..=30 => score.to_string().green(),
-        31..=70 => score.to_string().yellow(),
-        _ => score.to_string().red(),
-    };
+    #[command(flatten)]
+    filters: EmailFilterArgs,
 
-    println!("\nResults:");
-    println!("Thermal Threat Score: {}", score_color);
+    /// Show the text around each flagged URL (scrubbed under --anonymize)
+    #[arg(long)]
+    include_body_snippet: bool,
 
-    // Send notification for high scores
-    if score > 50 {
-        let notification_manager = NotificationManager::new();
-        let _ = notification_manager.send_notification(
-            "ThreatSentry Ultra",
-            "Temperature spike detected! Possible crypto-miner activity.",
-            score,
-        );
-    }
+    /// Extra scam phrases to score email text with, one per line as "phrase = weight"
+    #[arg(long)]
+    body_phrases: Option<PathBuf>,
 }
 
-fn run_kernel_monitor(duration: u64) {
-    println!("{}", "\n[KERNEL TELEMETRY]".bright_blue());
-    println!("Monitoring system processes and USB devices for {} seconds...", duration);
+#[derive(Args)]
+struct MicCommand {
+    /// Duration to monitor in seconds
+    #[arg(short, long, default_value_t = 10)]
+    duration: u64,
 
-    let kernel_monitor = KernelMonitor::new();
-    let notification_manager = NotificationManager::new();
+    /// How often the FFT thread checks for new audio, in milliseconds (lower = less latency, more CPU)
+    #[arg(long, default_value_t = 100)]
+    fft_poll_ms: u64,
 
-    // Start monitoring
-    match kernel_monitor.start_monitoring() {
-        Ok(_) => println!("Kernel monitoring started successfully"),
-        Err(e) => {
-            println!("{} {}", "Error starting kernel monitoring:".bright_red(), e);
-            return;
-        }
-    }
+    /// How many times to rebuild the audio stream if the input device disappears
+    #[arg(long, default_value_t = 3)]
+    mic_retries: u32,
 
-    // Create a progress bar
-    let pb = ProgressBar::new(duration);
-    pb.set_style(ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} seconds")
-        .unwrap()
-        .progress_chars("#>-"));
+    /// Lower edge of the monitored frequency band in Hz
+    #[arg(long, default_value_t = mic_monitor::DEFAULT_MIN_FREQ_HZ)]
+    min_freq: f32,
 
-    for i in 0..duration {
-        // Get suspicious processes
-        let suspicious_processes = kernel_monitor.get_suspicious_processes();
-        if !suspicious_processes.is_empty() {
-            println!("\nSuspicious processes detected:");
-            for process in &suspicious_processes {
-                println!("  - {} (PID: {}, CPU: {:.1}%, Score: {})",
-                    process.name.bright_yellow(),
-                    process.pid,
-                    process.cpu_usage,
-                    colorize_score(process.suspicious_score));
-            }
-        }
+    /// Upper edge of the monitored frequency band in Hz (must be below half the sample rate)
+    #[arg(long, default_value_t = mic_monitor::DEFAULT_MAX_FREQ_HZ)]
+    max_freq: f32,
 
-        // Get new USB devices
-        let new_usb_devices = kernel_monitor.get_new_usb_devices();
-        if !new_usb_devices.is_empty() {
-            println!("\nNew USB devices detected:");
-            for device in &new_usb_devices {
-                println!("  - {} (ID: {})",
-                    device.description.bright_yellow(),
-                    device.device_id);
-            }
+    /// Band power that counts as a detection, from 0.0 (silent band) to 1.0 (band as loud as the loudest sound)
+    #[arg(long, default_value_t = mic_monitor::DEFAULT_THRESHOLD, value_parser = parse_threshold)]
+    threshold: f32,
 
-            // Send notification for new USB devices
-            let _ = notification_manager.send_notification(
-                "USB Device Detected",
-                &format!("{} new USB device(s) connected", new_usb_devices.len()),
-                50,
-            );
-        }
+    /// Seconds after the last detection before the score drops back to 0
+    #[arg(long, default_value_t = mic_monitor::DEFAULT_DETECTION_WINDOW.as_secs(), value_parser = clap::value_parser!(u64).range(1..))]
+    detection_window: u64,
 
-        // Sleep for 1 second
-        if i < duration - 1 {
-            thread::sleep(Duration::from_secs(1));
-        }
+    /// Measure the room's noise floor for this many seconds first and derive the threshold from it
+    #[arg(long, conflicts_with = "threshold")]
+    calibrate: Option<u64>,
 
-        pb.inc(1);
-    }
+    /// Input device to monitor, by index or name (see --list-devices); defaults to the system default
+    #[arg(long)]
+    device: Option<String>,
 
-    pb.finish_with_message("Monitoring complete");
+    /// List input devices and their supported sample rates, then exit
+    #[arg(long)]
+    list_devices: bool,
 
-    // Stop monitoring
-    kernel_monitor.stop_monitoring();
+    /// Save the audio around each detection to a timestamped WAV file in this directory
+    #[arg(long)]
+    record_dir: Option<PathBuf>,
 
-    // Get threat score
-    let score = kernel_monitor.get_threat_score();
+    /// Samples between the starts of consecutive FFT frames (4096 = no overlap, 2048 = 50% overlap)
+    #[arg(long, default_value_t = mic_monitor::DEFAULT_HOP_SIZE as u64, value_parser = clap::value_parser!(u64).range(1..=mic_monitor::FFT_BUFFER_SIZE as u64))]
+    hop_size: u64,
 
-    // Display results
-    println!("\nResults:");
-    println!("Kernel Threat Score: {}", colorize_score(score));
+    /// Window function applied to each FFT frame (flat-top measures a carrier's amplitude most accurately)
+    #[arg(long, value_enum, default_value_t = WindowFunction::Hann)]
+    window: WindowFunction,
 
-    // Send notification for high scores
-    if score > 50 {
-        let _ = notification_manager.send_notification(
-            "ThreatSentry Ultra",
-            "Suspicious process or USB activity detected!",
-            score,
-        );
-    }
-}
+    /// Append each detection event (start, end, peak frequency, power) to this CSV file
+    #[arg(long)]
+    event_log: Option<PathBuf>,
 
-fn run_full_scan(username: &Option<String>, password: &Option<String>, duration: u64) {
-    println!("{}", "\n[FULL SYSTEM SCAN]".bright_blue());
-    println!("Running comprehensive threat scan for {} seconds...", duration);
+    /// Write every FFT frame's magnitude spectrum to this CSV file, one row per frame
+    #[arg(long)]
+    spectrogram: Option<PathBuf>,
 
-    // Initialize monitors
-    let mic_monitor = MicMonitor::new();
-    let mut thermal_monitor = ThermalMonitor::new();
-    let kernel_monitor = KernelMonitor::new();
+    /// Stop the spectrogram export after this many seconds to cap the file size
+    #[arg(long, requires = "spectrogram")]
+    spectrogram_max_secs: Option<u64>,
+}
 
-    // Start microphone monitoring
-    match mic_monitor.start_monitoring() {
-        Ok(_) => {
-            println!("{}", "Microphone monitoring started".green());
-        },
-        Err(e) => {
-            println!("{} {}", "Error starting microphone monitoring:".bright_red(), e);
-        }
-    }
+#[derive(Args)]
+struct KernelCommand {
+    /// Duration to monitor in seconds
+    #[arg(short, long, default_value_t = 60)]
+    duration: u64,
 
-    // Start kernel monitoring
-    match kernel_monitor.start_monitoring() {
-        Ok(_) => {
-            println!("{}", "Kernel monitoring started".green());
-        },
-        Err(e) => {
-            println!("{} {}", "Error starting kernel monitoring:".bright_red(), e);
-        }
-    }
+    /// List every running process sorted by suspicious score, not only flagged ones
+    #[arg(long)]
+    show_all: bool,
 
-    // Show progress bar
-    let pb = ProgressBar::new(duration);
-    pb.set_style(ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} seconds")
-        .unwrap()
-        .progress_chars("#>-"));
+    #[command(flatten)]
+    kernel: KernelArgs,
 
-    for _ in 0..duration {
-        // Check temperature
-        match thermal_monitor.check_temperature() {
-            Ok(temp) => {
-                pb.set_message(format!("Current temperature: {:.1}°C", temp));
-            },
-            Err(e) => {
-                println!("{} {}", "Error checking temperature:".bright_red(), e);
-            }
-        }
+    /// Response to a newly inserted USB device of an alerting class (block disables the device; needs admin)
+    #[arg(long, value_enum, default_value_t = ResponseAction::Notify)]
+    on_usb: ResponseAction,
 
-        thread::sleep(Duration::from_secs(1));
-        pb.inc(1);
-    }
+    /// Response to a suspicious process (block stops high-severity processes only)
+    #[arg(long, value_enum, default_value_t = ResponseAction::Log)]
+    on_process: ResponseAction,
+}
 
-    pb.finish_with_message("Monitoring complete");
+#[derive(Args)]
+struct FullCommand {
+    /// Duration to monitor in seconds
+    #[arg(short, long, default_value_t = 60)]
+    duration: u64,
 
-    // Stop microphone monitoring
-    mic_monitor.stop_monitoring();
+    /// Also write flagged URLs to this file as a STIX 2.1 bundle
+    #[arg(long)]
+    stix: Option<PathBuf>,
 
-    // Stop kernel monitoring
-    kernel_monitor.stop_monitoring();
+    #[command(flatten)]
+    scan: ScanArgs,
+}
 
-    // Get results
-    let mic_score = mic_monitor.get_threat_score();
-    let thermal_score = thermal_monitor.get_threat_score();
-    let kernel_score = kernel_monitor.get_threat_score();
+#[derive(Args)]
+struct DaemonCommand {
+    /// Seconds between checks for new mail
+    #[arg(long, default_value_t = daemon::DEFAULT_EMAIL_INTERVAL_SECS, value_parser = clap::value_parser!(u64).range(10..))]
+    email_interval: u64,
 
-    // Run email scan if credentials provided
-    let mut email_score = 0;
-    if let (Some(username), Some(password)) = (username, password) {
-        println!("\nScanning emails...");
+    /// Seconds of combined scores that alerts and the exit code are based on
+    #[arg(long, default_value_t = daemon::DEFAULT_WINDOW_SECS, value_parser = clap::value_parser!(u64).range(1..=daemon::MAX_WINDOW_SECS))]
+    window: u64,
 
-        let email_monitor = EmailMonitor::new(
-            username.to_string(),
-            password.to_string(),
-            "imap.gmail.com".to_string(),
-        );
+    /// Percentile of the combined scores in the window that counts (100 = the highest)
+    #[arg(long, default_value_t = daemon::DEFAULT_PERCENTILE, value_parser = clap::value_parser!(u8).range(1..=100))]
+    percentile: u8,
 
-        // Fetch emails
-        match email_monitor.fetch_emails(5) {
-            Ok(emails) => {
-                // Extract and scan URLs
-                let urls = email_monitor.extract_urls(emails);
-                let scored_urls = email_monitor.scan_urls(urls);
-
-                // Display results and get highest score
-                println!("\nEmail Results:");
-                for (url, score) in &scored_urls {
-                    let score_color = match score {
-                        0..=30 => score.to_string().green(),
-                        31..=70 => score.to_string().yellow(),
-                        _ => score.to_string().red(),
-                    };
-
-                    println!("URL: {} | Threat Score: {}", url, score_color);
-
-                    // Update highest score
-                    if *score > email_score {
-                        email_score = *score;
-                    }
-                }
-            },
-            Err(e) => {
-                println!("{} {}", "Error fetching emails:".bright_red(), e);
-            }
-        }
-    }
+    #[command(flatten)]
+    scan: ScanArgs,
 
-    // Calculate combined threat score
-    let combined_score = (mic_score as u16 + thermal_score as u16 + kernel_score as u16 + email_score as u16) / 4;
+    /// Response to a newly inserted USB device of an alerting class
+    #[arg(long, value_enum, default_value_t = ResponseAction::Notify)]
+    on_usb: ResponseAction,
 
-    // Display final results
-    println!("\n{}", "FINAL RESULTS".bright_yellow());
-    println!("---------------------");
-    println!("Microphone Threat Score: {}", colorize_score(mic_score));
-    println!("Thermal Threat Score: {}", colorize_score(thermal_score));
-    println!("Kernel Threat Score: {}", colorize_score(kernel_score));
-    println!("Email Threat Score: {}", colorize_score(email_score));
-    println!("---------------------");
-    println!("Combined Threat Score: {}", colorize_score(combined_score as u8));
+    /// Response to a suspicious process
+    #[arg(long, value_enum, default_value_t = ResponseAction::Log)]
+    on_process: ResponseAction,
 
-    // Send notification for high combined score
-    if combined_score > 50 {
-        let notification_manager = NotificationManager::new();
-        let _ = notification_manager.send_notification(
-            "ThreatSentry Ultra",
-            &format!("High threat level detected! Score: {}", combined_score),
-            combined_score as u8,
-        );
-    }
+    /// Serve Prometheus metrics on this port at /metrics
+    #[arg(long)]
+    metrics_port: Option<u16>,
+
+    /// Address the metrics endpoint listens on (0.0.0.0 for every interface)
+    #[arg(long, default_value = metrics::DEFAULT_METRICS_BIND, requires = "metrics_port")]
+    metrics_bind: String,
 }
 
-fn colorize_score(score: u8) -> colored::ColoredString {
-    match score {
-        0..=30 => score.to_string().green(),
-        31..=70 => score.to_string().yellow(),
-        _ => score.to_string().red(),
-    }
+#[derive(Args)]
+struct BaselineCommand {
+    /// Duration to sample in seconds; run it during normal use, not under unusual load
+    #[arg(short, long, default_value_t = 300, value_parser = clap::value_parser!(u64).range(10..))]
+    duration: u64,
+
+    /// Standard deviations above the learned mean that count as anomalous
+    #[arg(long, default_value_t = baseline::DEFAULT_SIGMA, value_parser = parse_sigma)]
+    sigma: f32,
+
+    /// Lower edge of the microphone band in Hz (see `mic --min-freq`)
+    #[arg(long, default_value_t = mic_monitor::DEFAULT_MIN_FREQ_HZ)]
+    min_freq: f32,
+
+    /// Upper edge of the microphone band in Hz (see `mic --max-freq`)
+    #[arg(long, default_value_t = mic_monitor::DEFAULT_MAX_FREQ_HZ)]
+    max_freq: f32,
+
+    /// Input device to sample, by index or name (see `mic --list-devices`)
+    #[arg(long)]
+    device: Option<String>,
 }
 
-fn run_gui(username: &str, password: &str) {
-    println!("{}", "\n[GUI]".bright_blue());
-    println!("Launching ThreatSentry Ultra GUI...");
+#[derive(Args)]
+struct GuiCommand {
+    #[command(flatten)]
+    imap: ImapArgs,
+
+    /// Maximum amount any single monitor can contribute to the combined score (0-100)
+    #[arg(long)]
+    score_cap: Option<u8>,
 
-    match gui::run_gui(username.to_string(), password.to_string()) {
-        Ok(_) => println!("GUI closed successfully."),
-        Err(e) => println!("{} {}", "Error running GUI:".bright_red(), e),
+    /// Forget the last-seen message so email scanning starts from scratch
+    #[arg(long)]
+    reset_cursor: bool,
+
+    #[command(flatten)]
+    filters: EmailFilterArgs,
+
+    /// Points a gauge has to move past a severity boundary before its color changes
+    #[arg(long, default_value_t = 5)]
+    hysteresis: u8,
+
+    /// Interval between monitoring samples in milliseconds (50-5000)
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u64).range(gui::MIN_SAMPLE_MS..=gui::MAX_SAMPLE_MS))]
+    sample_ms: u64,
+
+    /// Screen repaint rate in frames per second (1-60)
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(gui::MIN_REPAINT_FPS..=gui::MAX_REPAINT_FPS))]
+    fps: u64,
+
+    /// Extra suspicious process names, one per line as "name = score" (score 0 disables a built-in name)
+    #[arg(long)]
+    signatures: Option<PathBuf>,
+
+    /// Trusted processes that are never flagged, one name per line, optionally "name = full image path"
+    #[arg(long)]
+    process_allowlist: Option<PathBuf>,
+
+    /// MaxMind-format city database (.mmdb, e.g. GeoLite2-City) used to place phishing URLs and suspicious connections on the threat map
+    #[arg(long, env = "THREATSENTRY_GEOIP_DB")]
+    geoip_db: Option<PathBuf>,
+
+    /// Resolve phishing URL host names through DNS to place them on the threat map; each lookup of an attacker's domain is sent from this machine
+    #[arg(long)]
+    geoip_resolve_hosts: bool,
+
+    /// Microphone band power that counts as a detection, from 0.0 to 1.0 (see `mic --threshold`); adjustable in Settings
+    #[arg(long, default_value_t = mic_monitor::DEFAULT_THRESHOLD, value_parser = parse_threshold)]
+    threshold: f32,
+
+    /// Lower edge of the monitored microphone band in Hz; adjustable in Settings
+    #[arg(long, default_value_t = mic_monitor::DEFAULT_MIN_FREQ_HZ)]
+    min_freq: f32,
+
+    /// Upper edge of the monitored microphone band in Hz; adjustable in Settings
+    #[arg(long, default_value_t = mic_monitor::DEFAULT_MAX_FREQ_HZ)]
+    max_freq: f32,
+
+    /// Temperature rise in °C that counts as a thermal spike; adjustable in Settings
+    #[arg(long, default_value_t = thermal_monitor::DEFAULT_SPIKE_DELTA_C)]
+    spike_delta: f32,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Write a configuration file listing every setting with its default
+    Init {
+        /// Where to write it
+        #[arg(default_value = config::DEFAULT_CONFIG_FILE)]
+        path: PathBuf,
+
+        /// Overwrite the file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+// Monitors that can be selected for the full scan
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum MonitorKind {
+    Mic,
+    Thermal,
+    Kernel,
+    Email,
+}
+
+// Settings shared by every command
+struct RunOptions {
+    // Unique per invocation; included in notifications and reports so they can be correlated
+    run_id: String,
+    min_score: u8,
+    thresholds: SeverityThresholds,
+    weights: MonitorWeights, // Per-monitor weights in the combined score, from the config file
+    thermal_baseline: Option<ThermalBaseline>, // Learned by `baseline`, from the config file
+    anonymize: bool,
+    webhook_url: Option<String>,
+    webhook_format: WebhookFormat,
+    webhook_timeout: Duration,
+    webhook_retries: u32,
+    slack_webhook: Option<String>,
+    discord_webhook: Option<String>,
+    smtp: Option<SmtpAlerter>, // Shared by every notifier so alert emails coalesce across the run
+    syslog_server: Option<String>,
+    syslog_protocol: SyslogProtocol,
+    syslog_facility: SyslogFacility,
+    mute: bool,
+    notify_cooldown: Duration,
+    notification_history: NotificationHistory, // Shared by every notifier so repeats are suppressed run-wide
+    deliveries: DeliveryQueue, // Shared by every notifier; network alerts go out on its thread
+    history: Option<HistoryStore>, // Scan history database, when --history-db is set
+}
+
+impl RunOptions {
+    // Whether a scored finding should be printed, notified, or reported
+    fn shows(&self, score: u8) -> bool {
+        score >= self.min_score
+    }
+
+    // Notification manager using the configured severity bands, tagged with this run and monitor
+    fn notifier(&self, monitor: &str) -> NotificationManager {
+        let mut notifier = NotificationManager::new()
+            .with_thresholds(self.thresholds)
+            .with_run_id(&self.run_id)
+            .with_monitor(monitor)
+            .with_sound(!self.mute)
+            .with_cooldown(self.notify_cooldown)
+            .with_history(self.notification_history.clone())
+            .with_deliveries(self.deliveries.clone());
+
+        if let Some(url) = &self.webhook_url {
+            notifier = notifier.with_webhook(url, self.webhook_format, self.webhook_timeout, self.webhook_retries);
+        }
+        if let Some(url) = &self.slack_webhook {
+            notifier = notifier.with_channel(Box::new(SlackWebhook::new(url, self.webhook_timeout, self.webhook_retries)));
+        }
+        if let Some(url) = &self.discord_webhook {
+            notifier = notifier.with_channel(Box::new(DiscordWebhook::new(url, self.webhook_timeout, self.webhook_retries)));
+        }
+        if let Some(server) = &self.syslog_server {
+            notifier = notifier.with_channel(Box::new(SyslogChannel::new(server, self.syslog_protocol, self.syslog_facility)));
+        }
+        if let Some(smtp) = &self.smtp {
+            notifier = notifier.with_smtp(smtp.clone());
+        }
+        notifier
+    }
+
+    // Add a score sample or finding to the scan history, if one is kept; a failed write is
+    // reported but doesn't stop the scan
+    fn record(&self, event: HistoryEvent) {
+        if let Some(history) = &self.history {
+            if let Err(e) = history.record(&event) {
+                say!("{} {}", "Error recording history:".bright_red(), e);
+            }
+        }
+    }
+
+    // Scrubber for reported findings when --anonymize is set
+    fn anonymizer(&self, username: Option<&str>) -> Option<Anonymizer> {
+        self.anonymize.then(|| Anonymizer::new(username))
+    }
+}
+
+fn main() -> ExitCode {
+    // Keep the matches to tell flags the user passed from clap defaults, which the config file overrides
+    // Argument errors exit with ERROR_EXIT_CODE rather than clap's 2, which means a high score
+    let matches = match Cli::command().try_get_matches() {
+        Ok(matches) => matches,
+        Err(e) => return clap_exit(e),
+    };
+    let cli = match Cli::from_arg_matches(&matches) {
+        Ok(cli) => cli,
+        Err(e) => return clap_exit(e),
+    };
+
+    output::set_format(cli.format);
+    configure_color(cli.no_color);
+
+    if let Some(path) = &cli.log_file {
+        if let Err(e) = output::set_log_file(path) {
+            say!("{} {}", "Error:".bright_red(), e);
+            return ExitCode::from(ERROR_EXIT_CODE);
+        }
+    }
+    logging::init(logging::level(cli.verbose, cli.quiet), colored::control::SHOULD_COLORIZE.should_colorize());
+
+    // Version output stays short and paste-friendly
+    if let Some(Commands::Version { full }) = &cli.command {
+        run_version(*full);
+        return ExitCode::SUCCESS;
+    }
+
+    if let Some(Commands::Config { action: ConfigAction::Init { path, force } }) = &cli.command {
+        return ran(run_config_init(path, *force));
+    }
+
+    let (cli, config) = match load_settings(&matches) {
+        Ok(settings) => settings,
+        Err(e) => {
+            say!("{} {}", "Error loading config file:".bright_red(), e);
+            return ExitCode::from(ERROR_EXIT_CODE);
+        }
+    };
+
+    // A service has no terminal, so all its output goes to the log file, without colors
+    if matches!(cli.command, Some(Commands::Daemon(_))) && output::has_log_file() {
+        output::send_text_to_log();
+        colored::control::set_override(false);
+    }
+
+    if !output::is_json() && !matches!(cli.command, Some(Commands::Daemon(_))) {
+        print_banner();
+    }
+
+    let options = match build_run_options(&cli, &config, uuid::Uuid::new_v4().to_string()) {
+        Ok(options) => options,
+        Err(e) => {
+            say!("{}", e.bright_red());
+            return ExitCode::from(ERROR_EXIT_CODE);
+        }
+    };
+
+    say!("Run ID: {}", options.run_id);
+
+    // Scanning commands return the highest score they found, or None when they failed; its
+    // severity band is the exit status
+    let thresholds = options.thresholds;
+    let notifier = options.notifier("ThreatSentry");
+    let scanned = |score: Option<u8>| match score {
+        Some(score) => ExitCode::from(thresholds.classify(score).exit_code()),
+        None => ExitCode::from(ERROR_EXIT_CODE),
+    };
+    let exit_code = match &cli.command {
+        Some(Commands::Email(EmailCommand { imap: ImapArgs { username: None, .. }, .. })) => {
+            say!("{} pass --username or set username under [email] in the config file", "No IMAP username:".bright_red());
+            ExitCode::from(ERROR_EXIT_CODE)
+        },
+        Some(Commands::Email(EmailCommand { imap: ImapArgs { password: None, .. }, oauth_token: None, .. })) => {
+            say!("{} pass --password or --oauth-token, or set one under [email] in the config file", "No IMAP credentials:".bright_red());
+            ExitCode::from(ERROR_EXIT_CODE)
+        },
+        Some(Commands::Email(args @ EmailCommand { imap: ImapArgs { username: Some(username), .. }, .. })) => {
+            scanned(run_email_monitor(username, args, &options))
+        },
+        Some(Commands::Mic(MicCommand { list_devices: true, .. })) => ran(run_list_input_devices()),
+        Some(Commands::Mic(args)) => scanned(run_mic_monitor(args, &options)),
+        Some(Commands::Thermal { duration, spike_delta, spike_window, spike_cooldown, thermal_log, thermal_log_max_mb }) => {
+            scanned(run_thermal_monitor(*duration, *spike_delta, *spike_window, *spike_cooldown, thermal_log, *thermal_log_max_mb, &options))
+        },
+        Some(Commands::Kernel(args)) => scanned(run_kernel_monitor(args, &options)),
+        Some(Commands::Full(args)) => scanned(run_full_scan(args, &options)),
+        Some(Commands::Daemon(_)) => scanned(run_daemon(&matches, &cli, options)),
+        Some(Commands::Baseline(args)) => ran(run_baseline(args, &cli.config, &config, &options)),
+        Some(Commands::Doctor { imap }) => {
+            run_doctor(imap, &options);
+            ExitCode::SUCCESS
+        },
+        Some(Commands::Gui(args)) => ran(run_gui(args, &cli.config, &options)),
+        Some(Commands::History { since }) => ran(run_history(*since, &options)),
+        Some(Commands::Version { .. }) | Some(Commands::Config { .. }) => ExitCode::SUCCESS,
+        None => {
+            say!("{}", "No command specified. Use --help for usage information.".yellow());
+            ExitCode::from(ERROR_EXIT_CODE)
+        }
+    };
+
+    // Repeats still held back by the notification cooldown are summarized before exiting
+    notifier.flush_repeats();
+    exit_code
+}
+
+// Exit status of a command that reports no score: success, or ERROR_EXIT_CODE when it failed
+fn ran(success: bool) -> ExitCode {
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(ERROR_EXIT_CODE)
+    }
+}
+
+// Print a clap error or the --help/--version text it carries; only real errors are failures
+fn clap_exit(error: clap::Error) -> ExitCode {
+    let _ = error.print();
+    ran(!error.use_stderr())
+}
+
+// Flags merged with the config file
+fn load_settings(matches: &ArgMatches) -> Result<(Cli, Config), String> {
+    let mut cli = Cli::from_arg_matches(matches).map_err(|e| e.to_string())?;
+    let config = match &cli.config {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    apply_config(&mut cli, matches, &config)?;
+    Ok((cli, config))
+}
+
+// Settings shared by every command; a bad SMTP setup or history database is reported up front
+// rather than at the first alert
+fn build_run_options(cli: &Cli, config: &Config, run_id: String) -> Result<RunOptions, String> {
+    let thresholds = SeverityThresholds::new(cli.low_max, cli.medium_max, cli.high_max)
+        .map_err(|e| format!("Invalid severity thresholds: {}", e))?;
+
+    let smtp = match (&cli.alert_email, &cli.smtp_server) {
+        (Some(to), Some(server)) => {
+            let credentials = cli.smtp_user.clone().zip(cli.smtp_pass.clone());
+            let from = cli.smtp_from.as_ref().or(cli.smtp_user.as_ref()).unwrap_or(to);
+            let smtp = SmtpAlerter::new(server, cli.smtp_port, cli.smtp_security, credentials, from, to)
+                .map_err(|e| format!("Invalid alert email settings: {}", e))?;
+            Some(smtp
+                .with_min_score(cli.alert_email_min_score)
+                .with_coalesce_window(Duration::from_secs(cli.alert_email_window)))
+        },
+        (Some(_), None) => return Err("Invalid alert email settings: --alert-email needs --smtp-server".to_string()),
+        _ => None,
+    };
+
+    let history = match &cli.history_db {
+        Some(path) => Some(HistoryStore::open(path, &run_id).map_err(|e| format!("Error opening scan history: {}", e))?),
+        None => None,
+    };
+
+    Ok(RunOptions {
+        run_id,
+        min_score: cli.min_score,
+        thresholds,
+        weights: config.scoring.weights.weights(),
+        thermal_baseline: config.baseline.thermal(),
+        anonymize: cli.anonymize,
+        webhook_url: cli.webhook_url.clone(),
+        webhook_format: cli.webhook_format,
+        webhook_timeout: Duration::from_secs(cli.webhook_timeout),
+        webhook_retries: cli.webhook_retries,
+        slack_webhook: cli.slack_webhook.clone(),
+        discord_webhook: cli.discord_webhook.clone(),
+        smtp,
+        syslog_server: cli.syslog_server.clone(),
+        syslog_protocol: cli.syslog_protocol,
+        syslog_facility: cli.syslog_facility,
+        mute: cli.mute,
+        notify_cooldown: Duration::from_secs(cli.notify_cooldown),
+        notification_history: NotificationHistory::new(),
+        deliveries: DeliveryQueue::new(),
+        history,
+    })
+}
+
+// Report build and capability info. Only local, unprivileged checks: no network, no PowerShell
+fn run_version(full: bool) {
+    say!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    output::emit(&json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "commit": env!("THREATSENTRY_GIT_COMMIT"),
+        "target": format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+    }));
+    if !full {
+        return;
+    }
+
+    say!("commit: {}", env!("THREATSENTRY_GIT_COMMIT"));
+    say!("target: {}-{}", std::env::consts::OS, std::env::consts::ARCH);
+    say!("build: {}", if cfg!(debug_assertions) { "debug" } else { "release" });
+
+    say!("\nbackends:");
+    say!("  audio: cpal ({})", cpal::default_host().id().name());
+    say!("  thermal: sysinfo components, battery/CPU-load proxy fallback");
+    say!("  processes: sysinfo");
+    say!("  connections: netstat2");
+    say!("  process i/o, usb: PowerShell (Get-CimInstance, Get-PnpDevice)");
+    say!("  email: IMAP over TLS (native-tls)");
+    say!("  notifications: notify-rust");
+    say!("  export: STIX 2.1");
+    say!("  history: {}", if cfg!(feature = "history") { "SQLite (rusqlite)" } else { "not built" });
+
+    say!("\ndevices:");
+    match MicMonitor::probe_input_device() {
+        Ok(detail) => say!("  audio input: {}", detail),
+        Err(e) => say!("  audio input: unavailable ({})", e),
+    }
+    let sensors = sysinfo::Components::new_with_refreshed_list();
+    say!("  temperature sensors: {}", sensors.iter().count());
+}
+
+// Write the default config file without clobbering an existing one
+fn run_config_init(path: &Path, force: bool) -> bool {
+    if path.exists() && !force {
+        say!("{} {} already exists (pass --force to overwrite)", "Error writing config file:".bright_red(), path.display());
+        return false;
+    }
+
+    match std::fs::write(path, config::default_config()) {
+        Ok(()) => {
+            say!("Wrote default configuration to {}", path.display());
+            true
+        },
+        Err(e) => {
+            say!("{} {}", "Error writing config file:".bright_red(), e);
+            false
+        },
+    }
+}
+
+// Fill in settings from the config file, except those the user set on the command line or
+// through an environment variable
+fn apply_config(cli: &mut Cli, matches: &ArgMatches, config: &Config) -> Result<(), String> {
+    let scoring = &config.scoring;
+    merge(matches, "min_score", &mut cli.min_score, &scoring.min_score);
+    merge(matches, "low_max", &mut cli.low_max, &scoring.low_max);
+    merge(matches, "medium_max", &mut cli.medium_max, &scoring.medium_max);
+    merge(matches, "high_max", &mut cli.high_max, &scoring.high_max);
+
+    let notifications = &config.notifications;
+    merge(matches, "mute", &mut cli.mute, &notifications.mute);
+    merge(matches, "notify_cooldown", &mut cli.notify_cooldown, &notifications.cooldown);
+    merge_option(matches, "webhook_url", &mut cli.webhook_url, &notifications.webhook_url);
+    merge_enum(matches, "webhook_format", &mut cli.webhook_format, &notifications.webhook_format, "notifications.webhook_format")?;
+    merge(matches, "webhook_timeout", &mut cli.webhook_timeout, &notifications.webhook_timeout);
+    merge(matches, "webhook_retries", &mut cli.webhook_retries, &notifications.webhook_retries);
+    merge_option(matches, "slack_webhook", &mut cli.slack_webhook, &notifications.slack_webhook);
+    merge_option(matches, "discord_webhook", &mut cli.discord_webhook, &notifications.discord_webhook);
+    merge_option(matches, "syslog_server", &mut cli.syslog_server, &notifications.syslog_server);
+    merge_enum(matches, "syslog_protocol", &mut cli.syslog_protocol, &notifications.syslog_protocol, "notifications.syslog_protocol")?;
+    merge_enum(matches, "syslog_facility", &mut cli.syslog_facility, &notifications.syslog_facility, "notifications.syslog_facility")?;
+    merge_option(matches, "alert_email", &mut cli.alert_email, &notifications.alert_email);
+    merge_option(matches, "smtp_server", &mut cli.smtp_server, &notifications.smtp_server);
+    merge(matches, "smtp_port", &mut cli.smtp_port, &notifications.smtp_port);
+    merge_enum(matches, "smtp_security", &mut cli.smtp_security, &notifications.smtp_security, "notifications.smtp_security")?;
+    merge_option(matches, "smtp_user", &mut cli.smtp_user, &notifications.smtp_user);
+    merge_option(matches, "smtp_pass", &mut cli.smtp_pass, &notifications.smtp_pass);
+    merge_option(matches, "smtp_from", &mut cli.smtp_from, &notifications.smtp_from);
+    merge(matches, "alert_email_min_score", &mut cli.alert_email_min_score, &notifications.alert_email_min_score);
+    merge(matches, "alert_email_window", &mut cli.alert_email_window, &notifications.alert_email_window);
+
+    merge_option(matches, "history_db", &mut cli.history_db, &config.history.db);
+
+    let Some((_, sub)) = matches.subcommand() else {
+        return Ok(());
+    };
+    let (email, mic, kernel) = (&config.email, &config.mic, &config.kernel);
+    // A threshold set in [mic] wins over the one learned by `baseline`
+    let mic_threshold = mic.threshold.or(config.baseline.mic_threshold());
+    match &mut cli.command {
+        Some(Commands::Email(EmailCommand { imap, oauth_token, filters, body_phrases, .. })) => {
+            merge_account(sub, email, imap)?;
+            merge_option(sub, "oauth_token", oauth_token, &email.oauth_token);
+            merge_url_lists(sub, email, filters);
+            merge_option(sub, "body_phrases", body_phrases, &email.body_phrases);
+        },
+        Some(Commands::Mic(MicCommand { min_freq, max_freq, threshold, detection_window, device, hop_size, window, .. })) => {
+            merge(sub, "min_freq", min_freq, &mic.min_freq);
+            merge(sub, "max_freq", max_freq, &mic.max_freq);
+            merge(sub, "threshold", threshold, &mic_threshold);
+            merge_option(sub, "device", device, &mic.device);
+            merge(sub, "detection_window", detection_window, &mic.detection_window);
+            merge(sub, "hop_size", hop_size, &mic.hop_size);
+            merge_enum(sub, "window", window, &mic.window, "mic.window")?;
+        },
+        Some(Commands::Thermal { spike_delta, spike_window, spike_cooldown, .. }) => {
+            merge(sub, "spike_delta", spike_delta, &config.thermal.spike_delta);
+            merge(sub, "spike_window", spike_window, &config.thermal.spike_window);
+            merge(sub, "spike_cooldown", spike_cooldown, &config.thermal.spike_cooldown);
+        },
+        Some(Commands::Kernel(KernelCommand { kernel: args, .. })) => {
+            merge_kernel(sub, kernel, args)?;
+        },
+        Some(Commands::Full(FullCommand { scan, .. }) | Commands::Daemon(DaemonCommand { scan, .. })) => {
+            merge_account(sub, email, &mut scan.imap)?;
+            merge_url_lists(sub, email, &mut scan.filters);
+            merge_option(sub, "body_phrases", &mut scan.body_phrases, &email.body_phrases);
+            merge(sub, "threshold", &mut scan.threshold, &mic_threshold);
+            merge_kernel(sub, kernel, &mut scan.kernel)?;
+        },
+        Some(Commands::Baseline(BaselineCommand { sigma, min_freq, max_freq, device, .. })) => {
+            merge(sub, "sigma", sigma, &config.baseline.sigma);
+            merge(sub, "min_freq", min_freq, &mic.min_freq);
+            merge(sub, "max_freq", max_freq, &mic.max_freq);
+            merge_option(sub, "device", device, &mic.device);
+        },
+        Some(Commands::Doctor { imap }) => {
+            merge_account(sub, email, imap)?;
+        },
+        Some(Commands::Gui(GuiCommand { imap, filters, signatures, process_allowlist, geoip_db, geoip_resolve_hosts, threshold, min_freq, max_freq, spike_delta, .. })) => {
+            merge_account(sub, email, imap)?;
+            merge_url_lists(sub, email, filters);
+            merge_process_lists(sub, kernel, signatures, process_allowlist);
+            merge_option(sub, "geoip_db", geoip_db, &config.geoip.db);
+            merge(sub, "geoip_resolve_hosts", geoip_resolve_hosts, &config.geoip.resolve_hosts);
+            merge(sub, "threshold", threshold, &mic_threshold);
+            merge(sub, "min_freq", min_freq, &mic.min_freq);
+            merge(sub, "max_freq", max_freq, &mic.max_freq);
+            merge(sub, "spike_delta", spike_delta, &config.thermal.spike_delta);
+        },
+        _ => {},
+    }
+    if let Some(Commands::Daemon(DaemonCommand { window, percentile, .. })) = &mut cli.command {
+        merge(sub, "window", window, &config.daemon.window);
+        merge(sub, "percentile", percentile, &config.daemon.percentile);
+    }
+    Ok(())
+}
+
+// IMAP account settings shared by the commands that read mail
+fn merge_account(matches: &ArgMatches, email: &config::EmailConfig, imap: &mut ImapArgs) -> Result<(), String> {
+    merge_option(matches, "username", &mut imap.username, &email.username);
+    merge_option(matches, "password", &mut imap.password, &email.password);
+    merge(matches, "imap_server", &mut imap.imap_server, &email.imap_server);
+    merge(matches, "imap_port", &mut imap.imap_port, &email.imap_port);
+    merge_enum(matches, "imap_security", &mut imap.imap_security, &email.imap_security, "email.imap_security")
+}
+
+// URL scoring lists shared by the commands that scan mail
+fn merge_url_lists(matches: &ArgMatches, email: &config::EmailConfig, filters: &mut EmailFilterArgs) {
+    merge_option(matches, "blocklist", &mut filters.blocklist, &email.blocklist);
+    merge_option(matches, "allowlist", &mut filters.allowlist, &email.allowlist);
+    merge(matches, "brand_domain", &mut filters.brand_domain, &email.brand_domains);
+    merge_option(matches, "phishtank_key", &mut filters.phishtank_key, &email.phishtank_key);
+}
+
+// Process and USB settings shared by the commands that watch the kernel
+fn merge_kernel(matches: &ArgMatches, kernel: &config::KernelConfig, args: &mut KernelArgs) -> Result<(), String> {
+    merge_enums(matches, "usb_alert_class", &mut args.usb_alert_class, &kernel.usb_alert_classes, "kernel.usb_alert_classes")?;
+    merge_process_lists(matches, kernel, &mut args.signatures, &mut args.process_allowlist);
+    merge(matches, "spawn_threshold", &mut args.spawn_threshold, &kernel.spawn_threshold);
+    Ok(())
+}
+
+// Process signature and trust lists shared by the commands that watch processes
+fn merge_process_lists(matches: &ArgMatches, kernel: &config::KernelConfig, signatures: &mut Option<PathBuf>, process_allowlist: &mut Option<PathBuf>) {
+    merge_option(matches, "signatures", signatures, &kernel.signatures);
+    merge_option(matches, "process_allowlist", process_allowlist, &kernel.process_allowlist);
+}
+
+// Whether the user set an argument on the command line or through its environment variable
+fn user_set(matches: &ArgMatches, id: &str) -> bool {
+    matches!(matches.value_source(id), Some(ValueSource::CommandLine | ValueSource::EnvVariable))
+}
+
+fn merge<T: Clone>(matches: &ArgMatches, id: &str, field: &mut T, value: &Option<T>) {
+    if let Some(value) = value {
+        if !user_set(matches, id) {
+            *field = value.clone();
+        }
+    }
+}
+
+fn merge_option<T: Clone>(matches: &ArgMatches, id: &str, field: &mut Option<T>, value: &Option<T>) {
+    if value.is_some() && !user_set(matches, id) {
+        *field = value.clone();
+    }
+}
+
+// Enum settings are spelled in the file as on the command line, e.g. "start-tls"
+fn merge_enum<T: ValueEnum>(matches: &ArgMatches, id: &str, field: &mut T, value: &Option<String>, key: &str) -> Result<(), String> {
+    if let Some(value) = value {
+        if !user_set(matches, id) {
+            *field = parse_config_enum(value, key)?;
+        }
+    }
+    Ok(())
+}
+
+fn merge_enums<T: ValueEnum>(matches: &ArgMatches, id: &str, field: &mut Vec<T>, values: &Option<Vec<String>>, key: &str) -> Result<(), String> {
+    if let Some(values) = values {
+        if !user_set(matches, id) {
+            *field = values.iter().map(|value| parse_config_enum(value, key)).collect::<Result<_, _>>()?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_config_enum<T: ValueEnum>(value: &str, key: &str) -> Result<T, String> {
+    T::from_str(value, true).map_err(|_| {
+        let names: Vec<String> = T::value_variants().iter()
+            .filter_map(|variant| variant.to_possible_value())
+            .map(|possible| possible.get_name().to_string())
+            .collect();
+        format!("{} '{}' is not one of {}", key, value, names.join(", "))
+    })
+}
+
+// Turn off ANSI colors when asked to, when NO_COLOR is set, or when stdout is piped to a file
+fn configure_color(no_color: bool) {
+    let no_color_env = matches!(std::env::var_os("NO_COLOR"), Some(value) if !value.is_empty());
+
+    if no_color || no_color_env || !std::io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+}
+
+fn print_banner() {
+    say!("{}", r"
+ _____ _                    _   _____            _              _   _ _ _
+|_   _| |                  | | /  ___|          | |            | | | | | |
+  | | | |__  _ __ ___  __ _| |_\ `--.  ___ _ __ | |_ _ __ _   _| | | | | |_ _ __ __ _
+  | | | '_ \| '__/ _ \/ _` | __|`--. \/ _ \ '_ \| __| '__| | | | | | | | __| '__/ _` |
+  | | | | | | | |  __/ (_| | |_/\__/ /  __/ | | | |_| |  | |_| | |_| | | |_| | | (_| |
+  \_/ |_| |_|_|  \___|\__,_|\__\____/ \___|_| |_|\__|_|   \__, |\___/|_|\__|_|  \__,_|
+                                                            __/ |
+                                                           |___/
+    ".bright_cyan());
+    say!("{}", "Hardware-Powered Cyber Threat Intelligence".bright_green());
+    say!("{}", "---------------------------------------------".bright_blue());
+}
+
+fn run_email_monitor(username: &str, args: &EmailCommand, options: &RunOptions) -> Option<u8> {
+    let EmailCommand { imap, oauth_token, limit, incremental, reset_cursor, state_file, stix, filters, include_body_snippet, body_phrases } = args;
+    say!("{}", "\n[EMAIL MONITOR]".bright_blue());
+    if *incremental {
+        say!("Scanning new emails since the last scan for threats...");
+    } else {
+        say!("Scanning {} recent emails for threats...", limit);
+    }
+
+    // clap guarantees one of the two; the token wins when both are set
+    let email_monitor = match oauth_token {
+        Some(token) => EmailMonitor::with_oauth2(username.to_string(), token.to_string(), imap.imap_server.to_string()),
+        None => EmailMonitor::new(username.to_string(), imap.password.clone().unwrap_or_default(), imap.imap_server.to_string()),
+    };
+    let mut email_monitor = email_monitor.with_port(imap.imap_port).with_security(imap.imap_security);
+    if let Some(path) = state_file {
+        email_monitor = email_monitor.with_state_file(path.clone());
+    }
+    let email_monitor = with_email_filters(email_monitor, filters, body_phrases);
+
+    if *reset_cursor {
+        email_monitor.reset_cursor();
+    }
+
+    // Fetch emails
+    let fetched = if *incremental {
+        email_monitor.fetch_new_since_last(*limit)
+    } else {
+        email_monitor.fetch_emails(*limit)
+    };
+    let emails = match fetched {
+        Ok(emails) => emails,
+        Err(e) => {
+            say!("{} {}", "Error fetching emails:".bright_red(), e);
+            output::emit_error("email", &e.to_string());
+            return None;
+        }
+    };
+
+    // Links whose visible text points somewhere other than the target
+    let link_mismatches = email_monitor.extract_link_mismatches(&emails);
+
+    // Scam wording in each email, independent of any links it has
+    let body_scores: Vec<u8> = emails.iter().map(|email| email_monitor.score_body_text(&email.body)).collect();
+
+    // Extract and scan URLs
+    let results = email_monitor.scan_emails(&emails);
+    let scanned_urls: Vec<String> = results.iter().map(|result| result.url.clone()).collect();
+    let homographs = email_monitor.detect_homographs(&scanned_urls);
+    let header_findings = email_monitor.analyze_headers();
+
+    // Highest score over everything found, including findings below --min-score
+    let email_score = results.iter().map(|result| result.score)
+        .chain(body_scores.iter().copied())
+        .chain(header_findings.iter().map(|finding| finding.score))
+        .max()
+        .unwrap_or(0);
+
+    let header_findings: Vec<_> = header_findings.into_iter()
+        .filter(|finding| options.shows(finding.score))
+        .collect();
+
+    let anonymizer = options.anonymizer(Some(username));
+    let flagged: Vec<ScanResult> = results.into_iter()
+        .filter(|result| options.shows(result.score))
+        .map(|result| ScanResult {
+            url: scrub(&anonymizer, &result.url),
+            snippet: scrub(&anonymizer, &result.snippet),
+            phishtank_match: result.phishtank_match.map(|raw| scrub(&anonymizer, &raw)),
+            score: result.score,
+        })
+        .collect();
+    let flagged_urls: Vec<(String, u8)> = flagged.iter()
+        .map(|result| (result.url.clone(), result.score))
+        .collect();
+
+    // Display results
+    say!("\nResults:");
+    for ScanResult { url, score, snippet, phishtank_match } in &flagged {
+        let score_color = colorize_score(*score, &options.thresholds);
+
+        say!("URL: {} | Threat Score: {}", url, score_color);
+        if let Some(raw) = phishtank_match {
+            say!("  PhishTank: {}", raw);
+        }
+        if *include_body_snippet {
+            say!("  Context: \"{}\"", snippet.dimmed());
+        }
+
+        // Send notification for high-risk URLs
+        if options.thresholds.is_alert(*score) {
+            options.record(HistoryEvent::new("email", EventKind::PhishingUrl, *score, url));
+            let notification_manager = options.notifier("Email");
+            let _ = notification_manager.send_notification(
+                "ThreatSentry Ultra",
+                &format!("Suspicious URL detected: {}", url),
+                *score,
+            );
+        }
+    }
+
+    if !link_mismatches.is_empty() {
+        say!("\n{}", "Links whose text doesn't match their target:".bright_red());
+        for (href, text) in &link_mismatches {
+            say!("  - shows \"{}\" but opens {}", scrub(&anonymizer, text), scrub(&anonymizer, href));
+        }
+    }
+
+    let suspicious_bodies: Vec<(&EmailMessage, u8)> = emails.iter()
+        .zip(body_scores.iter().copied())
+        .filter(|&(_, score)| score > 0 && options.shows(score))
+        .collect();
+    if !suspicious_bodies.is_empty() {
+        say!("\n{}", "Scam wording:".bright_red());
+        for (email, score) in &suspicious_bodies {
+            say!("Subject: {} | Threat Score: {}", scrub(&anonymizer, &email.subject), colorize_score(*score, &options.thresholds));
+            say!("  From: {} | Date: {}", scrub(&anonymizer, &email.from), email.date);
+        }
+    }
+
+    if !header_findings.is_empty() {
+        say!("\n{}", "Spoofed senders:".bright_red());
+        for finding in &header_findings {
+            say!("From: {} | Threat Score: {}", scrub(&anonymizer, &finding.from), colorize_score(finding.score, &options.thresholds));
+            say!("  {}", scrub(&anonymizer, &finding.reason));
+            if let Some(return_path) = &finding.return_path {
+                say!("  Return-Path: {}", scrub(&anonymizer, return_path));
+            }
+
+            if options.thresholds.is_alert(finding.score) {
+                let _ = options.notifier("Email").send_notification(
+                    "ThreatSentry Ultra",
+                    &format!("Possible spoofed sender: {}", scrub(&anonymizer, &finding.from)),
+                    finding.score,
+                );
+            }
+        }
+    }
+
+    if !homographs.is_empty() {
+        say!("\n{}", "Lookalike (homograph) domains:".bright_red());
+        for (url, finding) in &homographs {
+            say!("  - {} renders as {} ({})", scrub(&anonymizer, url), finding.rendered, finding.reason);
+        }
+    }
+
+    options.record(HistoryEvent::score("email", email_score));
+
+    output::emit(&json!({
+        "monitor": "email",
+        "run_id": options.run_id,
+        "score": email_score,
+        "urls": flagged.iter().map(|result| json!({
+            "url": result.url,
+            "score": result.score,
+            "phishtank_match": result.phishtank_match,
+            "snippet": include_body_snippet.then_some(&result.snippet),
+        })).collect::<Vec<_>>(),
+        "link_mismatches": link_mismatches.iter().map(|(href, text)| json!({
+            "text": scrub(&anonymizer, text),
+            "href": scrub(&anonymizer, href),
+        })).collect::<Vec<_>>(),
+        "scam_wording": suspicious_bodies.iter().map(|(email, score)| json!({
+            "uid": email.uid,
+            "subject": scrub(&anonymizer, &email.subject),
+            "from": scrub(&anonymizer, &email.from),
+            "date": email.date,
+            "score": score,
+        })).collect::<Vec<_>>(),
+        "spoofed_senders": header_findings.iter().map(|finding| json!({
+            "from": scrub(&anonymizer, &finding.from),
+            "return_path": finding.return_path.as_deref().map(|path| scrub(&anonymizer, path)),
+            "reason": scrub(&anonymizer, &finding.reason),
+            "score": finding.score,
+        })).collect::<Vec<_>>(),
+        "homographs": homographs.iter().map(|(url, finding)| json!({
+            "url": scrub(&anonymizer, url),
+            "rendered": finding.rendered,
+            "reason": finding.reason,
+        })).collect::<Vec<_>>(),
+    }));
+
+    export_stix(stix, &flagged_urls, &[], options);
+
+    Some(email_score)
+}
+
+// Apply the URL scoring lists and extra scam phrases to an email monitor
+fn with_email_filters(email_monitor: EmailMonitor, filters: &EmailFilterArgs, body_phrases: &Option<PathBuf>) -> EmailMonitor {
+    let mut email_monitor = email_monitor.with_brand_domains(filters.brand_domain.clone());
+    if let Some(blocklist) = load_blocklist(&filters.blocklist) {
+        email_monitor = email_monitor.with_blocklist(blocklist);
+    }
+    if let Some(allowlist) = load_allowlist(&filters.allowlist) {
+        email_monitor = email_monitor.with_allowlist(allowlist);
+    }
+    if let Some(api_key) = &filters.phishtank_key {
+        email_monitor = email_monitor.with_phishtank(api_key.to_string());
+    }
+    if let Some(phrases) = load_body_phrases(body_phrases) {
+        email_monitor = email_monitor.with_body_phrases(phrases);
+    }
+    email_monitor
+}
+
+fn run_list_input_devices() -> bool {
+    say!("{}", "\n[INPUT DEVICES]".bright_blue());
+
+    let devices = match MicMonitor::list_input_devices() {
+        Ok(devices) => devices,
+        Err(e) => {
+            say!("{} {}", "Error listing input devices:".bright_red(), e);
+            output::emit_error("input_devices", &e);
+            return false;
+        }
+    };
+
+    output::emit(&json!({
+        "input_devices": devices.iter().map(|device| json!({
+            "index": device.index,
+            "name": device.name,
+            "default": device.is_default,
+            "configs": device.sample_rates.iter().map(|(min_rate, max_rate, channels)| json!({
+                "min_rate_hz": min_rate,
+                "max_rate_hz": max_rate,
+                "channels": channels,
+            })).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+    }));
+
+    if devices.is_empty() {
+        say!("{}", "No input devices found".yellow());
+        return true;
+    }
+
+    for device in &devices {
+        let marker = if device.is_default { " (default)".green().to_string() } else { String::new() };
+        say!("[{}] {}{}", device.index, device.name, marker);
+
+        if device.sample_rates.is_empty() {
+            say!("    supported configs unavailable");
+        }
+        for (min_rate, max_rate, channels) in &device.sample_rates {
+            if min_rate == max_rate {
+                say!("    {} Hz, {} channel(s)", min_rate, channels);
+            } else {
+                say!("    {}-{} Hz, {} channel(s)", min_rate, max_rate, channels);
+            }
+        }
+    }
+
+    say!("\nPick one with --device <INDEX> or --device \"<NAME>\"");
+    true
+}
+
+fn run_mic_monitor(args: &MicCommand, options: &RunOptions) -> Option<u8> {
+    let &MicCommand { duration, fft_poll_ms, mic_retries, min_freq, max_freq, threshold, detection_window, calibrate, window, spectrogram_max_secs, .. } = args;
+    let MicCommand { device, record_dir, event_log, spectrogram, .. } = args;
+    say!("{}", "\n[MICROPHONE MONITOR]".bright_blue());
+    say!("Monitoring microphone for {:.0}-{:.0} Hz signals for {} seconds...", min_freq, max_freq, duration);
+
+    let mut mic_monitor = MicMonitor::with_band(min_freq, max_freq).with_window(window);
+    if let Some(device) = device {
+        mic_monitor = mic_monitor.with_device(device);
+    }
+    mic_monitor.set_fft_poll_interval(Duration::from_millis(fft_poll_ms));
+    mic_monitor.set_max_reconnect_attempts(mic_retries);
+    mic_monitor.set_hop_size(args.hop_size as usize);
+    mic_monitor.set_threshold(threshold);
+    mic_monitor.set_detection_window(Duration::from_secs(detection_window));
+    if let Some(dir) = record_dir {
+        if let Err(e) = mic_monitor.enable_recording(dir) {
+            say!("{} {}", "Error enabling recording:".bright_red(), e);
+            output::emit_error("mic", &e);
+            return None;
+        }
+    }
+    if let Some(path) = event_log {
+        if let Err(e) = mic_monitor.set_event_log(path) {
+            say!("{} {}", "Error opening event log:".bright_red(), e);
+            output::emit_error("mic", &e);
+            return None;
+        }
+    }
+    if let Some(path) = spectrogram {
+        if let Err(e) = mic_monitor.enable_spectrogram_csv(path, spectrogram_max_secs.map(Duration::from_secs)) {
+            say!("{} {}", "Error creating spectrogram file:".bright_red(), e);
+            output::emit_error("mic", &e);
+            return None;
+        }
+    }
+
+    // Start monitoring
+    match mic_monitor.start_monitoring() {
+        Ok(_) => {
+            // A score from simulated audio would read as a real detection in the exit status
+            if mic_monitor.get_health() == MicHealth::Simulated {
+                mic_monitor.stop_monitoring();
+                let e = "No usable input device; simulated audio is not scored";
+                say!("{} {}", "Error:".bright_red(), e);
+                output::emit_error("mic", e);
+                return None;
+            }
+
+            if let Some(secs) = calibrate {
+                if let Err(e) = mic_monitor.calibrate_threshold(Duration::from_secs(secs), mic_monitor::DEFAULT_CALIBRATION_FACTOR) {
+                    say!("{} {}; keeping threshold {:.2}", "Calibration failed:".bright_red(), e, mic_monitor.get_threshold());
+                }
+            }
+
+            // Show progress bar
+            let pb = progress_bar(duration);
+
+            // The live score decays once a signal stops, so keep the highest one seen during the
+            // scan, along with the carrier frequency at that moment
+            let mut score = 0;
+            let mut peak = None;
+            for _ in 0..duration {
+                thread::sleep(Duration::from_secs(1));
+                mic_monitor.check_stream_health();
+                let current = mic_monitor.get_threat_score();
+                if current > score || score == 0 {
+                    score = current;
+                    peak = mic_monitor.get_peak_frequency();
+                }
+                pb.inc(1);
+            }
+
+            pb.finish_with_message("Monitoring complete");
+
+            // Stop monitoring and get results
+            let health = mic_monitor.get_health();
+            mic_monitor.stop_monitoring();
+
+            // Display results
+            let score_color = colorize_score(score, &options.thresholds);
+
+            say!("\nResults:");
+            say!("Mic Threat Score: {}", score_color);
+            match &health {
+                MicHealth::Live => say!("Mic Health: {}", health.to_string().green()),
+                MicHealth::Lost(_) | MicHealth::Recovering(_) => {
+                    say!("Mic Health: {} (the score only covers audio captured before the device was lost)", health.to_string().bright_red());
+                },
+                MicHealth::Simulated => say!("Mic Health: {}", health.to_string().yellow()),
+            }
+
+            if let Some((frequency, magnitude)) = peak {
+                say!("Peak: {:.1} kHz (magnitude {:.2})", frequency / 1000.0, magnitude);
+            }
+
+            let modulation = mic_monitor.get_modulation_metrics();
+            say!("Peak Tracking: {}/{} active frames, duty cycle {:.0}%, peak drift {:.1} Hz{}",
+                modulation.active_frames,
+                modulation.frames,
+                modulation.duty_cycle * 100.0,
+                modulation.peak_freq_std_hz,
+                if modulation.is_modulated() { " (modulated, possible data channel)" } else { "" });
+
+            let clips = mic_monitor.recorded_clips();
+            if record_dir.is_some() {
+                say!("Audio Clips: {}", clips.len());
+                for clip in &clips {
+                    say!("  - {}", clip.display());
+                }
+            }
+
+            options.record(HistoryEvent::score("mic", score));
+
+            output::emit(&json!({
+                "monitor": "mic",
+                "run_id": options.run_id,
+                "score": score,
+                "health": health.to_string(),
+                "threshold": mic_monitor.get_threshold(),
+                "peak_hz": peak.map(|(frequency, _)| frequency),
+                "peak_magnitude": peak.map(|(_, magnitude)| magnitude),
+                "active_frames": modulation.active_frames,
+                "frames": modulation.frames,
+                "duty_cycle": modulation.duty_cycle,
+                "peak_drift_hz": modulation.peak_freq_std_hz,
+                "modulated": modulation.is_modulated(),
+                "clips": clips.iter().map(|clip| clip.display().to_string()).collect::<Vec<_>>(),
+            }));
+
+            // Send notification for high scores
+            if options.thresholds.is_alert(score) {
+                let notification_manager = options.notifier("Microphone");
+                let _ = notification_manager.send_notification(
+                    "ThreatSentry Ultra",
+                    "High-frequency audio signal detected!",
+                    score,
+                );
+            }
+
+            Some(score)
+        },
+        Err(e) => {
+            say!("{} {}", "Error starting microphone monitoring:".bright_red(), e);
+            output::emit_error("mic", &e);
+            None
+        }
+    }
+}
+
+fn run_thermal_monitor(duration: u64, spike_delta: f32, spike_window: u64, spike_cooldown: u64, thermal_log: &Option<PathBuf>, thermal_log_max_mb: u64, options: &RunOptions) -> Option<u8> {
+    say!("{}", "\n[THERMAL MONITOR]".bright_blue());
+    say!("Monitoring system temperature for {} seconds...", duration);
+
+    let mut thermal_monitor = match ThermalMonitor::with_spike_params(spike_delta, Duration::from_secs(spike_window)) {
+        Ok(thermal_monitor) => thermal_monitor,
+        Err(e) => {
+            say!("{} {}", "Error:".bright_red(), e);
+            output::emit_error("thermal", &e);
+            return None;
+        }
+    };
+    thermal_monitor.set_spike_cooldown(Duration::from_secs(spike_cooldown));
+    if let Some(baseline) = options.thermal_baseline {
+        thermal_monitor.set_baseline(baseline);
+    }
+    if let Some(path) = thermal_log {
+        if let Err(e) = thermal_monitor.set_log_file(path, thermal_log_max_mb * 1024 * 1024) {
+            say!("{} {}", "Error opening thermal log:".bright_red(), e);
+            output::emit_error("thermal", &e);
+            return None;
+        }
+    }
+
+    // Show progress bar
+    let pb = progress_bar(duration);
+
+    // A spike only raises the live score for its cooldown, so keep the highest score seen during the scan
+    let mut score = 0;
+    let mut readings = Vec::new();
+    let scan_start = Instant::now();
+    for elapsed in 1..=duration {
+        let spikes = thermal_monitor.spike_count();
+        match thermal_monitor.check_temperature() {
+            Ok(temp) => {
+                pb.set_message(format!("Current temperature: {:.1}°C", temp));
+                readings.push(temp);
+                if thermal_monitor.spike_count() > spikes {
+                    options.record(HistoryEvent::new("thermal", EventKind::ThermalSpike, thermal_monitor.get_threat_score(), &format!("{:.1}°C", temp)));
+                }
+            },
+            Err(e) => {
+                say!("{} {}", "Error checking temperature:".bright_red(), e);
+            }
+        }
+        score = score.max(thermal_monitor.get_threat_score());
+
+        // Sleep until the next whole second of the scan, so a slow reading (PowerShell on
+        // Windows) doesn't stretch the scan past its duration
+        thread::sleep((scan_start + Duration::from_secs(elapsed)).saturating_duration_since(Instant::now()));
+        pb.inc(1);
+    }
+
+    pb.finish_with_message("Monitoring complete");
+
+    // Get results
+    score = score.max(thermal_monitor.get_threat_score());
+
+    // Display results
+    let score_color = colorize_score(score, &options.thresholds);
+    let hottest = thermal_monitor.get_hottest_component();
+
+    say!("\nResults:");
+    say!("Thermal Threat Score: {}", score_color);
+    say!("Temperature Trend: {:+.2}°C/min (trend score: {:.0})",
+        thermal_monitor.get_temperature_slope(),
+        thermal_monitor.get_slope_score());
+    if let Some((label, temp)) = &hottest {
+        let hotspot_score = thermal_monitor.get_hotspot_score();
+        say!("Hottest Component: {} at {:.1}°C{}", label, temp,
+            if hotspot_score > 0.0 { format!(" (hotspot score: {:.0})", hotspot_score) } else { String::new() });
+    }
+
+    options.record(HistoryEvent::score("thermal", score));
+
+    output::emit(&json!({
+        "monitor": "thermal",
+        "run_id": options.run_id,
+        "score": score,
+        "spike": thermal_monitor.spike_detected(),
+        "avg_temp": (!readings.is_empty()).then(|| readings.iter().sum::<f32>() / readings.len() as f32),
+        "max_temp": readings.iter().copied().reduce(f32::max),
+        "slope_c_per_min": thermal_monitor.get_temperature_slope(),
+        "hottest_component": hottest.as_ref().map(|(label, temp)| json!({ "label": label, "temp": temp })),
+    }));
+
+    // Send notification for high scores
+    if options.thresholds.is_alert(score) {
+        let notification_manager = options.notifier("Thermal");
+        let _ = notification_manager.send_notification(
+            "ThreatSentry Ultra",
+            "Temperature spike detected! Possible crypto-miner activity.",
+            score,
+        );
+    }
+
+    Some(score)
+}
+
+fn run_kernel_monitor(args: &KernelCommand, options: &RunOptions) -> Option<u8> {
+    let &KernelCommand { duration, show_all, on_usb, on_process, .. } = args;
+    say!("{}", "\n[KERNEL TELEMETRY]".bright_blue());
+    say!("Monitoring system processes and USB devices for {} seconds...", duration);
+
+    let kernel_monitor = kernel_monitor_for(&args.kernel);
+    let notification_manager = options.notifier("Kernel");
+    let mut dispatcher = ActionDispatcher::new(on_usb, on_process, options.thresholds, options.notifier("Kernel"));
+
+    // Start monitoring
+    match kernel_monitor.start_monitoring() {
+        Ok(_) => say!("Kernel monitoring started successfully"),
+        Err(e) => {
+            say!("{} {}", "Error starting kernel monitoring:".bright_red(), e);
+            output::emit_error("kernel", &e);
+            return None;
+        }
+    }
+
+    // Create a progress bar
+    let pb = progress_bar(duration);
+
+    // Everything reported during the scan, for the JSON result; processes by PID, latest sample wins
+    let mut reported_processes = std::collections::BTreeMap::new();
+    let mut inserted_usb = Vec::new();
+    let mut removed_usb = Vec::new();
+
+    for i in 0..duration {
+        // Get suspicious processes
+        let suspicious_processes: Vec<_> = kernel_monitor.get_suspicious_processes()
+            .into_iter()
+            .filter(|process| options.shows(process.suspicious_score))
+            .collect();
+        if !suspicious_processes.is_empty() {
+            say!("\nSuspicious processes detected:");
+            for process in &suspicious_processes {
+                say!("  - {} (PID: {}, CPU: {:.1}%, Score: {}){}",
+                    process.name.bright_yellow(),
+                    process.pid,
+                    process.cpu_usage,
+                    colorize_score(process.suspicious_score, &options.thresholds),
+                    if process.memory_resident { " [memory-resident]".bright_red().bold() } else { "".normal() });
+                if let Some(path) = process.exe_path.as_ref().filter(|_| process.memory_resident) {
+                    say!("    Image missing from disk: {}", path);
+                }
+                if let Some(path) = process.exe_path.as_ref().filter(|_| process.unsigned_staged) {
+                    say!("    Unsigned image in a temp/downloads directory: {}", path.bright_red());
+                }
+                if process.high_outbound {
+                    say!("    Sustained outbound traffic: {}", format!("{:.1} KB/s", process.bytes_sent.unwrap_or(0.0) / 1024.0).bright_red());
+                }
+                say!("    Signature: {}", kernel_monitor.verify_signature(process.pid).describe());
+                if !process.parent_name.is_empty() {
+                    say!("    Parent: {} (PID: {})", process.parent_name, process.parent_pid);
+                }
+            }
+        }
+        for process in &suspicious_processes {
+            dispatcher.dispatch(&SecurityEvent::SuspiciousProcess(process));
+            reported_processes.insert(process.pid, json!({
+                "name": process.name,
+                "pid": process.pid,
+                "parent_name": process.parent_name,
+                "parent_pid": process.parent_pid,
+                "cpu": process.cpu_usage,
+                "score": process.suspicious_score,
+                "exe_path": process.exe_path,
+                "memory_resident": process.memory_resident,
+                "unsigned_staged": process.unsigned_staged,
+                "bytes_sent_per_sec": process.bytes_sent,
+                "bytes_recv_per_sec": process.bytes_recv,
+                "high_outbound": process.high_outbound,
+            }));
+        }
+
+        // Get new USB devices
+        let new_usb_devices = kernel_monitor.get_new_usb_devices();
+        if !new_usb_devices.is_empty() {
+            say!("\nNew USB devices detected:");
+            for device in &new_usb_devices {
+                say!("  - {} [{}] (ID: {})",
+                    device.description.bright_yellow(),
+                    device.class.name(),
+                    device.device_id);
+            }
+        }
+        for device in &new_usb_devices {
+            options.record(HistoryEvent::new("kernel", EventKind::UsbInserted, device.class.risk_score(), &format!("{} [{}] (ID: {})", device.description, device.class.name(), device.device_id)));
+        }
+        inserted_usb.extend(new_usb_devices.iter().map(usb_json));
+
+        // Get removed USB devices
+        let removed_usb_devices = kernel_monitor.get_removed_usb_devices();
+        if !removed_usb_devices.is_empty() {
+            say!("\nUSB devices removed:");
+            for device in &removed_usb_devices {
+                say!("  - {} [{}] (ID: {})",
+                    device.description,
+                    device.class.name(),
+                    device.device_id);
+            }
+        }
+        removed_usb.extend(removed_usb_devices.iter().map(usb_json));
+
+        // Respond to new USB devices of the alerting classes
+        for device in &kernel_monitor.get_alerting_usb_devices() {
+            dispatcher.dispatch(&SecurityEvent::UsbInserted(device));
+        }
+        notification_manager.flush_expired();
+
+        // Sleep for 1 second
+        if i < duration - 1 {
+            thread::sleep(Duration::from_secs(1));
+        }
+
+        pb.inc(1);
+    }
+
+    pb.finish_with_message("Monitoring complete");
+
+    // Stop monitoring
+    kernel_monitor.stop_monitoring();
+
+    // Show the processes sending the most over TCP
+    match kernel_monitor.get_top_talkers(5) {
+        Some(talkers) if talkers.iter().any(|p| options.shows(p.suspicious_score)) => {
+            say!("\nTop talkers:");
+            for process in talkers.iter().filter(|p| options.shows(p.suspicious_score)) {
+                say!("  - {} (PID: {}, Out: {:.1} KB/s, In: {:.1} KB/s){}",
+                    process.name,
+                    process.pid,
+                    process.bytes_sent.unwrap_or(0.0) / 1024.0,
+                    process.bytes_recv.unwrap_or(0.0) / 1024.0,
+                    if process.high_outbound { " [sustained high outbound]".bright_red() } else { "".normal() });
+            }
+        },
+        Some(_) => {},
+        None => say!("\nPer-process network throughput: unavailable"),
+    }
+
+    // Show processes started by parents that shouldn't start them, with their ancestry
+    let chains = kernel_monitor.suspicious_chains();
+    if !chains.is_empty() {
+        say!("\nSuspicious process chains:");
+        for chain in &chains {
+            say!("  - {}", chain.join(" -> ").bright_yellow());
+        }
+    }
+
+    // Show bursts of newly started processes
+    let spawn_bursts = kernel_monitor.get_spawn_bursts();
+    if !spawn_bursts.is_empty() {
+        say!("\nSpawn bursts:");
+        for burst in &spawn_bursts {
+            say!("  - {} (PID: {}) started {} processes ({:.1}/s)",
+                burst.parent_name.bright_yellow(),
+                burst.parent_pid.map_or("?".to_string(), |pid| pid.to_string()),
+                burst.spawned,
+                burst.rate);
+        }
+    }
+
+    // Show outbound connections to non-standard ports
+    let connections = kernel_monitor.get_suspicious_connections();
+    if !connections.is_empty() {
+        say!("\nSuspicious connections:");
+        for connection in &connections {
+            say!("  - {} (PID: {}) :{} -> {}",
+                connection.process_name.bright_yellow(),
+                connection.pid.map_or("?".to_string(), |pid| pid.to_string()),
+                connection.local_port,
+                connection.remote);
+        }
+    }
+
+    // List the full process table for manual triage
+    if show_all {
+        let all_processes: Vec<_> = kernel_monitor.get_all_processes()
+            .into_iter()
+            .filter(|process| options.shows(process.suspicious_score))
+            .collect();
+        say!("\nAll processes ({}):", all_processes.len());
+        for process in &all_processes {
+            say!("  - {} (PID: {}, CPU: {:.1}%, Score: {}){}",
+                process.name,
+                process.pid,
+                process.cpu_usage,
+                colorize_score(process.suspicious_score, &options.thresholds),
+                if process.trusted { " [trusted]" } else { "" });
+        }
+    }
+
+    // Get threat score
+    let score = kernel_monitor.get_threat_score();
+
+    // Display results
+    let surface = kernel_monitor.get_surface_changes();
+
+    say!("\nResults:");
+    say!("Kernel Threat Score: {}", colorize_score(score, &options.thresholds));
+    say!("Process Spawn Rate: {:.1}/s", kernel_monitor.get_spawn_rate());
+    say!("Surface Change Score: {} ({} new USB devices, {} new outbound talkers, {} new outbound connections, {} new processes)",
+        colorize_score(kernel_monitor.get_surface_change_score(), &options.thresholds),
+        surface.new_usb_devices,
+        surface.new_outbound.len(),
+        surface.new_connections.len(),
+        surface.new_processes.len());
+
+    options.record(HistoryEvent::score("kernel", score));
+
+    output::emit(&json!({
+        "monitor": "kernel",
+        "run_id": options.run_id,
+        "score": score,
+        "spawn_rate": kernel_monitor.get_spawn_rate(),
+        "surface_change_score": kernel_monitor.get_surface_change_score(),
+        "suspicious_processes": reported_processes.into_values().collect::<Vec<_>>(),
+        "usb_inserted": inserted_usb,
+        "usb_removed": removed_usb,
+        "suspicious_chains": chains,
+        "spawn_bursts": spawn_bursts.iter().map(|burst| json!({
+            "parent_name": burst.parent_name,
+            "parent_pid": burst.parent_pid,
+            "spawned": burst.spawned,
+            "rate": burst.rate,
+        })).collect::<Vec<_>>(),
+        "suspicious_connections": connections.iter().map(|connection| json!({
+            "process_name": connection.process_name,
+            "pid": connection.pid,
+            "local_port": connection.local_port,
+            "remote": connection.remote.to_string(),
+        })).collect::<Vec<_>>(),
+    }));
+
+    // Send notification for high scores
+    if options.thresholds.is_alert(score) {
+        let _ = notification_manager.send_notification(
+            "ThreatSentry Ultra",
+            "Suspicious process or USB activity detected!",
+            score,
+        );
+    }
+
+    Some(score)
+}
+
+fn run_full_scan(args: &FullCommand, options: &RunOptions) -> Option<u8> {
+    let FullCommand { duration, stix, scan } = args;
+    say!("{}", "\n[FULL SYSTEM SCAN]".bright_blue());
+    say!("Running comprehensive threat scan for {} seconds...", duration);
+
+    let builders = monitor_builders(scan, options, None, None);
+
+    // Live scores decay once a signal or spike passes, so each monitor's highest score is kept.
+    // Monitors are polled every second from the start of the scan; one that has no reading
+    // when the scan ends is polled once more, so even a zero-length scan gets one from each
+    let polled: Vec<Mutex<PollResults>> = builders.iter().map(|_| Mutex::default()).collect();
+    let stop = AtomicBool::new(false);
+    let started = Barrier::new(builders.len() + 1);
+    thread::scope(|scope| {
+        for (build, results) in builders.into_iter().zip(&polled) {
+            let (stop, started) = (&stop, &started);
+            scope.spawn(move || run_monitor(build, stop, started, results));
+        }
+
+        // Show progress bar once every monitor has started
+        started.wait();
+        let scan_start = Instant::now();
+        let pb = progress_bar(*duration);
+        let notifier = options.notifier("Full scan");
+        for elapsed in 1..=*duration {
+            // Ticks are scheduled from the start, so the time spent on each doesn't add up
+            thread::sleep((scan_start + Duration::from_secs(elapsed)).saturating_duration_since(Instant::now()));
+            notifier.flush_expired();
+            pb.set_message(polled.iter()
+                .filter_map(|results| {
+                    let results = results.lock().unwrap();
+                    results.highest.map(|score| format!("{} {}", results.name, score))
+                })
+                .collect::<Vec<_>>()
+                .join(" | "));
+            pb.inc(1);
+        }
+        stop.store(true, Ordering::SeqCst);
+
+        pb.finish_with_message("Monitoring complete");
+    });
+
+    let mut polled: Vec<PollResults> = polled.into_iter().map(|results| results.into_inner().unwrap()).collect();
+    let mut findings: Vec<(String, Finding)> = Vec::new();
+    for results in &mut polled {
+        findings.extend(results.findings.drain(..).map(|finding| (results.name.clone(), finding)));
+    }
+
+    // Results from the monitors that actually produced a reading
+    let scores: Vec<(&str, u8, f32)> = polled.iter()
+        .filter_map(|results| results.highest.map(|score| (results.name.as_str(), score, options.weights.for_monitor(&results.name))))
+        .collect();
+    for (name, score, _) in &scores {
+        options.record(HistoryEvent::score(name, *score));
+    }
+
+    // Display what was found, recording the noteworthy findings
+    let mut flagged_urls = Vec::new();
+    let mut flagged_ips = Vec::new();
+    let anonymizer = options.anonymizer(scan.imap.username.as_deref());
+    let shown: Vec<&(String, Finding)> = findings.iter().filter(|(_, finding)| options.shows(finding.score)).collect();
+    if !shown.is_empty() {
+        say!("\nFindings:");
+    }
+    for (name, finding) in shown {
+        let detail = scrub(&anonymizer, &finding.detail);
+        say_finding(finding, &detail, &options.thresholds);
+        match finding.kind {
+            FindingKind::Url => flagged_urls.push((detail.clone(), finding.score)),
+            FindingKind::Connection => flagged_ips.extend(connection_ip(&finding.detail).map(|ip| (ip, finding.score))),
+            _ => {},
+        }
+        if let Some(kind) = history_event_kind(finding, &options.thresholds) {
+            options.record(HistoryEvent::new(name, kind, finding.score, &detail));
+        }
+    }
+
+    // Calculate the weighted combined threat score over the monitors that ran,
+    // clamping each monitor's contribution first
+    let capped: Vec<(u8, f32)> = scores.iter()
+        .map(|&(_, score, weight)| (scoring::cap_contribution(score, scan.score_cap), weight))
+        .collect();
+    let combined_score = scoring::combined_score(&capped);
+    options.record(HistoryEvent::score("combined", combined_score));
+
+    // Display final results
+    say!("\n{}", "FINAL RESULTS".bright_yellow());
+    say!("---------------------");
+    for (name, score, _) in &scores {
+        say!("{} Threat Score: {}", capitalize(name), colorize_score(*score, &options.thresholds));
+    }
+    say!("---------------------");
+    say!("Combined Threat Score: {}", colorize_score(combined_score, &options.thresholds));
+
+    output::emit(&json!({
+        "monitor": "full",
+        "run_id": options.run_id,
+        "combined_score": combined_score,
+        "scores": scores.iter()
+            .map(|(name, score, _)| (name.to_string(), json!(score)))
+            .collect::<serde_json::Map<_, _>>(),
+        "flagged_urls": flagged_urls.iter().map(|(url, score)| json!({ "url": url, "score": score })).collect::<Vec<_>>(),
+    }));
+
+    // Send notification for high combined score
+    if options.thresholds.is_alert(combined_score) {
+        let notification_manager = options.notifier("Full scan");
+        let _ = notification_manager.send_notification(
+            "ThreatSentry Ultra",
+            &format!("High threat level detected! Score: {}", combined_score),
+            combined_score,
+        );
+    }
+
+    export_stix(stix, &flagged_urls, &flagged_ips, options);
+
+    // The combined score averages the monitors, so one critical finding could hide behind
+    // quiet ones; the exit status follows the worst single monitor instead
+    scores.iter().map(|&(_, score, _)| score).max()
+}
+
+// Kernel monitor with the process lists, USB alert classes and spawn threshold applied
+fn kernel_monitor_for(args: &KernelArgs) -> KernelMonitor {
+    let mut kernel_monitor = KernelMonitor::new();
+    if let Some(signatures) = load_signatures(&args.signatures) {
+        kernel_monitor = kernel_monitor.with_signatures(signatures);
+    }
+    if let Some(entries) = load_process_allowlist(&args.process_allowlist) {
+        kernel_monitor = kernel_monitor.with_process_allowlist(entries);
+    }
+    kernel_monitor.set_usb_alert_classes(args.usb_alert_class.clone());
+    kernel_monitor.set_spawn_rate_threshold(args.spawn_threshold);
+    kernel_monitor
+}
+
+type MonitorBuilder<'a> = Box<dyn FnOnce() -> Box<dyn Monitor> + Send + 'a>;
+
+// Builders for the selected monitors, so each one is built, started, polled and stopped on its
+// own thread and a slow poll (temperatures read through PowerShell, IMAP fetches) can't hold up
+// the others. The full scan re-reads the newest mail and only observes; the daemon checks for
+// new mail every email_interval and has its kernel monitor apply the configured responses
+fn monitor_builders<'a>(scan: &'a ScanArgs, options: &RunOptions, email_interval: Option<Duration>, responses: Option<KernelResponses>) -> Vec<MonitorBuilder<'a>> {
+    let ScanArgs { imap, monitors, filters, body_phrases, threshold, kernel, .. } = scan;
+    let mut builders: Vec<MonitorBuilder<'a>> = Vec::new();
+    if monitors.contains(&MonitorKind::Mic) {
+        builders.push(Box::new(move || -> Box<dyn Monitor> {
+            let mic_monitor = MicMonitor::new();
+            mic_monitor.set_threshold(*threshold);
+            Box::new(mic_monitor)
+        }));
+    }
+    if monitors.contains(&MonitorKind::Thermal) {
+        let baseline = options.thermal_baseline;
+        builders.push(Box::new(move || -> Box<dyn Monitor> {
+            let mut thermal_monitor = ThermalMonitor::new();
+            if let Some(baseline) = baseline {
+                thermal_monitor.set_baseline(baseline);
+            }
+            Box::new(thermal_monitor)
+        }));
+    }
+    if monitors.contains(&MonitorKind::Kernel) {
+        builders.push(Box::new(move || -> Box<dyn Monitor> {
+            let kernel_monitor = kernel_monitor_for(kernel);
+            match responses {
+                Some(responses) => Box::new(RespondingKernelMonitor { kernel_monitor, responses }),
+                None => Box::new(kernel_monitor),
+            }
+        }));
+    }
+    // Email is only scanned when credentials were provided
+    if monitors.contains(&MonitorKind::Email) {
+        match (&imap.username, &imap.password) {
+            (Some(username), Some(password)) => builders.push(Box::new(move || -> Box<dyn Monitor> {
+                let mut email_monitor = EmailMonitor::new(
+                    username.to_string(),
+                    password.to_string(),
+                    imap.imap_server.to_string(),
+                ).with_port(imap.imap_port).with_security(imap.imap_security);
+                if let Some(interval) = email_interval {
+                    email_monitor = email_monitor.with_incremental_polls(interval);
+                }
+                Box::new(with_email_filters(email_monitor, filters, body_phrases))
+            })),
+            _ => say!("{}", "No email account configured; running without email scanning".yellow()),
+        }
+    }
+    builders
+}
+
+// Build and start a monitor, wait for the others to start, then poll it every second until
+// stop is set
+fn run_monitor(build: MonitorBuilder, stop: &AtomicBool, started: &Barrier, results: &Mutex<PollResults>) {
+    let mut monitor = build();
+    match monitor.start() {
+        Ok(_) => say!("{}", format!("Started {} monitoring", monitor.name()).green()),
+        Err(e) => say!("{} {}", format!("Error starting {} monitoring:", monitor.name()).bright_red(), e),
+    }
+    results.lock().unwrap().name = monitor.name().to_string();
+
+    started.wait();
+    poll_until_stopped(monitor.as_mut(), Instant::now(), Duration::from_secs(1), stop, results);
+    monitor.stop();
+}
+
+// What the daemon's kernel monitor does about what it finds
+struct KernelResponses {
+    dispatcher: ActionDispatcher,
+    min_score: u8, // Processes scored below --min-score are left alone
+    metrics: Option<Metrics>,
+}
+
+// The daemon's kernel monitor: every poll also applies --on-usb and --on-process, on the
+// monitor's own thread, and reports removed USB devices
+struct RespondingKernelMonitor {
+    kernel_monitor: KernelMonitor,
+    responses: KernelResponses,
+}
+
+impl Monitor for RespondingKernelMonitor {
+    fn name(&self) -> &str {
+        self.kernel_monitor.name()
+    }
+
+    fn start(&self) -> Result<(), String> {
+        self.kernel_monitor.start()
+    }
+
+    fn poll(&mut self) -> MonitorSample {
+        let KernelResponses { dispatcher, min_score, metrics } = &mut self.responses;
+        // The dispatcher acts on each process and device once, however many polls report it
+        for process in self.kernel_monitor.get_suspicious_processes().iter().filter(|process| process.suspicious_score >= *min_score) {
+            if dispatcher.dispatch(&SecurityEvent::SuspiciousProcess(process)) {
+                if let Some(metrics) = metrics {
+                    metrics.count(Detection::SuspiciousProcess);
+                }
+            }
+        }
+        for device in &self.kernel_monitor.get_alerting_usb_devices() {
+            dispatcher.dispatch(&SecurityEvent::UsbInserted(device));
+        }
+        for device in &self.kernel_monitor.get_removed_usb_devices() {
+            say!("USB device removed: {} [{}] (ID: {})", device.description, device.class.name(), device.device_id);
+        }
+        self.kernel_monitor.poll()
+    }
+
+    fn stop(&self) {
+        self.kernel_monitor.stop();
+    }
+}
+
+// Print a finding from a monitor's poll, with its detail already scrubbed
+fn say_finding(finding: &Finding, detail: &str, thresholds: &SeverityThresholds) {
+    let score_color = colorize_score(finding.score, thresholds);
+    match finding.kind {
+        FindingKind::Url => say!("URL: {} | Threat Score: {}", detail, score_color),
+        FindingKind::Sender => say!("From: {} | Threat Score: {}", detail, score_color),
+        FindingKind::Wording => say!("Scam wording: {} | Threat Score: {}", detail, score_color),
+        FindingKind::ThermalSpike => say!("Temperature spike: {} | Threat Score: {}", detail, score_color),
+        FindingKind::UsbDevice => say!("New USB device: {} | Threat Score: {}", detail, score_color),
+        FindingKind::Connection => say!("Suspicious connection: {} | Threat Score: {}", detail, score_color),
+    }
+}
+
+// Remote address of a connection finding, whose detail starts with "ip:port"
+fn connection_ip(detail: &str) -> Option<IpAddr> {
+    let address: SocketAddr = detail.split_whitespace().next()?.parse().ok()?;
+    Some(address.ip())
+}
+
+// History event for a finding worth keeping: alerting URLs, thermal spikes and new USB devices
+fn history_event_kind(finding: &Finding, thresholds: &SeverityThresholds) -> Option<EventKind> {
+    match finding.kind {
+        FindingKind::Url if thresholds.is_alert(finding.score) => Some(EventKind::PhishingUrl),
+        FindingKind::ThermalSpike => Some(EventKind::ThermalSpike),
+        FindingKind::UsbDevice => Some(EventKind::UsbInserted),
+        _ => None,
+    }
+}
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// Run every selected monitor until SIGINT/SIGTERM. SIGHUP stops the monitors, reloads the config
+// file and starts them again with the new settings; a config that fails to load keeps the old ones
+// Runs until stopped; the result is the windowed combined score at that point, for the exit code
+fn run_daemon(matches: &ArgMatches, cli: &Cli, options: RunOptions) -> Option<u8> {
+    say!("{}", "\n[DAEMON]".bright_blue());
+
+    let signal = ShutdownSignal::new();
+    if let Err(e) = signal.register() {
+        say!("{} {}", "Error:".bright_red(), e);
+        output::emit_error("daemon", &e);
+        return None;
+    }
+
+    // Combined scores, kept across reloads so a reload doesn't forget a sustained elevation. Alerts,
+    // the exit code and the metrics endpoint all read this one history
+    let history = Arc::new(Mutex::new(ScoreHistory::new()));
+
+    // The endpoint outlives reloads, so a scrape never finds it down; changing the port needs a restart
+    let metrics = match &cli.command {
+        Some(Commands::Daemon(DaemonCommand { metrics_port: Some(port), metrics_bind, .. })) => match Metrics::serve(metrics_bind, *port, history.clone()) {
+            Ok(metrics) => {
+                say!("Serving metrics on http://{}:{}/metrics", metrics_bind, port);
+                Some(metrics)
+            },
+            Err(e) => {
+                say!("{} {}", "Error:".bright_red(), e);
+                output::emit_error("daemon", &e);
+                return None;
+            },
+        },
+        _ => None,
+    };
+
+    let started = Instant::now();
+    let mut reloads = 0;
+    let mut reloaded_cli = None;
+    let mut options = options;
+    let (mut window, mut percentile);
+    loop {
+        let current = reloaded_cli.as_ref().unwrap_or(cli);
+        let Some(Commands::Daemon(args)) = &current.command else {
+            return None;
+        };
+
+        (window, percentile) = (Duration::from_secs(args.window), args.percentile);
+        run_daemon_monitors(args, &options, metrics.as_ref(), &signal, &history);
+        if signal.is_stopping() {
+            break;
+        }
+
+        // Reopening the log lets logrotate move the old one away first
+        if let Some(path) = &current.log_file {
+            if let Err(e) = output::set_log_file(path) {
+                say!("{} {}", "Error:".bright_red(), e);
+            }
+        }
+
+        say!("Reloading configuration...");
+        let settings = load_settings(matches)
+            .and_then(|(cli, config)| build_run_options(&cli, &config, options.run_id.clone()).map(|options| (cli, options)));
+        match settings {
+            Ok((cli, new_options)) => {
+                reloaded_cli = Some(cli);
+                options = new_options;
+                reloads += 1;
+            },
+            Err(e) => say!("{} {}; keeping the previous settings", "Error reloading configuration:".bright_red(), e),
+        }
+    }
+
+    let score = history.lock().unwrap().percentile(window, percentile).unwrap_or(0);
+    say!("Daemon stopped after {}s", started.elapsed().as_secs());
+    output::emit(&json!({
+        "monitor": "daemon",
+        "run_id": options.run_id,
+        "uptime_secs": started.elapsed().as_secs(),
+        "reloads": reloads,
+        "score": score,
+    }));
+    Some(score)
+}
+
+// One daemon session: start the selected monitors, each polled on its own thread, combine their
+// latest scores every second until a stop or reload is requested, then stop them all. Alerts
+// follow the percentile of the combined scores in the window rather than the latest one
+fn run_daemon_monitors(args: &DaemonCommand, options: &RunOptions, metrics: Option<&Metrics>, signal: &ShutdownSignal, history: &Mutex<ScoreHistory>) {
+    let DaemonCommand { email_interval, percentile, scan, on_usb, on_process, .. } = args;
+    let responses = KernelResponses {
+        dispatcher: ActionDispatcher::new(*on_usb, *on_process, options.thresholds, options.notifier("Kernel")),
+        min_score: options.min_score,
+        metrics: metrics.cloned(),
+    };
+    let builders = monitor_builders(scan, options, Some(Duration::from_secs(*email_interval)), Some(responses));
+
+    let polled: Vec<Mutex<PollResults>> = builders.iter().map(|_| Mutex::default()).collect();
+    let stop = AtomicBool::new(false);
+    let started = Barrier::new(builders.len() + 1);
+    let anonymizer = options.anonymizer(scan.imap.username.as_deref());
+    let notifier = options.notifier("Daemon");
+    thread::scope(|scope| {
+        for (build, results) in builders.into_iter().zip(&polled) {
+            let (stop, started) = (&stop, &started);
+            scope.spawn(move || run_monitor(build, stop, started, results));
+        }
+        started.wait();
+        let names: Vec<String> = polled.iter().map(|results| results.lock().unwrap().name.clone()).collect();
+
+        let mut last_status = Instant::now();
+        let mut alerting = false; // Whether the windowed score is already above the alert level
+        while !signal.is_stopping() && !signal.take_reload() {
+            // Each monitor counts with its latest reading, so the combined score falls again once
+            // a signal passes or the mail of a check window is no longer suspicious
+            let mut scores: Vec<(&str, u8, f32)> = Vec::new();
+            let mut findings: Vec<(&str, Finding)> = Vec::new();
+            for (name, results) in names.iter().zip(&polled) {
+                let mut results = results.lock().unwrap();
+                if let Some(score) = results.latest {
+                    scores.push((name, score, options.weights.for_monitor(name)));
+                }
+                findings.extend(results.findings.drain(..).map(|finding| (name.as_str(), finding)));
+            }
+
+            for (name, finding) in &findings {
+                let event = history_event_kind(finding, &options.thresholds);
+                if let (Some(metrics), Some(event)) = (metrics, event) {
+                    match event {
+                        EventKind::PhishingUrl => metrics.count(Detection::PhishingUrl),
+                        EventKind::ThermalSpike => metrics.count(Detection::ThermalSpike),
+                        EventKind::UsbInserted => metrics.count(Detection::UsbInserted),
+                        EventKind::Score => {},
+                    }
+                }
+                if !options.shows(finding.score) {
+                    continue;
+                }
+
+                let detail = scrub(&anonymizer, &finding.detail);
+                say_finding(finding, &detail, &options.thresholds);
+                if let Some(event) = event {
+                    options.record(HistoryEvent::new(name, event, finding.score, &detail));
+                }
+                if event == Some(EventKind::PhishingUrl) {
+                    let _ = options.notifier("Email").send_notification(
+                        "ThreatSentry Ultra",
+                        &format!("Suspicious URL detected: {}", detail),
+                        finding.score,
+                    );
+                }
+            }
+
+            // Calculate the weighted combined threat score over the monitors that ran,
+            // clamping each monitor's contribution first
+            let capped: Vec<(u8, f32)> = scores.iter()
+                .map(|&(_, score, weight)| (scoring::cap_contribution(score, scan.score_cap), weight))
+                .collect();
+            let combined_score = scoring::combined_score(&capped);
+            if let Some(metrics) = metrics {
+                metrics.set_scores(&scores);
+            }
+            let windowed_score = {
+                let mut history = history.lock().unwrap();
+                history.record(combined_score);
+                history.percentile(Duration::from_secs(args.window), *percentile).unwrap_or(combined_score)
+            };
+
+            // Notify once when the windowed score rises past the alert level, not on every sample
+            if options.thresholds.is_alert(windowed_score) && !alerting {
+                let _ = notifier.send_notification(
+                    "ThreatSentry Ultra",
+                    &format!("High threat level detected! Score: {}", windowed_score),
+                    windowed_score,
+                );
+            }
+            alerting = options.thresholds.is_alert(windowed_score);
+
+            if last_status.elapsed() >= daemon::STATUS_INTERVAL {
+                last_status = Instant::now();
+                let summary: Vec<String> = scores.iter().map(|(name, score, _)| format!("{} {}", name, score)).collect();
+                say!("Scores: {} | combined {} | p{} over {}s: {}", summary.join(", "), combined_score, percentile, args.window, windowed_score);
+                for &(name, score, _) in &scores {
+                    options.record(HistoryEvent::score(name, score));
+                }
+                options.record(HistoryEvent::score("combined", combined_score));
+            }
+
+            notifier.flush_expired();
+            signal.wait(Duration::from_secs(1));
+        }
+
+        say!("Stopping monitors...");
+        stop.store(true, Ordering::SeqCst);
+    });
+
+    // A reload starts a new notification history, so summaries held back by this one go out now
+    notifier.flush_repeats();
+}
+
+// Detection thresholds are on the normalized 0.0-1.0 band power scale
+fn parse_threshold(value: &str) -> Result<f32, String> {
+    let threshold: f32 = value.parse().map_err(|_| format!("'{}' is not a number", value))?;
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(format!("{} is outside 0.0-1.0", threshold));
+    }
+    Ok(threshold)
+}
+
+// Spawn-rate thresholds are new processes per second and must be positive
+fn parse_spawn_rate(value: &str) -> Result<f32, String> {
+    let rate: f32 = value.parse().map_err(|_| format!("'{}' is not a number", value))?;
+    if !(rate > 0.0 && rate.is_finite()) {
+        return Err(format!("{} must be greater than 0", rate));
+    }
+    Ok(rate)
+}
+
+fn parse_sigma(value: &str) -> Result<f32, String> {
+    let sigma: f32 = value.parse().map_err(|_| format!("'{}' is not a number", value))?;
+    if !(sigma > 0.0 && sigma.is_finite()) {
+        return Err(format!("{} must be greater than 0", sigma));
+    }
+    Ok(sigma)
+}
+
+// Load --blocklist if given; a bad file is reported and scanning falls back to the heuristics
+fn load_blocklist(path: &Option<PathBuf>) -> Option<Blocklist> {
+    let path = path.as_ref()?;
+    match Blocklist::load(path) {
+        Ok(blocklist) => Some(blocklist),
+        Err(e) => {
+            say!("{} {}", "Error loading blocklist:".bright_red(), e);
+            None
+        }
+    }
+}
+
+fn load_allowlist(path: &Option<PathBuf>) -> Option<Allowlist> {
+    let path = path.as_ref()?;
+    match Allowlist::load(path) {
+        Ok(allowlist) => Some(allowlist),
+        Err(e) => {
+            say!("{} {}", "Error loading allowlist:".bright_red(), e);
+            None
+        }
+    }
+}
+
+fn load_body_phrases(path: &Option<PathBuf>) -> Option<Vec<(String, u8)>> {
+    let path = path.as_ref()?;
+    match EmailMonitor::load_body_phrases(path) {
+        Ok(phrases) => Some(phrases),
+        Err(e) => {
+            say!("{} {}", "Error loading body phrases:".bright_red(), e);
+            None
+        }
+    }
+}
+
+fn load_signatures(path: &Option<PathBuf>) -> Option<HashMap<String, u8>> {
+    let path = path.as_ref()?;
+    match KernelMonitor::load_signatures(path) {
+        Ok(signatures) => Some(signatures),
+        Err(e) => {
+            say!("{} {}", "Error loading signatures:".bright_red(), e);
+            None
+        }
+    }
+}
+
+fn load_process_allowlist(path: &Option<PathBuf>) -> Option<Vec<(String, Option<String>)>> {
+    let path = path.as_ref()?;
+    match KernelMonitor::load_process_allowlist(path) {
+        Ok(entries) => Some(entries),
+        Err(e) => {
+            say!("{} {}", "Error loading process allowlist:".bright_red(), e);
+            None
+        }
+    }
+}
+
+// Apply --anonymize to a reported value
+fn scrub(anonymizer: &Option<Anonymizer>, text: &str) -> String {
+    match anonymizer {
+        Some(anonymizer) => anonymizer.scrub(text),
+        None => text.to_string(),
+    }
+}
+
+// Write flagged URLs to a STIX bundle when --stix was given
+// Only alerts are exported: a threat-intel platform treats every indicator as a finding, and
+// blocklisted or PhishTank-confirmed URLs always score in the alert bands
+fn export_stix(stix: &Option<PathBuf>, urls: &[(String, u8)], ips: &[(IpAddr, u8)], options: &RunOptions) {
+    if let Some(path) = stix {
+        let urls: Vec<(String, u8)> = urls.iter().filter(|(_, score)| options.thresholds.is_alert(*score)).cloned().collect();
+        let ips: Vec<(IpAddr, u8)> = ips.iter().filter(|(_, score)| options.thresholds.is_alert(*score)).copied().collect();
+        match stix::write_bundle(path, &urls, &ips, &options.run_id) {
+            Ok(count) => say!("Exported {} STIX indicator(s) to {}", count, path.display()),
+            Err(e) => say!("{} {}", "Error exporting STIX bundle:".bright_red(), e),
+        }
+    }
+}
+
+// Per-monitor score statistics and event counts from the scan history since a Unix timestamp
+fn run_history(since: i64, options: &RunOptions) -> bool {
+    say!("{}", "\n[SCAN HISTORY]".bright_blue());
+
+    let Some(history) = &options.history else {
+        let e = "pass --history-db or set db under [history] in the config file";
+        say!("{} {}", "No scan history:".bright_red(), e);
+        output::emit_error("history", e);
+        return false;
+    };
+    let (monitors, events) = match history.summarize(since, options.thresholds.medium_max) {
+        Ok(summary) => summary,
+        Err(e) => {
+            say!("{} {}", "Error:".bright_red(), e);
+            output::emit_error("history", &e);
+            return false;
+        }
+    };
+
+    let since_time = chrono::DateTime::from_timestamp(since, 0)
+        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    say!("Since {}\n", since_time);
+
+    if monitors.is_empty() && events.is_empty() {
+        say!("{}", "Nothing recorded in this period.".yellow());
+    }
+    if !monitors.is_empty() {
+        say!("{:<10} {:>7} {:>7} {:>5} {:>5}", "Monitor", "Samples", "Average", "Max", "High");
+        for summary in &monitors {
+            say!("{:<10} {:>7} {:>7.1} {:>5} {:>5}",
+                summary.monitor,
+                summary.samples,
+                summary.average,
+                summary.max,
+                summary.high);
+        }
+    }
+    if !events.is_empty() {
+        say!("\nEvents:");
+        for event in &events {
+            say!("  - {} {}: {}", event.monitor, event.kind, event.count);
+        }
+    }
+
+    output::emit(&json!({
+        "monitor": "history",
+        "since": chrono::DateTime::from_timestamp(since, 0).map(|time| time.to_rfc3339()),
+        "monitors": monitors.iter().map(|summary| json!({
+            "monitor": summary.monitor,
+            "samples": summary.samples,
+            "average": summary.average,
+            "max": summary.max,
+            "high": summary.high,
+        })).collect::<Vec<_>>(),
+        "events": events.iter().map(|event| json!({
+            "monitor": event.monitor,
+            "kind": event.kind,
+            "count": event.count,
+        })).collect::<Vec<_>>(),
+    }));
+    true
+}
+
+// Sample temperature, CPU load and microphone band power for `duration` seconds and store their
+// mean and standard deviation in the config file, where thermal and mic scoring pick them up
+fn run_baseline(args: &BaselineCommand, config_path: &Option<PathBuf>, config: &Config, options: &RunOptions) -> bool {
+    let &BaselineCommand { duration, sigma, min_freq, max_freq, .. } = args;
+    say!("{}", "\n[BASELINE]".bright_blue());
+
+    let path = match config_path {
+        Some(path) => path,
+        None => {
+            let e = "baseline needs --config to store what it learns (create one with `config init`)";
+            say!("{} {}", "Error:".bright_red(), e);
+            output::emit_error("baseline", e);
+            return false;
+        }
+    };
+
+    say!("Learning normal temperature, CPU load and {:.0}-{:.0} Hz microphone noise for {} seconds...", min_freq, max_freq, duration);
+    say!("Keep the machine in normal use; anything unusual now becomes part of the baseline.");
+
+    // The microphone is optional: without a live input device only thermal is learned, since
+    // simulated audio would teach the baseline noise
+    let mut mic_monitor = MicMonitor::with_band(min_freq, max_freq);
+    if let Some(device) = &args.device {
+        mic_monitor = mic_monitor.with_device(device);
+    }
+    let mic_live = match mic_monitor.start_monitoring() {
+        Ok(()) if mic_monitor.get_health() == MicHealth::Live => true,
+        Ok(()) => {
+            say!("{}", "No live microphone; only learning temperature and CPU load.".yellow());
+            mic_monitor.stop_monitoring();
+            false
+        },
+        Err(e) => {
+            say!("{} {}; only learning temperature and CPU load.", "Microphone unavailable:".yellow(), e);
+            false
+        }
+    };
+
+    // CPU load is measured between two refreshes, so the first reading is discarded
+    let mut thermal_monitor = ThermalMonitor::new();
+    if let Err(e) = thermal_monitor.read_baseline_sample() {
+        say!("{} {}", "Error reading temperature:".bright_red(), e);
+        output::emit_error("baseline", &e);
+        if mic_live {
+            mic_monitor.stop_monitoring();
+        }
+        return false;
+    }
+
+    let pb = progress_bar(duration);
+    let mut temperatures = Vec::new();
+    let mut cpu_loads = Vec::new();
+    let mut mic_powers = Vec::new();
+    let start = Instant::now();
+    for elapsed in 1..=duration {
+        thread::sleep((start + Duration::from_secs(elapsed)).saturating_duration_since(Instant::now()));
+        match thermal_monitor.read_baseline_sample() {
+            Ok((temp, cpu_usage)) => {
+                pb.set_message(format!("{:.1}°C, CPU {:.0}%", temp, cpu_usage));
+                temperatures.push(temp);
+                cpu_loads.push(cpu_usage);
+            },
+            Err(e) => pb.set_message(format!("Skipped reading: {}", e)),
+        }
+        if mic_live {
+            mic_monitor.check_stream_health();
+            if mic_monitor.get_health() == MicHealth::Live {
+                mic_powers.push(mic_monitor.get_ultrasonic_power());
+            }
+        }
+        pb.inc(1);
+    }
+    pb.finish_with_message("Sampling complete");
+    if mic_live {
+        mic_monitor.stop_monitoring();
+    }
+
+    let temperature = MetricBaseline::from_samples(&temperatures, baseline::MIN_TEMP_STD_DEV);
+    let cpu = MetricBaseline::from_samples(&cpu_loads, baseline::MIN_CPU_STD_DEV);
+    let mic = MetricBaseline::from_samples(&mic_powers, baseline::MIN_MIC_STD_DEV);
+
+    let (temperature, cpu) = match (temperature, cpu) {
+        (Some(temperature), Some(cpu)) => (temperature, cpu),
+        _ => {
+            let e = "too few temperature readings to learn a baseline";
+            say!("{} {}", "Error:".bright_red(), e);
+            output::emit_error("baseline", e);
+            return false;
+        }
+    };
+
+    let mut values = vec![
+        ("baseline.sigma", sigma as f64),
+        ("baseline.temp_mean", temperature.mean as f64),
+        ("baseline.temp_std", temperature.std_dev as f64),
+        ("baseline.cpu_mean", cpu.mean as f64),
+        ("baseline.cpu_std", cpu.std_dev as f64),
+    ];
+    if let Some(mic) = mic {
+        values.push(("baseline.mic_mean", mic.mean as f64));
+        values.push(("baseline.mic_std", mic.std_dev as f64));
+    }
+    if let Err(e) = Config::save_values(path, &values) {
+        say!("{} {}", "Error saving baseline:".bright_red(), e);
+        output::emit_error("baseline", &e);
+        return false;
+    }
+
+    say!("\nResults:");
+    say!("{:<14} {:>10} {:>10} {:>12}", "Metric", "Mean", "Std dev", format!("Flag above ({}σ)", sigma));
+    say!("{:<14} {:>10.1} {:>10.1} {:>12.1}", "Temperature", temperature.mean, temperature.std_dev, temperature.upper_bound(sigma));
+    say!("{:<14} {:>10.1} {:>10.1} {:>12.1}", "CPU load", cpu.mean, cpu.std_dev, cpu.upper_bound(sigma));
+    match &mic {
+        Some(mic) => say!("{:<14} {:>10.3} {:>10.3} {:>12.3}", "Mic power", mic.mean, mic.std_dev, mic.upper_bound(sigma)),
+        None => say!("{:<14} {:>10}", "Mic power", "not learned"),
+    }
+    say!("Saved to {}", path.display());
+    if mic.is_some() && config.mic.threshold.is_some() {
+        say!("{}", "Note: [mic] threshold is set in the config file and takes precedence over the learned mic baseline; remove it to use the baseline.".yellow());
+    }
+
+    output::emit(&json!({
+        "monitor": "baseline",
+        "run_id": options.run_id,
+        "sigma": sigma,
+        "samples": temperatures.len(),
+        "temperature": { "mean": temperature.mean, "std_dev": temperature.std_dev },
+        "cpu": { "mean": cpu.mean, "std_dev": cpu.std_dev },
+        "mic": mic.map(|mic| json!({ "mean": mic.mean, "std_dev": mic.std_dev })),
+    }));
+    true
+}
+
+fn run_doctor(imap: &ImapArgs, options: &RunOptions) {
+    say!("{}", "\n[DOCTOR]".bright_blue());
+    say!("Probing monitoring capabilities...\n");
+
+    let thermal_monitor = ThermalMonitor::new();
+    let notification_manager = options.notifier("Doctor");
+
+    let mut checks: Vec<(&str, Result<String, String>)> = vec![
+        ("Audio input device", MicMonitor::probe_input_device()),
+        ("Thermal source", thermal_monitor.probe_temperature_source()),
+        ("Process enumeration", KernelMonitor::probe_process_enumeration()),
+        ("USB enumeration", KernelMonitor::probe_usb_enumeration()),
+    ];
+
+    // Only check IMAP when credentials were given
+    let imap_checked = if let (Some(username), Some(password)) = (&imap.username, &imap.password) {
+        let email_monitor = EmailMonitor::new(
+            username.to_string(),
+            password.to_string(),
+            imap.imap_server.to_string(),
+        ).with_port(imap.imap_port).with_security(imap.imap_security);
+        checks.push(("IMAP connection", email_monitor.probe_connection()));
+        true
+    } else {
+        false
+    };
+
+    checks.push((
+        "Notification backend",
+        notification_manager
+            .send_notification("ThreatSentry Ultra", "Doctor self-test notification", 0)
+            .map(|_| "Test notification sent".to_string()),
+    ));
+
+    let mut failures = 0;
+    for (name, result) in &checks {
+        match result {
+            Ok(detail) => say!("{} {}: {}", "[ OK ]".green(), name, detail),
+            Err(reason) => {
+                failures += 1;
+                say!("{} {}: {}", "[FAIL]".bright_red(), name, reason);
+            }
+        }
+    }
+
+    if !imap_checked {
+        say!("{} IMAP connection: no credentials given", "[SKIP]".yellow());
+    }
+
+    output::emit(&json!({
+        "monitor": "doctor",
+        "run_id": options.run_id,
+        "failures": failures,
+        "checks": checks.iter().map(|(name, result)| match result {
+            Ok(detail) => json!({ "name": name, "ok": true, "detail": detail }),
+            Err(reason) => json!({ "name": name, "ok": false, "detail": reason }),
+        }).chain((!imap_checked).then(|| json!({ "name": "IMAP connection", "ok": null, "detail": "no credentials given" })))
+        .collect::<Vec<_>>(),
+    }));
+
+    say!("---------------------");
+    if failures == 0 {
+        say!("{}", "All subsystems available. Scores are based on real data.".green());
+    } else {
+        say!("{}", format!("{} subsystem(s) unavailable. Those monitors fall back to simulated data.", failures).yellow());
+    }
+}
+
+fn usb_json(device: &kernel_monitor::UsbDeviceInfo) -> serde_json::Value {
+    json!({
+        "device_id": device.device_id,
+        "description": device.description,
+        "class": device.class.name(),
+    })
+}
+
+// Seconds-based progress bar for a timed scan; hidden in JSON mode
+fn progress_bar(duration: u64) -> ProgressBar {
+    if output::is_json() {
+        return ProgressBar::hidden();
+    }
+
+    let pb = ProgressBar::new(duration);
+    pb.set_style(ProgressStyle::default_bar()
+        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} seconds")
+        .unwrap()
+        .progress_chars("#>-"));
+    pb
+}
+
+fn colorize_score(score: u8, thresholds: &SeverityThresholds) -> colored::ColoredString {
+    score.to_string().color(thresholds.classify(score).color())
+}
+
+fn run_gui(args: &GuiCommand, config_path: &Option<PathBuf>, run_options: &RunOptions) -> bool {
+    let GuiCommand { imap, filters, .. } = args;
+    say!("{}", "\n[GUI]".bright_blue());
+    say!("Launching ThreatSentry Ultra GUI...");
+
+    // Email scanning only runs when both credentials were given
+    let credentials = match (&imap.username, &imap.password) {
+        (Some(username), Some(password)) => Some((username.to_string(), password.to_string())),
+        _ => {
+            say!("{}", "No email credentials given; email scanning is disabled.".yellow());
+            None
+        }
+    };
+
+    let options = gui::GuiOptions {
+        score_cap: args.score_cap,
+        reset_cursor: args.reset_cursor,
+        thresholds: run_options.thresholds,
+        blocklist: filters.blocklist.clone(),
+        allowlist: filters.allowlist.clone(),
+        brand_domains: filters.brand_domain.clone(),
+        phishtank_key: filters.phishtank_key.clone(),
+        imap_server: imap.imap_server.to_string(),
+        imap_port: imap.imap_port,
+        imap_security: imap.imap_security,
+        run_id: run_options.run_id.clone(),
+        hysteresis: args.hysteresis,
+        anonymize: run_options.anonymize,
+        sample_ms: args.sample_ms,
+        repaint_fps: args.fps,
+        signatures: args.signatures.clone(),
+        process_allowlist: args.process_allowlist.clone(),
+        weights: run_options.weights,
+        geoip_db: args.geoip_db.clone(),
+        geoip_resolve_hosts: args.geoip_resolve_hosts,
+        tuning: gui::Tuning { mic_threshold: args.threshold, min_freq: args.min_freq, max_freq: args.max_freq, spike_delta: args.spike_delta },
+        config_path: config_path.clone(),
+        thermal_baseline: run_options.thermal_baseline,
+    };
+
+    match gui::run_gui(credentials, options) {
+        Ok(_) => {
+            say!("GUI closed successfully.");
+            true
+        },
+        Err(e) => {
+            say!("{} {}", "Error running GUI:".bright_red(), e);
+            false
+        },
     }
 }
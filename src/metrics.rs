@@ -0,0 +1,109 @@
+// Prometheus metrics for the daemon (`--metrics-port`). The monitoring loop updates a shared
+// snapshot after every sample, and a server thread renders it in the text exposition format
+// on GET /metrics, so a scrape never waits on a monitor. The combined score is read from the
+// daemon's score history rather than kept a second time
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tiny_http::{Header, Method, Response, Server};
+use tracing::debug;
+
+use crate::scoring::ScoreHistory;
+
+// Interface the endpoint listens on unless --metrics-bind is given. Scores say a lot about
+// the machine, so they aren't exposed to the network by default
+pub const DEFAULT_METRICS_BIND: &str = "127.0.0.1";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Detection {
+    PhishingUrl,
+    SuspiciousProcess,
+    ThermalSpike,
+    UsbInserted,
+}
+
+impl Detection {
+    const ALL: [Detection; 4] = [Detection::PhishingUrl, Detection::SuspiciousProcess, Detection::ThermalSpike, Detection::UsbInserted];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Detection::PhishingUrl => "phishing_url",
+            Detection::SuspiciousProcess => "suspicious_process",
+            Detection::ThermalSpike => "thermal_spike",
+            Detection::UsbInserted => "usb_inserted",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Snapshot {
+    scores: Vec<(String, u8)>, // Latest score of each running monitor
+    detections: BTreeMap<Detection, u64>, // Since the daemon started, across reloads
+}
+
+#[derive(Clone)]
+pub struct Metrics {
+    snapshot: Arc<Mutex<Snapshot>>,
+}
+
+impl Metrics {
+    // Listen on bind:port and answer scrapes on a background thread for the rest of the process.
+    // history holds the combined scores the daemon records
+    pub fn serve(bind: &str, port: u16, history: Arc<Mutex<ScoreHistory>>) -> Result<Self, String> {
+        let server = Server::http((bind, port))
+            .map_err(|e| format!("Failed to serve metrics on {}:{}: {}", bind, port, e))?;
+
+        let metrics = Metrics {
+            snapshot: Arc::new(Mutex::new(Snapshot::default())),
+        };
+        let snapshot = metrics.snapshot.clone();
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let path = request.url().split('?').next().unwrap_or_default();
+                let response = if *request.method() == Method::Get && path == "/metrics" {
+                    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"text/plain; version=0.0.4; charset=utf-8"[..])
+                        .expect("static header is valid");
+                    let combined_score = history.lock().unwrap().latest().unwrap_or(0);
+                    Response::from_string(Self::render(&snapshot.lock().unwrap(), combined_score)).with_header(content_type)
+                } else {
+                    Response::from_string("Not found\n").with_status_code(404)
+                };
+                if let Err(e) = request.respond(response) {
+                    debug!("Failed to answer metrics request: {}", e);
+                }
+            }
+        });
+
+        Ok(metrics)
+    }
+
+    // Replace the gauges with this sample's scores; monitors that aren't running drop out
+    pub fn set_scores(&self, scores: &[(&str, u8, f32)]) {
+        self.snapshot.lock().unwrap().scores = scores.iter().map(|&(name, score, _)| (name.to_string(), score)).collect();
+    }
+
+    pub fn count(&self, detection: Detection) {
+        *self.snapshot.lock().unwrap().detections.entry(detection).or_insert(0) += 1;
+    }
+
+    fn render(snapshot: &Snapshot, combined_score: u8) -> String {
+        let mut text = String::new();
+        for (name, score) in &snapshot.scores {
+            let metric = format!("threatsentry_{}_score", name);
+            text.push_str(&format!("# HELP {} Current {} threat score (0-100)\n", metric, name));
+            text.push_str(&format!("# TYPE {} gauge\n{} {}\n", metric, metric, score));
+        }
+
+        text.push_str("# HELP threatsentry_combined_score Weighted combined threat score of the running monitors (0-100)\n");
+        text.push_str(&format!("# TYPE threatsentry_combined_score gauge\nthreatsentry_combined_score {}\n", combined_score));
+
+        // Every kind is listed from the start, so rate() works before the first detection
+        text.push_str("# HELP threatsentry_detections_total Detections since the daemon started, by kind\n");
+        text.push_str("# TYPE threatsentry_detections_total counter\n");
+        for detection in Detection::ALL {
+            let count = snapshot.detections.get(&detection).copied().unwrap_or(0);
+            text.push_str(&format!("threatsentry_detections_total{{kind=\"{}\"}} {}\n", detection.name(), count));
+        }
+        text
+    }
+}
@@ -1,86 +1,595 @@
 use cpal::traits::{DeviceTrait, HostTrait};
 use cpal::SampleFormat;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::thread;
-use rustfft::{FftPlanner, num_complex::Complex32};
+use rustfft::{Fft, FftPlanner, num_complex::Complex32};
 use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{debug, error, info, warn};
+
+use crate::audio_clip::ClipRecorder;
+use crate::monitor::{Monitor, MonitorSample};
+use crate::spectrogram::SpectrogramWriter;
+
+// Number of FFT frames kept for peak tracking
+const PEAK_TRACK_FRAMES: usize = 64;
+
+// Default interval between checks of the sample buffer for a full FFT frame
+const DEFAULT_FFT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// How many frames of samples may pile up between polls before the oldest are dropped
+const MAX_PENDING_FRAMES: usize = 8;
+
+// Least time between stream health checks made by poll, however often it's called
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Samples per FFT frame (power of 2 for FFT)
+pub const FFT_BUFFER_SIZE: usize = 4096;
+
+// Default number of samples between the starts of consecutive FFT frames. Half a frame
+// means every sample is analyzed twice, so a short burst that straddles a frame boundary
+// still lands near the middle of some frame, where the Hann window doesn't attenuate it
+pub const DEFAULT_HOP_SIZE: usize = FFT_BUFFER_SIZE / 2;
+
+// Sample rates tried, lowest first, when the default config can't capture the monitored band
+const PREFERRED_SAMPLE_RATES: [u32; 5] = [44100, 48000, 88200, 96000, 192000];
+
+// Default number of times a failed audio stream is rebuilt before the mic is reported lost
+const DEFAULT_MAX_RECONNECT_ATTEMPTS: u32 = 3;
+
+// Default band searched for ultrasonic signals, in Hz
+pub const DEFAULT_MIN_FREQ_HZ: f32 = 15000.0;
+pub const DEFAULT_MAX_FREQ_HZ: f32 = 20000.0;
+
+// Default time after the last detection before the score drops back to 0
+pub const DEFAULT_DETECTION_WINDOW: Duration = Duration::from_secs(5);
+
+// Default detection threshold on the average band power. FFT magnitudes are normalized to
+// the loudest bin of each frame, so the band power is on a 0.0-1.0 scale: 0.0 is silence in
+// the band and 1.0 means the band is as loud as the loudest sound in the frame
+pub const DEFAULT_THRESHOLD: f32 = 0.2;
+
+// Calibration sets the threshold to the ambient band power times this factor
+pub const DEFAULT_CALIBRATION_FACTOR: f32 = 2.0;
+
+// Lowest threshold calibration (or a learned baseline) will pick, so a silent room doesn't
+// flag every faint tone
+pub const MIN_CALIBRATED_THRESHOLD: f32 = 0.02;
+
+// Whether the microphone score is backed by a working audio stream
+#[derive(Debug, Clone, PartialEq)]
+pub enum MicHealth {
+    Live,
+    Simulated,
+    Recovering(String),
+    Lost(String),
+}
+
+impl std::fmt::Display for MicHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MicHealth::Live => write!(f, "live"),
+            MicHealth::Simulated => write!(f, "simulated (no input device)"),
+            MicHealth::Recovering(reason) => write!(f, "reconnecting ({})", reason),
+            MicHealth::Lost(reason) => write!(f, "mic lost ({})", reason),
+        }
+    }
+}
+
+// Summary of how the ultrasonic peak behaved over the recent frames
+#[derive(Debug, Clone, Default)]
+pub struct ModulationMetrics {
+    pub frames: usize,
+    pub active_frames: usize,
+    pub duty_cycle: f32,
+    pub peak_freq_std_hz: f32,
+    pub transitions: usize,
+}
+
+impl ModulationMetrics {
+    // A data-carrying channel keeps showing up but either keys on/off or shifts its carrier,
+    // while a steady tone from electronics stays on the same bin the whole time
+    pub fn is_modulated(&self) -> bool {
+        if self.active_frames < 8 {
+            return false;
+        }
+
+        let keyed = self.transitions >= 4 && self.duty_cycle > 0.2 && self.duty_cycle < 0.9;
+        let shifting = self.peak_freq_std_hz > 50.0;
+        keyed || shifting
+    }
+}
+
+// Window applied to each frame before the FFT. Hann suits general detection; flat-top
+// smears a carrier over more bins but measures its amplitude most accurately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum WindowFunction {
+    #[default]
+    Hann,
+    Hamming,
+    Blackman,
+    FlatTop,
+    // No window at all, i.e. the most spectral leakage
+    Rectangular,
+}
+
+impl WindowFunction {
+    // Coefficients for a frame of the given length (periodic form, as used for spectral analysis)
+    pub fn coefficients(&self, len: usize) -> Vec<f32> {
+        let cos_term = |i: usize, k: f32| (k * 2.0 * std::f32::consts::PI * i as f32 / len as f32).cos();
+
+        (0..len)
+            .map(|i| match self {
+                WindowFunction::Hann => 0.5 - 0.5 * cos_term(i, 1.0),
+                WindowFunction::Hamming => 0.54 - 0.46 * cos_term(i, 1.0),
+                WindowFunction::Blackman => 0.42 - 0.5 * cos_term(i, 1.0) + 0.08 * cos_term(i, 2.0),
+                WindowFunction::FlatTop => 0.21557895 - 0.41663158 * cos_term(i, 1.0) + 0.27726316 * cos_term(i, 2.0)
+                    - 0.083578947 * cos_term(i, 3.0) + 0.006947368 * cos_term(i, 4.0),
+                WindowFunction::Rectangular => 1.0,
+            })
+            .collect()
+    }
+}
+
+// One ultrasonic event: consecutive detections with no gap longer than the detection window
+struct DetectionEvent {
+    start: chrono::DateTime<chrono::Local>,
+    end: chrono::DateTime<chrono::Local>,
+    last_seen: Instant,
+    peak_hz: Option<f32>,
+    max_power: f32,
+}
+
+// Append-only CSV audit trail of detection events, one line per event rather than per frame
+struct EventLog {
+    file: File,
+    current: Option<DetectionEvent>,
+}
+
+impl EventLog {
+    fn open(path: &Path) -> Result<Self, String> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open event log {}: {}", path.display(), e))?;
+
+        let is_empty = file.metadata().map(|metadata| metadata.len() == 0).unwrap_or(false);
+        if is_empty {
+            writeln!(file, "start,end,peak_hz,max_power")
+                .map_err(|e| format!("Failed to write event log {}: {}", path.display(), e))?;
+        }
+
+        Ok(EventLog { file, current: None })
+    }
+
+    // A detecting frame either extends the current event or starts a new one
+    fn record(&mut self, power: f32, peak_hz: Option<f32>, window: Duration) {
+        self.close_if_quiet(window);
+
+        let now = chrono::Local::now();
+        match &mut self.current {
+            Some(event) => {
+                event.end = now;
+                event.last_seen = Instant::now();
+                if power > event.max_power {
+                    event.max_power = power;
+                    event.peak_hz = peak_hz;
+                }
+            },
+            None => {
+                self.current = Some(DetectionEvent {
+                    start: now,
+                    end: now,
+                    last_seen: Instant::now(),
+                    peak_hz,
+                    max_power: power,
+                });
+            },
+        }
+    }
+
+    // Write out the current event once nothing has been detected for the window
+    fn close_if_quiet(&mut self, window: Duration) {
+        if matches!(&self.current, Some(event) if event.last_seen.elapsed() > window) {
+            self.close();
+        }
+    }
+
+    fn close(&mut self) {
+        if let Some(event) = self.current.take() {
+            let peak = event.peak_hz.map(|hz| format!("{:.1}", hz)).unwrap_or_default();
+            let line = format!("{},{},{},{:.4}", event.start.to_rfc3339(), event.end.to_rfc3339(), peak, event.max_power);
+            if let Err(e) = writeln!(self.file, "{}", line).and_then(|_| self.file.flush()) {
+                error!("Failed to write event log: {}", e);
+            }
+        }
+    }
+}
+
+// An input device as reported by the audio host, for picking one with --device
+#[derive(Debug, Clone)]
+pub struct InputDeviceInfo {
+    pub index: usize,
+    pub name: String,
+    pub is_default: bool,
+    // Supported sample rate ranges in Hz, with the channel count of each config
+    pub sample_rates: Vec<(u32, u32, u16)>,
+}
+
+// Magnitude spectrum of the latest FFT frame, with enough metadata to map bins to frequencies
+#[derive(Debug, Clone, Default)]
+pub struct Spectrum {
+    pub sample_rate: u32,
+    pub bin_count: usize,
+    pub magnitudes: Vec<f32>,
+    // Monitored band in Hz, and the strongest frequency in it as (frequency_hz, magnitude)
+    pub band: (f32, f32),
+    pub peak: Option<(f32, f32)>,
+}
+
+impl Spectrum {
+    // Center frequency of a bin in Hz; the bins span 0 to the Nyquist frequency
+    pub fn bin_frequency(&self, bin: usize) -> f32 {
+        if self.bin_count == 0 {
+            return 0.0;
+        }
+        bin as f32 * self.sample_rate as f32 / (2 * self.bin_count) as f32
+    }
+}
 
-// Store frequency power as a simple f32 instead of using FrequencySpectrum
 pub struct MicMonitor {
     is_monitoring: Arc<Mutex<bool>>,
-    high_freq_detected: Arc<Mutex<bool>>,
+    last_detection: Arc<Mutex<Option<Instant>>>,
+    detection_window: Duration,
     frequency_power: Arc<Mutex<f32>>,
     sample_rate: Arc<Mutex<u32>>,
+    channels: Arc<Mutex<u16>>,
     fft_results: Arc<Mutex<Vec<f32>>>,
     ultrasonic_power: Arc<Mutex<f32>>,
     stream_handle: Arc<Mutex<Option<cpal::Stream>>>,
+    peak_track: Arc<Mutex<VecDeque<Option<f32>>>>,
+    peak: Arc<Mutex<Option<(f32, f32)>>>,
+    fft_poll_interval: Duration,
+    fft_buffer: Arc<Mutex<VecDeque<f32>>>,
+    stream_error: Arc<Mutex<Option<String>>>,
+    health: Arc<Mutex<MicHealth>>,
+    reconnect_attempts: Arc<Mutex<u32>>,
+    max_reconnect_attempts: u32,
+    band: Arc<Mutex<(f32, f32)>>, // (min, max) Hz, shared so it can be retuned while monitoring
+    threshold: Arc<Mutex<f32>>,
+    device_selector: Option<String>,
+    hop_size: usize,
+    window: WindowFunction,
+    recorder: Option<Arc<ClipRecorder>>,
+    event_log: Option<Arc<Mutex<EventLog>>>,
+    spectrogram: Option<Arc<Mutex<SpectrogramWriter>>>,
+    last_health_check: Option<Instant>,
 }
 
 impl MicMonitor {
     pub fn new() -> Self {
         MicMonitor {
             is_monitoring: Arc::new(Mutex::new(false)),
-            high_freq_detected: Arc::new(Mutex::new(false)),
+            last_detection: Arc::new(Mutex::new(None)),
+            detection_window: DEFAULT_DETECTION_WINDOW,
             frequency_power: Arc::new(Mutex::new(0.0)),
             sample_rate: Arc::new(Mutex::new(44100)),
+            channels: Arc::new(Mutex::new(1)),
             fft_results: Arc::new(Mutex::new(Vec::new())),
             ultrasonic_power: Arc::new(Mutex::new(0.0)),
             stream_handle: Arc::new(Mutex::new(None)),
+            peak_track: Arc::new(Mutex::new(VecDeque::with_capacity(PEAK_TRACK_FRAMES))),
+            peak: Arc::new(Mutex::new(None)),
+            fft_poll_interval: DEFAULT_FFT_POLL_INTERVAL,
+            fft_buffer: Arc::new(Mutex::new(VecDeque::new())),
+            stream_error: Arc::new(Mutex::new(None)),
+            health: Arc::new(Mutex::new(MicHealth::Simulated)),
+            reconnect_attempts: Arc::new(Mutex::new(0)),
+            max_reconnect_attempts: DEFAULT_MAX_RECONNECT_ATTEMPTS,
+            band: Arc::new(Mutex::new((DEFAULT_MIN_FREQ_HZ, DEFAULT_MAX_FREQ_HZ))),
+            threshold: Arc::new(Mutex::new(DEFAULT_THRESHOLD)),
+            device_selector: None,
+            hop_size: DEFAULT_HOP_SIZE,
+            window: WindowFunction::default(),
+            recorder: None,
+            event_log: None,
+            spectrogram: None,
+            last_health_check: None,
+        }
+    }
+
+    // Save the audio around each new detection as a WAV clip in dir (see audio_clip).
+    // Only real input is recorded; simulated monitoring has no audio to save
+    pub fn enable_recording(&mut self, dir: &Path) -> Result<(), String> {
+        self.recorder = Some(Arc::new(ClipRecorder::new(dir)?));
+        Ok(())
+    }
+
+    // Append each detection event (start, end, peak frequency, max power) to a CSV file.
+    // Detections less than the detection window apart are merged into one event, so a
+    // continuous beacon is one line rather than one per frame
+    pub fn set_event_log(&mut self, path: &Path) -> Result<(), String> {
+        self.event_log = Some(Arc::new(Mutex::new(EventLog::open(path)?)));
+        Ok(())
+    }
+
+    // Write every FFT frame's magnitude spectrum to a CSV (see spectrogram), optionally only
+    // for the first max_duration of monitoring
+    pub fn enable_spectrogram_csv(&mut self, path: &Path, max_duration: Option<Duration>) -> Result<(), String> {
+        self.spectrogram = Some(Arc::new(Mutex::new(SpectrogramWriter::create(path, max_duration)?)));
+        Ok(())
+    }
+
+    // Clips written so far this run
+    pub fn recorded_clips(&self) -> Vec<PathBuf> {
+        self.recorder.as_ref().map(|recorder| recorder.clips()).unwrap_or_default()
+    }
+
+    // Use a specific input device instead of the system default, e.g. a USB measurement mic
+    // with a flat high-frequency response. Matches a device index from list_input_devices,
+    // an exact name, or failing those a case-insensitive part of a name
+    pub fn with_device(mut self, name_or_index: &str) -> Self {
+        self.device_selector = Some(name_or_index.to_string());
+        self
+    }
+
+    // Monitor a different band, e.g. 18-22kHz, or 17-18kHz for near-ultrasonic ad beacons.
+    // The band is checked against the device's Nyquist frequency when monitoring starts
+    pub fn with_band(min_freq_hz: f32, max_freq_hz: f32) -> Self {
+        let monitor = Self::new();
+        *monitor.band.lock().unwrap() = (min_freq_hz, max_freq_hz);
+        monitor
+    }
+
+    // Retune the band while monitoring; takes effect on the next frame. The stream isn't
+    // reopened, so the band has to fit the sample rate already in use
+    pub fn set_band(&self, min_freq_hz: f32, max_freq_hz: f32) -> Result<(), String> {
+        Self::check_band(min_freq_hz, max_freq_hz, *self.sample_rate.lock().unwrap())?;
+        *self.band.lock().unwrap() = (min_freq_hz, max_freq_hz);
+        Ok(())
+    }
+
+    pub fn get_band(&self) -> (f32, f32) {
+        *self.band.lock().unwrap()
+    }
+
+    // The band has to be non-empty and lie below the Nyquist frequency, since the FFT
+    // has no bins above sample_rate / 2
+    pub fn validate_band(&self, sample_rate: u32) -> Result<(), String> {
+        let (min_freq_hz, max_freq_hz) = self.get_band();
+        Self::check_band(min_freq_hz, max_freq_hz, sample_rate)
+    }
+
+    fn check_band(min_freq_hz: f32, max_freq_hz: f32, sample_rate: u32) -> Result<(), String> {
+        let nyquist = sample_rate as f32 / 2.0;
+        if min_freq_hz < 0.0 || min_freq_hz >= max_freq_hz {
+            return Err(format!("Invalid frequency band {:.0}-{:.0} Hz: the minimum must be below the maximum",
+                               min_freq_hz, max_freq_hz));
+        }
+        if max_freq_hz >= nyquist {
+            return Err(format!("Maximum frequency {:.0} Hz must be below half the sample rate ({:.0} Hz at {} Hz); \
+                                ultrasonic detection is impossible at this rate, try another --device or a lower --max-freq",
+                               max_freq_hz, nyquist, sample_rate));
+        }
+        Ok(())
+    }
+
+    // How often the FFT thread wakes up to look for a full frame. A frame itself takes
+    // buffer_size / sample_rate to fill (~93ms at 44.1kHz), which bounds detection latency
+    // from below. Shorter intervals cut latency down to that floor at the cost of CPU;
+    // longer intervals save CPU, and every complete frame is still processed on wake-up
+    pub fn set_fft_poll_interval(&mut self, interval: Duration) {
+        self.fft_poll_interval = interval.max(Duration::from_millis(1));
+    }
+
+    // How long a detection keeps counting toward the threat score once the signal stops
+    pub fn set_detection_window(&mut self, window: Duration) {
+        self.detection_window = window;
+    }
+
+    // Average band power (0.0-1.0, see DEFAULT_THRESHOLD) above which a frame counts as a
+    // detection. Raise it in noisy rooms; takes effect on the next frame while monitoring
+    pub fn set_threshold(&self, threshold: f32) {
+        *self.threshold.lock().unwrap() = threshold.clamp(0.0, 1.0);
+    }
+
+    pub fn get_threshold(&self) -> f32 {
+        *self.threshold.lock().unwrap()
+    }
+
+    // Measure the ambient band power for a while and set the threshold to that baseline times
+    // factor. Needs a live stream, since simulated data says nothing about the room
+    pub fn calibrate_threshold(&self, duration: Duration, factor: f32) -> Result<f32, String> {
+        if self.get_health() != MicHealth::Live {
+            return Err(format!("calibration needs a live microphone (currently {})", self.get_health()));
+        }
+
+        info!("Calibrating noise floor for {} seconds, keep the room as usual...", duration.as_secs());
+
+        let started = Instant::now();
+        let mut readings = Vec::new();
+        while started.elapsed() < duration {
+            thread::sleep(self.fft_poll_interval);
+            readings.push(self.get_ultrasonic_power());
+        }
+
+        if readings.is_empty() {
+            return Err("no audio frames were captured during calibration".to_string());
+        }
+
+        let baseline = readings.iter().sum::<f32>() / readings.len() as f32;
+        let threshold = (baseline * factor).clamp(MIN_CALIBRATED_THRESHOLD, 1.0);
+        self.set_threshold(threshold);
+
+        // Anything flagged while calibrating was measured against the old threshold
+        *self.last_detection.lock().unwrap() = None;
+        *self.frequency_power.lock().unwrap() = 0.0;
+        self.peak_track.lock().unwrap().clear();
+
+        info!("Noise floor {:.4}, threshold set to {:.4}", baseline, threshold);
+        Ok(threshold)
+    }
+
+    // Window function applied to each frame (Hann unless set)
+    pub fn with_window(mut self, window: WindowFunction) -> Self {
+        self.window = window;
+        self
+    }
+
+    // Samples the analysis window advances per FFT frame, from 1 up to FFT_BUFFER_SIZE (no
+    // overlap). Smaller hops improve time resolution for short beacons at the cost of CPU
+    pub fn set_hop_size(&mut self, hop_size: usize) {
+        self.hop_size = hop_size.clamp(1, FFT_BUFFER_SIZE);
+    }
+
+    // How many times check_stream_health rebuilds a failed stream before giving up
+    pub fn set_max_reconnect_attempts(&mut self, attempts: u32) {
+        self.max_reconnect_attempts = attempts;
+    }
+
+    pub fn get_health(&self) -> MicHealth {
+        self.health.lock().unwrap().clone()
+    }
+
+    // Check that a real input device is available, without starting a stream
+    pub fn probe_input_device() -> Result<String, String> {
+        let host = cpal::default_host();
+        let device = host.default_input_device()
+            .ok_or_else(|| "No input device available".to_string())?;
+        let name = device.name().unwrap_or_else(|_| "Unknown".to_string());
+        let config = device.default_input_config()
+            .map_err(|e| format!("{}: failed to read default input config: {}", name, e))?;
+
+        Ok(format!("{} ({} Hz, {} channel(s))", name, config.sample_rate().0, config.channels()))
+    }
+
+    // Every input device with the sample rates it supports, in the order the host reports them
+    pub fn list_input_devices() -> Result<Vec<InputDeviceInfo>, String> {
+        let host = cpal::default_host();
+        let default_name = host.default_input_device().and_then(|device| device.name().ok());
+        let devices = host.input_devices()
+            .map_err(|e| format!("Failed to enumerate input devices: {}", e))?;
+
+        Ok(devices.enumerate()
+            .map(|(index, device)| {
+                let name = device.name().unwrap_or_else(|_| "Unknown".to_string());
+                let sample_rates = device.supported_input_configs()
+                    .map(|configs| configs
+                        .map(|config| (config.min_sample_rate().0, config.max_sample_rate().0, config.channels()))
+                        .collect())
+                    .unwrap_or_default();
+
+                InputDeviceInfo {
+                    index,
+                    is_default: default_name.as_deref() == Some(name.as_str()),
+                    name,
+                    sample_rates,
+                }
+            })
+            .collect())
+    }
+
+    // The device picked by with_device, or the host's default input device
+    fn select_input_device(&self) -> Result<cpal::Device, String> {
+        let host = cpal::default_host();
+
+        let selector = match &self.device_selector {
+            Some(selector) => selector,
+            None => return host.default_input_device()
+                .ok_or_else(|| "No input device available".to_string()),
+        };
+
+        let devices: Vec<cpal::Device> = host.input_devices()
+            .map_err(|e| format!("Failed to enumerate input devices: {}", e))?
+            .collect();
+        let names: Vec<String> = devices.iter()
+            .map(|device| device.name().unwrap_or_default())
+            .collect();
+
+        let position = selector.parse::<usize>().ok()
+            .filter(|&index| index < devices.len())
+            .or_else(|| names.iter().position(|name| name == selector))
+            .or_else(|| {
+                let needle = selector.to_lowercase();
+                names.iter().position(|name| name.to_lowercase().contains(&needle))
+            });
+
+        match position {
+            Some(index) => Ok(devices.into_iter().nth(index).unwrap()),
+            None => Err(format!("No input device matches \"{}\" (see --list-devices)", selector)),
         }
     }
 
     pub fn start_monitoring(&self) -> Result<(), String> {
-        println!("Starting microphone monitoring...");
+        debug!("Starting microphone monitoring...");
 
         // Set monitoring flag
         let mut is_monitoring = self.is_monitoring.lock().unwrap();
         *is_monitoring = true;
         drop(is_monitoring); // Release the lock
 
-        // Initialize the audio device
-        let host = cpal::default_host();
+        *self.reconnect_attempts.lock().unwrap() = 0;
+        *self.stream_error.lock().unwrap() = None;
 
-        // Get the default input device
-        let device = match host.default_input_device() {
-            Some(device) => device,
-            None => {
-                println!("No input device available. Using simulated data.");
-                // Fallback to simulation
-                return self.start_simulated_monitoring();
-            }
-        };
-
-        println!("Using input device: {}", device.name().unwrap_or_else(|_| "Unknown".to_string()));
-
-        // Get the default config
-        let config = match device.default_input_config() {
-            Ok(config) => config,
+        // Open the default input device, falling back to simulation if there isn't a usable one
+        let stream = match self.open_input_stream() {
+            Ok(stream) => stream,
+            Err(e) if self.device_selector.is_some() => {
+                // Simulated data would hide that the requested device isn't being monitored
+                *self.is_monitoring.lock().unwrap() = false;
+                return Err(e);
+            },
             Err(e) => {
-                println!("Error getting default input config: {}. Using simulated data.", e);
-                // Fallback to simulation
+                warn!("{}. Using simulated data.", e);
+                if let Err(e) = self.validate_band(*self.sample_rate.lock().unwrap()) {
+                    *self.is_monitoring.lock().unwrap() = false;
+                    return Err(e);
+                }
+                *self.health.lock().unwrap() = MicHealth::Simulated;
                 return self.start_simulated_monitoring();
             }
         };
 
-        println!("Sample format: {:?}, channels: {}, sample rate: {}",
-                 config.sample_format(), config.channels(), config.sample_rate().0);
-
-        // Update sample rate
-        *self.sample_rate.lock().unwrap() = config.sample_rate().0;
+        // The band can only be checked once the device's sample rate is known
+        if let Err(e) = self.validate_band(*self.sample_rate.lock().unwrap()) {
+            *self.is_monitoring.lock().unwrap() = false;
+            return Err(e);
+        }
 
-        // Clone the shared state for the callback
-        let high_freq_detected = self.high_freq_detected.clone();
+        // Clone the shared state for the FFT thread
+        let last_detection = self.last_detection.clone();
         let frequency_power = self.frequency_power.clone();
         let is_monitoring_clone = self.is_monitoring.clone();
         let fft_results = self.fft_results.clone();
         let ultrasonic_power = self.ultrasonic_power.clone();
-        let sample_rate = *self.sample_rate.lock().unwrap();
+        let peak_track = self.peak_track.clone();
+        let peak_shared = self.peak.clone();
+        let sample_rate_shared = self.sample_rate.clone();
+        let poll_interval = self.fft_poll_interval;
+        let fft_buffer = self.fft_buffer.clone();
+        let band_shared = self.band.clone();
+        let threshold_shared = self.threshold.clone();
+        let detection_window = self.detection_window;
+        let recorder = self.recorder.clone();
+        let event_log = self.event_log.clone();
+        let spectrogram = self.spectrogram.clone();
 
         // Buffer for FFT processing
-        let buffer_size = 4096; // Power of 2 for FFT
-        let fft_buffer = Arc::new(Mutex::new(VecDeque::new()));
-        let fft_buffer_clone = fft_buffer.clone();
+        let buffer_size = FFT_BUFFER_SIZE;
+        let hop_size = self.hop_size;
+        let window_coefficients = self.window.coefficients(buffer_size);
+
+        // Warn when the poll interval is long enough that frames queue up between polls
+        let sample_rate = *self.sample_rate.lock().unwrap();
+        let frame_duration = Duration::from_secs_f32(buffer_size as f32 / sample_rate as f32);
+        if poll_interval > frame_duration * MAX_PENDING_FRAMES as u32 {
+            warn!("FFT poll interval of {}ms exceeds the buffered window; older audio will be dropped",
+                     poll_interval.as_millis());
+        }
 
         // Start the FFT processing thread
         let _fft_thread = thread::spawn(move || {
@@ -88,108 +597,232 @@ impl MicMonitor {
             let mut planner = FftPlanner::new();
             let fft = planner.plan_fft_forward(buffer_size);
             
-            // Frequency resolution: sample_rate / buffer_size
-            let freq_resolution = sample_rate as f32 / buffer_size as f32;
-            
-            // Ultrasonic frequency range (15-20kHz)
-            let min_freq_idx = (15000.0 / freq_resolution) as usize;
-            let max_freq_idx = (20000.0 / freq_resolution) as usize;
-            
             while *is_monitoring_clone.lock().unwrap() {
+                // The sample rate can change when the stream is rebuilt on a different device
+                let sample_rate = *sample_rate_shared.lock().unwrap();
+
+                // Frequency resolution: sample_rate / buffer_size
+                let freq_resolution = sample_rate as f32 / buffer_size as f32;
+
+                // Bins covering the monitored band (15-20kHz unless configured otherwise)
+                let (min_freq_hz, max_freq_hz) = *band_shared.lock().unwrap();
+                let min_freq_idx = (min_freq_hz / freq_resolution) as usize;
+                let max_freq_idx = (max_freq_hz / freq_resolution) as usize;
+
                 // Check if we have enough samples for FFT
                 let mut buffer_lock = fft_buffer.lock().unwrap();
+
+                // Don't let the buffer grow without bound if polling is slower than frames arrive
+                let max_pending = buffer_size * MAX_PENDING_FRAMES;
+                if buffer_lock.len() > max_pending {
+                    let excess = buffer_lock.len() - max_pending;
+                    buffer_lock.drain(..excess);
+                }
                 
-                if buffer_lock.len() >= buffer_size {
-                    // Prepare input for FFT
-                    let mut fft_input: Vec<Complex32> = buffer_lock.drain(..buffer_size)
-                        .map(|sample| Complex32::new(sample, 0.0))
-                        .collect();
-                    
-                    // Apply window function (Hann window) to reduce spectral leakage
-                    for i in 0..buffer_size {
-                        let window = 0.5 * (1.0 - (2.0 * std::f32::consts::PI * i as f32 / buffer_size as f32).cos());
-                        fft_input[i] = fft_input[i] * window;
+                // Process every complete frame collected since the last poll
+                while let Some(frame) = Self::next_frame(&mut buffer_lock, buffer_size, hop_size) {
+                    let mut magnitudes = Self::magnitude_spectrum(fft.as_ref(), &frame, &window_coefficients);
+
+                    // Export the raw magnitudes, before they are normalized for detection
+                    if let Some(spectrogram) = &spectrogram {
+                        spectrogram.lock().unwrap().write_frame(sample_rate, buffer_size, &magnitudes);
                     }
                     
-                    // Create output buffer - no longer needed in rustfft 6.x
-                    // We'll modify the input buffer directly
-                    
-                    // Perform FFT - the API changed in rustfft 6.x
-                    fft.process(&mut fft_input);
-                    
-                    // Calculate magnitude spectrum
-                    let mut magnitudes: Vec<f32> = fft_input[..buffer_size/2]
-                        .iter()
-                        .map(|c| (c.norm_sqr()).sqrt())
-                        .collect();
-                    
-                    // Normalize magnitude spectrum
-                    if let Some(max_val) = magnitudes.iter().cloned().fold(None, |max, val| {
-                        match max {
-                            None => Some(val),
-                            Some(m) => Some(m.max(val))
-                        }
-                    }) {
-                        if max_val > 0.0 {
-                            for mag in &mut magnitudes {
-                                *mag /= max_val;
-                            }
-                        }
-                    }
+                    Self::normalize(&mut magnitudes);
                     
                     // Update FFT results for visualization
                     *fft_results.lock().unwrap() = magnitudes.clone();
                     
-                    // Check for ultrasonic frequencies (15-20kHz)
-                    let ultrasonic_range = &magnitudes[min_freq_idx.min(magnitudes.len())..max_freq_idx.min(magnitudes.len())];
-                    
-                    if !ultrasonic_range.is_empty() {
-                        // Calculate average power in ultrasonic range
-                        let avg_power = ultrasonic_range.iter().sum::<f32>() / ultrasonic_range.len() as f32;
+                    // Check for power in the monitored band
+                    if let Some(avg_power) = Self::band_power(&magnitudes, min_freq_idx, max_freq_idx) {
                         *ultrasonic_power.lock().unwrap() = avg_power;
                         
-                        // Threshold for detection
-                        let threshold = 0.2; // Adjust based on testing
+                        let threshold = *threshold_shared.lock().unwrap();
+
+                        // Track where the strongest ultrasonic bin sits in this frame
+                        let peak = Self::find_peak(&magnitudes, min_freq_idx, max_freq_idx, freq_resolution);
+                        *peak_shared.lock().unwrap() = peak;
+
                         if avg_power > threshold {
-                            *high_freq_detected.lock().unwrap() = true;
+                            let mut last_detection = last_detection.lock().unwrap();
+
+                            // A detection after a quiet spell starts a new event worth recording
+                            let new_event = match *last_detection {
+                                Some(at) => at.elapsed() > detection_window,
+                                None => true,
+                            };
+                            if new_event {
+                                if let Some(recorder) = &recorder {
+                                    recorder.trigger();
+                                }
+                            }
+
+                            *last_detection = Some(Instant::now());
+                            drop(last_detection);
                             *frequency_power.lock().unwrap() = avg_power;
-                            println!("Ultrasonic frequency detected! Power: {:.4}", avg_power);
+                            warn!("Ultrasonic frequency detected! Power: {:.4}", avg_power);
+
+                            if let Some(event_log) = &event_log {
+                                event_log.lock().unwrap().record(avg_power, peak.map(|(freq, _)| freq), detection_window);
+                            }
+                        } else if let Some(event_log) = &event_log {
+                            event_log.lock().unwrap().close_if_quiet(detection_window);
                         }
+
+                        let peak_freq = if avg_power > threshold {
+                            peak.map(|(freq, _)| freq)
+                        } else {
+                            None
+                        };
+                        Self::record_peak(&peak_track, peak_freq);
                     }
                 }
                 
-                // Sleep a bit to prevent high CPU usage
-                thread::sleep(Duration::from_millis(100));
+                drop(buffer_lock);
+
+                // Sleep until the next poll to keep CPU usage down
+                thread::sleep(poll_interval);
+            }
+
+            // An event still in progress ends with the monitoring session
+            if let Some(event_log) = &event_log {
+                event_log.lock().unwrap().close();
+            }
+            if let Some(spectrogram) = &spectrogram {
+                spectrogram.lock().unwrap().finish();
             }
         });
 
-        // Start the audio input stream
-        let err_fn = |err| eprintln!("Error in audio stream: {}", err);
+        // Store the stream handle
+        *self.stream_handle.lock().unwrap() = Some(stream);
+        *self.health.lock().unwrap() = MicHealth::Live;
+
+        info!("Microphone monitoring started successfully");
+        Ok(())
+    }
+
+    // Build an input stream on the selected (or current default) device, feeding the shared
+    // FFT buffer. Stream errors are recorded so check_stream_health can rebuild the stream
+    fn open_input_stream(&self) -> Result<cpal::Stream, String> {
+        let device = self.select_input_device()?;
 
+        info!("Using input device: {}", device.name().unwrap_or_else(|_| "Unknown".to_string()));
+
+        // Get the default config, switching to a higher rate if it can't capture the band
+        let mut config = device.default_input_config()
+            .map_err(|e| format!("Error getting default input config: {}", e))?;
+        let (_, max_freq_hz) = self.get_band();
+        if (config.sample_rate().0 as f32 / 2.0) <= max_freq_hz {
+            match self.find_band_capable_config(&device, config.sample_format()) {
+                Some(higher) => {
+                    info!("Default sample rate of {} Hz can't capture {:.0} Hz, using {} Hz instead",
+                             config.sample_rate().0, max_freq_hz, higher.sample_rate().0);
+                    config = higher;
+                },
+                None => {
+                    warn!("{} Hz is the best this device offers, which is too low to detect signals up to {:.0} Hz",
+                             config.sample_rate().0, max_freq_hz);
+                },
+            }
+        }
+
+        debug!("Sample format: {:?}, channels: {}, sample rate: {}",
+                 config.sample_format(), config.channels(), config.sample_rate().0);
+
+        // Update sample rate and channel count
+        *self.sample_rate.lock().unwrap() = config.sample_rate().0;
+        *self.channels.lock().unwrap() = config.channels();
+        if let Some(recorder) = &self.recorder {
+            recorder.set_format(config.sample_rate().0, config.channels());
+        }
+
+        // Record stream errors (e.g. the device was unplugged) instead of just logging them
+        let stream_error = self.stream_error.clone();
+        let err_fn = move |err: cpal::StreamError| {
+            error!("Error in audio stream: {}", err);
+            *stream_error.lock().unwrap() = Some(err.to_string());
+        };
+
+        let buffer = self.fft_buffer.clone();
         let stream = match config.sample_format() {
-            SampleFormat::F32 => self.build_input_stream::<f32>(&device, &config.into(), fft_buffer_clone, err_fn),
-            SampleFormat::I16 => self.build_input_stream::<i16>(&device, &config.into(), fft_buffer_clone, err_fn),
-            SampleFormat::U16 => self.build_input_stream::<u16>(&device, &config.into(), fft_buffer_clone, err_fn),
+            SampleFormat::F32 => self.build_input_stream::<f32>(&device, &config.into(), buffer, err_fn),
+            SampleFormat::I16 => self.build_input_stream::<i16>(&device, &config.into(), buffer, err_fn),
+            SampleFormat::U16 => self.build_input_stream::<u16>(&device, &config.into(), buffer, err_fn),
             _ => {
                 // Handle any new formats added to the enum in the future
-                println!("Unsupported sample format. Using simulated data.");
-                return self.start_simulated_monitoring();
+                return Err("Unsupported sample format".to_string());
             }
         };
 
-        let stream = match stream {
-            Ok(stream) => stream,
-            Err(err) => {
-                println!("Error building input stream: {}", err);
-                return self.start_simulated_monitoring();
+        stream.map_err(|e| format!("Error building input stream: {}", e))
+    }
+
+    // Lowest supported config whose Nyquist frequency is above the monitored band, preferring
+    // the default sample format and common rates over the edges of each supported range
+    fn find_band_capable_config(&self, device: &cpal::Device, preferred_format: SampleFormat) -> Option<cpal::SupportedStreamConfig> {
+        let min_rate = (self.get_band().1 * 2.0).floor() as u32 + 1;
+        let supported = [SampleFormat::F32, SampleFormat::I16, SampleFormat::U16];
+
+        let mut candidates: Vec<cpal::SupportedStreamConfig> = device.supported_input_configs().ok()?
+            .filter(|range| supported.contains(&range.sample_format()))
+            .filter_map(|range| {
+                let (low, high) = (range.min_sample_rate().0, range.max_sample_rate().0);
+                PREFERRED_SAMPLE_RATES.iter()
+                    .copied()
+                    .chain(std::iter::once(high))
+                    .filter(|&rate| rate >= min_rate && rate >= low && rate <= high)
+                    .min()
+                    .map(|rate| range.with_sample_rate(cpal::SampleRate(rate)))
+            })
+            .collect();
+
+        candidates.sort_by_key(|config| (config.sample_format() != preferred_format, config.sample_rate().0));
+        candidates.into_iter().next()
+    }
+
+    // Called periodically by whoever owns the monitor. After a stream error the stream is
+    // dropped and rebuilt on the selected or current default device, one attempt per call, until the
+    // attempt budget runs out and the microphone is reported as lost
+    pub fn check_stream_health(&self) -> MicHealth {
+        let error = self.stream_error.lock().unwrap().take();
+        let mut health = self.health.lock().unwrap();
+
+        if let Some(error) = error {
+            if *health == MicHealth::Live {
+                warn!("Audio input failed: {}", error);
+                *self.stream_handle.lock().unwrap() = None;
+                *health = MicHealth::Recovering(error);
             }
-        };
+        }
 
-        // Store the stream handle
-        *self.stream_handle.lock().unwrap() = Some(stream);
+        if let MicHealth::Recovering(reason) = health.clone() {
+            if !*self.is_monitoring.lock().unwrap() {
+                return health.clone();
+            }
 
-        println!("Microphone monitoring started successfully");
-        Ok(())
+            let mut attempts = self.reconnect_attempts.lock().unwrap();
+            if *attempts >= self.max_reconnect_attempts {
+                error!("Microphone lost after {} reconnect attempt(s)", *attempts);
+                *health = MicHealth::Lost(reason);
+            } else {
+                *attempts += 1;
+                warn!("Reconnecting audio input (attempt {}/{})...", *attempts, self.max_reconnect_attempts);
+
+                match self.open_input_stream() {
+                    Ok(stream) => {
+                        // Samples from the old device shouldn't be mixed into the new stream's frames
+                        self.fft_buffer.lock().unwrap().clear();
+                        *self.stream_handle.lock().unwrap() = Some(stream);
+                        *attempts = 0;
+                        *health = MicHealth::Live;
+                        info!("Audio input recovered");
+                    },
+                    Err(e) => warn!("Reconnect failed: {}", e),
+                }
+            }
+        }
+
+        health.clone()
     }
 
     fn build_input_stream<T>(
@@ -203,16 +836,17 @@ impl MicMonitor {
         T: cpal::Sample<Float = f32> + cpal::SizedSample + Send + 'static,
     {
         let is_monitoring = self.is_monitoring.clone();
+        let recorder = self.recorder.clone();
+        let channels = (*self.channels.lock().unwrap()).max(1) as usize;
         
         device.build_input_stream(
             config,
             move |data: &[T], _: &cpal::InputCallbackInfo| {
                 if *is_monitoring.lock().unwrap() {
-                    // Convert samples to f32 and store in buffer
-                    let mut buffer_lock = buffer.lock().unwrap();
-                    for &sample in data {
-                        let sample_f32 = sample.to_float_sample();
-                        buffer_lock.push_back(sample_f32);
+                    Self::downmix(data, channels, &mut buffer.lock().unwrap());
+
+                    if let Some(recorder) = &recorder {
+                        recorder.push(data.iter().map(|sample| sample.to_float_sample()));
                     }
                 }
             },
@@ -221,13 +855,29 @@ impl MicMonitor {
         )
     }
 
+    // Samples are interleaved per channel; down-mix each frame to one mono sample so the FFT
+    // sees the real sample rate and bins map to the right frequencies
+    fn downmix<T>(data: &[T], channels: usize, buffer: &mut VecDeque<f32>)
+    where
+        T: cpal::Sample<Float = f32>,
+    {
+        for frame in data.chunks_exact(channels) {
+            let sum: f32 = frame.iter().map(|sample| sample.to_float_sample()).sum();
+            buffer.push_back(sum / channels as f32);
+        }
+    }
+
     fn start_simulated_monitoring(&self) -> Result<(), String> {
         // Clone the shared state for the callback
-        let high_freq_detected = self.high_freq_detected.clone();
+        let last_detection = self.last_detection.clone();
         let frequency_power = self.frequency_power.clone();
         let is_monitoring_clone = self.is_monitoring.clone();
         let fft_results = self.fft_results.clone();
         let ultrasonic_power = self.ultrasonic_power.clone();
+        let peak_track = self.peak_track.clone();
+        let peak_shared = self.peak.clone();
+        let sample_rate = *self.sample_rate.lock().unwrap();
+        let band_shared = self.band.clone();
 
         // Create a thread for simulated monitoring
         thread::spawn(move || {
@@ -239,12 +889,17 @@ impl MicMonitor {
             while *is_monitoring_clone.lock().unwrap() {
                 i += 1;
 
+                // Put the simulated peak in the middle of the monitored band
+                let (min_freq_hz, max_freq_hz) = *band_shared.lock().unwrap();
+                let center_freq = (min_freq_hz + max_freq_hz) / 2.0;
+                let center_fraction = center_freq / (sample_rate as f32 / 2.0);
+
                 // Every 5 iterations, simulate detecting a high frequency
                 if i % 5 == 0 {
                     // Update simulated FFT results
                     for j in 0..simulated_fft.len() {
-                        // Create a peak in the ultrasonic range (around 75-85% of the Nyquist frequency)
-                        let ultrasonic_center = (simulated_fft.len() as f32 * 0.8) as usize;
+                        // Create a peak in the middle of the monitored band
+                        let ultrasonic_center = (simulated_fft.len() as f32 * center_fraction) as usize;
                         let distance = (j as isize - ultrasonic_center as isize).abs();
                         
                         if distance < 50 {
@@ -260,27 +915,37 @@ impl MicMonitor {
                     *fft_results.lock().unwrap() = simulated_fft.clone();
                     
                     // Simulate high frequency detection
-                    *high_freq_detected.lock().unwrap() = true;
+                    *last_detection.lock().unwrap() = Some(Instant::now());
 
                     // Set a power value between 0.2 and 0.5
                     let power = 0.2 + (i as f32 % 10.0) / 30.0;
                     *frequency_power.lock().unwrap() = power;
                     *ultrasonic_power.lock().unwrap() = power;
 
-                    println!("Simulated ultrasonic frequency detected! Power: {:.4}", power);
+                    info!("Simulated ultrasonic frequency detected! Power: {:.4}", power);
+
+                    Self::record_peak(&peak_track, Some(center_freq));
                 } else {
                     // Update with just noise
                     for j in 0..simulated_fft.len() {
                         simulated_fft[j] = 0.05 + 0.1 * rand::random::<f32>();
                     }
                     *fft_results.lock().unwrap() = simulated_fft.clone();
+                    Self::record_peak(&peak_track, None);
                 }
 
+                // The simulated bins span 0 to the Nyquist frequency
+                let freq_resolution = sample_rate as f32 / 2.0 / simulated_fft.len() as f32;
+                *peak_shared.lock().unwrap() = Self::find_peak(&simulated_fft,
+                    (min_freq_hz / freq_resolution) as usize,
+                    (max_freq_hz / freq_resolution) as usize,
+                    freq_resolution);
+
                 thread::sleep(Duration::from_millis(500));
             }
         });
 
-        println!("Simulated microphone monitoring started");
+        info!("Simulated microphone monitoring started");
         Ok(())
     }
 
@@ -292,21 +957,42 @@ impl MicMonitor {
         // Stop the audio stream if it exists
         let mut stream_handle = self.stream_handle.lock().unwrap();
         *stream_handle = None;
+        drop(stream_handle);
+
+        // Clips still waiting for post-trigger audio are written with what was captured
+        if let Some(recorder) = &self.recorder {
+            recorder.finish();
+        }
+
+        // A later session shouldn't start out reporting this one's detections
+        *self.last_detection.lock().unwrap() = None;
+        *self.frequency_power.lock().unwrap() = 0.0;
         
-        println!("Microphone monitoring stopped");
+        info!("Microphone monitoring stopped");
     }
 
+    // Score for the signal currently in the band. It falls back to 0 once nothing has been
+    // detected for the detection window, so a beacon that stopped doesn't keep the score up
     pub fn get_threat_score(&self) -> u8 {
-        let high_freq_detected = self.high_freq_detected.lock().unwrap();
+        let recently_detected = match *self.last_detection.lock().unwrap() {
+            Some(at) => at.elapsed() <= self.detection_window,
+            None => false,
+        };
         let frequency_power = self.frequency_power.lock().unwrap();
 
-        if *high_freq_detected {
+        if recently_detected {
             // Calculate score based on the power of high frequencies
             let power = *frequency_power;
 
             // Scale the power to a score between 50 and 100
             // Higher power means higher threat score
-            let score = 50.0 + (power * 500.0);
+            let mut score = 50.0 + (power * 500.0);
+
+            // A persistent peak that looks like it is carrying data is more suspicious than a steady tone
+            if self.get_modulation_metrics().is_modulated() {
+                score += 20.0;
+            }
+
             let capped_score = if score > 100.0 { 100.0 } else { score };
             capped_score as u8
         } else {
@@ -315,11 +1001,262 @@ impl MicMonitor {
         }
     }
     
-    pub fn get_fft_results(&self) -> Vec<f32> {
-        self.fft_results.lock().unwrap().clone()
+    // Peak frequency variance and on/off keying over the recent frames
+    pub fn get_modulation_metrics(&self) -> ModulationMetrics {
+        let peak_track = self.peak_track.lock().unwrap();
+
+        let frames = peak_track.len();
+        let active: Vec<f32> = peak_track.iter().filter_map(|peak| *peak).collect();
+        if frames == 0 {
+            return ModulationMetrics::default();
+        }
+
+        let transitions = peak_track.iter()
+            .zip(peak_track.iter().skip(1))
+            .filter(|(a, b)| a.is_some() != b.is_some())
+            .count();
+
+        let peak_freq_std_hz = if active.len() > 1 {
+            let mean = active.iter().sum::<f32>() / active.len() as f32;
+            let variance = active.iter()
+                .map(|&f| (f - mean).powi(2))
+                .sum::<f32>() / (active.len() - 1) as f32;
+            variance.sqrt()
+        } else {
+            0.0
+        };
+
+        ModulationMetrics {
+            frames,
+            active_frames: active.len(),
+            duty_cycle: active.len() as f32 / frames as f32,
+            peak_freq_std_hz,
+            transitions,
+        }
+    }
+
+    // Strongest bin in [min_idx, max_idx) as (frequency_hz, magnitude). A parabola through
+    // the bin and its neighbours locates the true peak between bin centers, so a carrier's
+    // frequency is resolved more finely than the ~10Hz bin spacing
+    // Next FFT frame from the front of the buffer, once a whole one has arrived. Frames overlap:
+    // each one starts hop_size samples after the previous, and the rest of its samples stay
+    // buffered for the next frame
+    fn next_frame(buffer: &mut VecDeque<f32>, buffer_size: usize, hop_size: usize) -> Option<Vec<f32>> {
+        if buffer.len() < buffer_size {
+            return None;
+        }
+
+        let frame = buffer.iter().take(buffer_size).copied().collect();
+        buffer.drain(..hop_size.min(buffer_size));
+        Some(frame)
+    }
+
+    // Magnitudes of the bins from 0 to the Nyquist frequency for one windowed frame, not yet normalized
+    fn magnitude_spectrum(fft: &dyn Fft<f32>, frame: &[f32], window_coefficients: &[f32]) -> Vec<f32> {
+        let mut fft_input: Vec<Complex32> = frame.iter()
+            .map(|&sample| Complex32::new(sample, 0.0))
+            .collect();
+
+        // Apply the window function to reduce spectral leakage
+        for (sample, &window) in fft_input.iter_mut().zip(window_coefficients) {
+            *sample *= window;
+        }
+
+        fft.process(&mut fft_input);
+
+        fft_input[..frame.len() / 2]
+            .iter()
+            .map(|c| c.norm_sqr().sqrt())
+            .collect()
+    }
+
+    // Scale the magnitudes so the loudest bin is 1.0
+    fn normalize(magnitudes: &mut [f32]) {
+        let max_val = magnitudes.iter().cloned().fold(0.0, f32::max);
+        if max_val > 0.0 {
+            for mag in magnitudes.iter_mut() {
+                *mag /= max_val;
+            }
+        }
+    }
+
+    // Average normalized magnitude of the bins in [min_idx, max_idx); None when the range is empty
+    fn band_power(magnitudes: &[f32], min_idx: usize, max_idx: usize) -> Option<f32> {
+        let band = &magnitudes[min_idx.min(magnitudes.len())..max_idx.min(magnitudes.len())];
+        if band.is_empty() {
+            return None;
+        }
+        Some(band.iter().sum::<f32>() / band.len() as f32)
+    }
+
+    fn find_peak(magnitudes: &[f32], min_idx: usize, max_idx: usize, freq_resolution: f32) -> Option<(f32, f32)> {
+        let max_idx = max_idx.min(magnitudes.len());
+        if min_idx >= max_idx {
+            return None;
+        }
+
+        let (peak_idx, &peak) = magnitudes[min_idx..max_idx].iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(idx, magnitude)| (min_idx + idx, magnitude))?;
+
+        if peak_idx == 0 || peak_idx + 1 >= magnitudes.len() {
+            return Some((peak_idx as f32 * freq_resolution, peak));
+        }
+
+        let (left, right) = (magnitudes[peak_idx - 1], magnitudes[peak_idx + 1]);
+        let denominator = left - 2.0 * peak + right;
+        let offset = if denominator.abs() > f32::EPSILON {
+            (0.5 * (left - right) / denominator).clamp(-0.5, 0.5)
+        } else {
+            0.0
+        };
+
+        Some(((peak_idx as f32 + offset) * freq_resolution, peak - 0.25 * (left - right) * offset))
+    }
+
+    // Strongest frequency in the monitored band in the latest frame, as (frequency_hz, magnitude)
+    pub fn get_peak_frequency(&self) -> Option<(f32, f32)> {
+        *self.peak.lock().unwrap()
+    }
+
+    // Append one frame's peak (None when the band was below threshold) to the tracking window
+    fn record_peak(peak_track: &Arc<Mutex<VecDeque<Option<f32>>>>, peak_freq: Option<f32>) {
+        let mut peak_track = peak_track.lock().unwrap();
+        peak_track.push_back(peak_freq);
+        if peak_track.len() > PEAK_TRACK_FRAMES {
+            peak_track.pop_front();
+        }
+    }
+
+    pub fn get_fft_results(&self) -> Spectrum {
+        let magnitudes = self.fft_results.lock().unwrap().clone();
+        Spectrum {
+            sample_rate: *self.sample_rate.lock().unwrap(),
+            bin_count: magnitudes.len(),
+            magnitudes,
+            band: self.get_band(),
+            peak: self.get_peak_frequency(),
+        }
     }
     
     pub fn get_ultrasonic_power(&self) -> f32 {
         *self.ultrasonic_power.lock().unwrap()
     }
 }
+
+impl Monitor for MicMonitor {
+    fn name(&self) -> &str {
+        "mic"
+    }
+
+    fn start(&self) -> Result<(), String> {
+        self.start_monitoring()
+    }
+
+    fn poll(&mut self) -> MonitorSample {
+        // Rebuild the audio stream if the input device went away
+        let health_check_due = match self.last_health_check {
+            Some(at) => at.elapsed() >= HEALTH_CHECK_INTERVAL,
+            None => true,
+        };
+        if health_check_due {
+            self.last_health_check = Some(Instant::now());
+            self.check_stream_health();
+        }
+
+        // Simulated audio keeps the spectrum moving in the GUI but is never scored
+        let score = match self.get_health() {
+            MicHealth::Simulated => None,
+            _ => Some(self.get_threat_score()),
+        };
+        MonitorSample { score, findings: Vec::new() }
+    }
+
+    fn stop(&self) {
+        self.stop_monitoring();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 44100;
+    const TONE_HZ: f32 = 18000.0;
+
+    fn tone(freq_hz: f32, sample: usize) -> f32 {
+        (2.0 * std::f32::consts::PI * freq_hz * sample as f32 / SAMPLE_RATE as f32).sin()
+    }
+
+    // Power in a 100 Hz band around the tone, and its peak, for every frame the FFT thread
+    // would take from these samples
+    fn analyze(samples: &[f32], hop_size: usize) -> Vec<(f32, Option<(f32, f32)>)> {
+        let fft = FftPlanner::new().plan_fft_forward(FFT_BUFFER_SIZE);
+        let window = WindowFunction::Hann.coefficients(FFT_BUFFER_SIZE);
+        let freq_resolution = SAMPLE_RATE as f32 / FFT_BUFFER_SIZE as f32;
+        let (min_idx, max_idx) = ((17950.0 / freq_resolution) as usize, (18050.0 / freq_resolution) as usize);
+
+        let mut buffer: VecDeque<f32> = samples.iter().copied().collect();
+        let mut frames = Vec::new();
+        while let Some(frame) = MicMonitor::next_frame(&mut buffer, FFT_BUFFER_SIZE, hop_size) {
+            let mut magnitudes = MicMonitor::magnitude_spectrum(fft.as_ref(), &frame, &window);
+            MicMonitor::normalize(&mut magnitudes);
+            let power = MicMonitor::band_power(&magnitudes, min_idx, max_idx).unwrap();
+            frames.push((power, MicMonitor::find_peak(&magnitudes, min_idx, max_idx, freq_resolution)));
+        }
+        frames
+    }
+
+    #[test]
+    fn overlapping_frames_catch_a_burst_on_a_frame_boundary() {
+        // A 2048-sample burst of an 18 kHz tone centered on the boundary between the first two
+        // 4096-sample frames, over a steady 1 kHz tone standing in for room sound
+        let burst = FFT_BUFFER_SIZE - DEFAULT_HOP_SIZE / 2..FFT_BUFFER_SIZE + DEFAULT_HOP_SIZE / 2;
+        let samples: Vec<f32> = (0..FFT_BUFFER_SIZE * 2)
+            .map(|i| {
+                let room = 0.5 * tone(1000.0, i);
+                if burst.contains(&i) { room + tone(TONE_HZ, i) } else { room }
+            })
+            .collect();
+
+        // Without overlap the burst falls on the tapered edges of both frames and is missed
+        let separate = analyze(&samples, FFT_BUFFER_SIZE);
+        assert_eq!(separate.len(), 2);
+        assert!(separate.iter().all(|(power, _)| *power < DEFAULT_THRESHOLD));
+
+        // With 50% overlap the middle frame is centered on the burst
+        let overlapping = analyze(&samples, DEFAULT_HOP_SIZE);
+        assert_eq!(overlapping.len(), 3);
+        let (power, peak) = overlapping[1];
+        assert!(power > DEFAULT_THRESHOLD);
+        let (peak_hz, _) = peak.unwrap();
+        assert!((peak_hz - TONE_HZ).abs() < SAMPLE_RATE as f32 / FFT_BUFFER_SIZE as f32);
+    }
+
+    #[test]
+    fn stereo_stream_maps_bins_to_the_right_frequencies() {
+        // 44.1 kHz stereo with the 18 kHz tone on the left channel and silence on the right
+        let interleaved: Vec<f32> = (0..FFT_BUFFER_SIZE)
+            .flat_map(|i| [tone(TONE_HZ, i), 0.0])
+            .collect();
+
+        let mut buffer = VecDeque::new();
+        MicMonitor::downmix(&interleaved, 2, &mut buffer);
+        assert_eq!(buffer.len(), FFT_BUFFER_SIZE);
+
+        let fft = FftPlanner::new().plan_fft_forward(FFT_BUFFER_SIZE);
+        let window = WindowFunction::Hann.coefficients(FFT_BUFFER_SIZE);
+        let frame = MicMonitor::next_frame(&mut buffer, FFT_BUFFER_SIZE, FFT_BUFFER_SIZE).unwrap();
+        let mut magnitudes = MicMonitor::magnitude_spectrum(fft.as_ref(), &frame, &window);
+        MicMonitor::normalize(&mut magnitudes);
+
+        let loudest = magnitudes.iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .map(|(bin, _)| bin)
+            .unwrap();
+        let spectrum = Spectrum { sample_rate: SAMPLE_RATE, bin_count: magnitudes.len(), magnitudes, ..Spectrum::default() };
+        assert!((spectrum.bin_frequency(loudest) - TONE_HZ).abs() < SAMPLE_RATE as f32 / FFT_BUFFER_SIZE as f32);
+    }
+}
@@ -0,0 +1,94 @@
+// Common interface of the detectors, so the full scan, the daemon and the GUI can drive any set
+// of them the same way: start, poll once per sample, stop. A new detector only has to implement
+// Monitor and be added to the list; the combined score, findings display and history handle the rest
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How often a scheduled monitor checks for a stop request while it waits for its next poll
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+pub trait Monitor {
+    // Short lowercase key, as used by --monitors, the weights and the scan history
+    fn name(&self) -> &str;
+
+    // Begin any background capture. Monitors that only sample when polled have nothing to start
+    fn start(&self) -> Result<(), String>;
+
+    // Take one sample: the current score and anything new found since the previous poll
+    fn poll(&mut self) -> MonitorSample;
+
+    fn stop(&self);
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MonitorSample {
+    pub score: Option<u8>, // None until the monitor has a reading, e.g. before the first mail check succeeds
+    pub findings: Vec<Finding>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FindingKind {
+    Url,
+    Sender, // Spoofed sender headers
+    Wording, // Scam wording in a message body
+    ThermalSpike,
+    UsbDevice,
+    Connection, // Outbound connection from a process that isn't a known network app
+}
+
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub score: u8,
+    pub detail: String, // The URL, sender, peak temperature, device or remote address
+}
+
+impl Finding {
+    pub fn new(kind: FindingKind, score: u8, detail: String) -> Self {
+        Finding { kind, score, detail }
+    }
+}
+
+// What a monitor polled on its own thread has reported so far
+#[derive(Debug, Default)]
+pub struct PollResults {
+    pub name: String,
+    pub highest: Option<u8>, // Highest score of any poll; None until the monitor has a reading
+    pub latest: Option<u8>, // Score of the latest poll that had a reading
+    pub findings: Vec<Finding>, // Until taken by whoever reads the results
+}
+
+// Poll a monitor on the calling thread at start, start + interval, start + 2 * interval, ...
+// until stop is set, collecting into results. A poll that overruns (a PowerShell temperature
+// read, an IMAP fetch) delays only this monitor, and the ticks it missed are skipped rather
+// than polled back to back. Once stopped, a monitor without any reading yet is polled once more
+pub fn poll_until_stopped(monitor: &mut dyn Monitor, start: Instant, interval: Duration, stop: &AtomicBool, results: &Mutex<PollResults>) {
+    let mut due = start;
+    loop {
+        while !stop.load(Ordering::SeqCst) && Instant::now() < due {
+            thread::sleep(STOP_CHECK_INTERVAL.min(due.saturating_duration_since(Instant::now())));
+        }
+        let stopping = stop.load(Ordering::SeqCst);
+        if stopping && results.lock().unwrap().highest.is_some() {
+            break;
+        }
+
+        let sample = monitor.poll();
+        {
+            let mut results = results.lock().unwrap();
+            if let Some(score) = sample.score {
+                results.highest = Some(results.highest.map_or(score, |highest| highest.max(score)));
+                results.latest = Some(score);
+            }
+            results.findings.extend(sample.findings);
+        }
+        if stopping {
+            break;
+        }
+
+        let ticks = (start.elapsed().as_secs_f64() / interval.as_secs_f64()).floor() as u32 + 1;
+        due = start + interval * ticks;
+    }
+}
@@ -1,28 +1,410 @@
 use notify_rust::{Notification, Timeout};
+#[cfg(all(unix, not(target_os = "macos")))]
+use notify_rust::Urgency;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::error;
 
-pub struct NotificationManager;
+use crate::channels::{self, NotificationChannel, WebhookFormat};
+use crate::scoring::{Severity, SeverityThresholds};
+use crate::smtp_alert::SmtpAlerter;
+
+// How long one webhook request may take, and how many times a failed one is retried
+pub const DEFAULT_WEBHOOK_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_WEBHOOK_RETRIES: u32 = 3;
+
+// Identical notifications within this long of the first are suppressed and counted
+pub const DEFAULT_COOLDOWN_SECS: u64 = 60;
+
+// Sound played with critical notifications; each platform names its sounds differently
+#[cfg(target_os = "windows")]
+const CRITICAL_SEVERITY_SOUND: &str = "Reminder";
+#[cfg(target_os = "macos")]
+const CRITICAL_SEVERITY_SOUND: &str = "Sosumi";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const CRITICAL_SEVERITY_SOUND: &str = "dialog-warning";
+
+struct SentNotification {
+    sent: Instant,
+    title: String,
+    message: String,
+    urgency: u8,
+    monitor: Option<String>, // Monitor that raised it, for the repeat summary
+    repeats: u32, // Identical notifications suppressed since it was sent
+}
+
+impl SentNotification {
+    // The "(×N)" notification for the repeats suppressed since this one was sent
+    fn summary(&self) -> (String, String, u8, Option<String>) {
+        (format!("{} (×{})", self.title, self.repeats), self.message.clone(), self.urgency, self.monitor.clone())
+    }
+}
+
+// Notifications sent recently, keyed by title and message. Cloning shares the history, so
+// every notifier of a run suppresses the same repeats
+#[derive(Clone, Default)]
+pub struct NotificationHistory {
+    sent: Arc<Mutex<HashMap<String, SentNotification>>>,
+}
+
+impl NotificationHistory {
+    pub fn new() -> Self {
+        NotificationHistory::default()
+    }
+}
+
+type Delivery = Box<dyn FnOnce() + Send>;
+
+// Makes the network deliveries (webhook, chat channels, syslog, email) on a background thread,
+// in the order they were queued, so a slow or unreachable endpoint never stalls the monitor loop
+// that raised the alert. Cloning shares the thread; the last copy waits for the queue to drain,
+// so a run never ends with undelivered alerts
+#[derive(Clone)]
+pub struct DeliveryQueue {
+    sender: Arc<Mutex<mpsc::Sender<Delivery>>>,
+    queued: Arc<(Mutex<usize>, Condvar)>, // Deliveries not finished yet
+}
+
+impl DeliveryQueue {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel::<Delivery>();
+        let queued = Arc::new((Mutex::new(0), Condvar::new()));
+
+        let worker_queued = Arc::clone(&queued);
+        thread::spawn(move || {
+            for delivery in receiver {
+                delivery();
+                let (count, finished) = &*worker_queued;
+                *count.lock().unwrap() -= 1;
+                finished.notify_all();
+            }
+        });
+
+        DeliveryQueue {
+            sender: Arc::new(Mutex::new(sender)),
+            queued,
+        }
+    }
+
+    fn push(&self, delivery: Delivery) {
+        *self.queued.0.lock().unwrap() += 1;
+        if self.sender.lock().unwrap().send(delivery).is_err() {
+            *self.queued.0.lock().unwrap() -= 1;
+        }
+    }
+
+    // Block until everything queued so far has been delivered (or has failed)
+    pub fn wait(&self) {
+        let (count, finished) = &*self.queued;
+        let mut count = count.lock().unwrap();
+        while *count > 0 {
+            count = finished.wait(count).unwrap();
+        }
+    }
+}
+
+impl Drop for DeliveryQueue {
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.sender) == 1 {
+            self.wait();
+        }
+    }
+}
+
+pub struct NotificationManager {
+    thresholds: SeverityThresholds,
+    run_id: Option<String>,
+    webhook_url: Option<String>, // Alerts are also POSTed here
+    webhook_format: WebhookFormat,
+    webhook_timeout: Duration,
+    webhook_retries: u32,
+    channels: Vec<Arc<dyn NotificationChannel>>, // Alerts are fanned out to these
+    smtp: Option<SmtpAlerter>, // Emails alerts above its own score threshold
+    deliveries: Option<DeliveryQueue>, // Sends to the webhook, channels and email off the caller's thread
+    monitor: Option<String>, // Monitor the notifications come from, for alert emails
+    sound: bool, // Play a sound with high-severity notifications
+    cooldown: Duration,
+    history: NotificationHistory,
+}
 
 impl NotificationManager {
     pub fn new() -> Self {
-        NotificationManager
+        NotificationManager {
+            thresholds: SeverityThresholds::default(),
+            run_id: None,
+            webhook_url: None,
+            webhook_format: WebhookFormat::Generic,
+            webhook_timeout: Duration::from_secs(DEFAULT_WEBHOOK_TIMEOUT_SECS),
+            webhook_retries: DEFAULT_WEBHOOK_RETRIES,
+            channels: Vec::new(),
+            smtp: None,
+            deliveries: None,
+            monitor: None,
+            sound: true,
+            cooldown: Duration::from_secs(DEFAULT_COOLDOWN_SECS),
+            history: NotificationHistory::new(),
+        }
+    }
+
+    // Use custom severity bands for the urgency mapping
+    pub fn with_thresholds(mut self, thresholds: SeverityThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    // Tag every notification with the run that raised it, so it can be matched to the report
+    pub fn with_run_id(mut self, run_id: &str) -> Self {
+        self.run_id = Some(run_id.to_string());
+        self
+    }
+
+    // Also POST every alert to this URL, shaped for the service behind it
+    pub fn with_webhook(mut self, url: &str, format: WebhookFormat, timeout: Duration, retries: u32) -> Self {
+        self.webhook_url = Some(url.to_string());
+        self.webhook_format = format;
+        self.webhook_timeout = timeout;
+        self.webhook_retries = retries;
+        self
+    }
+
+    // Also send every alert to another channel (Slack, Discord, syslog)
+    pub fn with_channel(mut self, channel: Box<dyn NotificationChannel>) -> Self {
+        self.channels.push(Arc::from(channel));
+        self
+    }
+
+    // Also email alerts above the alerter's score threshold
+    pub fn with_smtp(mut self, smtp: SmtpAlerter) -> Self {
+        self.smtp = Some(smtp);
+        self
     }
 
+    // Name the monitor raising these notifications (shown in alert emails)
+    pub fn with_monitor(mut self, monitor: &str) -> Self {
+        self.monitor = Some(monitor.to_string());
+        self
+    }
+
+    // Play (or don't play) a sound with high-severity notifications
+    pub fn with_sound(mut self, sound: bool) -> Self {
+        self.sound = sound;
+        self
+    }
+
+    // Suppress repeats of a notification for this long; zero sends every one
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    // Share recently sent notifications with other managers, so repeats are suppressed run-wide
+    pub fn with_history(mut self, history: NotificationHistory) -> Self {
+        self.history = history;
+        self
+    }
+
+    // Share the background delivery thread with other managers, so a run's alerts go out in order
+    pub fn with_deliveries(mut self, deliveries: DeliveryQueue) -> Self {
+        self.deliveries = Some(deliveries);
+        self
+    }
+
+    // Send a notification unless an identical one went out within the cooldown. Repeats are
+    // counted and, once the cooldown has expired, collapsed into one "(×N)" notification
     pub fn send_notification(&self, title: &str, message: &str, urgency: u8) -> Result<(), String> {
+        let monitor = self.monitor.as_deref();
+        if self.cooldown.is_zero() {
+            return self.deliver(title, message, urgency, monitor);
+        }
+
+        let key = format!("{}\n{}", title, message);
+        let cooldown = self.cooldown;
+        let (expired, suppress) = {
+            let mut sent = self.history.sent.lock().unwrap();
+
+            // Summaries for other notifications whose repeats stopped
+            let expired = Self::take_summaries(&mut sent, |other_key, notification| {
+                notification.sent.elapsed() >= cooldown && *other_key != key
+            });
+
+            let suppress = match sent.get_mut(&key) {
+                Some(notification) if notification.sent.elapsed() < cooldown => {
+                    notification.repeats += 1;
+                    notification.urgency = notification.urgency.max(urgency);
+                    None
+                },
+                previous => {
+                    // This one plus the repeats suppressed during the last cooldown
+                    let count = previous.map_or(1, |notification| notification.repeats + 1);
+                    sent.insert(key, SentNotification {
+                        sent: Instant::now(),
+                        title: title.to_string(),
+                        message: message.to_string(),
+                        urgency,
+                        monitor: self.monitor.clone(),
+                        repeats: 0,
+                    });
+                    Some(count)
+                },
+            };
+            (expired, suppress)
+        };
+        self.deliver_summaries(expired);
+
+        match suppress {
+            None => Ok(()),
+            Some(1) => self.deliver(title, message, urgency, monitor),
+            Some(count) => self.deliver(&format!("{} (×{})", title, count), message, urgency, monitor),
+        }
+    }
+
+    // Send the summaries of notifications whose cooldown expired with repeats pending. Long
+    // running loops call this every tick, so a summary goes out when the cooldown expires
+    // rather than with the next notification
+    pub fn flush_expired(&self) {
+        let cooldown = self.cooldown;
+        let expired = Self::take_summaries(&mut self.history.sent.lock().unwrap(), |_, notification| {
+            notification.sent.elapsed() >= cooldown
+        });
+        self.deliver_summaries(expired);
+    }
+
+    // Send every summary still held back, expired or not, e.g. when a run ends
+    pub fn flush_repeats(&self) {
+        let pending = Self::take_summaries(&mut self.history.sent.lock().unwrap(), |_, _| true);
+        self.deliver_summaries(pending);
+    }
+
+    // Remove the entries `done` selects, returning summaries for those with repeats; the
+    // others are just forgotten
+    fn take_summaries(sent: &mut HashMap<String, SentNotification>, done: impl Fn(&String, &SentNotification) -> bool) -> Vec<(String, String, u8, Option<String>)> {
+        let mut summaries = Vec::new();
+        sent.retain(|key, notification| {
+            if !done(key, notification) {
+                return true;
+            }
+            if notification.repeats > 0 {
+                summaries.push(notification.summary());
+            }
+            false
+        });
+        summaries
+    }
+
+    fn deliver_summaries(&self, summaries: Vec<(String, String, u8, Option<String>)>) {
+        for (title, message, urgency, monitor) in summaries {
+            if let Err(e) = self.deliver(&title, &message, urgency, monitor.as_deref()) {
+                error!("Error sending notification: {}", e);
+            }
+        }
+    }
+
+    // Send to the webhook, channels, email and desktop, without deduplication
+    fn deliver(&self, title: &str, message: &str, urgency: u8, monitor: Option<&str>) -> Result<(), String> {
+        let severity = self.thresholds.classify(urgency);
+
+        let body = match &self.run_id {
+            Some(run_id) => format!("{}\nRun ID: {}", message, run_id),
+            None => message.to_string(),
+        };
+
+        self.queue_network_delivery(title, message, &body, urgency, severity, monitor);
+
         // Determine notification timeout based on urgency
-        let timeout = match urgency {
-            0..=30 => Timeout::Milliseconds(3000),  // Low urgency
-            31..=70 => Timeout::Milliseconds(5000), // Medium urgency
-            _ => Timeout::Milliseconds(10000),      // High urgency
+        let timeout = match severity {
+            Severity::Low => Timeout::Milliseconds(3000),
+            Severity::Medium => Timeout::Milliseconds(5000),
+            Severity::High => Timeout::Milliseconds(7500),
+            Severity::Critical => Timeout::Milliseconds(10000),
         };
 
+        let mut notification = Notification::new();
+        notification.summary(title)
+            .body(&body)
+            .timeout(timeout);
+
+        // Only the freedesktop backend has urgency levels; elsewhere the timeout and sound
+        // are all that set critical alerts apart
+        #[cfg(all(unix, not(target_os = "macos")))]
+        notification.urgency(match severity {
+            Severity::Low => Urgency::Low,
+            Severity::Medium | Severity::High => Urgency::Normal,
+            Severity::Critical => Urgency::Critical,
+        });
+
+        // Notification servers may ignore the sound, so also ring the terminal bell. It goes to
+        // stderr, which is unbuffered and stays out of --format json output
+        if self.sound && severity == Severity::Critical {
+            notification.sound_name(CRITICAL_SEVERITY_SOUND);
+            eprint!("\x07");
+        }
+
         // Send notification
-        match Notification::new()
-            .summary(title)
-            .body(message)
-            .timeout(timeout)
-            .show() {
-                Ok(_) => Ok(()),
-                Err(e) => Err(format!("Failed to send notification: {}", e)),
+        match notification.show() {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("Failed to send notification: {}", e)),
+        }
+    }
+
+    // Hand the webhook, channel and email sends to the delivery thread, or make them here when
+    // there's no queue. Their failures are logged, never returned, so they can't hide the desktop
+    // notification's outcome. Only alerts (high severity and up) leave the machine; lower
+    // scores, like the doctor's self-test, stay on the desktop
+    fn queue_network_delivery(&self, title: &str, message: &str, body: &str, urgency: u8, severity: Severity, monitor: Option<&str>) {
+        if !self.thresholds.is_alert(urgency) {
+            return;
+        }
+
+        // The webhook gets JSON, retried with exponential backoff. Chat formats carry the run ID
+        // in the message text, the generic one as a field of its own
+        let webhook = self.webhook_url.clone()
+            .map(|url| (url, match self.webhook_format {
+                WebhookFormat::Generic => json!({
+                    "title": title,
+                    "message": message,
+                    "score": urgency,
+                    "timestamp": chrono::Utc::now().to_rfc3339(),
+                    "hostname": channels::hostname(),
+                    "run_id": self.run_id,
+                }),
+                WebhookFormat::Slack => channels::slack_payload(title, body, monitor, urgency, severity),
+                WebhookFormat::Discord => channels::discord_payload(title, body, monitor, urgency, severity),
+                WebhookFormat::Teams => channels::teams_payload(title, body, monitor, urgency, severity),
+            }));
+        if webhook.is_none() && self.channels.is_empty() && self.smtp.is_none() {
+            return;
+        }
+
+        let (timeout, retries) = (self.webhook_timeout, self.webhook_retries);
+        let channels = self.channels.clone();
+        let smtp = self.smtp.clone();
+        let monitor = monitor.map(str::to_string);
+        let (title, body) = (title.to_string(), body.to_string());
+        let delivery: Delivery = Box::new(move || {
+            if let Some((url, payload)) = webhook {
+                if let Err(e) = channels::post_json(&url, &payload, timeout, retries) {
+                    error!("Error sending webhook notification: {}", e);
+                }
+            }
+
+            for channel in &channels {
+                if let Err(e) = channel.send(&title, &body, monitor.as_deref(), urgency, severity) {
+                    error!("Error sending {} notification: {}", channel.name(), e);
+                }
+            }
+
+            if let Some(smtp) = &smtp {
+                if let Err(e) = smtp.alert(&title, &body, monitor.as_deref(), urgency) {
+                    error!("Error sending alert email: {}", e);
+                }
             }
+        });
+        match &self.deliveries {
+            Some(deliveries) => deliveries.push(delivery),
+            None => delivery(),
+        }
     }
 }
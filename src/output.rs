@@ -0,0 +1,93 @@
+// Output format of the CLI. In JSON mode every command prints one result object to stdout for
+// scripts and dashboards (e.g. piped into jq), and the human-readable text goes to stderr
+use serde_json::{json, Value};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+// --log-file, where diagnostic logging goes instead of stderr
+static LOG: Mutex<Option<File>> = Mutex::new(None);
+
+// Whether the text output goes to the log file too, for the daemon, which has no terminal
+static TEXT_TO_LOG: AtomicBool = AtomicBool::new(false);
+
+// Timestamp of each line in the log file
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub fn set_format(format: OutputFormat) {
+    JSON.store(format == OutputFormat::Json, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+// Open the log file. Calling it again reopens the file, after it was rotated
+pub fn set_log_file(path: &Path) -> Result<(), String> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open log file {}: {}", path.display(), e))?;
+    *LOG.lock().unwrap() = Some(file);
+    Ok(())
+}
+
+pub fn has_log_file() -> bool {
+    LOG.lock().unwrap().is_some()
+}
+
+// Send the text output to the log file as well, each line timestamped
+pub fn send_text_to_log() {
+    TEXT_TO_LOG.store(true, Ordering::Relaxed);
+}
+
+// Diagnostic logging output: to the log file when there is one, otherwise stderr
+pub fn write_log(buf: &[u8]) -> io::Result<usize> {
+    match LOG.lock().unwrap().as_mut() {
+        Some(log) => log.write(buf),
+        None => io::stderr().write(buf),
+    }
+}
+
+// One message of text output (the say! macro): to stdout, or stderr in JSON mode so stdout only
+// carries the result, or the log file after send_text_to_log
+pub fn say(message: fmt::Arguments) {
+    if let Some(log) = LOG.lock().unwrap().as_mut().filter(|_| TEXT_TO_LOG.load(Ordering::Relaxed)) {
+        let timestamp = chrono::Local::now().format(TIMESTAMP_FORMAT);
+        for line in message.to_string().lines().filter(|line| !line.trim().is_empty()) {
+            let _ = writeln!(log, "{} {}", timestamp, line);
+        }
+        return;
+    }
+
+    if is_json() {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+// Print a command's result; only in JSON mode, the text output already covers it otherwise
+pub fn emit(result: &Value) {
+    if is_json() {
+        println!("{}", result);
+    }
+}
+
+// Result of a command that failed before producing anything, so scripts still get an object
+pub fn emit_error(monitor: &str, error: &str) {
+    emit(&json!({ "monitor": monitor, "error": error }));
+}
@@ -0,0 +1,105 @@
+// PhishTank URL lookups (https://phishtank.org), with a per-run cache so each unique
+// URL is only queried once
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const CHECK_URL_ENDPOINT: &str = "https://checkurl.phishtank.com/checkurl/";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Score for a URL PhishTank has verified as a phish, and for anything else it returned
+pub const PHISH_SCORE: u8 = 100;
+pub const CLEAN_SCORE: u8 = 0;
+
+// What PhishTank said about one URL
+#[derive(Debug, Clone)]
+pub struct PhishTankVerdict {
+    pub score: u8,
+    // Raw "results" object from the API for a database match, kept for display
+    pub raw_match: Option<String>,
+}
+
+pub struct PhishTankClient {
+    api_key: String,
+    http: reqwest::blocking::Client,
+    cache: Mutex<HashMap<String, u8>>,
+    matches: Mutex<HashMap<String, String>>,
+}
+
+impl PhishTankClient {
+    pub fn new(api_key: String) -> Result<Self, String> {
+        let http = reqwest::blocking::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            // PhishTank asks clients to identify themselves in the user agent
+            .user_agent(format!("phishtank/{}", env!("CARGO_PKG_NAME")))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        Ok(PhishTankClient {
+            api_key,
+            http,
+            cache: Mutex::new(HashMap::new()),
+            matches: Mutex::new(HashMap::new()),
+        })
+    }
+
+    // Verdict for a URL, from the cache when it was already looked up this run.
+    // Failed lookups are not cached, so they are retried on the next scan
+    pub fn check_url(&self, url: &str) -> Result<PhishTankVerdict, String> {
+        if let Some(&score) = self.cache.lock().unwrap().get(url) {
+            return Ok(PhishTankVerdict {
+                score,
+                raw_match: self.matches.lock().unwrap().get(url).cloned(),
+            });
+        }
+
+        let verdict = self.query(url)?;
+
+        self.cache.lock().unwrap().insert(url.to_string(), verdict.score);
+        if let Some(raw_match) = &verdict.raw_match {
+            self.matches.lock().unwrap().insert(url.to_string(), raw_match.clone());
+        }
+
+        Ok(verdict)
+    }
+
+    // Raw API match for a URL that was found in the PhishTank database
+    pub fn raw_match(&self, url: &str) -> Option<String> {
+        self.matches.lock().unwrap().get(url).cloned()
+    }
+
+    fn query(&self, url: &str) -> Result<PhishTankVerdict, String> {
+        let response = self.http
+            .post(CHECK_URL_ENDPOINT)
+            .form(&[("url", url), ("format", "json"), ("app_key", self.api_key.as_str())])
+            .send()
+            .map_err(|e| format!("PhishTank request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("PhishTank returned HTTP {}", response.status()));
+        }
+
+        let body: Value = response.json()
+            .map_err(|e| format!("Invalid PhishTank response: {}", e))?;
+        let results = body.get("results")
+            .ok_or_else(|| "PhishTank response has no results".to_string())?;
+
+        let in_database = results["in_database"].as_bool().unwrap_or(false);
+        let verified = results["verified"].as_bool().unwrap_or(false);
+        let valid = results["valid"].as_bool().unwrap_or(false);
+
+        // Only a verified, still-valid entry is a confirmed phish; unknown URLs and
+        // entries that were rejected or taken down count as clean
+        let score = if in_database && verified && valid {
+            PHISH_SCORE
+        } else {
+            CLEAN_SCORE
+        };
+
+        Ok(PhishTankVerdict {
+            score,
+            raw_match: in_database.then(|| results.to_string()),
+        })
+    }
+}
@@ -0,0 +1,250 @@
+// Session report written from the GUI: scores over time, findings and the threat under
+// analysis in one self-contained HTML file, with the charts embedded as SVG so it can be
+// mailed or archived as is. There is no PDF writer; the page has print styles, so a
+// browser's "Save as PDF" gives a clean copy
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::email_monitor::ScanResult;
+use crate::scoring::{Severity, SeverityThresholds};
+
+// Suggested file name in the save dialog
+pub const DEFAULT_REPORT_FILE: &str = "threatsentry-report.html";
+
+const CHART_WIDTH: f64 = 720.0;
+const CHART_HEIGHT: f64 = 220.0;
+const CHART_MARGIN: f64 = 45.0;
+
+// Line colors for successive series in a chart
+const SERIES_COLORS: [&str; 6] = ["#1f77b4", "#d62728", "#2ca02c", "#9467bd", "#ff7f0e", "#17becf"];
+
+pub struct ChartSeries {
+    pub name: String,
+    pub points: Vec<(f64, f64)>, // (x, y), oldest first
+}
+
+pub struct SessionReport {
+    pub run_id: String,
+    pub duration: Duration,
+    pub thresholds: SeverityThresholds,
+    pub scores: Vec<(String, u8)>, // Current score of each monitor, then the combined score
+    pub score_history: Vec<ChartSeries>, // x: seconds relative to now (negative)
+    pub temperature: Vec<(f64, f64)>, // (seconds relative to now, °C)
+    pub mic_power: Vec<(f64, f64)>, // (seconds relative to now, power)
+    pub urls: Vec<ScanResult>,
+    pub processes: Vec<(String, bool)>, // (summary, memory-resident)
+    pub connections: Vec<String>,
+    pub usb_devices: Vec<String>,
+    pub selected_threat: Option<(String, String)>, // (title, details) of the threat under analysis
+}
+
+impl SessionReport {
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_html())
+            .map_err(|e| format!("Failed to write report {}: {}", path.display(), e))
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        html.push_str(&format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>ThreatSentry report {}</title>\n<style>{}</style>\n</head>\n<body>\n",
+            escape(&self.run_id),
+            STYLE,
+        ));
+
+        html.push_str("<h1>ThreatSentry Ultra Session Report</h1>\n");
+        html.push_str(&format!(
+            "<p class=\"meta\">Generated {} &middot; Run {} &middot; Session length {}</p>\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S %Z"),
+            escape(&self.run_id),
+            format_duration(self.duration),
+        ));
+
+        // Current scores
+        html.push_str("<h2>Threat Scores</h2>\n<table>\n<tr><th>Monitor</th><th>Score</th><th>Severity</th></tr>\n");
+        for (monitor, score) in &self.scores {
+            let severity = self.thresholds.classify(*score);
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td><span class=\"badge\" style=\"background:{}\">{}</span></td></tr>\n",
+                escape(monitor),
+                score,
+                severity_color(severity),
+                severity.name(),
+            ));
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Scores Over Time</h2>\n");
+        html.push_str(&line_chart(&self.score_history, "Seconds ago", Some((0.0, 100.0))));
+
+        html.push_str("<h2>Temperature History</h2>\n");
+        html.push_str(&line_chart(
+            &[ChartSeries { name: "Temperature (°C)".to_string(), points: self.temperature.clone() }],
+            "Seconds ago",
+            None,
+        ));
+
+        html.push_str("<h2>Microphone Activity</h2>\n");
+        html.push_str(&line_chart(
+            &[ChartSeries { name: "Microphone Power".to_string(), points: self.mic_power.clone() }],
+            "Seconds ago",
+            None,
+        ));
+
+        // Findings
+        html.push_str("<h2>Detected URLs</h2>\n");
+        if self.urls.is_empty() {
+            html.push_str("<p class=\"none\">No URLs detected</p>\n");
+        } else {
+            html.push_str("<table>\n<tr><th>Score</th><th>URL</th><th>Context</th></tr>\n");
+            for result in &self.urls {
+                html.push_str(&format!(
+                    "<tr><td><span class=\"badge\" style=\"background:{}\">{}</span></td><td class=\"url\">{}</td><td>{}</td></tr>\n",
+                    severity_color(self.thresholds.classify(result.score)),
+                    result.score,
+                    escape(&result.url),
+                    escape(&result.snippet),
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Suspicious Processes</h2>\n");
+        let processes: Vec<String> = self.processes.iter()
+            .map(|(process, memory_resident)| if *memory_resident {
+                format!("{} <strong>[memory-resident]</strong>", escape(process))
+            } else {
+                escape(process)
+            })
+            .collect();
+        html.push_str(&list(&processes, "No suspicious processes detected"));
+
+        html.push_str("<h2>Suspicious Connections</h2>\n");
+        html.push_str(&list(&self.connections.iter().map(|c| escape(c)).collect::<Vec<_>>(), "No suspicious connections detected"));
+
+        html.push_str("<h2>USB Devices Inserted</h2>\n");
+        html.push_str(&list(&self.usb_devices.iter().map(|d| escape(d)).collect::<Vec<_>>(), "No USB devices inserted"));
+
+        if let Some((title, details)) = &self.selected_threat {
+            html.push_str(&format!("<h2>Threat Analysis: {}</h2>\n<pre>{}</pre>\n", escape(title), escape(details)));
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em auto;max-width:60em;color:#222}\
+h1{margin-bottom:0}h2{border-bottom:1px solid #ccc;padding-bottom:.2em;margin-top:1.6em}\
+.meta{color:#666}.none{color:#666;font-style:italic}\
+table{border-collapse:collapse;width:100%}th,td{border:1px solid #ddd;padding:.3em .5em;text-align:left;vertical-align:top}\
+th{background:#f3f3f3}.url{word-break:break-all}\
+.badge{color:#fff;padding:.1em .5em;border-radius:.3em;font-weight:bold}\
+pre{background:#f6f6f6;padding:1em;white-space:pre-wrap}\
+svg{max-width:100%;height:auto}\
+@media print{body{margin:0;max-width:none}h2{break-after:avoid}table,svg{break-inside:avoid}}";
+
+// Badge color of a band, darker than the GUI's so white text stays readable
+fn severity_color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Low => "#2e7d32",
+        Severity::Medium => "#b8860b",
+        Severity::High => "#e65100",
+        Severity::Critical => "#c62828",
+    }
+}
+
+fn list(items: &[String], empty: &str) -> String {
+    if items.is_empty() {
+        return format!("<p class=\"none\">{}</p>\n", empty);
+    }
+    let mut html = String::from("<ul>\n");
+    for item in items {
+        html.push_str(&format!("<li>{}</li>\n", item));
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+// Inline SVG line chart of one or more series over a shared x axis. The y axis spans
+// `y_range` when given, otherwise the data
+fn line_chart(series: &[ChartSeries], x_label: &str, y_range: Option<(f64, f64)>) -> String {
+    let points = || series.iter().flat_map(|s| s.points.iter());
+    if points().next().is_none() {
+        return "<p class=\"none\">No data collected</p>\n".to_string();
+    }
+
+    let (mut x_min, mut x_max) = points().fold((f64::MAX, f64::MIN), |(lo, hi), (x, _)| (lo.min(*x), hi.max(*x)));
+    let (mut y_min, mut y_max) = y_range.unwrap_or_else(|| points().fold((f64::MAX, f64::MIN), |(lo, hi), (_, y)| (lo.min(*y), hi.max(*y))));
+    // A flat range is padded so the scale below doesn't divide by zero
+    if x_max <= x_min {
+        x_min -= 1.0;
+        x_max += 1.0;
+    }
+    if y_max <= y_min {
+        y_min -= 1.0;
+        y_max += 1.0;
+    }
+
+    let (left, top) = (CHART_MARGIN, CHART_MARGIN / 2.0);
+    let (width, height) = (CHART_WIDTH - 1.5 * CHART_MARGIN, CHART_HEIGHT - 1.5 * CHART_MARGIN);
+    let to_svg = |x: f64, y: f64| (left + (x - x_min) / (x_max - x_min) * width, top + (y_max - y) / (y_max - y_min) * height);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\" width=\"{}\" height=\"{}\" font-size=\"11\">\n",
+        CHART_WIDTH, CHART_HEIGHT, CHART_WIDTH, CHART_HEIGHT,
+    );
+    svg.push_str(&format!("<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"none\" stroke=\"#999\"/>\n", left, top, width, height));
+
+    // Horizontal grid with y labels, and the x range at the ends of the axis
+    for step in 0..=4 {
+        let value = y_min + (y_max - y_min) * step as f64 / 4.0;
+        let (_, y) = to_svg(x_min, value);
+        svg.push_str(&format!("<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#e5e5e5\"/>\n", left, y, left + width, y));
+        svg.push_str(&format!("<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>\n", left - 4.0, y, format_tick(value)));
+    }
+    let axis_y = top + height + 14.0;
+    svg.push_str(&format!("<text x=\"{:.1}\" y=\"{:.1}\">{}</text>\n", left, axis_y, format_tick(x_min)));
+    svg.push_str(&format!("<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>\n", left + width, axis_y, format_tick(x_max)));
+    svg.push_str(&format!("<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" fill=\"#666\">{}</text>\n", left + width / 2.0, axis_y, escape(x_label)));
+
+    for (i, series) in series.iter().enumerate() {
+        let color = SERIES_COLORS[i % SERIES_COLORS.len()];
+        let coordinates: Vec<String> = series.points.iter()
+            .map(|(x, y)| {
+                let (x, y) = to_svg(*x, *y);
+                format!("{:.1},{:.1}", x, y)
+            })
+            .collect();
+        svg.push_str(&format!("<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"/>\n", color, coordinates.join(" ")));
+
+        // Legend along the top edge
+        let legend_x = left + i as f64 * 120.0;
+        svg.push_str(&format!("<rect x=\"{:.1}\" y=\"4\" width=\"10\" height=\"10\" fill=\"{}\"/>\n", legend_x, color));
+        svg.push_str(&format!("<text x=\"{:.1}\" y=\"13\">{}</text>\n", legend_x + 14.0, escape(&series.name)));
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn format_tick(value: f64) -> String {
+    if value.fract().abs() < 0.05 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}h {:02}m {:02}s", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,343 @@
+// Shared helpers for turning per-monitor scores into the combined threat score
+
+use colored::Color;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Clamp a single monitor's contribution before it is combined with the others,
+// so one flaky sensor can't pin the combined score on its own
+pub fn cap_contribution(score: u8, cap: Option<u8>) -> u8 {
+    match cap {
+        Some(cap) => score.min(cap),
+        None => score,
+    }
+}
+
+// How much each monitor counts toward the combined score, relative to the others
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonitorWeights {
+    pub mic: f32,
+    pub thermal: f32,
+    pub kernel: f32,
+    pub email: f32,
+}
+
+impl Default for MonitorWeights {
+    fn default() -> Self {
+        MonitorWeights {
+            mic: 1.0,
+            thermal: 1.0,
+            kernel: 1.0,
+            email: 1.0,
+        }
+    }
+}
+
+impl MonitorWeights {
+    // Weight of a monitor by its name; monitors without a setting of their own weigh 1
+    pub fn for_monitor(&self, name: &str) -> f32 {
+        match name {
+            "mic" => self.mic,
+            "thermal" => self.thermal,
+            "kernel" => self.kernel,
+            "email" => self.email,
+            _ => 1.0,
+        }
+    }
+}
+
+// Combined threat score: the weighted mean of the (already capped) scores of the monitors that
+// actually ran, as (score, weight) pairs. Dividing by the total weight keeps it 0-100 whatever
+// the weights are. A monitor that didn't run is left out rather than counted as 0, which would
+// dilute a real threat from the others
+pub fn combined_score(scores: &[(u8, f32)]) -> u8 {
+    let total_weight: f32 = scores.iter().map(|&(_, weight)| weight).sum();
+    if total_weight <= 0.0 {
+        return 0;
+    }
+
+    let weighted: f32 = scores.iter().map(|&(score, weight)| score as f32 * weight).sum();
+    (weighted / total_weight).round().min(100.0) as u8
+}
+
+// Severity band a score falls into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    // Band of a score under the default thresholds, for output that shouldn't depend on
+    // the user's --low-max/--medium-max/--high-max (such as exported indicators)
+    pub fn from_score(score: u8) -> Self {
+        SeverityThresholds::default().classify(score)
+    }
+
+    // Terminal color of the band
+    pub fn color(&self) -> Color {
+        match self {
+            Severity::Low => Color::Green,
+            Severity::Medium => Color::Yellow,
+            Severity::High => Color::Red,
+            Severity::Critical => Color::BrightRed,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Low => "Low",
+            Severity::Medium => "Medium",
+            Severity::High => "High",
+            Severity::Critical => "Critical",
+        }
+    }
+    // Process exit status of a command whose highest score fell in this band, so scripts
+    // can gate on it without parsing the output
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Severity::Low => 0,
+            Severity::Medium => 1,
+            Severity::High | Severity::Critical => 2,
+        }
+    }
+}
+
+// Process exit status of a command that couldn't run or finish: bad arguments or config, or a
+// monitor that failed before producing a score. Kept apart from the band codes so a failed scan
+// never reads as a clean one
+pub const ERROR_EXIT_CODE: u8 = 3;
+
+// Upper bounds (inclusive) of the low, medium and high bands; everything above high_max
+// is critical. The one place score boundaries are defined: notifications, alerts, CLI
+// colors and GUI colors all classify through it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeverityThresholds {
+    pub low_max: u8,
+    pub medium_max: u8,
+    pub high_max: u8,
+}
+
+impl Default for SeverityThresholds {
+    fn default() -> Self {
+        SeverityThresholds {
+            low_max: 30,
+            medium_max: 50,
+            high_max: 70,
+        }
+    }
+}
+
+impl SeverityThresholds {
+    pub fn new(low_max: u8, medium_max: u8, high_max: u8) -> Result<Self, String> {
+        if low_max >= medium_max {
+            return Err(format!(
+                "Low threshold ({}) must be below the medium threshold ({})",
+                low_max, medium_max
+            ));
+        }
+        if medium_max >= high_max {
+            return Err(format!(
+                "Medium threshold ({}) must be below the high threshold ({})",
+                medium_max, high_max
+            ));
+        }
+        if high_max >= 100 {
+            return Err(format!("High threshold ({}) must be below 100", high_max));
+        }
+
+        Ok(SeverityThresholds { low_max, medium_max, high_max })
+    }
+
+    pub fn classify(&self, score: u8) -> Severity {
+        if score <= self.low_max {
+            Severity::Low
+        } else if score <= self.medium_max {
+            Severity::Medium
+        } else if score <= self.high_max {
+            Severity::High
+        } else {
+            Severity::Critical
+        }
+    }
+
+    // Whether a score is high enough to notify about: high severity and up
+    pub fn is_alert(&self, score: u8) -> bool {
+        self.classify(score) >= Severity::High
+    }
+}
+
+// Severity classification with hysteresis: a score has to cross a band boundary by
+// `margin` before the band changes, so a score hovering around 70 doesn't flap
+// between high and critical every frame
+#[derive(Debug, Clone)]
+pub struct SeverityClassifier {
+    thresholds: SeverityThresholds,
+    margin: u8,
+    current: Option<Severity>,
+}
+
+impl SeverityClassifier {
+    pub fn new(thresholds: SeverityThresholds, margin: u8) -> Self {
+        SeverityClassifier {
+            thresholds,
+            margin,
+            current: None,
+        }
+    }
+
+    pub fn classify(&mut self, score: u8) -> Severity {
+        let raw = self.thresholds.classify(score);
+        let current = match self.current {
+            Some(current) => current,
+            None => {
+                self.current = Some(raw);
+                return raw;
+            }
+        };
+
+        // Band the score would be in with the boundaries pushed up or down by the margin
+        let raised = SeverityThresholds {
+            low_max: self.thresholds.low_max.saturating_add(self.margin),
+            medium_max: self.thresholds.medium_max.saturating_add(self.margin),
+            high_max: self.thresholds.high_max.saturating_add(self.margin),
+        }.classify(score);
+        let lowered = SeverityThresholds {
+            low_max: self.thresholds.low_max.saturating_sub(self.margin),
+            medium_max: self.thresholds.medium_max.saturating_sub(self.margin),
+            high_max: self.thresholds.high_max.saturating_sub(self.margin),
+        }.classify(score);
+
+        let next = if raised > current {
+            raised
+        } else if lowered < current {
+            lowered
+        } else {
+            current
+        };
+
+        self.current = Some(next);
+        next
+    }
+}
+
+// Default number of combined scores kept by ScoreHistory
+const SCORE_HISTORY_CAPACITY: usize = 3600;
+
+// Bounded history of combined scores, owned by whatever computes the aggregate so every
+// consumer (GUI, exporters, status endpoints) reads the same series
+pub struct ScoreHistory {
+    samples: VecDeque<(Instant, u8)>,
+    capacity: usize,
+}
+
+impl ScoreHistory {
+    pub fn new() -> Self {
+        Self::with_capacity(SCORE_HISTORY_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        ScoreHistory {
+            samples: VecDeque::with_capacity(capacity.min(SCORE_HISTORY_CAPACITY)),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn record(&mut self, score: u8) {
+        self.samples.push_back((Instant::now(), score));
+        while self.samples.len() > self.capacity {
+            self.samples.pop_front();
+        }
+    }
+
+    // The most recent score; None before the first one
+    pub fn latest(&self) -> Option<u8> {
+        self.samples.back().map(|&(_, score)| score)
+    }
+
+    // Nearest-rank percentile (1-100) of the scores from the last `window`; None when there are none
+    pub fn percentile(&self, window: Duration, percentile: u8) -> Option<u8> {
+        let mut scores: Vec<u8> = self.recent_scores(window).into_iter().map(|(_, score)| score).collect();
+        if scores.is_empty() {
+            return None;
+        }
+
+        scores.sort_unstable();
+        let rank = (percentile.clamp(1, 100) as usize * scores.len()).div_ceil(100);
+        Some(scores[rank.max(1) - 1])
+    }
+
+    // Scores from the last `window`, oldest first, as (seconds ago, score)
+    pub fn recent_scores(&self, window: Duration) -> Vec<(f64, u8)> {
+        let now = Instant::now();
+        self.samples.iter()
+            .map(|(time, score)| (now.duration_since(*time), *score))
+            .filter(|(age, _)| *age <= window)
+            .map(|(age, score)| (age.as_secs_f64(), score))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asymmetric_weights_blend_toward_the_heavier_monitor() {
+        let weights = MonitorWeights { mic: 1.0, thermal: 0.5, kernel: 3.0, email: 1.0 };
+        let scores = [
+            (20, weights.for_monitor("mic")),
+            (80, weights.for_monitor("thermal")),
+            (90, weights.for_monitor("kernel")),
+        ];
+
+        // (20 * 1 + 80 * 0.5 + 90 * 3) / 4.5 = 73.3
+        assert_eq!(combined_score(&scores), 73);
+        assert_eq!(combined_score(&[(20, 1.0), (80, 1.0), (90, 1.0)]), 63);
+    }
+
+    #[test]
+    fn zero_weights_and_missing_monitors_stay_in_range() {
+        assert_eq!(combined_score(&[]), 0);
+        assert_eq!(combined_score(&[(90, 0.0), (40, 0.0)]), 0);
+        assert_eq!(combined_score(&[(100, 0.0), (100, 2.0)]), 100);
+    }
+
+    #[test]
+    fn percentile_ignores_a_single_spike() {
+        let mut history = ScoreHistory::new();
+        for score in [20, 22, 19, 95, 21, 20, 23, 18, 20, 22] {
+            history.record(score);
+        }
+
+        let window = Duration::from_secs(60);
+        assert_eq!(history.percentile(window, 90), Some(23));
+        assert_eq!(history.percentile(window, 100), Some(95));
+        assert_eq!(history.percentile(window, 50), Some(20));
+    }
+
+    #[test]
+    fn percentile_follows_sustained_elevation() {
+        let mut history = ScoreHistory::new();
+        let window = Duration::from_secs(60);
+        assert_eq!(history.percentile(window, 90), None);
+
+        for score in [20, 80, 82, 85, 81, 84, 83, 80, 86, 82] {
+            history.record(score);
+        }
+        assert!(history.percentile(window, 90).unwrap() >= 80);
+    }
+
+    #[test]
+    fn old_scores_fall_out_of_the_percentile_window() {
+        let mut history = ScoreHistory::new();
+        history.record(90);
+        std::thread::sleep(Duration::from_millis(80));
+        history.record(10);
+
+        assert_eq!(history.percentile(Duration::from_millis(50), 100), Some(10));
+        assert_eq!(history.latest(), Some(10));
+    }
+}
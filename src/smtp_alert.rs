@@ -0,0 +1,157 @@
+// Email alerts for unattended machines, where nobody sees a desktop notification. Alerts that
+// arrive within the coalescing window of the last email are held back and sent together as
+// one digest, so a flapping detection can't flood the inbox
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::error;
+
+use crate::channels;
+use crate::email_monitor::ConnectionSecurity;
+
+// Alerts scored above this are emailed
+pub const DEFAULT_MIN_SCORE: u8 = 70;
+
+// Alerts within this long of the previous email are coalesced into the next one
+pub const DEFAULT_COALESCE_WINDOW_SECS: u64 = 300;
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct Outbox {
+    last_sent: Option<Instant>,
+    pending: Vec<(String, String)>, // Summary and full text of alerts not emailed yet
+}
+
+// Cloning shares the outbox, so every notifier of a run coalesces into the same emails
+#[derive(Clone)]
+pub struct SmtpAlerter {
+    transport: SmtpTransport,
+    from: Mailbox,
+    to: Mailbox,
+    min_score: u8,
+    coalesce_window: Duration,
+    outbox: Arc<Mutex<Outbox>>,
+}
+
+impl SmtpAlerter {
+    pub fn new(
+        server: &str,
+        port: u16,
+        security: ConnectionSecurity,
+        credentials: Option<(String, String)>,
+        from: &str,
+        to: &str,
+    ) -> Result<Self, String> {
+        let from: Mailbox = from.parse().map_err(|e| format!("Invalid sender address '{}': {}", from, e))?;
+        let to: Mailbox = to.parse().map_err(|e| format!("Invalid alert address '{}': {}", to, e))?;
+
+        let builder = match security {
+            ConnectionSecurity::ImplicitTls => SmtpTransport::relay(server),
+            ConnectionSecurity::StartTls => SmtpTransport::starttls_relay(server),
+            ConnectionSecurity::Plaintext => Ok(SmtpTransport::builder_dangerous(server)),
+        }.map_err(|e| format!("Failed to set up SMTP connection to {}: {}", server, e))?;
+
+        let mut builder = builder.port(port).timeout(Some(SMTP_TIMEOUT));
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(SmtpAlerter {
+            transport: builder.build(),
+            from,
+            to,
+            min_score: DEFAULT_MIN_SCORE,
+            coalesce_window: Duration::from_secs(DEFAULT_COALESCE_WINDOW_SECS),
+            outbox: Arc::new(Mutex::new(Outbox {
+                last_sent: None,
+                pending: Vec::new(),
+            })),
+        })
+    }
+
+    // Only alerts scored above this are emailed
+    pub fn with_min_score(mut self, min_score: u8) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    pub fn with_coalesce_window(mut self, window: Duration) -> Self {
+        self.coalesce_window = window;
+        self
+    }
+
+    // Queue an alert, and email everything queued unless an email went out within the window
+    pub fn alert(&self, title: &str, message: &str, monitor: Option<&str>, score: u8) -> Result<(), String> {
+        if score <= self.min_score {
+            return Ok(());
+        }
+
+        let monitor = monitor.unwrap_or("unknown");
+        let summary = format!("{} alert (score {}) on {}", monitor, score, channels::hostname());
+        let entry = format!(
+            "{}\nMonitor: {}\nScore: {}/100\nHost: {}\nTime: {}\n\n{}",
+            title,
+            monitor,
+            score,
+            channels::hostname(),
+            chrono::Local::now().to_rfc3339(),
+            message,
+        );
+
+        let mut outbox = self.outbox.lock().unwrap();
+        outbox.pending.push((summary, entry));
+        if matches!(outbox.last_sent, Some(sent) if sent.elapsed() < self.coalesce_window) {
+            return Ok(());
+        }
+
+        let batch: Vec<(String, String)> = outbox.pending.drain(..).collect();
+        outbox.last_sent = Some(Instant::now());
+        drop(outbox);
+
+        self.send(&batch)
+    }
+
+    // Email whatever is still queued, e.g. at the end of a run
+    pub fn flush(&self) -> Result<(), String> {
+        let batch: Vec<(String, String)> = self.outbox.lock().unwrap().pending.drain(..).collect();
+        if batch.is_empty() {
+            return Ok(());
+        }
+        self.send(&batch)
+    }
+
+    fn send(&self, batch: &[(String, String)]) -> Result<(), String> {
+        let subject = match batch {
+            [(summary, _)] => format!("[ThreatSentry] {}", summary),
+            _ => format!("[ThreatSentry] {} alerts on {}", batch.len(), channels::hostname()),
+        };
+        let body = batch.iter()
+            .map(|(_, entry)| entry.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n----------------------------------------\n\n");
+
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(self.to.clone())
+            .subject(subject)
+            .body(body)
+            .map_err(|e| format!("Failed to build alert email: {}", e))?;
+
+        self.transport.send(&email)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to send alert email to {}: {}", self.to, e))
+    }
+}
+
+impl Drop for SmtpAlerter {
+    // The last copy sends the alerts still held back, so a run never ends with unsent alerts
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.outbox) == 1 {
+            if let Err(e) = self.flush() {
+                error!("Error sending alert email: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+// Export of the raw time-frequency data: every FFT frame's magnitude spectrum as one CSV row,
+// with a header row of bin center frequencies, for offline analysis in other tools
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+// Rows are flushed to disk at least this often, so little is lost if the scan is interrupted
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct SpectrogramWriter {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    max_duration: Option<Duration>,
+    started: Option<Instant>,
+    last_flush: Instant,
+    sample_rate: Option<u32>,
+    finished: bool,
+}
+
+impl SpectrogramWriter {
+    // Creates (or truncates) the CSV. With max_duration, rows stop after that much time
+    pub fn create(path: &Path, max_duration: Option<Duration>) -> Result<Self, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create spectrogram file {}: {}", path.display(), e))?;
+
+        Ok(SpectrogramWriter {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            max_duration,
+            started: None,
+            last_flush: Instant::now(),
+            sample_rate: None,
+            finished: false,
+        })
+    }
+
+    // Append one frame. magnitudes[i] is the bin centered on i * sample_rate / fft_size Hz
+    pub fn write_frame(&mut self, sample_rate: u32, fft_size: usize, magnitudes: &[f32]) {
+        if self.finished {
+            return;
+        }
+
+        let started = *self.started.get_or_insert_with(Instant::now);
+        if matches!(self.max_duration, Some(max) if started.elapsed() > max) {
+            info!("Spectrogram export reached its time limit; {} is complete", self.path.display());
+            self.finish();
+            return;
+        }
+
+        match self.sample_rate {
+            None => {
+                let header: Vec<String> = (0..magnitudes.len())
+                    .map(|bin| format!("{:.1}", bin as f32 * sample_rate as f32 / fft_size as f32))
+                    .collect();
+                if let Err(e) = writeln!(self.writer, "timestamp,{}", header.join(",")) {
+                    self.fail(e);
+                    return;
+                }
+                self.sample_rate = Some(sample_rate);
+            },
+            // The header's frequencies would no longer match the columns
+            Some(rate) if rate != sample_rate => {
+                warn!("Sample rate changed from {} to {} Hz; stopping spectrogram export to {}",
+                         rate, sample_rate, self.path.display());
+                self.finish();
+                return;
+            },
+            Some(_) => {},
+        }
+
+        let row: Vec<String> = magnitudes.iter().map(|magnitude| format!("{:.5}", magnitude)).collect();
+        if let Err(e) = writeln!(self.writer, "{},{}", chrono::Local::now().to_rfc3339(), row.join(",")) {
+            self.fail(e);
+            return;
+        }
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.last_flush = Instant::now();
+            if let Err(e) = self.writer.flush() {
+                self.fail(e);
+            }
+        }
+    }
+
+    // Flush what's buffered and stop writing
+    pub fn finish(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            error!("Failed to write spectrogram {}: {}", self.path.display(), e);
+        }
+        self.finished = true;
+    }
+
+    fn fail(&mut self, error: std::io::Error) {
+        error!("Failed to write spectrogram {}: {}", self.path.display(), error);
+        self.finished = true;
+    }
+}
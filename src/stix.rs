@@ -0,0 +1,111 @@
+// STIX 2.1 export of flagged findings, so they can be ingested by threat-intel platforms
+use serde_json::{json, Value};
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::scoring::Severity;
+
+// Escape a value for use inside a single-quoted STIX pattern string
+fn escape_pattern_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+// Labelled by the default bands, so exported indicators don't depend on local --*-max settings
+fn indicator_type(score: u8) -> &'static str {
+    if Severity::from_score(score) >= Severity::High {
+        "malicious-activity"
+    } else {
+        "anomalous-activity"
+    }
+}
+
+// A STIX indicator object for one scored URL
+fn url_indicator(url: &str, score: u8, timestamp: &str, run_id: &str) -> Value {
+    json!({
+        "type": "indicator",
+        "spec_version": "2.1",
+        "id": format!("indicator--{}", Uuid::new_v4()),
+        "created": timestamp,
+        "modified": timestamp,
+        "valid_from": timestamp,
+        "name": format!("Suspicious URL: {}", url),
+        "indicator_types": [indicator_type(score)],
+        "labels": ["phishing", "email"],
+        "pattern": format!("[url:value = '{}']", escape_pattern_value(url)),
+        "pattern_type": "stix",
+        "confidence": score,
+        "x_threatsentry_score": score,
+        "x_threatsentry_run_id": run_id,
+    })
+}
+
+// A STIX indicator object for the remote address of a suspicious connection
+fn ip_indicator(ip: IpAddr, score: u8, timestamp: &str, run_id: &str) -> Value {
+    let object_type = match ip {
+        IpAddr::V4(_) => "ipv4-addr",
+        IpAddr::V6(_) => "ipv6-addr",
+    };
+
+    json!({
+        "type": "indicator",
+        "spec_version": "2.1",
+        "id": format!("indicator--{}", Uuid::new_v4()),
+        "created": timestamp,
+        "modified": timestamp,
+        "valid_from": timestamp,
+        "name": format!("Suspicious IP: {}", ip),
+        "indicator_types": [indicator_type(score)],
+        "labels": ["network"],
+        "pattern": format!("[{}:value = '{}']", object_type, ip),
+        "pattern_type": "stix",
+        "confidence": score,
+        "x_threatsentry_score": score,
+        "x_threatsentry_run_id": run_id,
+    })
+}
+
+// A STIX bundle holding an indicator for every scored URL and IP address
+pub fn build_bundle(urls: &[(String, u8)], ips: &[(IpAddr, u8)], run_id: &str) -> Value {
+    let timestamp = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+
+    let objects: Vec<Value> = urls.iter()
+        .map(|(url, score)| url_indicator(url, *score, &timestamp, run_id))
+        .chain(ips.iter().map(|(ip, score)| ip_indicator(*ip, *score, &timestamp, run_id)))
+        .collect();
+
+    json!({
+        "type": "bundle",
+        "id": format!("bundle--{}", Uuid::new_v4()),
+        "objects": objects,
+    })
+}
+
+// Write the bundle for the given URLs and IPs to a file, returning the number of indicators written
+pub fn write_bundle(path: &Path, urls: &[(String, u8)], ips: &[(IpAddr, u8)], run_id: &str) -> Result<usize, String> {
+    let bundle = build_bundle(urls, ips, run_id);
+    let contents = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| format!("Failed to serialize STIX bundle: {}", e))?;
+
+    fs::write(path, contents)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    Ok(urls.len() + ips.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ip_indicators_use_the_pattern_of_their_address_family() {
+        let ips = ["203.0.113.7".parse().unwrap(), "2001:db8::7".parse().unwrap()];
+        let bundle = build_bundle(&[], &[(ips[0], 60), (ips[1], 40)], "run");
+
+        assert_eq!(bundle["objects"][0]["pattern"], "[ipv4-addr:value = '203.0.113.7']");
+        assert_eq!(bundle["objects"][0]["indicator_types"][0], "malicious-activity");
+        assert_eq!(bundle["objects"][1]["pattern"], "[ipv6-addr:value = '2001:db8::7']");
+        assert_eq!(bundle["objects"][1]["indicator_types"][0], "anomalous-activity");
+    }
+}
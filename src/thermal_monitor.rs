@@ -1,29 +1,204 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
+use sysinfo::{Components, System};
+use tracing::{error, warn};
+
+use crate::baseline::ThermalBaseline;
+use crate::monitor::{Finding, FindingKind, Monitor, MonitorSample};
+#[cfg(all(windows, feature = "powershell-cpu"))]
 use std::process::Command;
+#[cfg(all(windows, feature = "powershell-cpu"))]
 use std::str::FromStr;
+#[cfg(windows)]
 use windows::Win32::System::Power::GetSystemPowerStatus;
+#[cfg(windows)]
 use windows::Win32::System::Power::SYSTEM_POWER_STATUS;
+#[cfg(windows)]
+use serde::Deserialize;
+
+// One ACPI thermal zone from WMI; CurrentTemperature is in tenths of a Kelvin
+#[cfg(windows)]
+#[derive(Deserialize)]
+#[serde(rename = "MSAcpi_ThermalZoneTemperature")]
+#[serde(rename_all = "PascalCase")]
+struct AcpiThermalZone {
+    current_temperature: u32,
+}
+
+// Sensor labels that identify a CPU temperature component across platforms
+// (coretemp "Core 0"/"Package id 0", k10temp "Tctl"/"Tdie", macOS "CPU")
+const CPU_SENSOR_LABELS: [&str; 5] = ["cpu", "core", "package", "tctl", "tdie"];
+
+// Number of readings kept for trend detection (about two minutes at one reading per second)
+const TREND_WINDOW: usize = 120;
+
+// Sustained climb (°C per minute) above which the trend starts to count, and the minimum
+// span of readings needed before a slope is trusted
+const SLOPE_THRESHOLD_C_PER_MIN: f32 = 0.5;
+const MIN_TREND_SPAN: Duration = Duration::from_secs(60);
+
+// Default spike rule: a rise of more than 10°C over the coolest reading of the last 10 seconds.
+// Windows longer than the trend history (~2 minutes of readings) are cut to it
+pub const DEFAULT_SPIKE_DELTA_C: f32 = 10.0;
+pub const DEFAULT_SPIKE_WINDOW: Duration = Duration::from_secs(10);
+
+// How far one component may run above the median of the others before it counts as a hotspot,
+// e.g. a miner pinning a few cores or the GPU while the rest of the machine stays cool
+const HOTSPOT_DELTA_C: f32 = 20.0;
+
+// Default time a temperature spike keeps the score elevated
+pub const DEFAULT_SPIKE_COOLDOWN: Duration = Duration::from_secs(30);
+
+// Score while a spike is within its cooldown
+const SPIKE_SCORE: u8 = 80;
+
+const LOG_HEADER: &str = "timestamp,temp_c,cpu_pct,score";
+
+// CSV log of every reading. When it grows past max_bytes it is renamed to "<path>.1",
+// replacing the previous one, and a fresh file is started
+struct ThermalLog {
+    path: PathBuf,
+    file: File,
+    max_bytes: u64,
+}
+
+impl ThermalLog {
+    fn open(path: &Path, max_bytes: u64) -> Result<Self, String> {
+        let file = Self::open_file(path)?;
+        Ok(ThermalLog { path: path.to_path_buf(), file, max_bytes })
+    }
+
+    fn open_file(path: &Path) -> Result<File, String> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open thermal log {}: {}", path.display(), e))?;
+
+        let is_empty = file.metadata().map(|metadata| metadata.len() == 0).unwrap_or(false);
+        if is_empty {
+            writeln!(file, "{}", LOG_HEADER)
+                .map_err(|e| format!("Failed to write thermal log {}: {}", path.display(), e))?;
+        }
+        Ok(file)
+    }
+
+    fn append(&mut self, temp_c: f32, cpu_pct: f32, score: u8) -> Result<(), String> {
+        let size = self.file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        if size >= self.max_bytes {
+            let mut rotated = self.path.clone().into_os_string();
+            rotated.push(".1");
+            fs::rename(&self.path, &rotated)
+                .map_err(|e| format!("Failed to rotate thermal log {}: {}", self.path.display(), e))?;
+            self.file = Self::open_file(&self.path)?;
+        }
+
+        writeln!(self.file, "{},{:.1},{:.1},{}", chrono::Local::now().to_rfc3339(), temp_c, cpu_pct, score)
+            .map_err(|e| format!("Failed to write thermal log {}: {}", self.path.display(), e))
+    }
+}
 
 pub struct ThermalMonitor {
-    last_temp: f32,
-    last_check: Instant,
-    spike_detected: bool,
+    last_spike: Option<Instant>,
+    spike_count: u32, // Separate spikes seen, not counting readings within one spike's cooldown
+    spike_cooldown: Duration,
+    spike_delta_c: f32,
+    spike_window: Duration,
     temperature_history: Vec<f32>,
     cpu_usage_history: Vec<f32>,
+    trend_history: VecDeque<(Instant, f32)>,
+    components: Components,
+    system: Mutex<System>,
+    log: Option<ThermalLog>,
+    baseline: Option<ThermalBaseline>, // Learned normal; replaces the fixed temperature and CPU cutoffs
 }
 
 impl ThermalMonitor {
     pub fn new() -> Self {
         ThermalMonitor {
-            last_temp: 0.0,
-            last_check: Instant::now(),
-            spike_detected: false,
+            last_spike: None,
+            spike_count: 0,
+            spike_cooldown: DEFAULT_SPIKE_COOLDOWN,
+            spike_delta_c: DEFAULT_SPIKE_DELTA_C,
+            spike_window: DEFAULT_SPIKE_WINDOW,
             temperature_history: Vec::with_capacity(10),
             cpu_usage_history: Vec::with_capacity(10),
+            trend_history: VecDeque::with_capacity(TREND_WINDOW),
+            components: Components::new_with_refreshed_list(),
+            system: Mutex::new(System::new()),
+            log: None,
+            baseline: None,
+        }
+    }
+
+    // Tune the spike rule: a desktop with aggressive boost clocks needs a larger delta, a
+    // thermally limited laptop a smaller one or a longer window
+    pub fn with_spike_params(spike_delta_c: f32, spike_window: Duration) -> Result<Self, String> {
+        if spike_window.is_zero() {
+            return Err("Spike window must be greater than zero".to_string());
         }
+
+        let mut monitor = Self::new();
+        monitor.set_spike_delta(spike_delta_c)?;
+        monitor.spike_window = spike_window;
+        Ok(monitor)
+    }
+
+    // Change the spike delta while monitoring; the next reading is judged against it
+    pub fn set_spike_delta(&mut self, spike_delta_c: f32) -> Result<(), String> {
+        if spike_delta_c.is_nan() || spike_delta_c <= 0.0 {
+            return Err(format!("Spike delta must be a positive number of °C, got {}", spike_delta_c));
+        }
+        self.spike_delta_c = spike_delta_c;
+        Ok(())
+    }
+
+    // Append every reading as "timestamp,temp_c,cpu_pct,score" to a CSV, for long-term trends
+    // like the slow creep of a persistent miner. Rotated once it reaches max_bytes
+    pub fn set_log_file(&mut self, path: &Path, max_bytes: u64) -> Result<(), String> {
+        self.log = Some(ThermalLog::open(path, max_bytes)?);
+        Ok(())
+    }
+
+    // Score the average temperature and CPU load by how far they are above this machine's
+    // learned normal rather than by fixed cutoffs
+    pub fn set_baseline(&mut self, baseline: ThermalBaseline) {
+        self.baseline = Some(baseline);
     }
 
-    // Get CPU usage using PowerShell
+    // How long a spike keeps the score at the spike level before it falls back to the
+    // history-based score
+    pub fn set_spike_cooldown(&mut self, cooldown: Duration) {
+        self.spike_cooldown = cooldown;
+    }
+
+    // Check that a real temperature source is readable, without falling back to simulated data
+    pub fn probe_temperature_source(&self) -> Result<String, String> {
+        if let Some((label, temp)) = Self::hottest_cpu_component(&self.components) {
+            return Ok(format!("Sensor \"{}\" reads {:.1}°C", label, temp));
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Ok(temp) = Self::read_sysfs_temperature() {
+            return Ok(format!("Thermal zone reads {:.1}°C", temp));
+        }
+
+        #[cfg(windows)]
+        if let Ok(temp) = Self::read_wmi_temperature() {
+            return Ok(format!("ACPI thermal zone reads {:.1}°C", temp));
+        }
+
+        let cpu_usage = self.get_cpu_usage()?;
+        Ok(format!("CPU load counter readable ({:.1}%), used as temperature proxy", cpu_usage))
+    }
+
+    // Get CPU usage using PowerShell. Spawning it takes about a second, so only used when
+    // built with the powershell-cpu feature
+    #[cfg(all(windows, feature = "powershell-cpu"))]
     fn get_cpu_usage(&self) -> Result<f32, String> {
         let output = Command::new("powershell")
             .args(&["-Command", "(Get-Counter '\\Processor(_Total)\\% Processor Time').CounterSamples.CookedValue"])
@@ -36,9 +211,129 @@ impl ThermalMonitor {
             .map_err(|e| format!("Failed to parse CPU usage: {}", e))
     }
 
-    // Get system temperature using battery and CPU usage as proxies
-    fn get_system_temperature(&self) -> Result<f32, String> {
+    // CPU usage since the previous call (the first call has nothing to compare against and reads 0)
+    #[cfg(not(all(windows, feature = "powershell-cpu")))]
+    fn get_cpu_usage(&self) -> Result<f32, String> {
+        let mut system = self.system.lock().unwrap();
+        system.refresh_cpu_usage();
+        Ok(system.global_cpu_info().cpu_usage())
+    }
+
+    // Hottest kernel thermal zone (/sys/class/thermal/thermal_zone*/temp, in millidegrees), in °C
+    #[cfg(target_os = "linux")]
+    pub fn read_sysfs_temperature() -> Result<f32, String> {
+        let entries = std::fs::read_dir("/sys/class/thermal")
+            .map_err(|e| format!("Failed to read /sys/class/thermal: {}", e))?;
+
+        entries.filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("thermal_zone"))
+            .filter_map(|entry| std::fs::read_to_string(entry.path().join("temp")).ok())
+            .filter_map(|millidegrees| millidegrees.trim().parse::<i64>().ok())
+            .filter(|&millidegrees| millidegrees > 0)
+            .map(|millidegrees| millidegrees as f32 / 1000.0)
+            .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .ok_or_else(|| "No readable thermal zone in /sys/class/thermal".to_string())
+    }
+
+    // Hottest ACPI thermal zone from WMI (root\WMI MSAcpi_ThermalZoneTemperature), in °C.
+    // Querying it usually needs administrator rights, and not every firmware exposes a zone
+    #[cfg(windows)]
+    pub fn read_wmi_temperature() -> Result<f32, String> {
+        let com = wmi::COMLibrary::new()
+            .map_err(|e| format!("Failed to initialize COM: {}", e))?;
+        let connection = wmi::WMIConnection::with_namespace_path("root\\WMI", com)
+            .map_err(|e| format!("Failed to connect to root\\WMI: {}", e))?;
+        let zones: Vec<AcpiThermalZone> = connection.query()
+            .map_err(|e| format!("Failed to query MSAcpi_ThermalZoneTemperature: {}", e))?;
+
+        zones.iter()
+            .filter(|zone| zone.current_temperature > 0)
+            .map(|zone| zone.current_temperature as f32 / 10.0 - 273.15)
+            .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .ok_or_else(|| "WMI reported no thermal zone".to_string())
+    }
+
+    // Pick the hottest CPU-labeled sensor, or the hottest sensor of any kind when none is
+    // labeled as a CPU. None when the platform reports no usable components
+    fn hottest_cpu_component(components: &Components) -> Option<(String, f32)> {
+        let readings: Vec<(String, f32)> = components.iter()
+            .map(|component| (component.label().to_string(), component.temperature()))
+            .filter(|(_, temp)| temp.is_finite() && *temp > 0.0)
+            .collect();
+
+        let hottest = |candidates: Vec<&(String, f32)>| {
+            candidates.into_iter()
+                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+                .cloned()
+        };
+
+        let cpu_readings: Vec<&(String, f32)> = readings.iter()
+            .filter(|(label, _)| {
+                let label = label.to_lowercase();
+                CPU_SENSOR_LABELS.iter().any(|cpu_label| label.contains(cpu_label))
+            })
+            .collect();
+
+        hottest(cpu_readings).or_else(|| hottest(readings.iter().collect()))
+    }
+
+    // Latest reading of every temperature sensor, e.g. "Core 0", "Package id 0", "amdgpu edge",
+    // hottest first. Refreshed by check_temperature
+    pub fn get_component_temperatures(&self) -> Vec<(String, f32)> {
+        let mut readings: Vec<(String, f32)> = self.components.iter()
+            .map(|component| (component.label().to_string(), component.temperature()))
+            .filter(|(_, temp)| temp.is_finite() && *temp > 0.0)
+            .collect();
+        readings.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        readings
+    }
+
+    pub fn get_hottest_component(&self) -> Option<(String, f32)> {
+        self.get_component_temperatures().into_iter().next()
+    }
+
+    // Score for one component running far hotter than the median of the others. Needs at
+    // least three sensors so there is a meaningful "rest of the machine" to compare with
+    pub fn get_hotspot_score(&self) -> f32 {
+        let readings = self.get_component_temperatures();
+        if readings.len() < 3 {
+            return 0.0;
+        }
+
+        let hottest = readings[0].1;
+        let others: Vec<f32> = readings[1..].iter().map(|(_, temp)| *temp).collect();
+        let median = others[others.len() / 2];
+
+        let excess = hottest - median - HOTSPOT_DELTA_C;
+        if excess > 0.0 {
+            (10.0 + excess * 2.0).min(30.0)
+        } else {
+            0.0
+        }
+    }
+
+    // Get system temperature from hardware sensors, falling back to battery and CPU usage as proxies
+    fn get_system_temperature(&mut self) -> Result<f32, String> {
+        // Real sensor readings where the platform exposes them
+        self.components.refresh();
+        if let Some((_, temp)) = Self::hottest_cpu_component(&self.components) {
+            return Ok(temp);
+        }
+
+        // Kernel thermal zones cover machines whose sensors aren't exposed through hwmon
+        #[cfg(target_os = "linux")]
+        if let Ok(temp) = Self::read_sysfs_temperature() {
+            return Ok(temp);
+        }
+
+        // The ACPI thermal zone is still a real reading, unlike the proxies below
+        #[cfg(windows)]
+        if let Ok(temp) = Self::read_wmi_temperature() {
+            return Ok(temp);
+        }
+
         // Try to get battery information first
+        #[cfg(windows)]
         unsafe {
             let mut power_status = SYSTEM_POWER_STATUS::default();
             let result = GetSystemPowerStatus(&mut power_status);
@@ -65,7 +360,7 @@ impl ThermalMonitor {
                 Ok(estimated_temp)
             },
             Err(e) => {
-                println!("Error getting CPU usage: {}. Using simulated data.", e);
+                warn!("Error getting CPU usage: {}. Using simulated data.", e);
                 // If we can't get CPU usage, use a simulated value
                 let current_temp = 45.0 + (rand::random::<f32>() * 5.0);
                 Ok(current_temp)
@@ -73,12 +368,20 @@ impl ThermalMonitor {
         }
     }
 
+    // One temperature and CPU load reading for `baseline`, without the simulated values
+    // check_temperature falls back to, which would teach the baseline noise
+    pub fn read_baseline_sample(&mut self) -> Result<(f32, f32), String> {
+        let temp = self.get_system_temperature()?;
+        let cpu_usage = self.get_cpu_usage()?;
+        Ok((temp, cpu_usage))
+    }
+
     pub fn check_temperature(&mut self) -> Result<f32, String> {
         // Try to get real temperature data
         let current_temp = match self.get_system_temperature() {
             Ok(temp) => temp,
             Err(e) => {
-                println!("Error getting temperature: {}. Using simulated data.", e);
+                warn!("Error getting temperature: {}. Using simulated data.", e);
                 45.0 + (rand::random::<f32>() * 5.0)
             }
         };
@@ -89,6 +392,12 @@ impl ThermalMonitor {
             Err(_) => rand::random::<f32>() * 100.0, // Simulate CPU usage if we can't get real data
         };
 
+        self.record_reading(current_temp, cpu_usage);
+        Ok(current_temp)
+    }
+
+    // Add one reading to the history, check it for a spike and log it
+    fn record_reading(&mut self, current_temp: f32, cpu_usage: f32) {
         // Store in history
         self.temperature_history.push(current_temp);
         if self.temperature_history.len() > 10 {
@@ -100,28 +409,115 @@ impl ThermalMonitor {
             self.cpu_usage_history.remove(0);
         }
 
-        // Check for temperature spike
-        if self.last_temp > 0.0 {
-            let temp_diff = current_temp - self.last_temp;
-            let time_diff = self.last_check.elapsed();
+        self.trend_history.push_back((Instant::now(), current_temp));
+        if self.trend_history.len() > TREND_WINDOW {
+            self.trend_history.pop_front();
+        }
+
+        // Check for temperature spike: a rise of more than the spike delta over the coolest
+        // earlier reading within the spike window
+        let window_start = Instant::now().checked_sub(self.spike_window);
+        let coolest_recent = self.trend_history.iter()
+            .rev()
+            .skip(1)
+            .take_while(|(time, _)| match window_start {
+                Some(start) => *time >= start,
+                None => true,
+            })
+            .map(|(_, temp)| *temp)
+            .fold(None, |coolest: Option<f32>, temp| Some(coolest.map_or(temp, |c| c.min(temp))));
 
-            // If temperature increased by more than 10°C in less than 10 seconds
-            if temp_diff > 10.0 && time_diff < Duration::from_secs(10) {
-                self.spike_detected = true;
-                println!("Temperature spike detected! {:.1}°C → {:.1}°C", self.last_temp, current_temp);
+        if let Some(coolest) = coolest_recent {
+            if current_temp - coolest > self.spike_delta_c {
+                if !self.in_spike_cooldown() {
+                    warn!("Temperature spike detected! {:.1}°C → {:.1}°C", coolest, current_temp);
+                    self.spike_count += 1;
+                }
+                self.last_spike = Some(Instant::now());
             }
         }
 
-        self.last_temp = current_temp;
-        self.last_check = Instant::now();
+        if self.log.is_some() {
+            let score = self.get_threat_score();
+            if let Some(log) = self.log.as_mut() {
+                if let Err(e) = log.append(current_temp, cpu_usage, score) {
+                    error!("{}", e);
+                }
+            }
+        }
+    }
 
-        Ok(current_temp)
+    // Temperature trend in °C per minute, from a linear regression over the recent readings
+    pub fn get_temperature_slope(&self) -> f32 {
+        if self.trend_history.len() < 2 {
+            return 0.0;
+        }
+
+        let start = self.trend_history[0].0;
+        let points: Vec<(f32, f32)> = self.trend_history.iter()
+            .map(|(time, temp)| (time.duration_since(start).as_secs_f32() / 60.0, *temp))
+            .collect();
+
+        let n = points.len() as f32;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f32>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f32>() / n;
+
+        let covariance: f32 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+        let variance_x: f32 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+
+        if variance_x > 0.0 {
+            covariance / variance_x
+        } else {
+            0.0
+        }
+    }
+
+    // Score contribution of a sustained positive slope, once enough history has built up
+    pub fn get_slope_score(&self) -> f32 {
+        let span = match (self.trend_history.front(), self.trend_history.back()) {
+            (Some((first, _)), Some((last, _))) => last.duration_since(*first),
+            _ => return 0.0,
+        };
+
+        if span < MIN_TREND_SPAN {
+            return 0.0;
+        }
+
+        let slope = self.get_temperature_slope();
+        if slope > SLOPE_THRESHOLD_C_PER_MIN {
+            ((slope - SLOPE_THRESHOLD_C_PER_MIN) * 20.0).min(30.0)
+        } else {
+            0.0
+        }
+    }
+
+    // Whether any spike was seen since monitoring started, even if its cooldown is over
+    pub fn spike_detected(&self) -> bool {
+        self.last_spike.is_some()
+    }
+
+    // Whether the most recent spike is still within its cooldown
+    fn in_spike_cooldown(&self) -> bool {
+        match self.last_spike {
+            Some(at) => at.elapsed() <= self.spike_cooldown,
+            None => false,
+        }
+    }
+
+    pub fn spike_count(&self) -> u32 {
+        self.spike_count
+    }
+
+    // Most recent reading from check_temperature, in °C
+    pub fn last_temperature(&self) -> Option<f32> {
+        self.temperature_history.last().copied()
     }
 
     pub fn get_threat_score(&self) -> u8 {
-        // If a spike was detected, that's an immediate high threat
-        if self.spike_detected {
-            return 80;
+        // A recent spike is an immediate high threat; once temperatures have been back to
+        // normal for the cooldown, the score is based on the history again
+        if self.in_spike_cooldown() {
+            return SPIKE_SCORE;
         }
 
         // Calculate score based on temperature history and CPU usage
@@ -144,26 +540,42 @@ impl ThermalMonitor {
             };
 
             // Calculate threat score based on multiple factors
-            // 1. High average temperature (above 60°C is concerning)
-            // 2. High CPU usage (above 80% is concerning)
+            // 1. High average temperature (above 60°C, or the learned baseline's bound, is concerning)
+            // 2. High CPU usage (above 80%, or the learned baseline's bound, is concerning)
             // 3. High temperature variance (unusual fluctuations)
 
-            let temp_score = if avg_temp > 60.0 {
-                ((avg_temp - 60.0) * 2.0).min(40.0)
-            } else {
-                0.0
-            };
+            let (temp_score, cpu_score) = match &self.baseline {
+                Some(baseline) => (
+                    baseline.temperature.deviation_score(avg_temp, baseline.sigma, 40.0),
+                    baseline.cpu.deviation_score(avg_cpu, baseline.sigma, 40.0),
+                ),
+                None => {
+                    let temp_score = if avg_temp > 60.0 {
+                        ((avg_temp - 60.0) * 2.0).min(40.0)
+                    } else {
+                        0.0
+                    };
 
-            let cpu_score = if avg_cpu > 80.0 {
-                ((avg_cpu - 80.0) * 2.0).min(40.0)
-            } else {
-                0.0
+                    let cpu_score = if avg_cpu > 80.0 {
+                        ((avg_cpu - 80.0) * 2.0).min(40.0)
+                    } else {
+                        0.0
+                    };
+
+                    (temp_score, cpu_score)
+                },
             };
 
             let variance_score = (temp_variance * 10.0).min(20.0);
 
+            // 4. Sustained upward trend (a miner ramping up never trips the spike rule)
+            let slope_score = self.get_slope_score();
+
+            // 5. A single component much hotter than the rest, which the average hides
+            let hotspot_score = self.get_hotspot_score();
+
             // Combine scores
-            let total_score = temp_score + cpu_score + variance_score;
+            let total_score = (temp_score + cpu_score + variance_score + slope_score + hotspot_score).min(100.0);
 
             return total_score as u8;
         }
@@ -172,3 +584,58 @@ impl ThermalMonitor {
         0
     }
 }
+
+impl Monitor for ThermalMonitor {
+    fn name(&self) -> &str {
+        "thermal"
+    }
+
+    // Temperatures are read when polled, so there's nothing running in the background
+    fn start(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn poll(&mut self) -> MonitorSample {
+        let spikes = self.spike_count;
+        let mut findings = Vec::new();
+        match self.check_temperature() {
+            Ok(temp) if self.spike_count > spikes => {
+                findings.push(Finding::new(FindingKind::ThermalSpike, self.get_threat_score(), format!("{:.1}°C", temp)));
+            },
+            Ok(_) => {},
+            Err(e) => error!("Error checking temperature: {}", e),
+        }
+
+        MonitorSample {
+            score: Some(self.get_threat_score()),
+            findings,
+        }
+    }
+
+    fn stop(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spike_score_drops_after_cooldown() {
+        let mut monitor = ThermalMonitor::new();
+        monitor.set_spike_cooldown(Duration::from_millis(200));
+
+        monitor.record_reading(45.0, 10.0);
+        monitor.record_reading(45.5, 10.0);
+        monitor.record_reading(60.0, 10.0);
+        assert_eq!(monitor.get_threat_score(), SPIKE_SCORE);
+        assert_eq!(monitor.spike_count(), 1);
+
+        std::thread::sleep(Duration::from_millis(300));
+        for _ in 0..10 {
+            monitor.record_reading(45.0, 10.0);
+        }
+        assert!(monitor.get_threat_score() < SPIKE_SCORE);
+        assert_eq!(monitor.spike_count(), 1);
+        assert!(monitor.spike_detected());
+    }
+}
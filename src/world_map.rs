@@ -0,0 +1,265 @@
+// Low-resolution world outline for the GUI threat map. Coastlines are hand-simplified to a
+// few dozen [longitude, latitude] points per landmass, enough to recognise the continents at
+// map-panel size without bundling a full coastline dataset
+
+// Equirectangular projection of a position onto the unit rectangle: x runs west to east and
+// y north to south, both 0.0-1.0. A map drawn twice as wide as it is tall keeps its proportions
+pub fn project(longitude: f32, latitude: f32) -> (f32, f32) {
+    let x = (longitude.clamp(-180.0, 180.0) + 180.0) / 360.0;
+    let y = (90.0 - latitude.clamp(-90.0, 90.0)) / 180.0;
+    (x, y)
+}
+
+// Closed outlines of the landmasses
+pub const LAND: &[&[[f32; 2]]] = &[
+    // North America
+    &[
+        [-168.0, 65.6], [-163.0, 66.5], [-166.0, 68.8], [-156.8, 71.3], [-148.0, 70.3], [-141.0, 69.6],
+        [-134.0, 69.5], [-128.0, 70.2], [-122.0, 69.3], [-115.0, 68.3], [-108.0, 68.3], [-98.0, 67.8],
+        [-94.0, 68.9], [-87.5, 67.5], [-90.0, 64.0], [-94.0, 61.0], [-94.0, 59.0], [-92.5, 57.0],
+        [-85.0, 55.3], [-82.3, 52.9], [-79.3, 51.5], [-78.8, 54.5], [-77.0, 58.0], [-77.8, 62.3],
+        [-72.0, 61.9], [-69.5, 59.0], [-65.0, 60.4], [-61.5, 56.5], [-56.0, 52.5], [-60.0, 50.2],
+        [-66.5, 50.0], [-64.8, 47.0], [-61.0, 45.5], [-66.0, 44.5], [-70.2, 43.6], [-70.0, 41.7],
+        [-74.0, 40.6], [-76.0, 37.0], [-75.5, 35.2], [-79.0, 33.0], [-81.5, 31.0], [-80.0, 27.0],
+        [-80.4, 25.2], [-82.0, 26.5], [-83.0, 29.0], [-85.0, 29.8], [-89.0, 30.2], [-90.0, 29.0],
+        [-94.0, 29.6], [-97.0, 27.5], [-97.5, 24.0], [-97.0, 21.5], [-95.0, 18.7], [-91.5, 18.5],
+        [-90.5, 21.0], [-87.0, 21.5], [-88.0, 16.0], [-84.0, 15.8], [-83.5, 11.0], [-81.5, 9.0],
+        [-79.0, 9.5], [-77.3, 8.6], [-78.0, 7.3], [-80.0, 7.3], [-81.0, 8.0], [-84.0, 9.7],
+        [-86.0, 11.0], [-88.0, 13.3], [-91.5, 14.0], [-94.5, 16.2], [-98.0, 16.0], [-101.5, 17.5],
+        [-105.5, 20.0], [-105.5, 22.5], [-108.0, 25.5], [-111.0, 28.0], [-114.7, 31.5], [-112.5, 28.5],
+        [-109.9, 22.9], [-112.0, 25.0], [-114.0, 28.0], [-116.5, 31.5], [-117.1, 32.5], [-118.5, 34.0],
+        [-120.6, 34.6], [-122.5, 37.8], [-124.0, 40.4], [-124.3, 43.0], [-124.0, 46.3], [-124.7, 48.4],
+        [-127.5, 50.5], [-130.0, 54.5], [-133.0, 57.0], [-136.0, 58.3], [-140.0, 59.7], [-146.0, 60.8],
+        [-150.0, 59.5], [-154.0, 58.0], [-158.0, 56.5], [-162.0, 55.2], [-164.5, 54.5], [-160.0, 56.5],
+        [-157.5, 58.7], [-162.0, 59.5], [-165.0, 60.5], [-166.0, 61.5], [-165.0, 63.0], [-161.0, 64.5],
+        [-166.0, 64.5],
+    ],
+    // Baffin Island
+    &[
+        [-62.0, 66.7], [-65.0, 62.5], [-71.5, 63.0], [-78.0, 64.5], [-74.0, 68.0], [-80.0, 70.0],
+        [-90.0, 73.5], [-80.0, 73.7], [-70.0, 70.6], [-67.0, 69.0],
+    ],
+    // Victoria Island
+    &[
+        [-118.0, 71.0], [-105.0, 69.0], [-101.0, 70.0], [-105.0, 73.5], [-115.0, 73.3], [-119.0, 71.5],
+    ],
+    // Newfoundland
+    &[
+        [-59.3, 47.6], [-55.6, 51.6], [-53.0, 49.0], [-52.7, 47.5], [-53.6, 46.6], [-56.0, 47.6],
+    ],
+    // Greenland
+    &[
+        [-73.0, 78.5], [-60.0, 82.0], [-40.0, 83.5], [-20.0, 82.5], [-18.0, 77.0], [-20.0, 72.0],
+        [-22.0, 70.0], [-32.0, 68.0], [-40.0, 65.5], [-43.0, 60.0], [-48.0, 61.0], [-51.0, 64.0],
+        [-53.0, 67.0], [-55.0, 70.5], [-58.0, 75.5], [-66.0, 76.5],
+    ],
+    // Cuba
+    &[
+        [-85.0, 21.8], [-82.0, 23.2], [-77.0, 22.3], [-74.2, 20.2], [-77.7, 19.9], [-80.0, 21.8],
+    ],
+    // Hispaniola
+    &[
+        [-74.4, 18.5], [-72.8, 19.9], [-69.9, 19.6], [-68.4, 18.5], [-71.3, 17.7],
+    ],
+    // South America
+    &[
+        [-77.3, 8.6], [-75.5, 10.6], [-72.0, 12.0], [-71.0, 11.5], [-68.0, 10.5], [-64.0, 10.6],
+        [-61.5, 10.0], [-60.0, 8.5], [-57.0, 6.0], [-52.0, 5.0], [-50.0, 1.5], [-48.5, -1.0],
+        [-44.0, -2.5], [-39.0, -3.5], [-35.0, -5.5], [-35.0, -9.0], [-38.5, -13.0], [-39.0, -17.8],
+        [-40.8, -22.0], [-43.0, -23.0], [-48.0, -25.8], [-48.6, -28.5], [-52.0, -32.2], [-54.0, -34.8],
+        [-57.5, -38.5], [-62.0, -39.0], [-65.0, -42.5], [-67.0, -46.0], [-65.8, -47.8], [-68.5, -50.2],
+        [-68.3, -52.4], [-68.5, -55.0], [-71.0, -53.8], [-74.5, -52.0], [-75.5, -48.0], [-73.5, -43.0],
+        [-73.7, -37.0], [-71.6, -33.0], [-71.4, -28.0], [-70.3, -18.5], [-75.2, -15.3], [-77.0, -12.0],
+        [-79.6, -7.2], [-81.2, -5.0], [-80.1, -2.5], [-80.0, 0.0], [-79.5, 1.5], [-77.5, 4.0],
+        [-77.3, 7.5],
+    ],
+    // Eurasia
+    &[
+        [-8.9, 37.0], [-8.9, 42.0], [-9.3, 43.2], [-1.5, 43.4], [-1.2, 46.0], [-4.5, 48.4],
+        [-1.5, 48.7], [1.5, 50.2], [4.0, 51.5], [5.0, 53.2], [8.6, 53.9], [8.1, 56.8],
+        [10.6, 57.7], [10.9, 56.0], [10.9, 54.4], [14.0, 54.0], [18.5, 54.8], [21.0, 55.5],
+        [21.0, 57.0], [24.0, 57.2], [23.5, 59.2], [28.5, 59.8], [23.0, 60.0], [21.4, 60.7],
+        [21.5, 63.0], [25.0, 65.0], [22.0, 65.8], [17.5, 62.5], [18.8, 59.9], [16.5, 57.0],
+        [16.0, 56.2], [13.0, 55.4], [11.0, 58.8], [8.0, 58.0], [5.5, 59.0], [5.0, 61.5],
+        [10.0, 64.0], [14.0, 67.5], [19.0, 70.0], [25.8, 71.1], [31.0, 70.0], [34.0, 69.2],
+        [41.0, 67.5], [44.0, 68.5], [53.0, 68.5], [60.0, 69.8], [66.0, 69.5], [72.5, 72.8],
+        [80.0, 73.5], [88.0, 75.5], [100.0, 76.5], [104.3, 77.7], [113.0, 73.5], [128.0, 73.0],
+        [140.0, 72.5], [150.0, 71.5], [160.0, 70.0], [170.0, 70.0], [180.0, 68.8], [180.0, 65.0],
+        [177.0, 64.7], [178.8, 62.5], [172.0, 60.5], [163.0, 60.0], [163.5, 57.5], [162.0, 56.0],
+        [158.6, 52.9], [156.7, 51.0], [156.0, 57.8], [160.0, 61.5], [155.0, 59.3], [143.0, 59.3],
+        [135.3, 54.7], [141.0, 53.0], [140.5, 48.5], [135.0, 43.5], [131.0, 42.6], [129.5, 41.0],
+        [128.5, 38.5], [129.4, 36.0], [126.5, 34.5], [126.2, 37.7], [124.7, 39.7], [121.5, 40.9],
+        [121.5, 39.0], [117.8, 39.0], [119.0, 37.2], [122.5, 37.2], [120.3, 36.0], [119.0, 34.5],
+        [120.8, 32.0], [121.9, 30.9], [122.0, 29.8], [121.5, 28.5], [119.6, 25.8], [117.0, 23.5],
+        [113.5, 22.2], [110.5, 21.0], [108.0, 21.6], [106.7, 20.0], [105.9, 18.8], [108.8, 15.3],
+        [109.2, 11.7], [107.0, 10.4], [105.0, 8.7], [104.8, 10.4], [103.0, 11.0], [100.8, 13.5],
+        [100.0, 12.0], [99.2, 9.3], [100.3, 7.5], [103.4, 4.2], [103.6, 1.3], [101.2, 2.8],
+        [100.3, 5.5], [98.3, 8.2], [98.7, 10.8], [97.7, 16.5], [94.3, 16.1], [94.3, 18.7],
+        [92.3, 21.0], [91.7, 22.7], [89.0, 21.8], [86.9, 21.4], [85.0, 19.5], [82.3, 16.6],
+        [80.3, 15.8], [80.2, 13.0], [79.8, 10.3], [77.5, 8.0], [76.5, 9.3], [74.8, 12.8],
+        [73.5, 16.0], [72.8, 19.2], [72.6, 21.4], [70.5, 20.8], [68.8, 22.3], [67.4, 24.0],
+        [66.7, 25.3], [62.0, 25.2], [57.3, 25.8], [56.3, 27.2], [54.0, 26.7], [51.5, 27.9],
+        [50.0, 30.1], [48.0, 30.0], [49.5, 27.0], [50.8, 24.8], [51.6, 24.1], [54.0, 24.1],
+        [56.3, 26.2], [56.4, 24.7], [58.6, 23.6], [59.8, 22.4], [57.8, 19.0], [55.0, 17.0],
+        [52.2, 15.6], [48.7, 14.0], [45.0, 12.8], [43.5, 12.7], [42.7, 15.7], [40.5, 20.5],
+        [38.5, 23.7], [36.6, 26.2], [35.0, 28.0], [34.6, 29.5], [32.6, 29.9], [32.3, 31.3],
+        [34.2, 31.3], [35.0, 33.0], [35.9, 35.5], [36.2, 36.7], [32.5, 36.1], [30.5, 36.5],
+        [28.2, 36.7], [27.3, 37.5], [26.3, 39.0], [26.2, 40.0], [26.0, 40.7], [24.0, 40.8],
+        [22.9, 40.6], [23.9, 38.0], [22.8, 36.5], [21.6, 37.9], [20.0, 39.6], [19.4, 41.8],
+        [16.0, 43.5], [13.6, 45.7], [12.3, 45.2], [12.4, 44.2], [14.0, 42.5], [16.0, 41.4],
+        [18.5, 40.2], [16.5, 39.7], [16.6, 38.0], [15.7, 38.0], [15.6, 40.0], [12.5, 41.8],
+        [10.5, 42.9], [8.8, 44.4], [7.0, 43.6], [4.5, 43.4], [3.1, 42.5], [3.2, 41.9],
+        [0.8, 41.0], [-0.3, 39.5], [0.2, 38.7], [-0.7, 37.6], [-2.1, 36.7], [-4.5, 36.6],
+        [-5.6, 36.0], [-6.3, 36.8], [-7.4, 37.2],
+    ],
+    // Great Britain
+    &[
+        [-5.7, 50.0], [1.4, 51.2], [1.7, 52.7], [0.0, 53.5], [-1.6, 55.6], [-2.1, 57.2],
+        [-1.8, 57.6], [-3.7, 57.7], [-3.0, 58.6], [-5.0, 58.6], [-6.2, 56.7], [-5.6, 55.3],
+        [-4.9, 54.8], [-3.4, 54.9], [-3.1, 53.3], [-4.7, 52.8], [-4.2, 52.3], [-5.3, 51.7],
+        [-3.0, 51.4], [-4.5, 51.0],
+    ],
+    // Ireland
+    &[
+        [-6.0, 52.2], [-6.0, 53.9], [-5.6, 54.6], [-7.4, 55.3], [-10.0, 54.2], [-9.5, 51.6],
+        [-7.3, 52.1],
+    ],
+    // Iceland
+    &[
+        [-22.5, 64.0], [-24.0, 65.5], [-22.0, 66.4], [-16.0, 66.5], [-13.6, 65.2], [-15.0, 64.3],
+        [-18.7, 63.4],
+    ],
+    // Svalbard
+    &[
+        [11.0, 78.5], [16.0, 80.0], [27.0, 80.0], [22.0, 77.5], [16.0, 76.5],
+    ],
+    // Novaya Zemlya
+    &[
+        [52.0, 71.5], [56.0, 75.5], [68.0, 77.0], [60.0, 74.0], [57.0, 70.5],
+    ],
+    // Africa
+    &[
+        [32.3, 31.3], [29.9, 31.2], [25.0, 31.6], [20.0, 32.1], [19.0, 30.3], [15.5, 31.5],
+        [13.0, 32.9], [11.0, 33.5], [10.0, 34.2], [11.0, 36.9], [9.8, 37.3], [3.0, 36.8],
+        [-1.5, 35.3], [-5.4, 35.9], [-6.9, 34.0], [-9.6, 30.4], [-13.0, 27.7], [-16.0, 24.0],
+        [-17.0, 21.0], [-16.0, 18.0], [-17.5, 14.7], [-16.7, 12.5], [-15.5, 11.0], [-13.0, 8.5],
+        [-11.0, 6.8], [-7.5, 4.4], [-4.0, 5.2], [-1.0, 5.0], [1.5, 6.2], [4.5, 6.3],
+        [6.0, 4.3], [8.5, 4.5], [9.6, 3.5], [9.8, 1.0], [9.3, -1.0], [11.8, -4.0],
+        [12.3, -6.0], [13.2, -9.0], [12.0, -13.5], [11.8, -17.0], [14.5, -22.5], [15.2, -27.0],
+        [17.0, -29.0], [18.4, -33.9], [20.0, -34.8], [22.0, -34.0], [25.6, -34.0], [27.5, -33.2],
+        [30.8, -29.8], [32.5, -28.5], [32.9, -26.0], [35.3, -24.0], [35.5, -21.0], [34.7, -19.7],
+        [36.9, -17.8], [40.5, -15.0], [40.5, -10.5], [39.3, -7.0], [39.7, -4.0], [41.5, -1.7],
+        [43.5, 0.5], [46.0, 2.0], [49.0, 6.0], [51.2, 10.4], [51.3, 11.8], [48.0, 11.2],
+        [44.0, 10.4], [43.2, 11.5], [42.7, 12.7], [41.0, 14.5], [39.2, 15.6], [38.2, 18.0],
+        [37.2, 21.0], [36.9, 22.0], [35.5, 24.0], [34.0, 26.7], [32.8, 28.6], [32.6, 29.9],
+    ],
+    // Madagascar
+    &[
+        [49.3, -12.0], [50.5, -15.5], [49.5, -17.5], [47.0, -25.0], [45.0, -25.5], [43.5, -22.0],
+        [44.0, -17.0], [46.5, -15.7], [48.0, -13.5],
+    ],
+    // Sri Lanka
+    &[
+        [79.9, 9.8], [81.2, 8.6], [81.8, 7.0], [80.6, 5.9], [79.8, 6.5],
+    ],
+    // Japan: Honshu, Shikoku and Kyushu
+    &[
+        [129.7, 33.2], [131.0, 34.4], [133.0, 35.5], [135.8, 35.6], [136.8, 37.3], [139.5, 38.5],
+        [140.0, 40.7], [141.5, 41.4], [142.0, 39.0], [141.0, 36.5], [140.8, 35.0], [139.8, 35.0],
+        [138.8, 34.6], [137.0, 34.6], [135.1, 33.5], [132.5, 33.7], [131.8, 31.4], [130.6, 31.1],
+    ],
+    // Hokkaido
+    &[
+        [140.0, 41.5], [141.5, 42.5], [143.3, 42.0], [145.5, 43.3], [144.5, 44.0], [141.9, 45.5],
+        [141.3, 43.3], [140.3, 43.0],
+    ],
+    // Sakhalin
+    &[
+        [142.0, 46.0], [143.5, 49.5], [144.7, 49.0], [142.5, 54.3], [142.0, 51.0],
+    ],
+    // Taiwan
+    &[
+        [120.1, 23.0], [121.0, 25.2], [121.9, 24.8], [120.8, 22.0],
+    ],
+    // Luzon
+    &[
+        [120.6, 18.5], [122.2, 18.5], [122.0, 16.5], [124.0, 13.3], [121.0, 13.8], [120.5, 15.5],
+    ],
+    // Mindanao
+    &[
+        [122.0, 7.0], [126.6, 7.3], [126.0, 9.3], [123.5, 8.6],
+    ],
+    // Sumatra
+    &[
+        [95.3, 5.6], [98.5, 3.7], [103.8, 1.0], [106.0, -3.0], [105.8, -5.8], [104.5, -5.9],
+        [101.0, -2.5], [98.7, 1.8],
+    ],
+    // Java
+    &[
+        [105.2, -6.8], [106.0, -5.9], [110.4, -6.9], [112.7, -6.9], [114.4, -7.7], [114.5, -8.7],
+        [110.0, -8.2], [106.4, -7.4],
+    ],
+    // Borneo
+    &[
+        [109.0, 1.7], [111.0, 1.9], [113.0, 3.2], [115.4, 5.0], [116.9, 7.0], [119.3, 5.2],
+        [117.9, 1.0], [116.5, -2.0], [116.0, -3.9], [114.5, -4.0], [111.7, -3.0], [110.0, -1.7],
+        [109.0, 0.0],
+    ],
+    // Sulawesi
+    &[
+        [119.5, -5.5], [119.0, -3.0], [119.5, 0.0], [120.0, 1.0], [125.0, 1.5], [121.0, -1.0],
+        [123.3, -1.0], [121.5, -4.5], [120.5, -5.5],
+    ],
+    // New Guinea
+    &[
+        [131.0, -1.0], [134.0, -0.8], [137.6, -1.5], [141.0, -2.6], [145.8, -4.9], [147.7, -6.7],
+        [150.5, -10.6], [147.5, -10.0], [146.0, -8.1], [143.5, -9.0], [141.0, -9.1], [139.0, -8.1],
+        [137.7, -5.2], [135.0, -4.4], [133.0, -4.0], [132.0, -2.8],
+    ],
+    // Australia
+    &[
+        [113.5, -22.0], [114.0, -26.0], [115.0, -29.5], [115.7, -32.0], [115.0, -34.3], [118.0, -35.0],
+        [123.5, -33.9], [126.0, -32.3], [131.0, -31.5], [134.2, -32.9], [135.9, -34.8], [137.5, -33.0],
+        [138.0, -35.7], [139.6, -37.2], [141.0, -38.3], [144.5, -38.3], [146.4, -39.1], [149.9, -37.5],
+        [150.8, -34.5], [153.1, -30.5], [153.5, -28.0], [153.0, -25.0], [150.8, -22.5], [149.0, -20.5],
+        [146.2, -18.5], [145.3, -15.0], [143.5, -14.0], [142.5, -10.7], [141.6, -13.0], [141.5, -17.0],
+        [140.0, -17.7], [137.0, -16.0], [135.5, -15.0], [136.7, -12.2], [133.0, -11.4], [131.0, -12.2],
+        [129.5, -14.8], [127.0, -14.0], [125.0, -15.0], [122.2, -17.7], [121.0, -19.5], [117.0, -20.7],
+        [114.2, -21.8],
+    ],
+    // Tasmania
+    &[
+        [144.6, -40.7], [148.3, -40.9], [148.0, -43.0], [146.8, -43.6], [145.2, -42.2],
+    ],
+    // New Zealand, North Island
+    &[
+        [172.7, -34.4], [174.8, -36.8], [178.5, -37.7], [177.0, -39.4], [175.0, -41.6], [173.9, -39.2],
+    ],
+    // New Zealand, South Island
+    &[
+        [172.7, -40.5], [174.2, -41.7], [173.0, -43.8], [171.2, -44.4], [169.0, -46.6], [166.5, -46.0],
+        [168.0, -44.0], [171.0, -42.0],
+    ],
+    // Antarctica
+    &[
+        [-180.0, -78.0], [-150.0, -77.0], [-120.0, -73.5], [-100.0, -72.0], [-75.0, -73.0], [-62.0, -66.0],
+        [-57.0, -63.3], [-60.0, -75.0], [-45.0, -78.0], [-30.0, -76.0], [-10.0, -71.0], [10.0, -70.0],
+        [30.0, -69.5], [50.0, -66.5], [70.0, -68.0], [90.0, -66.5], [110.0, -66.0], [130.0, -66.2],
+        [150.0, -68.5], [165.0, -71.0], [170.0, -77.5], [180.0, -78.0], [180.0, -90.0], [-180.0, -90.0],
+    ],
+];
+
+// Inland seas large enough to show at this scale
+pub const INLAND_SEAS: &[&[[f32; 2]]] = &[
+    // Black Sea
+    &[
+        [27.5, 42.5], [28.6, 44.2], [29.7, 45.3], [30.7, 46.5], [32.0, 46.2], [33.5, 44.4],
+        [36.5, 45.3], [38.0, 44.5], [40.0, 43.4], [41.6, 41.6], [40.0, 40.9], [36.0, 41.7],
+        [33.0, 42.0], [29.0, 41.2], [28.0, 41.6],
+    ],
+    // Caspian Sea
+    &[
+        [47.0, 45.0], [49.2, 46.5], [53.0, 46.8], [51.5, 45.0], [52.8, 41.5], [53.0, 40.0],
+        [54.0, 37.3], [51.0, 36.7], [49.0, 37.6], [49.5, 40.3], [47.6, 41.6], [47.5, 43.5],
+    ],
+];